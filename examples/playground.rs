@@ -0,0 +1,21 @@
+//! Opens the playground on every device, with the WGSL file named on the command line
+//! loaded, if there is one:
+//!
+//! ```text
+//! cargo run --example playground --features playground -- kernel.wgsl
+//! ```
+
+use wisc::playground::Playground;
+use wisc::prelude::*;
+
+fn main() {
+    let mut playground = Playground::new(Workgroup::from_devices(VDevice::all()));
+
+    if let Some(path) = std::env::args().nth(1)
+        && let Err(error) = playground.load(&path)
+    {
+        eprintln!("{error}");
+    }
+
+    playground.show().expect("Failed to open the playground");
+}
@@ -0,0 +1,254 @@
+//! A suite of standard kernels timed on every device of a Workgroup, for comparing
+//! hardware and for weighting devices by what they measurably do rather than by what
+//! their limits suggest.
+
+use std::time::Duration;
+
+use crate::calibration::{SAXPY_WGSL, time_dispatch};
+use crate::error::WiscError;
+use crate::vdevice::VDevice;
+use crate::workgroup::{Weighting, Workgroup};
+
+const ELEMENTS: u32 = 1 << 20;
+const GEMM_SIZE: u32 = 256;
+
+const COPY_WGSL: &str = "
+@group(0) @binding(0) var<storage, read> src: array<vec4<f32>>;
+@group(0) @binding(1) var<storage, read_write> dst: array<vec4<f32>>;
+
+@compute @workgroup_size(256, 1, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x < arrayLength(&dst)) {
+        dst[id.x] = src[id.x];
+    }
+}
+";
+
+const REDUCE_WGSL: &str = "
+@group(0) @binding(0) var<storage, read> data: array<f32>;
+@group(0) @binding(1) var<storage, read_write> sums: array<f32>;
+
+var<workgroup> partial: array<f32, 256>;
+
+@compute @workgroup_size(256, 1, 1)
+fn main(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(local_invocation_index) lane: u32,
+    @builtin(workgroup_id) group: vec3<u32>,
+) {
+    partial[lane] = data[id.x];
+    workgroupBarrier();
+
+    for (var width = 128u; width > 0u; width >>= 1u) {
+        if (lane < width) {
+            partial[lane] += partial[lane + width];
+        }
+        workgroupBarrier();
+    }
+
+    if (lane == 0u) {
+        sums[group.x] = partial[0];
+    }
+}
+";
+
+const GEMM_WGSL: &str = "
+const N: u32 = 256u;
+
+@group(0) @binding(0) var<storage, read> ab: array<f32>;
+@group(0) @binding(1) var<storage, read_write> c: array<f32>;
+
+var<workgroup> a_tile: array<array<f32, 16>, 16>;
+var<workgroup> b_tile: array<array<f32, 16>, 16>;
+
+@compute @workgroup_size(16, 16, 1)
+fn main(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(local_invocation_id) local: vec3<u32>,
+) {
+    var sum = 0.0;
+
+    for (var tile = 0u; tile < N; tile += 16u) {
+        a_tile[local.y][local.x] = ab[id.y * N + tile + local.x];
+        b_tile[local.y][local.x] = ab[N * N + (tile + local.y) * N + id.x];
+        workgroupBarrier();
+
+        for (var k = 0u; k < 16u; k++) {
+            sum += a_tile[local.y][k] * b_tile[k][local.x];
+        }
+        workgroupBarrier();
+    }
+
+    c[id.y * N + id.x] = sum;
+}
+";
+
+/// How one kernel of the suite fared on one device.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KernelScore {
+    pub kernel: &'static str,
+    /// What the kernel sustained in `unit`s, over its fastest run.
+    pub throughput: f64,
+    pub unit: &'static str,
+    /// The throughput as a fraction of the fastest device's, so 1.0 on the device that
+    /// ran it fastest.
+    pub normalized: f32,
+}
+
+/// How one device fared across the suite.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceBenchmark {
+    /// The device's index in the Workgroup.
+    pub device: usize,
+    pub label: String,
+    pub kernels: Vec<KernelScore>,
+    /// The geometric mean of the device's normalized kernel scores.
+    pub score: f32,
+}
+
+/// What [`Workgroup::benchmark_suite`] measured, device by device.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkSuite {
+    pub devices: Vec<DeviceBenchmark>,
+}
+
+impl BenchmarkSuite {
+    /// Weights the devices by their scores, in Workgroup order, for building a Workgroup
+    /// over the same devices that divides work as they measured.
+    pub fn weighting(&self) -> Weighting {
+        Weighting::Manual(self.devices.iter().map(|device| device.score).collect())
+    }
+}
+
+/// A kernel of the suite: its source, the two buffers it binds (in bytes), the workgroups
+/// it dispatches, and how much work one dispatch does in `unit`s.
+struct Kernel {
+    name: &'static str,
+    source: &'static str,
+    input_bytes: u64,
+    output_bytes: u64,
+    workgroups: (u32, u32, u32),
+    work: f64,
+    unit: &'static str,
+}
+
+fn kernels() -> [Kernel; 4] {
+    let n = ELEMENTS as u64;
+    let gemm = GEMM_SIZE as u64;
+
+    [
+        Kernel {
+            name: "saxpy",
+            source: SAXPY_WGSL,
+            input_bytes: n * 4,
+            output_bytes: n * 4,
+            workgroups: (ELEMENTS / 256, 1, 1),
+            work: 2.0 * n as f64 / 1e9,
+            unit: "GFLOP/s",
+        },
+        Kernel {
+            name: "gemm",
+            source: GEMM_WGSL,
+            input_bytes: 2 * gemm * gemm * 4,
+            output_bytes: gemm * gemm * 4,
+            workgroups: (GEMM_SIZE / 16, GEMM_SIZE / 16, 1),
+            work: 2.0 * (gemm * gemm * gemm) as f64 / 1e9,
+            unit: "GFLOP/s",
+        },
+        Kernel {
+            name: "reduction",
+            source: REDUCE_WGSL,
+            input_bytes: n * 4,
+            output_bytes: n / 256 * 4,
+            workgroups: (ELEMENTS / 256, 1, 1),
+            work: (n * 4) as f64 / 1e9,
+            unit: "GB/s",
+        },
+        Kernel {
+            name: "copy",
+            source: COPY_WGSL,
+            input_bytes: n * 16,
+            output_bytes: n * 16,
+            workgroups: (ELEMENTS / 256, 1, 1),
+            work: (2 * n * 16) as f64 / 1e9,
+            unit: "GB/s",
+        },
+    ]
+}
+
+impl Kernel {
+    /// The kernel's fastest of a few runs on `vd`, after one to warm up.
+    fn time(&self, vd: &VDevice) -> Result<Duration, WiscError> {
+        time_dispatch(
+            vd,
+            self.name,
+            self.source,
+            (self.input_bytes, self.output_bytes),
+            self.workgroups,
+        )
+    }
+}
+
+impl Workgroup {
+    /// Times a suite of standard kernels (saxpy, a tiled matrix multiply, a reduction and
+    /// a memory copy) on each device in turn, and scores the devices against each other.
+    ///
+    /// Takes a moment and occupies every device while it runs, so it suits a setup step or
+    /// a hardware comparison rather than the middle of a workload.
+    pub fn benchmark_suite(&self) -> Result<BenchmarkSuite, WiscError> {
+        let kernels = kernels();
+
+        let mut throughputs: Vec<Vec<f64>> = vec![];
+        for vd in &self.vdevices {
+            throughputs.push(
+                kernels
+                    .iter()
+                    .map(|kernel| {
+                        let seconds = kernel.time(vd)?.as_secs_f64().max(1e-9);
+                        Ok(kernel.work / seconds)
+                    })
+                    .collect::<Result<_, WiscError>>()?,
+            );
+        }
+
+        let best: Vec<f64> = (0..kernels.len())
+            .map(|k| {
+                throughputs
+                    .iter()
+                    .map(|device| device[k])
+                    .fold(0.0, f64::max)
+            })
+            .collect();
+
+        let devices = self
+            .vdevices
+            .iter()
+            .zip(throughputs)
+            .enumerate()
+            .map(|(vdi, (vd, throughputs))| {
+                let kernels: Vec<KernelScore> = kernels
+                    .iter()
+                    .zip(throughputs)
+                    .zip(&best)
+                    .map(|((kernel, throughput), best)| KernelScore {
+                        kernel: kernel.name,
+                        throughput,
+                        unit: kernel.unit,
+                        normalized: (throughput / best) as f32,
+                    })
+                    .collect();
+
+                let log_sum: f32 = kernels.iter().map(|kernel| kernel.normalized.ln()).sum();
+
+                DeviceBenchmark {
+                    device: vdi,
+                    label: vd.label.clone(),
+                    score: (log_sum / kernels.len() as f32).exp(),
+                    kernels,
+                }
+            })
+            .collect();
+
+        Ok(BenchmarkSuite { devices })
+    }
+}
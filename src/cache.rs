@@ -0,0 +1,286 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+use wgpu::util::DeviceExt;
+
+use crate::shader;
+use crate::vdevice::VDevice;
+
+// Bind groups keep their buffers alive, so only the most recent ones are remembered.
+const BIND_GROUP_CAPACITY: usize = 32;
+// Tasks built from generated source could compile any number of distinct modules.
+const MODULE_CAPACITY: usize = 64;
+// Pipelines keep their modules alive, including those replaced by reloading a shader.
+const PIPELINE_CAPACITY: usize = 64;
+
+type BindGroupKey = (Vec<wgpu::BindGroupLayoutEntry>, Vec<wgpu::Buffer>);
+
+/// The module, entry point, layout, override constants (by their bits) and immediate size
+/// a pipeline was created with.
+type PipelineKey = (
+    wgpu::ShaderModule,
+    String,
+    Vec<wgpu::BindGroupLayoutEntry>,
+    Vec<(String, u64)>,
+    u32,
+);
+
+/// Bind group layouts, pipelines and bind groups already created on one VDevice, so tasks
+/// built again (say, every iteration of a loop) reuse their pipelines, and tasks that bind
+/// the same buffers in the same layout (say, successive kernels of one algorithm) share
+/// their bind groups and only swap the pipeline.
+#[derive(Default)]
+pub(crate) struct BindingCache {
+    layouts: Mutex<HashMap<Vec<wgpu::BindGroupLayoutEntry>, wgpu::BindGroupLayout>>,
+    pub(crate) pipelines: PipelineStore,
+    bind_groups: Mutex<VecDeque<(BindGroupKey, wgpu::BindGroup)>>,
+    // Small runtime-supplied uniforms, by contents, so they don't defeat the bind groups.
+    uniforms: Mutex<VecDeque<(Vec<u8>, wgpu::Buffer)>>,
+}
+
+impl BindingCache {
+    pub(crate) fn layout(
+        &self,
+        vd: &VDevice,
+        entries: &[wgpu::BindGroupLayoutEntry],
+    ) -> wgpu::BindGroupLayout {
+        let mut layouts = self.layouts.lock().unwrap();
+
+        layouts
+            .entry(entries.to_vec())
+            .or_insert_with(|| {
+                vd.device
+                    .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                        label: None,
+                        entries,
+                    })
+            })
+            .clone()
+    }
+
+    /// A uniform buffer holding `contents`.
+    pub(crate) fn uniform(&self, vd: &VDevice, label: &str, contents: &[u8]) -> wgpu::Buffer {
+        let mut uniforms = self.uniforms.lock().unwrap();
+
+        if let Some((_, buffer)) = uniforms.iter().find(|(cached, _)| cached == contents) {
+            return buffer.clone();
+        }
+
+        let buffer = vd
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents,
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+
+        if uniforms.len() == BIND_GROUP_CAPACITY {
+            uniforms.pop_front();
+        }
+        uniforms.push_back((contents.to_vec(), buffer.clone()));
+
+        buffer
+    }
+
+    /// Binds `buffers` to the bindings of `entries`, in order.
+    pub(crate) fn bind_group(
+        &self,
+        vd: &VDevice,
+        layout: &wgpu::BindGroupLayout,
+        entries: &[wgpu::BindGroupLayoutEntry],
+        buffers: &[wgpu::Buffer],
+    ) -> wgpu::BindGroup {
+        let key = (entries.to_vec(), buffers.to_vec());
+        let mut bind_groups = self.bind_groups.lock().unwrap();
+
+        if let Some((_, bind_group)) = bind_groups.iter().find(|(cached, _)| *cached == key) {
+            return bind_group.clone();
+        }
+
+        let bind_group_entries: Vec<wgpu::BindGroupEntry> = entries
+            .iter()
+            .zip(buffers.iter())
+            .map(|(entry, buffer)| wgpu::BindGroupEntry {
+                binding: entry.binding,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect();
+
+        let bind_group = vd.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout,
+            entries: &bind_group_entries,
+        });
+
+        if bind_groups.len() == BIND_GROUP_CAPACITY {
+            bind_groups.pop_front();
+        }
+        bind_groups.push_back((key, bind_group.clone()));
+
+        bind_group
+    }
+
+    /// Drops everything cached, destroying the uniform buffers, and returns how many
+    /// pipelines there were.
+    pub(crate) fn release(self) -> usize {
+        for (_, buffer) in self.uniforms.into_inner().unwrap() {
+            buffer.destroy();
+        }
+
+        self.pipelines.len()
+    }
+}
+
+/// Compute pipelines by everything they were created with. Those of a [`BindingCache`] are
+/// bounded, while a [`TaskTemplate`](crate::template::TaskTemplate) keeps all of its own.
+pub(crate) struct PipelineStore {
+    capacity: Option<usize>,
+    pipelines: Mutex<VecDeque<(PipelineKey, wgpu::ComputePipeline)>>,
+}
+
+impl Default for PipelineStore {
+    fn default() -> Self {
+        Self {
+            capacity: Some(PIPELINE_CAPACITY),
+            pipelines: Mutex::default(),
+        }
+    }
+}
+
+impl PipelineStore {
+    pub(crate) fn unbounded() -> Self {
+        Self {
+            capacity: None,
+            pipelines: Mutex::default(),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.pipelines.lock().unwrap().len()
+    }
+
+    /// The pipeline running `kernel` of `module` with the layout of `entries`, creating it
+    /// with `create` if there isn't one yet.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn pipeline(
+        &self,
+        module: &wgpu::ShaderModule,
+        kernel: &str,
+        entries: &[wgpu::BindGroupLayoutEntry],
+        constants: &[(&str, f64)],
+        immediate_size: u32,
+        create: impl FnOnce() -> wgpu::ComputePipeline,
+    ) -> wgpu::ComputePipeline {
+        let key = (
+            module.clone(),
+            kernel.to_string(),
+            entries.to_vec(),
+            constants
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_bits()))
+                .collect(),
+            immediate_size,
+        );
+
+        if let Some((_, pipeline)) = self
+            .pipelines
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(cached, _)| *cached == key)
+        {
+            return pipeline.clone();
+        }
+
+        // Create it without holding the lock, since the driver may compile it.
+        let pipeline = create();
+
+        let mut pipelines = self.pipelines.lock().unwrap();
+
+        if self
+            .capacity
+            .is_some_and(|capacity| pipelines.len() == capacity)
+        {
+            pipelines.pop_front();
+        }
+        pipelines.push_back((key, pipeline.clone()));
+
+        pipeline
+    }
+}
+
+/// Shader modules already compiled on one VDevice, by a hash of their source, so that
+/// tasks built again from the same inline shader skip compiling it.
+#[derive(Debug, Default)]
+pub(crate) struct ModuleCache {
+    modules: Mutex<VecDeque<(u64, wgpu::ShaderModule)>>,
+}
+
+impl ModuleCache {
+    /// The module compiled from `descriptor`, compiling it if it hasn't been already.
+    pub(crate) fn module(
+        &self,
+        vd: &VDevice,
+        descriptor: &wgpu::ShaderModuleDescriptor,
+    ) -> wgpu::ShaderModule {
+        let Some(key) = source_hash(&descriptor.source) else {
+            return shader::create_module(vd, descriptor);
+        };
+
+        if let Some((_, module)) = self
+            .modules
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(cached, _)| *cached == key)
+        {
+            return module.clone();
+        }
+
+        // Compile without holding the lock, which can take a while.
+        let module = shader::create_module(vd, descriptor);
+
+        let mut modules = self.modules.lock().unwrap();
+
+        if modules.len() == MODULE_CAPACITY {
+            modules.pop_front();
+        }
+        modules.push_back((key, module.clone()));
+
+        module
+    }
+}
+
+/// A hash of everything in `source` that the compiled module depends on, or `None` for
+/// kinds of source that aren't cached.
+pub(crate) fn source_hash(source: &wgpu::ShaderSource) -> Option<u64> {
+    let mut hasher = DefaultHasher::new();
+
+    match source {
+        wgpu::ShaderSource::Wgsl(code) => {
+            "wgsl".hash(&mut hasher);
+            code.hash(&mut hasher);
+        }
+        #[cfg(feature = "spirv")]
+        wgpu::ShaderSource::SpirV(words) => {
+            "spirv".hash(&mut hasher);
+            words.hash(&mut hasher);
+        }
+        #[cfg(feature = "glsl")]
+        wgpu::ShaderSource::Glsl {
+            shader,
+            stage,
+            defines,
+        } => {
+            "glsl".hash(&mut hasher);
+            shader.hash(&mut hasher);
+            stage.hash(&mut hasher);
+            defines.hash(&mut hasher);
+        }
+        _ => return None,
+    }
+
+    Some(hasher.finish())
+}
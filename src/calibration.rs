@@ -0,0 +1,283 @@
+//! Weighting devices by how fast they measurably run a standard kernel, rather than by
+//! what their limits and types suggest.
+//!
+//! What a calibration measures can be kept in a profile file, by adapter and driver, so
+//! that later Workgroups over the same kinds of devices are weighted by it without
+//! measuring again. Workgroups only use a file when given one (see
+//! [`WorkgroupBuilder::calibration_file`](crate::workgroup::WorkgroupBuilder::calibration_file))
+//! or built with [`Weighting::Calibrated`](crate::workgroup::Weighting::Calibrated), which
+//! keeps it in the user's cache directory by default.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use web_time::Instant;
+
+use crate::error::WiscError;
+use crate::pipeline_cache::write_atomically;
+use crate::vdevice::VDevice;
+use crate::workgroup::Workgroup;
+
+// 4 MiB in each of saxpy's two buffers.
+const ELEMENTS: u32 = 1 << 20;
+const REPEATS: usize = 3;
+
+pub(crate) const SAXPY_WGSL: &str = "
+@group(0) @binding(0) var<storage, read> x: array<f32>;
+@group(0) @binding(1) var<storage, read_write> y: array<f32>;
+
+@compute @workgroup_size(256, 1, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x < arrayLength(&y)) {
+        y[id.x] = 2.0 * x[id.x] + y[id.x];
+    }
+}
+";
+
+/// What [`Workgroup::calibrate`] measured on one device.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceCalibration {
+    /// The device's index in the Workgroup.
+    pub device: usize,
+    pub label: String,
+    /// The fastest of the timed saxpy runs.
+    pub time: Duration,
+    /// What that run sustained, in GFLOP/s.
+    pub throughput: f64,
+    /// The weighting the device was given, its share of the total throughput.
+    pub weighting: f32,
+}
+
+/// What [`Workgroup::calibrate`] measured, device by device.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calibration {
+    pub devices: Vec<DeviceCalibration>,
+}
+
+impl Workgroup {
+    /// Times saxpy over a few megabytes on each device in turn, and weights the devices by
+    /// the throughput they sustained, replacing the estimate from their limits. Tasks
+    /// built afterwards divide their buffers accordingly. The devices keep their order.
+    ///
+    /// The throughputs are saved to the Workgroup's calibration file, if it has one, for
+    /// later Workgroups to load instead of calibrating. Failing to write it fails the
+    /// calibration, though the new weightings stay.
+    ///
+    /// Quicker than the `benchmarks` feature's suite of kernels, but still occupies every
+    /// device for a moment, so it belongs in a setup step. Fails, leaving the weightings
+    /// as they were, if a device fails to run the kernel.
+    pub fn calibrate(&mut self) -> Result<Calibration, WiscError> {
+        let bytes = ELEMENTS as u64 * 4;
+        let flops = 2.0 * ELEMENTS as f64;
+
+        let times = self
+            .vdevices
+            .iter()
+            .map(|vd| {
+                time_dispatch(
+                    vd,
+                    "saxpy",
+                    SAXPY_WGSL,
+                    (bytes, bytes),
+                    (ELEMENTS / 256, 1, 1),
+                )
+            })
+            .collect::<Result<Vec<Duration>, WiscError>>()?;
+
+        let throughputs: Vec<f64> = times
+            .iter()
+            .map(|time| flops / time.as_secs_f64().max(1e-9) / 1e9)
+            .collect();
+        let total: f64 = throughputs.iter().sum();
+
+        self.vdevice_weightings = throughputs
+            .iter()
+            .map(|throughput| (throughput / total) as f32)
+            .collect();
+
+        let devices = self
+            .vdevices
+            .iter()
+            .enumerate()
+            .map(|(vdi, vd)| DeviceCalibration {
+                device: vdi,
+                label: vd.label.clone(),
+                time: times[vdi],
+                throughput: throughputs[vdi],
+                weighting: self.vdevice_weightings[vdi],
+            })
+            .collect();
+
+        if let Some(path) = &self.calibration_file {
+            save_profiles(path, &self.vdevices, &throughputs)?;
+        }
+
+        Ok(Calibration { devices })
+    }
+}
+
+/// Where [`Weighting::Calibrated`](crate::workgroup::Weighting::Calibrated) keeps
+/// calibration profiles unless a Workgroup is given a file of its own: in the user's cache
+/// directory, if there is one.
+pub(crate) fn default_calibration_file() -> Option<PathBuf> {
+    let cache = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))?;
+
+    Some(cache.join("wisc").join("calibration"))
+}
+
+/// Identifies the kind of adapter and driver `vd` runs on, which its profile is kept under.
+fn profile_key(vd: &VDevice) -> String {
+    let info = &vd.info;
+
+    format!(
+        "{:?}/{:04x}/{:04x}/{}/{}",
+        info.backend, info.vendor, info.device, info.driver, info.driver_info
+    )
+    .replace(['\n', '\r'], " ")
+}
+
+/// The throughputs in the profile file at `path`, by profile key. A missing or unreadable
+/// file holds none; so do lines that don't parse.
+fn read_profiles(path: &Path) -> HashMap<String, f64> {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return HashMap::new();
+    };
+
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (throughput, key) = line.split_once('\t')?;
+            let throughput: f64 = throughput.parse().ok()?;
+
+            (throughput > 0.0).then(|| (key.to_string(), throughput))
+        })
+        .collect()
+}
+
+/// Records the `throughputs` measured on `vdevices` in the profile file at `path`, keeping
+/// what it holds for other adapters.
+fn save_profiles(path: &Path, vdevices: &[VDevice], throughputs: &[f64]) -> Result<(), WiscError> {
+    let mut profiles = read_profiles(path);
+    for (vd, &throughput) in vdevices.iter().zip(throughputs) {
+        profiles.insert(profile_key(vd), throughput);
+    }
+
+    let mut keys: Vec<&String> = profiles.keys().collect();
+    keys.sort();
+
+    let contents: String = keys
+        .into_iter()
+        .map(|key| format!("{}\t{key}\n", profiles[key]))
+        .collect();
+
+    write_atomically(path, contents.as_bytes())
+        .map_err(|error| WiscError::CacheFile(format!("{}: {error}", path.display())))
+}
+
+/// Weights for `vdevices` from the profile file at `path`, if it has a profile for every
+/// one of them. Throughputs measured on some devices can't be weighed against estimates
+/// for the others, so anything less is no use.
+pub(crate) fn load_weights(path: &Path, vdevices: &[VDevice]) -> Option<Vec<f32>> {
+    let profiles = read_profiles(path);
+
+    vdevices
+        .iter()
+        .map(|vd| {
+            profiles
+                .get(&profile_key(vd))
+                .map(|&throughput| throughput as f32)
+        })
+        .collect()
+}
+
+/// The fastest of a few dispatches of `workgroups` of `source`'s `main` on `vd`, after
+/// one to warm up. The kernel binds two storage buffers of the given sizes, in bytes.
+pub(crate) fn time_dispatch(
+    vd: &VDevice,
+    name: &str,
+    source: &'static str,
+    (input_bytes, output_bytes): (u64, u64),
+    (x, y, z): (u32, u32, u32),
+) -> Result<Duration, WiscError> {
+    vd.check_lost()?;
+
+    let module = vd.modules.module(
+        vd,
+        &wgpu::ShaderModuleDescriptor {
+            label: Some("WISC Benchmark"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        },
+    );
+
+    let pipeline = vd
+        .device
+        .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(name),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+    let buffer = |size| {
+        vd.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("WISC Benchmark (VDevice {})", vd.label)),
+            size,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        })
+    };
+    let (input, output) = (buffer(input_bytes), buffer(output_bytes));
+
+    let bind_group = vd.device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: None,
+        layout: &pipeline.get_bind_group_layout(0),
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: input.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: output.as_entire_binding(),
+            },
+        ],
+    });
+
+    let mut fastest = Duration::MAX;
+
+    for run in 0..=REPEATS {
+        let mut encoder = vd
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("WISC Benchmark"),
+            });
+
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: None,
+                timestamp_writes: None,
+            });
+
+            compute_pass.set_pipeline(&pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[]);
+            compute_pass.dispatch_workgroups(x, y, z);
+        }
+
+        let started = Instant::now();
+        vd.queue.submit([encoder.finish()]);
+        vd.wait()?;
+
+        if run > 0 {
+            fastest = fastest.min(started.elapsed());
+        }
+    }
+
+    Ok(fastest)
+}
@@ -0,0 +1,121 @@
+use crate::collective::{ReduceOp, Reducible};
+use crate::error::WiscError;
+use crate::prelude::Workgroup;
+use crate::report::RunReport;
+use crate::task::Task;
+use crate::workgroup::VBufferHandle;
+
+type TaskStep<'c> = Box<dyn for<'w> FnOnce(&'w mut Workgroup) -> Result<Task<'w>, WiscError> + 'c>;
+type CollectiveStep<'c> = Box<dyn FnOnce(&mut Workgroup) -> Result<(), WiscError> + 'c>;
+
+enum Step<'c> {
+    Task(TaskStep<'c>),
+    Collective(CollectiveStep<'c>),
+}
+
+/// A sequence of tasks and collectives run back to back on one Workgroup.
+///
+/// Each task step is a closure that builds its task from the workgroup, so later stages can
+/// depend on buffers produced by earlier ones.
+///
+/// ```no_run
+/// use wisc::prelude::*;
+/// use wisc::{chain::Chain, collective::ReduceOp};
+///
+/// let mut workgroup = Workgroup::from_devices(VDevice::all());
+/// let data = workgroup.create_vbuffer(vec![1.0f32; 1024]);
+/// let max = workgroup.create_vbuffer(vec![0.0f32; 4]);
+/// let out = workgroup.create_vbuffer(vec![0.0f32; 1024]);
+///
+/// Chain::new(&mut workgroup)
+///     .then(move |wg| {
+///         TaskBuilder::new(wg, include_wgsl!("../tests/partial_max.wgsl"))
+///             .with_kernel("main")
+///             .with_size((1, 1, 1))
+///             .with_input_buffer(0, data)
+///             .with_output_buffer(1, max)
+///             .build()
+///     })
+///     .reduce_broadcast::<f32>(max, ReduceOp::Max)
+///     .then(move |wg| {
+///         TaskBuilder::new(wg, include_wgsl!("../tests/normalize.wgsl"))
+///             .with_kernel("main")
+///             .with_size((4, 1, 1))
+///             .with_input_buffer(0, data)
+///             .with_uniform_input(1, max)
+///             .with_output_buffer(2, out)
+///             .build()
+///     })
+///     .run()
+///     .expect("Chain failed");
+/// ```
+pub struct Chain<'c> {
+    workgroup: &'c mut Workgroup,
+    steps: Vec<Step<'c>>,
+}
+
+impl<'c> Chain<'c> {
+    pub fn new(workgroup: &'c mut Workgroup) -> Self {
+        Self {
+            workgroup,
+            steps: vec![],
+        }
+    }
+
+    /// Appends a task, built when the chain reaches it.
+    pub fn then<F>(mut self, task: F) -> Self
+    where
+        F: for<'w> FnOnce(&'w mut Workgroup) -> Result<Task<'w>, WiscError> + 'c,
+    {
+        self.steps.push(Step::Task(Box::new(task)));
+
+        self
+    }
+
+    /// Appends a [`Workgroup::reduce_broadcast`] of `buffer_handle` across the devices.
+    pub fn reduce_broadcast<T: Reducible>(
+        mut self,
+        buffer_handle: VBufferHandle,
+        op: ReduceOp,
+    ) -> Self {
+        self.steps.push(Step::Collective(Box::new(move |wg| {
+            wg.reduce_broadcast::<T>(buffer_handle, op).map(|_| ())
+        })));
+
+        self
+    }
+
+    /// Appends a [`Workgroup::all_gather`] of `buffer_handle` across the devices.
+    pub fn all_gather(mut self, buffer_handle: VBufferHandle) -> Self {
+        self.steps.push(Step::Collective(Box::new(move |wg| {
+            wg.all_gather(buffer_handle).map(|_| ())
+        })));
+
+        self
+    }
+
+    /// Appends a [`Workgroup::halo_exchange`] of `buffer_handle`, typically between the
+    /// iterations of a stencil.
+    pub fn halo_exchange(mut self, buffer_handle: VBufferHandle) -> Self {
+        self.steps.push(Step::Collective(Box::new(move |wg| {
+            wg.halo_exchange(buffer_handle).map(|_| ())
+        })));
+
+        self
+    }
+
+    /// Runs every step in order, returning the reports of the task steps. Stops at the
+    /// first task or collective that fails, returning its error.
+    pub fn run(self) -> Result<Vec<RunReport>, WiscError> {
+        let mut reports = Vec::new();
+
+        for step in self.steps {
+            match step {
+                Step::Task(build) => reports.push(build(self.workgroup)?.run()?),
+                Step::Collective(collective) => collective(self.workgroup)?,
+            }
+        }
+
+        Ok(reports)
+    }
+}
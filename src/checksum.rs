@@ -0,0 +1,117 @@
+use crate::vdevice::VDevice;
+
+// Every invocation folds a strided share of the words into a position-weighted sum, so
+// both flipped and misplaced words change the result, then adds it to the totals.
+const CHECKSUM_WGSL: &str = "
+@group(0) @binding(0) var<storage, read> words: array<u32>;
+@group(0) @binding(1) var<storage, read_write> sums: array<atomic<u32>, 2>;
+
+@compute @workgroup_size(256, 1, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>, @builtin(num_workgroups) n: vec3<u32>) {
+    let stride = n.x * 256u;
+    var plain = 0u;
+    var weighted = 0u;
+
+    for (var i = id.x; i < arrayLength(&words); i += stride) {
+        plain += words[i];
+        weighted += words[i] * (i + 1u);
+    }
+
+    atomicAdd(&sums[0], plain);
+    atomicAdd(&sums[1], weighted);
+}
+";
+
+const WORKGROUPS: u32 = 64;
+
+/// The size of a checksum, as the device writes it.
+pub(crate) const CHECKSUM_SIZE: u64 = 2 * std::mem::size_of::<u32>() as u64;
+
+/// A checksum computed on the host, the same way the checksum kernel computes it on the
+/// device.
+pub(crate) fn checksum(bytes: &[u8]) -> [u32; 2] {
+    bytes
+        .chunks_exact(4)
+        .enumerate()
+        .fold([0u32; 2], |[plain, weighted], (i, word)| {
+            let word = u32::from_le_bytes(word.try_into().unwrap());
+
+            [
+                plain.wrapping_add(word),
+                weighted.wrapping_add(word.wrapping_mul(i as u32 + 1)),
+            ]
+        })
+}
+
+/// Records a command buffer that checksums each of `buffers` into the matching entry of
+/// `checksums`, and copies those into `staging` for readback if they can't be mapped
+/// directly.
+pub(crate) fn encode(
+    vd: &VDevice,
+    buffers: &[wgpu::Buffer],
+    checksums: &[wgpu::Buffer],
+    staging: &[wgpu::Buffer],
+) -> wgpu::CommandBuffer {
+    let module = vd
+        .device
+        .create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("WISC Checksum"),
+            source: wgpu::ShaderSource::Wgsl(CHECKSUM_WGSL.into()),
+        });
+
+    let pipeline = vd
+        .device
+        .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("WISC Checksum"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+    let mut encoder = vd
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("WISC Checksum"),
+        });
+
+    // The checksums are summed into, so they start from zero every run.
+    for checksum in checksums {
+        encoder.clear_buffer(checksum, 0, None);
+    }
+
+    for (buffer, checksum) in buffers.iter().zip(checksums) {
+        let bind_group = vd.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: checksum.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: None,
+            timestamp_writes: None,
+        });
+
+        compute_pass.set_pipeline(&pipeline);
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.dispatch_workgroups(WORKGROUPS, 1, 1);
+    }
+
+    for (checksum, staging) in checksums.iter().zip(staging) {
+        if checksum != staging {
+            encoder.copy_buffer_to_buffer(checksum, 0, staging, 0, CHECKSUM_SIZE);
+        }
+    }
+
+    encoder.finish()
+}
@@ -0,0 +1,356 @@
+use std::any::TypeId;
+use std::ops::Range;
+
+use bytemuck::Pod;
+use wgpu::util::DeviceExt;
+
+use crate::error::WiscError;
+use crate::prelude::Workgroup;
+use crate::task::{vbuffer_bytes, vbuffer_write};
+use crate::vbuffer::{Residency, Resident};
+use crate::workgroup::VBufferHandle;
+
+/// How per-device values are combined by a collective.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReduceOp {
+    Sum,
+    Product,
+    Min,
+    Max,
+}
+
+/// An element type that collectives know how to combine.
+pub trait Reducible: Pod {
+    fn reduce(self, other: Self, op: ReduceOp) -> Self;
+}
+
+// Integers wrap on overflow, as the kernels doing the same reductions on devices do.
+macro_rules! reducible {
+    (integer: $($t:ty),*) => {
+        $(reducible!(@impl $t, <$t>::wrapping_add, <$t>::wrapping_mul);)*
+    };
+    (float: $($t:ty),*) => {
+        $(reducible!(@impl $t, std::ops::Add::add, std::ops::Mul::mul);)*
+    };
+    (@impl $t:ty, $add:expr, $mul:expr) => {
+        impl Reducible for $t {
+            fn reduce(self, other: Self, op: ReduceOp) -> Self {
+                match op {
+                    ReduceOp::Sum => $add(self, other),
+                    ReduceOp::Product => $mul(self, other),
+                    ReduceOp::Min => if other < self { other } else { self },
+                    ReduceOp::Max => if other > self { other } else { self },
+                }
+            }
+        }
+    };
+}
+
+reducible!(integer: u32, i32, u64, i64);
+reducible!(float: f32, f64);
+
+/// How the copies of a replicated (unmanaged) output, one from each device, are combined
+/// into the host copy when a task runs.
+pub enum Merge<T> {
+    /// Keep the first (strongest) device's copy.
+    OverwriteFirst,
+    ReduceAdd,
+    ReduceMin,
+    ReduceMax,
+    /// Combine the copies element by element, folding each device's value into the running
+    /// result in device order.
+    Custom(Box<dyn Fn(T, T) -> T + Send + Sync>),
+}
+
+/// What a [`Merge`] does, for telling runs apart in result cache fingerprints. Custom
+/// merges can't be told apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum MergeKey {
+    OverwriteFirst,
+    Reduce(ReduceOp),
+    Custom,
+}
+
+/// A [`Merge`] with its element type erased, applied to the bytes of a running result and
+/// of the next device's copy.
+pub(crate) type Merger = Box<dyn Fn(&mut [u8], &[u8]) + Send + Sync>;
+
+impl<T: Reducible> Merge<T> {
+    pub(crate) fn key(&self) -> MergeKey {
+        match self {
+            Merge::OverwriteFirst => MergeKey::OverwriteFirst,
+            Merge::ReduceAdd => MergeKey::Reduce(ReduceOp::Sum),
+            Merge::ReduceMin => MergeKey::Reduce(ReduceOp::Min),
+            Merge::ReduceMax => MergeKey::Reduce(ReduceOp::Max),
+            Merge::Custom(_) => MergeKey::Custom,
+        }
+    }
+
+    pub(crate) fn into_merger(self) -> Merger {
+        match self {
+            Merge::OverwriteFirst => Box::new(|_, _| {}),
+            Merge::ReduceAdd => reduce_merger::<T>(ReduceOp::Sum),
+            Merge::ReduceMin => reduce_merger::<T>(ReduceOp::Min),
+            Merge::ReduceMax => reduce_merger::<T>(ReduceOp::Max),
+            Merge::Custom(combine) => elementwise_merger(combine),
+        }
+    }
+}
+
+pub(crate) fn reduce_merger<T: Reducible>(op: ReduceOp) -> Merger {
+    elementwise_merger(Box::new(move |a: T, b| a.reduce(b, op)))
+}
+
+fn elementwise_merger<T: Pod>(combine: Box<dyn Fn(T, T) -> T + Send + Sync>) -> Merger {
+    let stride = std::mem::size_of::<T>();
+
+    Box::new(move |acc, next| {
+        // Mapped device memory carries no alignment guarantee for T.
+        for (a, b) in acc.chunks_exact_mut(stride).zip(next.chunks_exact(stride)) {
+            let merged = combine(
+                bytemuck::pod_read_unaligned(a),
+                bytemuck::pod_read_unaligned(b),
+            );
+            a.copy_from_slice(bytemuck::bytes_of(&merged));
+        }
+    })
+}
+
+impl Workgroup {
+    /// Combines every device's copy of `buffer_handle`, as written by the last task that
+    /// output it, element by element with `op`. The combined result replaces the host copy
+    /// and is uploaded to every device, aliased so that the next task binds it directly
+    /// (as a storage or uniform input) without another upload.
+    ///
+    /// This is the usual way to turn per-device partial results, such as a local maximum,
+    /// into a global value for the next pass. Fails if no task has written the buffer yet,
+    /// `T` is not its element type, or a device holds only part of it.
+    pub fn reduce_broadcast<T: Reducible>(
+        &mut self,
+        buffer_handle: VBufferHandle,
+        op: ReduceOp,
+    ) -> Result<VBufferHandle, WiscError> {
+        let vbuffer = self
+            .vbuffers
+            .get(buffer_handle)
+            .ok_or(WiscError::UnknownVBuffer)?;
+        let resident = vbuffer
+            .residency
+            .resident()
+            .ok_or(WiscError::Uninitialized)?;
+
+        if vbuffer.typeid != TypeId::of::<T>() {
+            return Err(WiscError::TypeMismatch);
+        }
+
+        if resident
+            .ranges
+            .iter()
+            .any(|range| *range != (0..vbuffer.length))
+        {
+            return Err(WiscError::InvalidPartition(
+                "every device must hold the whole buffer to reduce it",
+            ));
+        }
+
+        let mut combined: Option<Vec<T>> = None;
+
+        for (vd, buffer) in self.vdevices.iter().zip(resident.buffers.iter()) {
+            let bytes = vd.read_buffer(buffer)?;
+            let partial: Vec<T> =
+                bytemuck::pod_collect_to_vec(&bytes[..vbuffer.length * vbuffer.stride]);
+
+            combined = Some(match combined {
+                None => partial,
+                Some(acc) => acc
+                    .into_iter()
+                    .zip(partial)
+                    .map(|(a, b)| a.reduce(b, op))
+                    .collect(),
+            });
+        }
+
+        let vbuffer = self
+            .vbuffers
+            .get_mut(buffer_handle)
+            .ok_or(WiscError::UnknownVBuffer)?;
+        let host = vbuffer
+            .inner
+            .downcast_mut::<Vec<T>>()
+            .ok_or(WiscError::TypeMismatch)?;
+
+        // A workgroup without devices has nothing to combine.
+        if let Some(combined) = combined {
+            *host = combined;
+            vbuffer.assume_init = None;
+            vbuffer.pending_readback = false;
+        }
+
+        self.broadcast_host_copy(buffer_handle)
+    }
+
+    /// Gives every device the whole of `buffer_handle` when each device only wrote its own
+    /// partition of it, so the next task can read any element on any device.
+    ///
+    /// Each device's partition is copied back to the host according to the partition plan
+    /// of the task that wrote it, and the assembled buffer is uploaded to every device,
+    /// aliased for the next task. Fails if no task has written the buffer yet.
+    pub fn all_gather(&mut self, buffer_handle: VBufferHandle) -> Result<VBufferHandle, WiscError> {
+        let vbuffer = self
+            .vbuffers
+            .get_mut(buffer_handle)
+            .ok_or(WiscError::UnknownVBuffer)?;
+        let resident = vbuffer
+            .residency
+            .resident()
+            .ok_or(WiscError::Uninitialized)?;
+
+        let stride = vbuffer.stride;
+
+        // Only each device's owned elements are authoritative; its halo may be stale.
+        let partitions: Vec<(Range<usize>, Vec<u8>)> = self
+            .vdevices
+            .iter()
+            .enumerate()
+            .map(|(vdi, vd)| {
+                let (held, owned) = (&resident.ranges[vdi], &resident.owned[vdi]);
+                let offset = (owned.start - held.start) * stride;
+                let bytes = vd.read_buffer_range(
+                    &resident.buffers[vdi],
+                    offset..offset + owned.len() * stride,
+                )?;
+
+                Ok((owned.clone(), bytes))
+            })
+            .collect::<Result<_, WiscError>>()?;
+
+        for (owned, bytes) in partitions {
+            vbuffer_write(vbuffer, owned.start * stride, &bytes);
+        }
+
+        // What the devices own covers a buffer whose results were left on them.
+        if vbuffer.pending_readback {
+            if let Some(assume_init) = vbuffer.assume_init.take() {
+                assume_init(vbuffer.inner.as_mut(), vbuffer.length);
+            }
+            vbuffer.pending_readback = false;
+        }
+
+        self.broadcast_host_copy(buffer_handle)
+    }
+
+    /// Refreshes the halo of every device's copy of `buffer_handle` from the neighbours that
+    /// own those elements, then aliases the copies for the next task.
+    ///
+    /// Multi-step stencils over overlapping partitions call this between iterations instead
+    /// of gathering and re-scattering the whole field. Only the halo regions travel, staged
+    /// through the host. Fails if no task has written the buffer yet or a halo region is
+    /// not four-byte aligned, as device copies require.
+    pub fn halo_exchange(
+        &mut self,
+        buffer_handle: VBufferHandle,
+    ) -> Result<VBufferHandle, WiscError> {
+        let vbuffer = self
+            .vbuffers
+            .get_mut(buffer_handle)
+            .ok_or(WiscError::UnknownVBuffer)?;
+        let resident = vbuffer
+            .residency
+            .resident()
+            .ok_or(WiscError::Uninitialized)?;
+        let stride = vbuffer.stride;
+
+        // (source device, destination device, elements) for every halo region.
+        let mut transfers: Vec<(usize, usize, Range<usize>)> = Vec::new();
+
+        for (dst, held) in resident.ranges.iter().enumerate() {
+            for (src, owned) in resident.owned.iter().enumerate() {
+                if src == dst {
+                    continue;
+                }
+
+                let start = held.start.max(owned.start);
+                let end = held.end.min(owned.end);
+
+                // Elements the destination owns itself never need refreshing.
+                let own = &resident.owned[dst];
+                if start < end && (end <= own.start || start >= own.end) {
+                    transfers.push((src, dst, start..end));
+                }
+            }
+        }
+
+        let aligned = |n: usize| (n * stride).is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT as usize);
+        if transfers.iter().any(|(src, dst, region)| {
+            !aligned(region.start - resident.ranges[*src].start)
+                || !aligned(region.start - resident.ranges[*dst].start)
+                || !aligned(region.len())
+        }) {
+            return Err(WiscError::InvalidPartition(
+                "halo regions must be four-byte aligned",
+            ));
+        }
+
+        for (src, dst, region) in transfers {
+            let src_offset = (region.start - resident.ranges[src].start) * stride;
+            let dst_offset = (region.start - resident.ranges[dst].start) * stride;
+
+            let bytes = self.vdevices[src].read_buffer_range(
+                &resident.buffers[src],
+                src_offset..src_offset + region.len() * stride,
+            )?;
+
+            self.vdevices[dst].queue.write_buffer(
+                &resident.buffers[dst],
+                dst_offset as wgpu::BufferAddress,
+                &bytes,
+            );
+        }
+
+        vbuffer.residency = match std::mem::replace(&mut vbuffer.residency, Residency::Host) {
+            Residency::Retained(resident)
+            | Residency::Aliased(resident)
+            | Residency::Uploaded(resident) => Residency::Aliased(resident),
+            Residency::Host => unreachable!(),
+        };
+
+        Ok(buffer_handle)
+    }
+
+    // Uploads the host copy of a buffer to every device and aliases the uploads.
+    pub(crate) fn broadcast_host_copy(
+        &mut self,
+        buffer_handle: VBufferHandle,
+    ) -> Result<VBufferHandle, WiscError> {
+        let vbuffer = self
+            .vbuffers
+            .get_mut(buffer_handle)
+            .ok_or(WiscError::UnknownVBuffer)?;
+        let bytes = vbuffer_bytes(vbuffer);
+
+        let buffers = self
+            .vdevices
+            .iter()
+            .map(|vd| {
+                vd.device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some(&format!("WISC Broadcast Buffer (VDevice {})", vd.label)),
+                        contents: bytes,
+                        usage: wgpu::BufferUsages::STORAGE
+                            | wgpu::BufferUsages::UNIFORM
+                            | wgpu::BufferUsages::INDIRECT
+                            | wgpu::BufferUsages::COPY_SRC
+                            | wgpu::BufferUsages::COPY_DST,
+                    })
+            })
+            .collect();
+
+        vbuffer.residency = Residency::Aliased(Resident {
+            buffers,
+            ranges: vec![0..vbuffer.length; self.vdevices.len()],
+            owned: vec![0..vbuffer.length; self.vdevices.len()],
+        });
+
+        Ok(buffer_handle)
+    }
+}
@@ -0,0 +1,144 @@
+//! Complex numbers stored as interleaved real and imaginary parts, which shaders see as
+//! `vec2<f32>` (or `vec2<f64>`), `x` being the real part.
+
+use std::ops::{Add, Mul, Neg, Sub};
+
+use bytemuck::{Pod, Zeroable};
+
+/// WGSL helpers for complex arithmetic on `vec2<f32>`, to paste into kernels ahead of the
+/// code that uses them.
+pub const COMPLEX32_WGSL: &str = "\
+fn complex_mul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+fn complex_conj(a: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(a.x, -a.y);
+}
+
+fn complex_div(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return complex_mul(a, complex_conj(b)) / dot(b, b);
+}
+
+fn complex_abs(a: vec2<f32>) -> f32 {
+    return length(a);
+}
+
+fn complex_arg(a: vec2<f32>) -> f32 {
+    return atan2(a.y, a.x);
+}
+
+fn complex_from_polar(r: f32, theta: f32) -> vec2<f32> {
+    return r * vec2<f32>(cos(theta), sin(theta));
+}
+
+fn complex_exp(a: vec2<f32>) -> vec2<f32> {
+    return complex_from_polar(exp(a.x), a.y);
+}
+";
+
+/// WGSL helpers for complex arithmetic on `vec2<f64>`. Shaders using them need
+/// [`SHADER_F64`](wgpu::Features::SHADER_F64), and since most backends have no
+/// double-precision transcendentals, only the arithmetic is provided.
+pub const COMPLEX64_WGSL: &str = "\
+fn complex64_mul(a: vec2<f64>, b: vec2<f64>) -> vec2<f64> {
+    return vec2<f64>(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+fn complex64_conj(a: vec2<f64>) -> vec2<f64> {
+    return vec2<f64>(a.x, -a.y);
+}
+
+fn complex64_div(a: vec2<f64>, b: vec2<f64>) -> vec2<f64> {
+    return complex64_mul(a, complex64_conj(b)) / dot(b, b);
+}
+
+fn complex64_norm(a: vec2<f64>) -> f64 {
+    return dot(a, a);
+}
+";
+
+macro_rules! complex {
+    ($name:ident, $t:ty) => {
+        #[repr(C)]
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Pod, Zeroable)]
+        pub struct $name {
+            pub re: $t,
+            pub im: $t,
+        }
+
+        impl $name {
+            pub const fn new(re: $t, im: $t) -> Self {
+                Self { re, im }
+            }
+
+            /// `r * e^(i * theta)`.
+            pub fn from_polar(r: $t, theta: $t) -> Self {
+                Self::new(r * theta.cos(), r * theta.sin())
+            }
+
+            pub fn conj(self) -> Self {
+                Self::new(self.re, -self.im)
+            }
+
+            /// The squared magnitude.
+            pub fn norm(self) -> $t {
+                self.re * self.re + self.im * self.im
+            }
+
+            pub fn abs(self) -> $t {
+                self.re.hypot(self.im)
+            }
+
+            pub fn arg(self) -> $t {
+                self.im.atan2(self.re)
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+
+            fn add(self, other: Self) -> Self {
+                Self::new(self.re + other.re, self.im + other.im)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+
+            fn sub(self, other: Self) -> Self {
+                Self::new(self.re - other.re, self.im - other.im)
+            }
+        }
+
+        impl Mul for $name {
+            type Output = Self;
+
+            fn mul(self, other: Self) -> Self {
+                Self::new(
+                    self.re * other.re - self.im * other.im,
+                    self.re * other.im + self.im * other.re,
+                )
+            }
+        }
+
+        impl Mul<$t> for $name {
+            type Output = Self;
+
+            fn mul(self, scale: $t) -> Self {
+                Self::new(self.re * scale, self.im * scale)
+            }
+        }
+
+        impl Neg for $name {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self::new(-self.re, -self.im)
+            }
+        }
+    };
+}
+
+complex!(Complex32, f32);
+complex!(Complex64, f64);
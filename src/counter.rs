@@ -0,0 +1,84 @@
+use crate::vdevice::VDevice;
+
+/// A `u32` on one device that a kernel counts its results in, usually with `atomicAdd`,
+/// starting from zero. It is read back through a staging buffer with the task's results.
+pub(crate) struct DeviceCounter {
+    pub(crate) buffer: wgpu::Buffer,
+    staging: wgpu::Buffer,
+}
+
+impl DeviceCounter {
+    /// A zeroed counter for binding `id`.
+    pub(crate) fn new(vd: &VDevice, id: u32) -> Self {
+        let buffer = vd.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("WISC Counter {} (VDevice {})", id, vd.label)),
+            size: 4,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let staging = vd.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!(
+                "WISC Counter Staging {} (VDevice {})",
+                id, vd.label
+            )),
+            size: 4,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self { buffer, staging }
+    }
+
+    /// The buffer to map once the readback has been submitted.
+    pub(crate) fn staging(&self) -> &wgpu::Buffer {
+        &self.staging
+    }
+
+    /// The count, once the staging buffer is mapped. Unmaps it.
+    pub(crate) fn read(&self) -> usize {
+        let count: u32 = bytemuck::pod_read_unaligned(&self.staging.slice(..).get_mapped_range());
+        self.staging.unmap();
+
+        count as usize
+    }
+}
+
+/// Records zeroing each of `counters`, or nothing if there are none.
+pub(crate) fn encode_reset<'c>(
+    vd: &VDevice,
+    counters: impl IntoIterator<Item = &'c DeviceCounter>,
+) -> Option<wgpu::CommandBuffer> {
+    let mut counters = counters.into_iter().peekable();
+    counters.peek()?;
+
+    let mut encoder = vd
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+    for counter in counters {
+        encoder.clear_buffer(&counter.buffer, 0, None);
+    }
+
+    Some(encoder.finish())
+}
+
+/// Records copying each of `counters` to its staging buffer, or nothing if there are none.
+pub(crate) fn encode_readback<'c>(
+    vd: &VDevice,
+    counters: impl IntoIterator<Item = &'c DeviceCounter>,
+) -> Option<wgpu::CommandBuffer> {
+    let mut counters = counters.into_iter().peekable();
+    counters.peek()?;
+
+    let mut encoder = vd
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+    for counter in counters {
+        encoder.copy_buffer_to_buffer(&counter.buffer, 0, &counter.staging, 0, 4);
+    }
+
+    Some(encoder.finish())
+}
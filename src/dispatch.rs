@@ -0,0 +1,86 @@
+use crate::error::WiscError;
+use crate::workgroup::VBufferHandle;
+
+/// How many workgroups a task dispatches on each device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DispatchSize {
+    /// Exactly this many workgroups in each dimension, on every device.
+    Exact(u32, u32, u32),
+    /// The fewest workgroups for `count` elements, each invocation handling
+    /// `per_invocation` of them. Needs the kernel's workgroup size, so the shader must be
+    /// reflectable (WGSL) and its workgroup size must not depend on overrides.
+    ///
+    /// The workgroups are laid out along x, spilling over into y and then z only when
+    /// there are more than a device allows along one dimension. Kernels that may see that
+    /// many should index by `workgroup_id` and `num_workgroups` rather than by
+    /// `global_invocation_id.x` alone.
+    ForElements { count: usize, per_invocation: usize },
+    /// One invocation per element of a VBuffer. If the buffer is bound to the task, each
+    /// device covers only the elements it holds; otherwise, the whole buffer.
+    PerBuffer(VBufferHandle),
+    /// Read the workgroup counts on the device from a VBuffer holding three `u32`s, which
+    /// may have been written by an earlier task.
+    FromIndirect(VBufferHandle),
+}
+
+impl From<(u32, u32, u32)> for DispatchSize {
+    fn from((x, y, z): (u32, u32, u32)) -> Self {
+        DispatchSize::Exact(x, y, z)
+    }
+}
+
+impl DispatchSize {
+    /// Fails if the size is exact and any dimension of it is zero.
+    pub(crate) fn checked(self) -> Result<Self, WiscError> {
+        match self {
+            DispatchSize::Exact(x, y, z) if x == 0 || y == 0 || z == 0 => Err(
+                WiscError::InvalidDispatch("every dimension of the dispatch must be at least one"),
+            ),
+            size => Ok(size),
+        }
+    }
+}
+
+/// A [`DispatchSize`] resolved for one device.
+pub(crate) enum Dispatch {
+    Direct(u32, u32, u32),
+    Indirect(wgpu::Buffer),
+}
+
+impl Dispatch {
+    pub(crate) fn record(&self, compute_pass: &mut wgpu::ComputePass) {
+        match self {
+            Dispatch::Direct(x, y, z) => compute_pass.dispatch_workgroups(*x, *y, *z),
+            Dispatch::Indirect(buffer) => compute_pass.dispatch_workgroups_indirect(buffer, 0),
+        }
+    }
+}
+
+/// The workgroups along x needed for `elements` elements.
+pub(crate) fn workgroups_for(
+    elements: usize,
+    per_invocation: usize,
+    workgroup_size: [u32; 3],
+) -> Option<u32> {
+    let invocations = elements.div_ceil(per_invocation.max(1));
+    let per_workgroup = workgroup_size.iter().product::<u32>() as usize;
+
+    if per_workgroup == 0 {
+        return None;
+    }
+
+    u32::try_from(invocations.div_ceil(per_workgroup)).ok()
+}
+
+/// The smallest `(x, y, z)` grid of at least `workgroups` workgroups with no more than
+/// `limit` along any dimension, filling x first.
+pub(crate) fn spread(workgroups: u32, limit: u32) -> Option<(u32, u32, u32)> {
+    let (workgroups, limit) = (workgroups as u64, limit.max(1) as u64);
+
+    let z = workgroups.div_ceil(limit * limit).max(1);
+    let per_layer = workgroups.div_ceil(z);
+    let y = per_layer.div_ceil(limit).max(1);
+    let x = per_layer.div_ceil(y);
+
+    (z <= limit).then_some((x as u32, y as u32, z as u32))
+}
@@ -0,0 +1,245 @@
+//! Host tensors in the [DLPack](https://dmlc.github.io/dlpack/latest/) layout, so that
+//! Python ML frameworks (NumPy, PyTorch, JAX and so on) can use a task's results without
+//! copying them.
+//!
+//! This module stops at the C structs: wisc has no Python bindings, and so creates no
+//! capsules itself. Bindings built on it (with PyO3, say) wrap
+//! [`DLPackTensor::into_raw`] in a capsule named [`CAPSULE_NAME`] for `from_dlpack` to
+//! consume. A consumer renames the capsule [`USED_CAPSULE_NAME`] and takes over calling
+//! the deleter, so the capsule's destructor only calls it while the capsule still has its
+//! first name.
+
+use std::ffi::{CStr, c_void};
+use std::ptr::NonNull;
+
+use bytemuck::Pod;
+
+use crate::complex::{Complex32, Complex64};
+use crate::error::WiscError;
+use crate::workgroup::{VBufferHandle, Workgroup};
+
+/// `kDLCPU`: the memory is ordinary host memory.
+const DEVICE_CPU: i32 = 1;
+
+/// The name of a capsule holding a [`DLManagedTensor`] no consumer has taken yet.
+pub const CAPSULE_NAME: &CStr = c"dltensor";
+
+/// The name a consumer gives a capsule once it has taken the tensor, and with it the duty
+/// to call the deleter.
+pub const USED_CAPSULE_NAME: &CStr = c"used_dltensor";
+
+/// Where a tensor's memory lives.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DLDevice {
+    pub device_type: i32,
+    pub device_id: i32,
+}
+
+/// The type of a tensor's elements: a type code (`kDLInt` and so on), its size in bits, and
+/// the number of lanes of a vector element.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DLDataType {
+    pub code: u8,
+    pub bits: u8,
+    pub lanes: u16,
+}
+
+impl DLDataType {
+    pub const INT: u8 = 0;
+    pub const UINT: u8 = 1;
+    pub const FLOAT: u8 = 2;
+    pub const COMPLEX: u8 = 5;
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct DLTensor {
+    pub data: *mut c_void,
+    pub device: DLDevice,
+    pub ndim: i32,
+    pub dtype: DLDataType,
+    pub shape: *mut i64,
+    /// Null, since tensors are always compact and row-major.
+    pub strides: *mut i64,
+    pub byte_offset: u64,
+}
+
+/// A tensor along with what frees it. Whoever ends up holding it calls `deleter` once, with
+/// the tensor itself, when done with it.
+#[repr(C)]
+#[derive(Debug)]
+pub struct DLManagedTensor {
+    pub dl_tensor: DLTensor,
+    pub manager_ctx: *mut c_void,
+    pub deleter: Option<unsafe extern "C" fn(*mut DLManagedTensor)>,
+}
+
+/// An element type with a DLPack equivalent.
+pub trait DLPackElement: Pod {
+    const DTYPE: DLDataType;
+}
+
+macro_rules! dlpack_element {
+    ($($t:ty => $code:expr),*) => {
+        $(
+            impl DLPackElement for $t {
+                const DTYPE: DLDataType = DLDataType {
+                    code: $code,
+                    bits: (std::mem::size_of::<$t>() * 8) as u8,
+                    lanes: 1,
+                };
+            }
+        )*
+    };
+}
+
+dlpack_element!(
+    u8 => DLDataType::UINT,
+    u16 => DLDataType::UINT,
+    u32 => DLDataType::UINT,
+    u64 => DLDataType::UINT,
+    i8 => DLDataType::INT,
+    i16 => DLDataType::INT,
+    i32 => DLDataType::INT,
+    i64 => DLDataType::INT,
+    f32 => DLDataType::FLOAT,
+    f64 => DLDataType::FLOAT,
+    Complex32 => DLDataType::COMPLEX,
+    Complex64 => DLDataType::COMPLEX
+);
+
+/// What a tensor's memory and shape belong to until the deleter is called.
+struct Context<T> {
+    data: Vec<T>,
+    shape: Vec<i64>,
+}
+
+unsafe extern "C" fn delete<T>(managed: *mut DLManagedTensor) {
+    // SAFETY: both boxes were leaked by `DLPackTensor::new`, and the deleter runs once.
+    unsafe {
+        let managed = Box::from_raw(managed);
+        drop(Box::from_raw(managed.manager_ctx as *mut Context<T>));
+    }
+}
+
+/// A VBuffer's contents taken out of the runtime as a DLPack tensor, which frees them when
+/// dropped unless handed off with [`into_raw`](Self::into_raw).
+#[derive(Debug)]
+pub struct DLPackTensor {
+    managed: NonNull<DLManagedTensor>,
+}
+
+// SAFETY: the tensor owns its memory, and only reads it through `&self`.
+unsafe impl Send for DLPackTensor {}
+unsafe impl Sync for DLPackTensor {}
+
+impl DLPackTensor {
+    fn new<T: DLPackElement>(data: Vec<T>, shape: &[usize]) -> Self {
+        let mut context = Box::new(Context {
+            data,
+            shape: shape.iter().map(|dim| *dim as i64).collect(),
+        });
+
+        let dl_tensor = DLTensor {
+            data: context.data.as_mut_ptr() as *mut c_void,
+            device: DLDevice {
+                device_type: DEVICE_CPU,
+                device_id: 0,
+            },
+            ndim: shape.len() as i32,
+            dtype: T::DTYPE,
+            shape: context.shape.as_mut_ptr(),
+            strides: std::ptr::null_mut(),
+            byte_offset: 0,
+        };
+
+        let managed = Box::new(DLManagedTensor {
+            dl_tensor,
+            manager_ctx: Box::into_raw(context) as *mut c_void,
+            deleter: Some(delete::<T>),
+        });
+
+        Self {
+            managed: NonNull::from(Box::leak(managed)),
+        }
+    }
+
+    pub fn tensor(&self) -> &DLTensor {
+        // SAFETY: the tensor stays alive until `self` is dropped or handed off.
+        unsafe { &self.managed.as_ref().dl_tensor }
+    }
+
+    pub fn shape(&self) -> Vec<usize> {
+        let tensor = self.tensor();
+
+        // SAFETY: the shape holds `ndim` dimensions.
+        unsafe { std::slice::from_raw_parts(tensor.shape, tensor.ndim as usize) }
+            .iter()
+            .map(|dim| *dim as usize)
+            .collect()
+    }
+
+    /// The elements' bytes, in row-major order.
+    pub fn as_bytes(&self) -> &[u8] {
+        let tensor = self.tensor();
+        let elements: usize = self.shape().iter().product();
+        let len = elements * tensor.dtype.bits as usize / 8;
+
+        if len == 0 {
+            return &[];
+        }
+
+        // SAFETY: the data holds every element of the shape.
+        unsafe { std::slice::from_raw_parts(tensor.data as *const u8, len) }
+    }
+
+    /// Hands the tensor off, to a consumer that calls its deleter once done with it.
+    pub fn into_raw(self) -> *mut DLManagedTensor {
+        let managed = self.managed.as_ptr();
+        std::mem::forget(self);
+
+        managed
+    }
+}
+
+impl Drop for DLPackTensor {
+    fn drop(&mut self) {
+        let managed = self.managed.as_ptr();
+
+        // SAFETY: the tensor hasn't been handed off, so nothing else frees it.
+        unsafe {
+            if let Some(deleter) = (*managed).deleter {
+                deleter(managed);
+            }
+        }
+    }
+}
+
+impl Workgroup {
+    /// Takes the buffer's host contents out of the runtime as a row-major tensor of
+    /// `shape`, without copying them. Fails, leaving the buffer registered, as
+    /// [`take_vbuffer`](Self::take_vbuffer) does, or if `shape` doesn't hold exactly the
+    /// buffer's elements.
+    pub fn take_dlpack<T: DLPackElement>(
+        &mut self,
+        buffer_handle: VBufferHandle,
+        shape: &[usize],
+    ) -> Result<DLPackTensor, WiscError> {
+        let vbuffer = self
+            .vbuffers
+            .get(buffer_handle)
+            .ok_or(WiscError::UnknownVBuffer)?;
+
+        if shape.iter().product::<usize>() != vbuffer.length {
+            return Err(WiscError::ShapeMismatch(
+                "the shape doesn't hold the buffer's elements",
+            ));
+        }
+
+        let data: Vec<T> = self.take_vbuffer(buffer_handle)?;
+
+        Ok(DLPackTensor::new(data, shape))
+    }
+}
@@ -0,0 +1,121 @@
+use bytemuck::Pod;
+
+use crate::complex::{Complex32, Complex64};
+
+/// A host-side element type that crosses the GPU boundary as a plain [`Pod`] value.
+///
+/// This lets domain types such as `bool`, fieldless `#[repr(u32)]` enums, and newtype
+/// wrappers live in VBuffers without being transmuted to raw integers by hand. Converting
+/// back is checked, since a kernel may write values that are not valid for the type.
+///
+/// Use [`enum_element!`](crate::enum_element) and [`newtype_element!`](crate::newtype_element)
+/// to implement it for your own types.
+pub trait WiscElement: Sized + 'static {
+    /// The representation stored in the buffer and seen by shaders.
+    type Repr: Pod;
+
+    fn to_repr(self) -> Self::Repr;
+
+    /// Converts a raw value back, or returns `None` if it is not a valid `Self`.
+    fn from_repr(repr: Self::Repr) -> Option<Self>;
+}
+
+macro_rules! pod_element {
+    ($($t:ty),*) => {
+        $(
+            impl WiscElement for $t {
+                type Repr = $t;
+
+                fn to_repr(self) -> Self::Repr {
+                    self
+                }
+
+                fn from_repr(repr: Self::Repr) -> Option<Self> {
+                    Some(repr)
+                }
+            }
+        )*
+    };
+}
+
+pod_element!(
+    u8, u16, u32, u64, i8, i16, i32, i64, f32, f64, Complex32, Complex64
+);
+
+/// WGSL has no host-shareable `bool`, so booleans travel as `u32` zero or one.
+impl WiscElement for bool {
+    type Repr = u32;
+
+    fn to_repr(self) -> Self::Repr {
+        self as u32
+    }
+
+    fn from_repr(repr: Self::Repr) -> Option<Self> {
+        match repr {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
+/// Implements [`WiscElement`] for a fieldless enum with a primitive `#[repr]`, listing the
+/// variants that are valid to read back.
+///
+/// ```
+/// #[derive(Debug, Clone, Copy, PartialEq)]
+/// #[repr(u32)]
+/// enum Material {
+///     Air = 0,
+///     Water = 1,
+///     Stone = 2,
+/// }
+///
+/// wisc::enum_element!(Material: u32 { Air, Water, Stone });
+/// ```
+#[macro_export]
+macro_rules! enum_element {
+    ($name:ident : $repr:ty { $($variant:ident),* $(,)? }) => {
+        impl $crate::element::WiscElement for $name {
+            type Repr = $repr;
+
+            fn to_repr(self) -> Self::Repr {
+                self as $repr
+            }
+
+            fn from_repr(repr: Self::Repr) -> Option<Self> {
+                $(
+                    if repr == $name::$variant as $repr {
+                        return Some($name::$variant);
+                    }
+                )*
+
+                None
+            }
+        }
+    };
+}
+
+/// Implements [`WiscElement`] for a tuple struct wrapping another element type.
+///
+/// ```
+/// struct Meters(f32);
+///
+/// wisc::newtype_element!(Meters(f32));
+/// ```
+#[macro_export]
+macro_rules! newtype_element {
+    ($name:ident ( $inner:ty )) => {
+        impl $crate::element::WiscElement for $name {
+            type Repr = <$inner as $crate::element::WiscElement>::Repr;
+
+            fn to_repr(self) -> Self::Repr {
+                $crate::element::WiscElement::to_repr(self.0)
+            }
+
+            fn from_repr(repr: Self::Repr) -> Option<Self> {
+                <$inner as $crate::element::WiscElement>::from_repr(repr).map($name)
+            }
+        }
+    };
+}
@@ -0,0 +1,202 @@
+use std::fmt;
+use std::ops::Range;
+
+use crate::vdevice::Features;
+
+/// Everything that can go wrong building or running work on a Workgroup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WiscError {
+    /// The task has no shader.
+    MissingShader,
+    /// A shader file couldn't be read.
+    ShaderFile(String),
+    /// The pipeline cache couldn't be written to its directory.
+    CacheFile(String),
+    /// A buffer snapshot couldn't be written or read back.
+    SnapshotFile(String),
+    /// Memory from another API couldn't be imported.
+    ImportFailed(String),
+    /// The shader's source isn't usable.
+    InvalidShader(&'static str),
+    /// The shader's source doesn't parse. Holds the compiler's messages.
+    ShaderParse(String),
+    /// Every device rejected the shader. Holds the first device's reason.
+    ShaderRejected(String),
+    /// No shader is registered under this name.
+    UnknownShader(String),
+    /// No kernel was named and the shader doesn't have exactly one compute entry point to
+    /// fall back on. Lists the candidates, if the shader could be reflected.
+    MissingKernel { candidates: Vec<String> },
+    /// The shader has no compute entry point by the kernel's name. Lists the ones it has.
+    UnknownKernel {
+        kernel: String,
+        candidates: Vec<String>,
+    },
+    /// The shader has no override constant by this key. Lists the ones it has.
+    UnknownOverride {
+        key: String,
+        candidates: Vec<String>,
+    },
+    /// The task has no dispatch size.
+    MissingSize,
+    /// A dispatch size couldn't be worked out for the kernel.
+    InvalidDispatch(&'static str),
+    /// The handle doesn't refer to a VBuffer in this Workgroup.
+    UnknownVBuffer,
+    /// The VBuffer doesn't hold elements of the requested type.
+    TypeMismatch,
+    /// The VBuffer hasn't been written yet, so there is nothing to read.
+    Uninitialized,
+    /// The binding isn't allowed in this combination.
+    InvalidBinding(&'static str),
+    /// A binding doesn't agree with how the shader declares it.
+    BindingMismatch { binding: u32, reason: &'static str },
+    /// The buffer bound at `binding` on `device` would be `size` bytes, over the device's
+    /// `limit` of `max`.
+    BindingTooLarge {
+        binding: u32,
+        device: usize,
+        size: u64,
+        limit: &'static str,
+        max: u64,
+    },
+    /// The kernel's `var<workgroup>`s take `size` bytes, over `device`'s
+    /// `max_compute_workgroup_storage_size` of `max`.
+    WorkgroupStorageTooLarge {
+        kernel: String,
+        device: usize,
+        size: u32,
+        max: u32,
+    },
+    /// A partition plan isn't usable for the buffer.
+    InvalidPartition(&'static str),
+    /// A VBuffer's length doesn't match the shape it is used as.
+    ShapeMismatch(&'static str),
+    /// A device doesn't support features the task needs.
+    MissingFeature(Features),
+    /// A size or index doesn't fit where it has to go.
+    OutOfBounds,
+    /// A device was lost, or stopped responding, while waiting on it.
+    DeviceLost(String),
+    /// A device reported an error that wasn't caught where it happened: a command it
+    /// couldn't validate, or running out of memory. Holds wgpu's report.
+    DeviceError(String),
+    /// Device `device`, labelled `label`, hadn't finished its work by the deadline.
+    Timeout { device: usize, label: String },
+    /// There is no device to run on.
+    NoDevices,
+    /// No device in the Workgroup has this index or label.
+    UnknownDevice(String),
+    /// Device weightings must be finite, not negative, and not all zero.
+    InvalidWeighting(&'static str),
+    /// The work was cancelled before it ran, by a
+    /// [`Scheduler::shutdown`](crate::scheduler::Scheduler::shutdown).
+    Cancelled,
+    /// Mapping a buffer for readback failed.
+    MapFailed(String),
+    /// The results of `binding` that `device` computed for `elements` didn't arrive on the
+    /// host intact.
+    ChecksumMismatch {
+        binding: u32,
+        device: usize,
+        elements: Range<usize>,
+    },
+}
+
+impl fmt::Display for WiscError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WiscError::MissingShader => write!(f, "the task has no shader"),
+            WiscError::ShaderRejected(reason) => {
+                write!(f, "every device rejected the shader: {reason}")
+            }
+            WiscError::ShaderFile(reason) => write!(f, "reading a shader file failed: {reason}"),
+            WiscError::CacheFile(reason) => {
+                write!(f, "writing the pipeline cache failed: {reason}")
+            }
+            WiscError::SnapshotFile(reason) => write!(f, "a buffer snapshot failed: {reason}"),
+            WiscError::ImportFailed(reason) => write!(f, "importing memory failed: {reason}"),
+            WiscError::InvalidShader(reason) => write!(f, "invalid shader: {reason}"),
+            WiscError::ShaderParse(messages) => write!(f, "the shader doesn't parse:\n{messages}"),
+            WiscError::UnknownShader(name) => write!(f, "no shader is registered as {name:?}"),
+            WiscError::MissingKernel { candidates } if candidates.is_empty() => {
+                write!(
+                    f,
+                    "no kernel was named, and none could be found in the shader"
+                )
+            }
+            WiscError::MissingKernel { candidates } => write!(
+                f,
+                "no kernel was named, and the shader has several compute entry points ({}); \
+                 pick one with with_kernel",
+                candidates.join(", ")
+            ),
+            WiscError::UnknownKernel { kernel, candidates } => write!(
+                f,
+                "the shader has no compute entry point {kernel:?} (it has: {})",
+                candidates.join(", ")
+            ),
+            WiscError::UnknownOverride { key, candidates } => write!(
+                f,
+                "the shader has no override constant {key:?} (it has: {})",
+                candidates.join(", ")
+            ),
+            WiscError::MissingSize => write!(f, "the task has no dispatch size"),
+            WiscError::InvalidDispatch(reason) => write!(f, "invalid dispatch size: {reason}"),
+            WiscError::UnknownVBuffer => write!(f, "the VBuffer handle is not in this workgroup"),
+            WiscError::TypeMismatch => write!(f, "the VBuffer holds elements of another type"),
+            WiscError::Uninitialized => write!(f, "the VBuffer has not been written yet"),
+            WiscError::InvalidBinding(reason) => write!(f, "invalid binding: {reason}"),
+            WiscError::BindingMismatch { binding, reason } => {
+                write!(f, "binding {binding} doesn't match the shader: {reason}")
+            }
+            WiscError::BindingTooLarge {
+                binding,
+                device,
+                size,
+                limit,
+                max,
+            } => write!(
+                f,
+                "binding {binding} would be {size} bytes on device {device}, over its {limit} of {max}"
+            ),
+            WiscError::WorkgroupStorageTooLarge {
+                kernel,
+                device,
+                size,
+                max,
+            } => write!(
+                f,
+                "kernel `{kernel}` uses {size} bytes of workgroup storage, over device {device}'s max_compute_workgroup_storage_size of {max}"
+            ),
+            WiscError::ShapeMismatch(reason) => write!(f, "shape mismatch: {reason}"),
+            WiscError::InvalidPartition(reason) => write!(f, "invalid partition: {reason}"),
+            WiscError::MissingFeature(features) => {
+                write!(f, "a device doesn't support {features:?}")
+            }
+            WiscError::OutOfBounds => write!(f, "a size or index is out of bounds"),
+            WiscError::DeviceLost(reason) => write!(f, "device lost: {reason}"),
+            WiscError::DeviceError(report) => write!(f, "the device reported an error: {report}"),
+            WiscError::Timeout { device, label } => {
+                write!(f, "device {device} ({label}) didn't finish in time")
+            }
+            WiscError::NoDevices => write!(f, "there is no device to run on"),
+            WiscError::UnknownDevice(device) => write!(f, "there is no device {device}"),
+            WiscError::InvalidWeighting(reason) => write!(f, "invalid weighting: {reason}"),
+            WiscError::Cancelled => write!(f, "the work was cancelled before it ran"),
+            WiscError::MapFailed(reason) => write!(f, "mapping a buffer failed: {reason}"),
+            WiscError::ChecksumMismatch {
+                binding,
+                device,
+                elements,
+            } => write!(
+                f,
+                "binding {binding} from device {device} failed its checksum for elements {}..{}",
+                elements.start, elements.end
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WiscError {}
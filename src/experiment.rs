@@ -0,0 +1,481 @@
+//! Running one task over many combinations of parameters, for sweeps that only change a
+//! uniform, the immediates or an input between runs.
+
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bytemuck::Pod;
+use web_time::Instant;
+
+use crate::error::WiscError;
+use crate::failover::Recipe;
+use crate::task::{
+    Task, encode_commands, encode_readback, per_device_parallel, vbuffer_bytes, vbuffer_write,
+};
+use crate::vdevice::{self, Mapping};
+
+/// One combination of parameters for an [`Experiment`] run. Whatever it doesn't set is
+/// left as the task was built.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Params {
+    uniforms: Vec<(u32, Vec<u8>)>,
+    immediates: Option<Vec<u8>>,
+    inputs: Vec<(u32, TypeId, Vec<u8>)>,
+}
+
+impl Params {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the value of the uniform bound at `id` with
+    /// [`with_uniform_buffer`](crate::task::TaskBuilder::with_uniform_buffer). It must be
+    /// the same size.
+    pub fn with_uniform<T: Pod>(mut self, id: u32, value: T) -> Self {
+        let mut contents = bytemuck::bytes_of(&value).to_vec();
+        contents.resize(contents.len().next_multiple_of(16).max(16), 0);
+
+        self.uniforms.push((id, contents));
+
+        self
+    }
+
+    /// Replaces the task's immediates. They must be the same size.
+    pub fn with_immediates<T: Pod>(mut self, value: T) -> Self {
+        let mut immediates = bytemuck::bytes_of(&value).to_vec();
+        immediates.resize(immediates.len().next_multiple_of(4), 0);
+
+        self.immediates = Some(immediates);
+
+        self
+    }
+
+    /// Replaces the contents of the input bound at `id`, which must hold as many elements
+    /// of `T` as `data`. The VBuffer itself is left as it is.
+    pub fn with_input<T: Pod>(mut self, id: u32, data: &[T]) -> Self {
+        self.inputs
+            .push((id, TypeId::of::<T>(), bytemuck::cast_slice(data).to_vec()));
+
+        self
+    }
+}
+
+/// A [`Task`] run once for every [`Params`] it is given, with everything else it binds
+/// kept on the devices between runs.
+///
+/// Runs are submitted in batches, back to back, and the batch waited for as a whole, so
+/// short runs don't each pay for a round trip to the host. Each run in a batch reads back
+/// into staging buffers of its own, which, like the device copies of replaced inputs, the
+/// Experiment keeps for later batches.
+///
+/// Takes tasks whose bindings the host sets up alone, as
+/// [`Task::run`](crate::task::Task::run) describes for running a failed device's share
+/// elsewhere. When it is done, the outputs hold what the last run left.
+///
+/// ```no_run
+/// use wisc::experiment::{Experiment, Params};
+/// use wisc::prelude::*;
+///
+/// let mut workgroup = Workgroup::from_devices(VDevice::all());
+/// let input = workgroup.create_vbuffer(vec![6.0f32; 1024]);
+/// let output = workgroup.create_vbuffer_uninit::<f32>(1024);
+///
+/// let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("../tests/normalize.wgsl"))
+///     .with_size((4, 1, 1))
+///     .with_input_buffer(0, input)
+///     .with_uniform_buffer(1, 1.0f32)
+///     .with_output_buffer(2, output)
+///     .build()
+///     .expect("Failed to build task");
+///
+/// let params: Vec<Params> = (1..=8)
+///     .map(|divisor| Params::new().with_uniform(1, divisor as f32))
+///     .collect();
+///
+/// let results = Experiment::new(&mut task)
+///     .with_batch_size(4)
+///     .run(&params)
+///     .expect("Experiment failed");
+///
+/// for run in results.runs() {
+///     let quotients: Vec<f32> = run.output(2).unwrap();
+///     println!("{:?}: {} in {:?}", params[run.params], quotients[0], run.time);
+/// }
+/// ```
+pub struct Experiment<'e, 't> {
+    task: &'e mut Task<'t>,
+    batch_size: usize,
+    // Each batch slot's staging buffers, by device and output.
+    staging: Vec<Vec<Vec<wgpu::Buffer>>>,
+    // The device copies replaced inputs are written to, by binding and device.
+    inputs: HashMap<u32, Vec<Option<wgpu::Buffer>>>,
+}
+
+impl<'e, 't> Experiment<'e, 't> {
+    pub fn new(task: &'e mut Task<'t>) -> Self {
+        Self {
+            task,
+            batch_size: 1,
+            staging: vec![],
+            inputs: HashMap::new(),
+        }
+    }
+
+    /// Submits up to `runs` runs before waiting for their results; one by default. Larger
+    /// batches keep the devices busier, at the cost of a set of staging buffers per run,
+    /// and of timing runs only as a batch.
+    pub fn with_batch_size(mut self, runs: usize) -> Self {
+        self.batch_size = runs.max(1);
+
+        self
+    }
+
+    /// Runs the task once for each of `params`, in order, and returns their results.
+    pub fn run(&mut self, params: &[Params]) -> Result<ExperimentResults, WiscError> {
+        if self.task.recipes.is_none() {
+            return Err(WiscError::InvalidBinding(
+                "an experiment needs a task the host can set up again",
+            ));
+        }
+
+        for params in params {
+            self.check(params)?;
+        }
+
+        // Runs are set up from the host copies, which must hold what the devices were left.
+        let handles: Vec<_> = self.task.recipe_handles().collect();
+        for handle in handles {
+            self.task.workgroup.read_back_pending(handle)?;
+        }
+
+        let mut runs = Vec::with_capacity(params.len());
+
+        for (batch_index, batch) in params.chunks(self.batch_size).enumerate() {
+            let first = batch_index * self.batch_size;
+            runs.extend(self.run_batch(first, batch)?);
+        }
+
+        // Leave the outputs' host copies as current as their device copies.
+        if let Some(last) = runs.last() {
+            for ((_, handle), (_, _, bytes)) in self.task.output_buffers.iter().zip(&last.outputs) {
+                let vbuffer = &mut self.task.workgroup.vbuffers[*handle];
+
+                vbuffer_write(vbuffer, 0, bytes);
+                if let Some(assume_init) = vbuffer.assume_init.take() {
+                    assume_init(vbuffer.inner.as_mut(), vbuffer.length);
+                }
+                vbuffer.pending_readback = false;
+            }
+        }
+
+        Ok(ExperimentResults { runs })
+    }
+
+    /// Fails if `params` don't fit what the task binds.
+    fn check(&self, params: &Params) -> Result<(), WiscError> {
+        let task = &*self.task;
+
+        if let Some(immediates) = &params.immediates
+            && immediates.len() != task.immediates.len()
+        {
+            return Err(WiscError::InvalidBinding(
+                "the immediates are a different size than the task's",
+            ));
+        }
+
+        for (vdi, commands) in task.device_commands.iter().enumerate() {
+            let (Some(commands), Some(recipes)) = (commands, &task.recipes) else {
+                continue;
+            };
+
+            for (id, contents) in &params.uniforms {
+                match binding(commands, &recipes[vdi], *id) {
+                    Some((_, Recipe::Bytes(bytes))) if bytes.len() == contents.len() => {}
+                    Some((_, Recipe::Bytes(_))) => {
+                        return Err(WiscError::BindingMismatch {
+                            binding: *id,
+                            reason: "the uniform is a different size than the task's",
+                        });
+                    }
+                    _ => {
+                        return Err(WiscError::BindingMismatch {
+                            binding: *id,
+                            reason: "no uniform value is bound there",
+                        });
+                    }
+                }
+            }
+
+            for (id, typeid, data) in &params.inputs {
+                let Some((_, Recipe::Elements(handle, _))) = binding(commands, &recipes[vdi], *id)
+                else {
+                    return Err(WiscError::BindingMismatch {
+                        binding: *id,
+                        reason: "no input buffer is bound there",
+                    });
+                };
+                let vbuffer = &task.workgroup.vbuffers[*handle];
+
+                if vbuffer.typeid != *typeid {
+                    return Err(WiscError::TypeMismatch);
+                }
+                if vbuffer.length * vbuffer.stride != data.len() {
+                    return Err(WiscError::ShapeMismatch(
+                        "an input's replacement must be as long as the input",
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Submits `batch`, the runs from `first` on, waits for all of them, and reads back
+    /// each one's outputs.
+    fn run_batch(
+        &mut self,
+        first: usize,
+        batch: &[Params],
+    ) -> Result<Vec<ExperimentRun>, WiscError> {
+        let num_devices = self.task.workgroup.vdevices.len();
+
+        while self.staging.len() < batch.len() {
+            let slot = (0..num_devices)
+                .map(|vdi| {
+                    let vd = &self.task.workgroup.vdevices[vdi];
+
+                    self.task.output_wgpu_buffers[vdi]
+                        .iter()
+                        .map(|output| {
+                            vd.device.create_buffer(&wgpu::BufferDescriptor {
+                                label: Some(&format!(
+                                    "WISC Experiment Staging (VDevice {})",
+                                    vd.label
+                                )),
+                                size: output.size(),
+                                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                                mapped_at_creation: false,
+                            })
+                        })
+                        .collect()
+                })
+                .collect();
+
+            self.staging.push(slot);
+        }
+
+        let started = Instant::now();
+
+        for (slot, params) in batch.iter().enumerate() {
+            self.submit(slot, params);
+        }
+
+        let mappings: Vec<Mapping> = self.staging[..batch.len()]
+            .iter()
+            .flatten()
+            .flatten()
+            .map(vdevice::map_read)
+            .collect();
+
+        let waited = per_device_parallel(&self.task.workgroup.vdevices, |_, vd| vd.wait());
+        let finished = waited
+            .into_iter()
+            .chain(mappings.into_iter().map(Mapping::finish))
+            .collect::<Result<Vec<()>, WiscError>>();
+
+        if let Err(error) = finished {
+            for buffer in self.staging.iter().flatten().flatten() {
+                buffer.unmap();
+            }
+
+            return Err(error);
+        }
+
+        let time = started.elapsed() / batch.len() as u32;
+
+        let runs = (0..batch.len())
+            .map(|slot| ExperimentRun {
+                params: first + slot,
+                outputs: self.read_back(slot),
+                time,
+            })
+            .collect();
+
+        for buffer in self.staging[..batch.len()].iter().flatten().flatten() {
+            buffer.unmap();
+        }
+
+        Ok(runs)
+    }
+
+    /// Submits one run with `params`, reading back into batch slot `slot`.
+    fn submit(&mut self, slot: usize, params: &Params) {
+        let task = &*self.task;
+        let Some(recipes) = &task.recipes else {
+            return;
+        };
+
+        for (vdi, vd) in task.workgroup.vdevices.iter().enumerate() {
+            let Some(commands) = &task.device_commands[vdi] else {
+                continue;
+            };
+
+            let mut buffers = commands.buffers.clone();
+
+            for (id, contents) in &params.uniforms {
+                if let Some((index, _)) = binding(commands, &recipes[vdi], *id) {
+                    let label = format!("WISC Uniform Buffer {} (VDevice {})", id, vd.label);
+
+                    buffers[index] =
+                        task.workgroup.binding_caches[vdi].uniform(vd, &label, contents);
+                }
+            }
+
+            for (id, _, data) in &params.inputs {
+                let Some((index, Recipe::Elements(handle, range))) =
+                    binding(commands, &recipes[vdi], *id)
+                else {
+                    continue;
+                };
+                let stride = task.workgroup.vbuffers[*handle].stride;
+                let original = &commands.buffers[index];
+
+                let replaced = self
+                    .inputs
+                    .entry(*id)
+                    .or_insert_with(|| vec![None; task.device_commands.len()]);
+                let buffer = replaced[vdi].get_or_insert_with(|| {
+                    vd.device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some(&format!(
+                            "WISC Experiment Input {} (VDevice {})",
+                            id, vd.label
+                        )),
+                        size: original.size(),
+                        usage: original.usage() | wgpu::BufferUsages::COPY_DST,
+                        mapped_at_creation: false,
+                    })
+                });
+
+                vd.queue
+                    .write_buffer(buffer, 0, &data[range.start * stride..range.end * stride]);
+                buffers[index] = buffer.clone();
+            }
+
+            let immediates = params.immediates.as_ref().unwrap_or(&task.immediates);
+
+            vd.queue.submit(
+                std::iter::once(encode_commands(
+                    vd,
+                    &task.workgroup.binding_caches[vdi],
+                    &commands.bind_group_layout,
+                    &commands.pipeline,
+                    &commands.entries,
+                    &buffers,
+                    commands.dispatch.as_ref(),
+                    None,
+                    None,
+                    immediates,
+                ))
+                .chain(encode_readback(
+                    vd,
+                    &task.output_wgpu_buffers[vdi],
+                    &self.staging[slot][vdi],
+                )),
+            );
+        }
+    }
+
+    /// The outputs batch slot `slot` read back, each pieced together from what the devices
+    /// own of it, over the host copy for what none of them computed.
+    fn read_back(&self, slot: usize) -> Vec<(u32, TypeId, Vec<u8>)> {
+        let task = &*self.task;
+
+        task.output_buffers
+            .iter()
+            .enumerate()
+            .map(|(output_index, (id, handle))| {
+                let vbuffer = &task.workgroup.vbuffers[*handle];
+                let plan = &task.output_partitions[output_index];
+                // An uninitialized output is covered by the devices' shares.
+                let mut bytes = match vbuffer.assume_init {
+                    Some(_) => vec![0; vbuffer.length * vbuffer.stride],
+                    None => vbuffer_bytes(vbuffer).to_vec(),
+                };
+
+                for (vdi, commands) in task.device_commands.iter().enumerate() {
+                    if commands.is_none() {
+                        continue;
+                    }
+
+                    let (held, owned) = (&plan.held[vdi], &plan.owned[vdi]);
+                    let data = self.staging[slot][vdi][output_index]
+                        .slice(..)
+                        .get_mapped_range();
+
+                    let skip = (owned.start - held.start) * vbuffer.stride;
+                    let len = owned.len() * vbuffer.stride;
+
+                    bytes[owned.start * vbuffer.stride..][..len]
+                        .copy_from_slice(&data[skip..skip + len]);
+                }
+
+                (*id, vbuffer.typeid, bytes)
+            })
+            .collect()
+    }
+}
+
+/// The binding at `id` among a device's, with its index and what it holds.
+fn binding<'r>(
+    commands: &crate::task::DeviceCommands,
+    recipes: &'r [Recipe],
+    id: u32,
+) -> Option<(usize, &'r Recipe)> {
+    let index = commands
+        .entries
+        .iter()
+        .position(|entry| entry.binding == id)?;
+
+    Some((index, recipes.get(index)?))
+}
+
+/// What an [`Experiment`] returned: one run for each of the params it was given, in order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExperimentResults {
+    runs: Vec<ExperimentRun>,
+}
+
+impl ExperimentResults {
+    pub fn runs(&self) -> &[ExperimentRun] {
+        &self.runs
+    }
+}
+
+/// One run of an [`Experiment`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExperimentRun {
+    /// The index of the params it ran with.
+    pub params: usize,
+    /// How long the run took, on average across its batch: the batch's time from
+    /// submission to results, divided between its runs.
+    pub time: Duration,
+    outputs: Vec<(u32, TypeId, Vec<u8>)>,
+}
+
+impl ExperimentRun {
+    /// What the run left in the output bound at `id`.
+    pub fn output<T: Pod>(&self, id: u32) -> Result<Vec<T>, WiscError> {
+        let (_, typeid, bytes) = self
+            .outputs
+            .iter()
+            .find(|(output, _, _)| *output == id)
+            .ok_or(WiscError::InvalidBinding("no output is bound there"))?;
+
+        if *typeid != TypeId::of::<T>() {
+            return Err(WiscError::TypeMismatch);
+        }
+
+        Ok(bytemuck::pod_collect_to_vec(bytes))
+    }
+}
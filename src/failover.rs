@@ -0,0 +1,302 @@
+//! Running the share of a device that failed mid-run on one of the devices left, so that
+//! losing an integrated or external GPU halfway through doesn't fail the whole task.
+
+use std::ops::Range;
+
+use web_time::Instant;
+use wgpu::util::DeviceExt;
+
+use crate::dispatch::Dispatch;
+use crate::error::WiscError;
+use crate::task::{
+    Task, encode_commands, encode_readback, partition_bytes, per_device_parallel, vbuffer_write,
+};
+use crate::vdevice::{self, Mapping};
+use crate::workgroup::VBufferHandle;
+
+/// What one of a device's bindings holds, to set it up again on another device.
+#[derive(Clone)]
+pub(crate) enum Recipe {
+    /// The elements in the range of an input's host copy.
+    Elements(VBufferHandle, Range<usize>),
+    /// The elements in the range of an output's host copy, which are read back after.
+    Output(VBufferHandle, Range<usize>),
+    /// Fixed contents, like a uniform's.
+    Bytes(Vec<u8>),
+}
+
+impl Task<'_> {
+    /// Whether the devices left can run the shares of every `failed` device.
+    pub(crate) fn can_fail_over(&self, failed: &[usize]) -> bool {
+        // Shares are set up again from the host copies, which must be current.
+        self.recipes.is_some()
+            && self.recipe_handles().all(|handle| {
+                self.workgroup
+                    .vbuffers
+                    .get(handle)
+                    .is_none_or(|vbuffer| !vbuffer.pending_readback)
+            })
+            && failed.iter().all(|&vdi| {
+                self.device_commands[vdi].is_none() || self.stand_in(vdi, failed).is_some()
+            })
+    }
+
+    /// The VBuffers whose host copies the recipes set shares up from.
+    pub(crate) fn recipe_handles(&self) -> impl Iterator<Item = VBufferHandle> + '_ {
+        self.recipes
+            .iter()
+            .flatten()
+            .flatten()
+            .filter_map(|recipe| match recipe {
+                Recipe::Elements(handle, _) | Recipe::Output(handle, _) => Some(*handle),
+                Recipe::Bytes(_) => None,
+            })
+    }
+
+    /// The strongest device, other than the `failed` ones, that can run failed device
+    /// `vdi`'s share: one with a pipeline for the same bindings.
+    pub(crate) fn stand_in(&self, vdi: usize, failed: &[usize]) -> Option<usize> {
+        let commands = self.device_commands[vdi].as_ref()?;
+
+        // An indirect dispatch reads its size from the failed device.
+        let Some(Dispatch::Direct(x, y, z)) = commands.dispatch else {
+            return None;
+        };
+        let limit = |vdi: usize| {
+            self.workgroup.vdevices[vdi]
+                .device
+                .limits()
+                .max_compute_workgroups_per_dimension
+        };
+        let split = x > limit(vdi);
+
+        (0..self.device_commands.len()).find(|other| {
+            let limit = limit(*other);
+
+            !failed.contains(other)
+                && self.device_commands[*other]
+                    .as_ref()
+                    .is_some_and(|stand_in| stand_in.entries == commands.entries)
+                // Only a dispatch split already has the slice uniform to split it again by.
+                && y.max(z) <= limit
+                && (x <= limit || split)
+        })
+    }
+
+    /// Waits for every device that was submitted to, and for the readbacks of those that
+    /// finish. Returns the devices that failed along with the `failed` ones, if the
+    /// others can stand in for them, and when each device became idle; fails with the
+    /// first error otherwise.
+    pub(crate) fn settle(
+        &self,
+        mappings: Vec<Mapping>,
+        mut failed: Vec<usize>,
+    ) -> Result<(Vec<usize>, Vec<Instant>), WiscError> {
+        let vdevices = &self.workgroup.vdevices;
+
+        if self.recipes.is_none() {
+            // The devices that sat the task out may have been lost since; nothing waits on them.
+            let finished = crate::task::finish_timed(vdevices, mappings, &self.excluded)?;
+
+            return Ok((failed, finished));
+        }
+
+        let waited = per_device_parallel(vdevices, |vdi, vd| {
+            if failed.contains(&vdi) {
+                return Ok(Instant::now());
+            }
+
+            vd.wait().map(|()| Instant::now())
+        });
+        let mut finished = vec![Instant::now(); vdevices.len()];
+
+        let mut errors = vec![];
+        // The devices that were submitted to map their staging buffers in device order.
+        let mut mappings = mappings.into_iter();
+
+        for (vdi, waited) in waited.into_iter().enumerate() {
+            if failed.contains(&vdi) {
+                continue;
+            }
+
+            let device_mappings: Vec<Mapping> = mappings
+                .by_ref()
+                .take(self.read_back(vdi).count())
+                .collect();
+
+            if let Ok(at) = waited {
+                finished[vdi] = at;
+            }
+
+            if let Err(error) =
+                waited.and_then(|_| device_mappings.into_iter().try_for_each(Mapping::finish))
+            {
+                // Unmapping aborts whatever of its readback is still pending.
+                for (_, buffer) in self.read_back(vdi) {
+                    buffer.unmap();
+                }
+                if let Some(timestamps) = self.device_commands[vdi]
+                    .as_ref()
+                    .and_then(|commands| commands.timestamps.as_ref())
+                {
+                    timestamps.staging().unmap();
+                }
+                if let Some(statistics) = self.device_commands[vdi]
+                    .as_ref()
+                    .and_then(|commands| commands.statistics.as_ref())
+                {
+                    statistics.staging().unmap();
+                }
+
+                failed.push(vdi);
+                errors.push(error);
+            }
+        }
+
+        failed.sort_unstable();
+
+        if let Some(error) = errors.into_iter().next()
+            && !self.can_fail_over(&failed)
+        {
+            for buffer in self.staging() {
+                buffer.unmap();
+            }
+
+            return Err(error);
+        }
+
+        Ok((failed, finished))
+    }
+
+    /// Runs failed device `vdi`'s share on device `on`, and writes what it owns of each
+    /// output back to the host copies. Blocks until it has.
+    pub(crate) fn run_share_on(&mut self, vdi: usize, on: usize) -> Result<(), WiscError> {
+        let (Some(recipes), Some(commands), Some(stand_in)) = (
+            &self.recipes,
+            &self.device_commands[vdi],
+            &self.device_commands[on],
+        ) else {
+            return Ok(());
+        };
+
+        let vd = &self.workgroup.vdevices[on];
+        let mut outputs = vec![];
+        let mut staging = vec![];
+
+        let buffers: Vec<wgpu::Buffer> = recipes[vdi]
+            .iter()
+            .zip(&commands.entries)
+            .map(|(recipe, entry)| {
+                let label = format!(
+                    "WISC Failover Buffer {} (VDevice {})",
+                    entry.binding, vd.label
+                );
+
+                let (contents, usage, size) = match recipe {
+                    Recipe::Bytes(contents) => {
+                        return self.workgroup.binding_caches[on].uniform(vd, &label, contents);
+                    }
+                    Recipe::Elements(handle, range) => {
+                        let usage = match entry.ty {
+                            wgpu::BindingType::Buffer {
+                                ty: wgpu::BufferBindingType::Uniform,
+                                ..
+                            } => wgpu::BufferUsages::UNIFORM,
+                            _ => wgpu::BufferUsages::STORAGE,
+                        };
+
+                        (
+                            Some(partition_bytes(&self.workgroup.vbuffers[*handle], range)),
+                            usage,
+                            0,
+                        )
+                    }
+                    Recipe::Output(handle, range) => {
+                        let vbuffer = &self.workgroup.vbuffers[*handle];
+                        let usage = wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC;
+
+                        // An output without host contents yet has nothing to upload, and
+                        // starts out zeroed like on the device that failed.
+                        if vbuffer.assume_init.is_some() {
+                            (None, usage, (range.len() * vbuffer.stride) as u64)
+                        } else {
+                            (Some(partition_bytes(vbuffer, range)), usage, 0)
+                        }
+                    }
+                };
+
+                let buffer = match contents {
+                    Some(contents) => {
+                        vd.device
+                            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                                label: Some(&label),
+                                contents,
+                                usage,
+                            })
+                    }
+                    None => vd.device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some(&label),
+                        size,
+                        usage,
+                        mapped_at_creation: false,
+                    }),
+                };
+
+                if let Recipe::Output(..) = recipe {
+                    staging.push(vd.device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some(&format!("WISC Failover Staging (VDevice {})", vd.label)),
+                        size: buffer.size(),
+                        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                        mapped_at_creation: false,
+                    }));
+                    outputs.push(buffer.clone());
+                }
+
+                buffer
+            })
+            .collect();
+
+        vd.queue.submit(
+            std::iter::once(encode_commands(
+                vd,
+                &self.workgroup.binding_caches[on],
+                &stand_in.bind_group_layout,
+                &stand_in.pipeline,
+                &commands.entries,
+                &buffers,
+                commands.dispatch.as_ref(),
+                None,
+                None,
+                &self.immediates,
+            ))
+            .chain(encode_readback(vd, &outputs, &staging)),
+        );
+
+        let mappings: Vec<Mapping> = staging.iter().map(vdevice::map_read).collect();
+
+        vd.wait()?;
+        for mapping in mappings {
+            mapping.finish()?;
+        }
+
+        // Outputs come in the order they were bound, as do their staging buffers.
+        for (output_index, staging_buffer) in staging.iter().enumerate() {
+            let (_, handle) = self.output_buffers[output_index];
+            let plan = &self.output_partitions[output_index];
+            let (held, owned) = (&plan.held[vdi], &plan.owned[vdi]);
+
+            let data = staging_buffer.slice(..).get_mapped_range();
+            let vbuffer = &mut self.workgroup.vbuffers[handle];
+
+            // Only the owned elements go back; the halo around them is discarded.
+            let skip = (owned.start - held.start) * vbuffer.stride;
+            vbuffer_write(
+                vbuffer,
+                owned.start * vbuffer.stride,
+                &data[skip..skip + owned.len() * vbuffer.stride],
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+//! Batched fast Fourier transforms of [`Complex32`] signals. A buffer holds many signals of
+//! the same power-of-two length back to back, which are dealt out whole to the devices by
+//! their weightings, so every transform runs on a single device.
+
+use std::any::TypeId;
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::complex::{COMPLEX32_WGSL, Complex32};
+use crate::dispatch::DispatchSize;
+use crate::error::WiscError;
+use crate::partition::PartitionMode;
+use crate::report::RunReport;
+use crate::task::TaskBuilder;
+use crate::workgroup::{VBufferHandle, Workgroup};
+
+const SHADER: &str = "wisc::fft";
+
+// The kernel's workgroup size; one workgroup transforms one signal.
+const WORKGROUP_SIZE: usize = 64;
+
+/// Which way a transform goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// From the time domain to frequencies, `X[k] = Σ x[n] e^(-2πikn/N)`.
+    Forward,
+    /// Back again, scaled by `1/N` so that it undoes a forward transform.
+    Inverse,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct FftParams {
+    len: u32,
+    log2_len: u32,
+    sign: f32,
+    scale: f32,
+}
+
+/// Transforms every `len`-element signal in `input` into the matching signal of `output`.
+/// Both must hold the same whole number of `Complex32` signals, and `len` must be a power
+/// of two.
+pub fn fft(
+    workgroup: &mut Workgroup,
+    input: VBufferHandle,
+    output: VBufferHandle,
+    len: usize,
+    direction: Direction,
+) -> Result<RunReport, WiscError> {
+    if !len.is_power_of_two() {
+        return Err(WiscError::ShapeMismatch(
+            "transforms must be a power of two long",
+        ));
+    }
+
+    workgroup.has_expected_types(&[
+        (input, TypeId::of::<Complex32>()),
+        (output, TypeId::of::<Complex32>()),
+    ])?;
+
+    let total = workgroup.vbuffers[input].length;
+
+    if workgroup.vbuffers[output].length != total {
+        return Err(WiscError::ShapeMismatch(
+            "the input and output hold different numbers of values",
+        ));
+    }
+
+    if !total.is_multiple_of(len) {
+        return Err(WiscError::ShapeMismatch(
+            "the buffers don't hold a whole number of signals",
+        ));
+    }
+
+    // Never split a signal between devices.
+    workgroup.set_element_group_size(input, len)?;
+    workgroup.set_element_group_size(output, len)?;
+
+    let params = FftParams {
+        len: u32::try_from(len).map_err(|_| WiscError::OutOfBounds)?,
+        log2_len: len.trailing_zeros(),
+        sign: match direction {
+            Direction::Forward => -1.0,
+            Direction::Inverse => 1.0,
+        },
+        scale: match direction {
+            Direction::Forward => 1.0,
+            Direction::Inverse => 1.0 / len as f32,
+        },
+    };
+
+    if !workgroup.has_registered_shader(SHADER) {
+        let source = format!("{COMPLEX32_WGSL}{}", include_str!("fft.wgsl"));
+
+        workgroup.register_shader(
+            SHADER,
+            wgpu::ShaderModuleDescriptor {
+                label: Some(SHADER),
+                source: wgpu::ShaderSource::Wgsl(source.into()),
+            },
+        );
+    }
+
+    // A workgroup for every signal in the batch; devices holding fewer skip the rest.
+    TaskBuilder::from_workgroup(workgroup)
+        .with_registered_shader(SHADER)
+        .with_size(DispatchSize::ForElements {
+            count: total / len * WORKGROUP_SIZE,
+            per_invocation: 1,
+        })
+        .with_input_buffer_partitioned(0, input, PartitionMode::Weighted)
+        .with_output_buffer_partitioned(1, output, PartitionMode::Weighted)
+        .with_uniform_buffer(2, params)
+        .build()?
+        .run()
+}
@@ -0,0 +1,64 @@
+// Batched radix-2 transforms of complex signals stored as vec2<f32>. Each workgroup
+// transforms one signal in place in the output, one stage after another.
+
+struct FftParams {
+    len: u32,
+    log2_len: u32,
+    // -1 for forward transforms, 1 for inverse ones.
+    sign: f32,
+    // Applied to every output, 1/len for inverse transforms.
+    scale: f32,
+}
+
+@group(0) @binding(0) var<storage, read> input: array<vec2<f32>>;
+@group(0) @binding(1) var<storage, read_write> output: array<vec2<f32>>;
+@group(0) @binding(2) var<uniform> params: FftParams;
+
+const PI: f32 = 3.14159265358979;
+
+@compute @workgroup_size(64, 1, 1)
+fn fft(
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+    @builtin(local_invocation_id) local_id: vec3<u32>,
+) {
+    let n = params.len;
+    let signal = workgroup_id.x;
+    if (signal >= arrayLength(&output) / n) {
+        return;
+    }
+
+    let base = signal * n;
+
+    // Bit-reversed copy, so the butterflies can work in place.
+    for (var i = local_id.x; i < n; i += 64u) {
+        let j = select(reverseBits(i) >> (32u - params.log2_len), 0u, params.log2_len == 0u);
+        output[base + j] = input[base + i];
+    }
+
+    storageBarrier();
+
+    for (var stage = 1u; stage <= params.log2_len; stage++) {
+        let half = 1u << (stage - 1u);
+
+        for (var b = local_id.x; b < n / 2u; b += 64u) {
+            let k = b % half;
+            let i = base + (b / half) * (half << 1u) + k;
+            let j = i + half;
+
+            let w = complex_from_polar(1.0, params.sign * PI * f32(k) / f32(half));
+            let t = complex_mul(w, output[j]);
+            let u = output[i];
+
+            output[i] = u + t;
+            output[j] = u - t;
+        }
+
+        storageBarrier();
+    }
+
+    if (params.scale != 1.0) {
+        for (var i = local_id.x; i < n; i += 64u) {
+            output[base + i] *= params.scale;
+        }
+    }
+}
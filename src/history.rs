@@ -0,0 +1,71 @@
+//! A record of the dispatches a Workgroup issues, so tests can check that higher-level
+//! code produced the GPU work they expect without comparing outputs bit for bit.
+//!
+//! Recording is off until [`Workgroup::record_launches`] turns it on.
+
+use crate::workgroup::Workgroup;
+
+/// One kernel dispatch on one device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Launch {
+    /// The device's index in the Workgroup.
+    pub device: usize,
+    pub kernel: String,
+    /// The workgroups dispatched along x, y and z, or `None` for an indirect dispatch,
+    /// whose counts are only known on the device.
+    pub workgroups: Option<(u32, u32, u32)>,
+    /// Every binding, by number, with the size in bytes of the buffer bound to it.
+    pub bindings: Vec<(u32, u64)>,
+}
+
+impl Launch {
+    pub(crate) fn new(
+        device: usize,
+        kernel: &str,
+        workgroups: Option<(u32, u32, u32)>,
+        bindings: impl IntoIterator<Item = (u32, u64)>,
+    ) -> Self {
+        let mut bindings: Vec<(u32, u64)> = bindings.into_iter().collect();
+        bindings.sort_unstable();
+
+        Self {
+            device,
+            kernel: kernel.to_string(),
+            workgroups,
+            bindings,
+        }
+    }
+}
+
+/// Appends the launch made by `launch` to `history`, if it is being recorded.
+pub(crate) fn record(history: &mut Option<Vec<Launch>>, launch: impl FnOnce() -> Launch) {
+    if let Some(history) = history {
+        history.push(launch());
+    }
+}
+
+impl Workgroup {
+    /// Starts or stops recording every dispatch issued by tasks run on this Workgroup.
+    /// Stopping discards what was recorded.
+    pub fn record_launches(&mut self, enabled: bool) {
+        match enabled {
+            true => {
+                self.launches.get_or_insert_with(Vec::new);
+            }
+            false => self.launches = None,
+        }
+    }
+
+    /// The dispatches recorded so far, oldest first.
+    pub fn launch_history(&self) -> &[Launch] {
+        self.launches.as_deref().unwrap_or_default()
+    }
+
+    /// Takes the dispatches recorded so far, leaving recording on.
+    pub fn take_launch_history(&mut self) -> Vec<Launch> {
+        self.launches
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,254 @@
+//! Importing device memory that other Vulkan or CUDA code allocated, so wisc kernels can
+//! run on data already on a GPU without a trip through the host.
+//!
+//! Devices must be opened able to import memory, with
+//! [`WorkgroupBuilder::external_memory`](crate::workgroup::WorkgroupBuilder::external_memory).
+//! Memory is imported from opaque file descriptors (`VK_KHR_external_memory_fd`), like
+//! those `vkGetMemoryFdKHR` or CUDA's `cuMemExportToShareableHandle` hand out.
+
+use std::ffi::CStr;
+use std::ops::Range;
+use std::os::fd::{FromRawFd, IntoRawFd, OwnedFd};
+
+use ash::{khr, vk};
+use bytemuck::Pod;
+use wgpu::hal::api::Vulkan;
+
+use crate::error::WiscError;
+use crate::vbuffer::{Residency, Resident};
+use crate::vdevice::VDevice;
+use crate::workgroup::{VBufferHandle, Workgroup};
+
+const EXTENSION: &CStr = khr::external_memory_fd::NAME;
+
+/// A device allocation exported by another API as an opaque file descriptor, to import
+/// with [`Workgroup::import_external_memory`].
+#[derive(Debug)]
+pub struct ExternalMemory {
+    pub fd: OwnedFd,
+    /// The size of the whole allocation, in bytes.
+    pub size: u64,
+}
+
+impl Workgroup {
+    /// Registers `length` elements of `T` held in `memory` as a VBuffer that lives on the
+    /// device at `device` (in the Workgroup's order), without copying them anywhere. Bind
+    /// it with [`PartitionMode::on_device`](crate::partition::PartitionMode::on_device), so
+    /// that tasks run on that device alone: as an input the memory is read in place, and
+    /// as an output the results are written into it, as well as read back to the host.
+    ///
+    /// The VBuffer owns the descriptor from then on, and the memory is released once it is
+    /// gone. Fails if the device wasn't opened to import memory, or the allocation can't
+    /// hold `length` elements.
+    ///
+    /// # Safety
+    ///
+    /// `memory` must have been exported as an opaque file descriptor from the same
+    /// physical device, by a compatible driver. Nothing else may write to it while tasks
+    /// use it.
+    pub unsafe fn import_external_memory<T: Pod>(
+        &mut self,
+        device: usize,
+        memory: ExternalMemory,
+        length: usize,
+    ) -> Result<VBufferHandle, WiscError> {
+        let vd = self.vdevices.get(device).ok_or(WiscError::OutOfBounds)?;
+        let byte_len = (length * std::mem::size_of::<T>()) as u64;
+
+        if byte_len == 0 || byte_len > memory.size {
+            return Err(WiscError::OutOfBounds);
+        }
+
+        // SAFETY: the caller vouches for the memory.
+        let buffer = unsafe { import_buffer(vd, memory, byte_len)? };
+
+        // The other devices hold none of it, like devices that sit a task out.
+        let buffers = self
+            .vdevices
+            .iter()
+            .enumerate()
+            .map(|(vdi, other)| {
+                if vdi == device {
+                    return buffer.clone();
+                }
+
+                other.device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some(&format!("WISC Imported Buffer (VDevice {})", other.label)),
+                    size: 0,
+                    usage: wgpu::BufferUsages::COPY_SRC,
+                    mapped_at_creation: false,
+                })
+            })
+            .collect();
+        let ranges: Vec<Range<usize>> = (0..self.vdevices.len())
+            .map(|vdi| if vdi == device { 0..length } else { 0..0 })
+            .collect();
+
+        let handle = self.create_vbuffer_uninit::<T>(length);
+        let vbuffer = &mut self.vbuffers[handle];
+
+        vbuffer.imported = true;
+        vbuffer.residency = Residency::Aliased(Resident {
+            buffers,
+            owned: ranges.clone(),
+            ranges,
+        });
+
+        Ok(handle)
+    }
+}
+
+/// Opens `adapter` with importing memory enabled, or returns `None` if it isn't a Vulkan
+/// adapter that can.
+pub(crate) fn open_device(
+    adapter: &wgpu::Adapter,
+    descriptor: &wgpu::DeviceDescriptor,
+) -> Option<(wgpu::Device, wgpu::Queue)> {
+    // SAFETY: the HAL adapter is only used to open a device from it.
+    let hal_adapter = unsafe { adapter.as_hal::<Vulkan>() }?;
+    let instance = hal_adapter.shared_instance().raw_instance();
+
+    // SAFETY: the physical device was enumerated from this instance.
+    let supported = unsafe {
+        instance.enumerate_device_extension_properties(hal_adapter.raw_physical_device())
+    }
+    .ok()?;
+
+    if !supported
+        .iter()
+        .any(|extension| extension.extension_name_as_c_str() == Ok(EXTENSION))
+    {
+        return None;
+    }
+
+    // SAFETY: the callback only adds an extension the physical device supports.
+    let open_device = unsafe {
+        hal_adapter.open_with_callback(
+            descriptor.required_features,
+            &descriptor.memory_hints,
+            Some(Box::new(|args| {
+                if !args.extensions.contains(&EXTENSION) {
+                    args.extensions.push(EXTENSION);
+                }
+            })),
+        )
+    }
+    .ok()?;
+
+    drop(hal_adapter);
+
+    // SAFETY: the device was opened from this adapter, with the descriptor's features.
+    unsafe { adapter.create_device_from_hal(open_device, descriptor) }.ok()
+}
+
+/// A buffer of `byte_len` bytes on `vd`, backed by `memory`.
+///
+/// # Safety
+///
+/// As for [`Workgroup::import_external_memory`].
+unsafe fn import_buffer(
+    vd: &VDevice,
+    memory: ExternalMemory,
+    byte_len: u64,
+) -> Result<wgpu::Buffer, WiscError> {
+    let unsupported =
+        || WiscError::ImportFailed("the device wasn't opened to import memory".to_string());
+
+    // SAFETY: the raw device is only used to create the buffer and its memory, which are
+    // handed to wgpu before the guard is dropped.
+    let hal_device = unsafe { vd.device.as_hal::<Vulkan>() }.ok_or_else(unsupported)?;
+
+    if !hal_device.enabled_device_extensions().contains(&EXTENSION) {
+        return Err(unsupported());
+    }
+
+    let raw = hal_device.raw_device();
+    let instance = hal_device.shared_instance().raw_instance();
+    let failed = |error: vk::Result| WiscError::ImportFailed(error.to_string());
+
+    let mut external = vk::ExternalMemoryBufferCreateInfo::default()
+        .handle_types(vk::ExternalMemoryHandleTypeFlags::OPAQUE_FD);
+    let info = vk::BufferCreateInfo::default()
+        .size(byte_len)
+        .usage(
+            vk::BufferUsageFlags::STORAGE_BUFFER
+                | vk::BufferUsageFlags::UNIFORM_BUFFER
+                | vk::BufferUsageFlags::INDIRECT_BUFFER
+                | vk::BufferUsageFlags::TRANSFER_SRC
+                | vk::BufferUsageFlags::TRANSFER_DST,
+        )
+        .sharing_mode(vk::SharingMode::EXCLUSIVE)
+        .push_next(&mut external);
+
+    // SAFETY: the create info is valid, and the buffer is destroyed on every failure below.
+    let buffer = unsafe { raw.create_buffer(&info, None) }.map_err(failed)?;
+    let requirements = unsafe { raw.get_buffer_memory_requirements(buffer) };
+    let properties =
+        unsafe { instance.get_physical_device_memory_properties(hal_device.raw_physical_device()) };
+
+    let memory_type = (0..properties.memory_type_count).find(|&index| {
+        requirements.memory_type_bits & (1 << index) != 0
+            && properties.memory_types[index as usize]
+                .property_flags
+                .contains(vk::MemoryPropertyFlags::DEVICE_LOCAL)
+    });
+
+    let Some(memory_type) = memory_type.filter(|_| requirements.size <= memory.size) else {
+        unsafe { raw.destroy_buffer(buffer, None) };
+        return Err(WiscError::ImportFailed(
+            "the memory can't back a buffer of that size".to_string(),
+        ));
+    };
+
+    // Vulkan only takes ownership of the descriptor if the import succeeds.
+    let fd = memory.fd.into_raw_fd();
+    let mut import = vk::ImportMemoryFdInfoKHR::default()
+        .handle_type(vk::ExternalMemoryHandleTypeFlags::OPAQUE_FD)
+        .fd(fd);
+    let allocate = vk::MemoryAllocateInfo::default()
+        .allocation_size(memory.size)
+        .memory_type_index(memory_type)
+        .push_next(&mut import);
+
+    let device_memory = match unsafe { raw.allocate_memory(&allocate, None) } {
+        Ok(device_memory) => device_memory,
+        Err(error) => {
+            // SAFETY: the descriptor is still ours, since the import failed.
+            drop(unsafe { OwnedFd::from_raw_fd(fd) });
+            unsafe { raw.destroy_buffer(buffer, None) };
+            return Err(failed(error));
+        }
+    };
+
+    if let Err(error) = unsafe { raw.bind_buffer_memory(buffer, device_memory, 0) } {
+        unsafe {
+            raw.destroy_buffer(buffer, None);
+            raw.free_memory(device_memory, None);
+        }
+        return Err(failed(error));
+    }
+
+    // SAFETY: wgpu now owns the buffer and its memory, and frees both once it is dropped.
+    let hal_buffer = unsafe {
+        wgpu::hal::vulkan::Buffer::from_raw_managed(buffer, device_memory, 0, memory.size)
+    };
+
+    drop(hal_device);
+
+    // SAFETY: the buffer was created on this device, with usages covering these.
+    Ok(unsafe {
+        vd.device.create_buffer_from_hal::<Vulkan>(
+            hal_buffer,
+            &wgpu::BufferDescriptor {
+                label: Some(&format!("WISC Imported Buffer (VDevice {})", vd.label)),
+                size: byte_len,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::UNIFORM
+                    | wgpu::BufferUsages::INDIRECT
+                    | wgpu::BufferUsages::COPY_SRC
+                    | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            },
+        )
+    })
+}
@@ -0,0 +1,160 @@
+//! Kernels compiled ahead of time to SPIR-V, such as those written in Rust with rust-gpu,
+//! along with what their SPIR-V reveals about them.
+
+use crate::error::WiscError;
+use crate::reflect::{self, BindingKind};
+use crate::shader;
+use crate::task::TaskBuilder;
+use crate::workgroup::Workgroup;
+
+/// How a kernel declares a resource in bind group 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingAccess {
+    /// A uniform buffer, bound with
+    /// [`with_uniform_buffer`](TaskBuilder::with_uniform_buffer) or
+    /// [`with_uniform_input`](TaskBuilder::with_uniform_input).
+    Uniform,
+    /// A read-only storage buffer, bound as an input.
+    ReadOnly,
+    /// A read-write storage buffer, bound as an output.
+    ReadWrite,
+    /// Anything that isn't a buffer, which tasks can't bind.
+    Other,
+}
+
+/// A resource a kernel declares in bind group 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KernelBinding {
+    pub binding: u32,
+    pub access: BindingAccess,
+    /// Whether the entry point actually touches it. Tasks must bind every used binding.
+    pub used: bool,
+}
+
+/// One entry point of a SPIR-V module, ready to be run as tasks across a Workgroup.
+///
+/// ```no_run
+/// use wisc::{kernel::Kernel, prelude::*};
+///
+/// // Built by rust-gpu from `#[spirv(compute(threads(64)))] pub fn add(...)`.
+/// let spirv = std::fs::read("add.spv").expect("Failed to read the kernel");
+/// let kernel = Kernel::from_rust_gpu(&spirv, "add")?;
+///
+/// let mut workgroup = Workgroup::from_devices(VDevice::all());
+/// let a = workgroup.create_vbuffer(vec![1u32; 1024]);
+/// let b = workgroup.create_vbuffer(vec![2u32; 1024]);
+/// let sum = workgroup.create_vbuffer_uninit::<u32>(1024);
+///
+/// kernel
+///     .task(&mut workgroup)
+///     .with_size_per_element(sum)
+///     .with_input_buffer(0, a)
+///     .with_input_buffer(1, b)
+///     .with_output_buffer(2, sum)
+///     .build()?
+///     .run()?;
+/// # Ok::<(), WiscError>(())
+/// ```
+pub struct Kernel {
+    descriptor: wgpu::ShaderModuleDescriptor<'static>,
+    entry_point: String,
+    entry_points: Vec<String>,
+    bindings: Vec<KernelBinding>,
+    workgroup_size: Option<[u32; 3]>,
+}
+
+impl Kernel {
+    /// Loads the compute entry point `entry` from a SPIR-V binary built by rust-gpu (or any
+    /// other SPIR-V compiler). Fails if the bytes aren't SPIR-V, or the module has no
+    /// compute entry point by that name, listing the ones it has.
+    ///
+    /// SPIR-V that naga can't read is still accepted, but then nothing is known about it
+    /// beyond the given entry point.
+    pub fn from_rust_gpu(spirv_bytes: &[u8], entry: &str) -> Result<Self, WiscError> {
+        let descriptor = shader::spirv(spirv_bytes)?.0;
+
+        let Some(module) = reflect::parse(&descriptor.source)? else {
+            return Ok(Self {
+                descriptor,
+                entry_point: entry.to_string(),
+                entry_points: vec![entry.to_string()],
+                bindings: vec![],
+                workgroup_size: None,
+            });
+        };
+
+        let entry_points = reflect::compute_entry_points(&module);
+
+        if !entry_points.iter().any(|candidate| candidate == entry) {
+            return Err(WiscError::UnknownKernel {
+                kernel: entry.to_string(),
+                candidates: entry_points,
+            });
+        }
+
+        let mut bindings: Vec<KernelBinding> = reflect::bindings(&module, entry)
+            .into_iter()
+            .map(|binding| KernelBinding {
+                binding: binding.binding,
+                access: match binding.kind {
+                    BindingKind::Uniform => BindingAccess::Uniform,
+                    BindingKind::ReadOnlyStorage => BindingAccess::ReadOnly,
+                    BindingKind::ReadWriteStorage => BindingAccess::ReadWrite,
+                    BindingKind::Other => BindingAccess::Other,
+                },
+                used: binding.used,
+            })
+            .collect();
+
+        bindings.sort_by_key(|binding| binding.binding);
+
+        Ok(Self {
+            workgroup_size: reflect::workgroup_size(&module, entry),
+            descriptor,
+            entry_point: entry.to_string(),
+            entry_points,
+            bindings,
+        })
+    }
+
+    /// The entry point this kernel runs.
+    pub fn entry_point(&self) -> &str {
+        &self.entry_point
+    }
+
+    /// Every compute entry point in the module, in declaration order.
+    pub fn entry_points(&self) -> &[String] {
+        &self.entry_points
+    }
+
+    /// The resources the module declares in bind group 0, in binding order.
+    pub fn bindings(&self) -> &[KernelBinding] {
+        &self.bindings
+    }
+
+    /// The entry point's workgroup size, if it is known.
+    pub fn workgroup_size(&self) -> Option<[u32; 3]> {
+        self.workgroup_size
+    }
+
+    /// The same module's entry point `entry`, with its own reflection.
+    pub fn with_entry_point(&self, entry: &str) -> Result<Self, WiscError> {
+        match &self.descriptor.source {
+            wgpu::ShaderSource::SpirV(words) => {
+                Self::from_rust_gpu(bytemuck::cast_slice(words), entry)
+            }
+            _ => unreachable!("kernels are always SPIR-V"),
+        }
+    }
+
+    /// Starts a task that runs this kernel on `workgroup`.
+    pub fn task<'w>(&self, workgroup: &'w mut Workgroup) -> TaskBuilder<'w> {
+        TaskBuilder::new(workgroup, self.descriptor.clone()).with_kernel(self.entry_point.clone())
+    }
+
+    /// Compiles the module on every device of `workgroup` under `name`, for tasks started
+    /// with [`TaskBuilder::with_registered_shader`] and this kernel's entry point.
+    pub fn register(&self, workgroup: &mut Workgroup, name: &str) {
+        workgroup.register_shader(name, self.descriptor.clone());
+    }
+}
@@ -1,6 +1,57 @@
 pub mod prelude;
 
+/// The version of wgpu wisc is built on, for what wisc's own types don't cover. Unlike
+/// the rest of the API, this changes with each major version of wgpu.
+pub use wgpu;
+
+#[cfg(feature = "benchmarks")]
+pub mod bench;
+pub(crate) mod cache;
+pub mod calibration;
+pub mod chain;
+pub(crate) mod checksum;
+pub mod collective;
+pub mod complex;
+pub(crate) mod counter;
+pub mod dispatch;
+#[cfg(feature = "dlpack")]
+pub mod dlpack;
+pub mod element;
+pub mod error;
+pub mod experiment;
+pub(crate) mod failover;
+pub mod fft;
+pub mod history;
+#[cfg(all(feature = "vulkan-interop", unix))]
+pub mod interop;
+#[cfg(feature = "spirv")]
+pub mod kernel;
+pub mod nn;
+pub mod ops;
+pub mod partition;
+pub(crate) mod pipeline_cache;
+#[cfg(feature = "playground")]
+pub mod playground;
+pub mod precision;
+pub mod quant;
+pub(crate) mod recovery;
+pub(crate) mod reflect;
+pub mod report;
+pub(crate) mod result_cache;
+pub(crate) mod runtime;
+pub mod scheduler;
+pub mod shader;
+pub(crate) mod shutdown;
+pub(crate) mod snapshot;
+pub(crate) mod statistics;
+pub mod stream;
 pub mod task;
+pub mod template;
+pub(crate) mod throttle;
+pub mod timeslice;
+pub(crate) mod timestamp;
+pub(crate) mod trace;
 pub mod vbuffer;
 pub mod vdevice;
+pub(crate) mod watch;
 pub mod workgroup;
@@ -0,0 +1,252 @@
+//! A small set of neural network layers for inference, each run as a task over `f32`
+//! VBuffers holding row-major `[batch, features]` matrices. The batch is split across the
+//! devices by their weightings, one whole row at a time, while parameters like weights are
+//! given to every device in full.
+//!
+//! Layers set the element group size of the matrices they touch to their row length, so
+//! that partitioning never splits a row.
+
+use std::any::TypeId;
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::dispatch::DispatchSize;
+use crate::error::WiscError;
+use crate::partition::PartitionMode;
+use crate::report::RunReport;
+use crate::task::TaskBuilder;
+use crate::workgroup::{VBufferHandle, Workgroup};
+
+const SHADER: &str = "wisc::nn";
+
+/// The dimensions of a row-major matrix of `batch` rows of `features` each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shape {
+    pub batch: usize,
+    pub features: usize,
+}
+
+impl Shape {
+    pub fn new(batch: usize, features: usize) -> Self {
+        Self { batch, features }
+    }
+
+    pub fn len(&self) -> usize {
+        self.batch * self.features
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// An elementwise activation function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activation {
+    Relu,
+    Sigmoid,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct NnParams {
+    in_features: u32,
+    out_features: u32,
+    epsilon: f32,
+    _padding: u32,
+}
+
+impl NnParams {
+    fn new(in_features: usize, out_features: usize, epsilon: f32) -> Result<Self, WiscError> {
+        Ok(Self {
+            in_features: u32::try_from(in_features).map_err(|_| WiscError::OutOfBounds)?,
+            out_features: u32::try_from(out_features).map_err(|_| WiscError::OutOfBounds)?,
+            epsilon,
+            _padding: 0,
+        })
+    }
+}
+
+/// A fully connected layer: `output = input · weightsᵀ + bias`, where `weights` is
+/// `[out_features, shape.features]` and `bias` is `[out_features]`. `output` must hold
+/// `[shape.batch, out_features]`.
+pub fn dense(
+    workgroup: &mut Workgroup,
+    input: VBufferHandle,
+    shape: Shape,
+    weights: VBufferHandle,
+    bias: VBufferHandle,
+    output: VBufferHandle,
+    out_features: usize,
+) -> Result<RunReport, WiscError> {
+    let out_shape = Shape::new(shape.batch, out_features);
+
+    check_len(
+        workgroup,
+        input,
+        shape.len(),
+        "the input doesn't hold the shape",
+    )?;
+    check_len(
+        workgroup,
+        weights,
+        out_features * shape.features,
+        "the weights aren't [out_features, in_features]",
+    )?;
+    check_len(
+        workgroup,
+        bias,
+        out_features,
+        "the bias isn't [out_features]",
+    )?;
+    check_len(
+        workgroup,
+        output,
+        out_shape.len(),
+        "the output isn't [batch, out_features]",
+    )?;
+
+    rows(workgroup, input, shape)?;
+    rows(workgroup, output, out_shape)?;
+
+    layer(workgroup, "dense")
+        .with_size_per_element(output)
+        .with_input_buffer_partitioned(0, input, PartitionMode::Weighted)
+        .with_input_buffer(1, weights)
+        .with_input_buffer(2, bias)
+        .with_output_buffer_partitioned(3, output, PartitionMode::Weighted)
+        .with_uniform_buffer(4, NnParams::new(shape.features, out_features, 0.0)?)
+        .build()?
+        .run()
+}
+
+/// Applies `activation` to every element of `input`, writing the results to `output`.
+pub fn activation(
+    workgroup: &mut Workgroup,
+    activation: Activation,
+    input: VBufferHandle,
+    output: VBufferHandle,
+    shape: Shape,
+) -> Result<RunReport, WiscError> {
+    let kernel = match activation {
+        Activation::Relu => "relu",
+        Activation::Sigmoid => "sigmoid",
+    };
+
+    elementwise(workgroup, kernel, input, output, shape)?
+        .with_size_per_element(output)
+        .with_uniform_buffer(4, NnParams::new(shape.features, shape.features, 0.0)?)
+        .build()?
+        .run()
+}
+
+/// Turns every row of `input` into a probability distribution in `output`.
+pub fn softmax(
+    workgroup: &mut Workgroup,
+    input: VBufferHandle,
+    output: VBufferHandle,
+    shape: Shape,
+) -> Result<RunReport, WiscError> {
+    elementwise(workgroup, "softmax", input, output, shape)?
+        .with_size(per_row(shape))
+        .with_uniform_buffer(4, NnParams::new(shape.features, shape.features, 0.0)?)
+        .build()?
+        .run()
+}
+
+/// Normalizes every row of `input` to zero mean and unit variance, then scales it by
+/// `gamma` and shifts it by `beta` (both `[shape.features]`) into `output`. `epsilon` keeps
+/// the division stable for rows with little variance.
+pub fn layer_norm(
+    workgroup: &mut Workgroup,
+    input: VBufferHandle,
+    gamma: VBufferHandle,
+    beta: VBufferHandle,
+    output: VBufferHandle,
+    shape: Shape,
+    epsilon: f32,
+) -> Result<RunReport, WiscError> {
+    check_len(workgroup, gamma, shape.features, "gamma isn't [features]")?;
+    check_len(workgroup, beta, shape.features, "beta isn't [features]")?;
+
+    elementwise(workgroup, "layer_norm", input, output, shape)?
+        .with_size(per_row(shape))
+        .with_input_buffer(1, gamma)
+        .with_input_buffer(2, beta)
+        .with_uniform_buffer(4, NnParams::new(shape.features, shape.features, epsilon)?)
+        .build()?
+        .run()
+}
+
+/// A task running `kernel` from the layer shader, registering it on first use.
+fn layer<'w>(workgroup: &'w mut Workgroup, kernel: &str) -> TaskBuilder<'w> {
+    if !workgroup.has_registered_shader(SHADER) {
+        workgroup.register_shader(SHADER, crate::include_wgsl!("nn.wgsl"));
+    }
+
+    TaskBuilder::from_workgroup(workgroup)
+        .with_registered_shader(SHADER)
+        .with_kernel(kernel)
+}
+
+/// A task mapping the rows of `input` to the rows of `output`, both of `shape`.
+fn elementwise<'w>(
+    workgroup: &'w mut Workgroup,
+    kernel: &str,
+    input: VBufferHandle,
+    output: VBufferHandle,
+    shape: Shape,
+) -> Result<TaskBuilder<'w>, WiscError> {
+    check_len(
+        workgroup,
+        input,
+        shape.len(),
+        "the input doesn't hold the shape",
+    )?;
+    check_len(
+        workgroup,
+        output,
+        shape.len(),
+        "the output doesn't hold the shape",
+    )?;
+
+    rows(workgroup, input, shape)?;
+    rows(workgroup, output, shape)?;
+
+    Ok(layer(workgroup, kernel)
+        .with_input_buffer_partitioned(0, input, PartitionMode::Weighted)
+        .with_output_buffer_partitioned(3, output, PartitionMode::Weighted))
+}
+
+/// One invocation per row of the whole batch. Devices holding fewer rows skip the rest.
+fn per_row(shape: Shape) -> DispatchSize {
+    DispatchSize::ForElements {
+        count: shape.batch,
+        per_invocation: 1,
+    }
+}
+
+/// Whether `handle` holds `len` `f32`s.
+fn check_len(
+    workgroup: &Workgroup,
+    handle: VBufferHandle,
+    len: usize,
+    reason: &'static str,
+) -> Result<(), WiscError> {
+    workgroup.has_expected_types(&[(handle, TypeId::of::<f32>())])?;
+
+    if workgroup.vbuffers[handle].length != len {
+        return Err(WiscError::ShapeMismatch(reason));
+    }
+
+    Ok(())
+}
+
+/// Keeps partitioning from splitting the rows of `handle`.
+fn rows(workgroup: &mut Workgroup, handle: VBufferHandle, shape: Shape) -> Result<(), WiscError> {
+    if shape.features > 0 {
+        workgroup.set_element_group_size(handle, shape.features)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+// Row-major [batch, features] f32 matrices. Each device holds whole rows of the batch.
+
+struct NnParams {
+    in_features: u32,
+    out_features: u32,
+    epsilon: f32,
+    _padding: u32,
+}
+
+@group(0) @binding(0) var<storage, read> input: array<f32>;
+// Dense weights, [out_features, in_features], or the layer norm scale.
+@group(0) @binding(1) var<storage, read> weights: array<f32>;
+// Dense bias, [out_features], or the layer norm shift.
+@group(0) @binding(2) var<storage, read> bias: array<f32>;
+@group(0) @binding(3) var<storage, read_write> output: array<f32>;
+@group(0) @binding(4) var<uniform> params: NnParams;
+
+// One invocation per output element.
+@compute @workgroup_size(64, 1, 1)
+fn dense(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let index = global_id.x;
+    if (index >= arrayLength(&output)) {
+        return;
+    }
+
+    let row = index / params.out_features;
+    let column = index % params.out_features;
+
+    var sum = bias[column];
+    for (var k = 0u; k < params.in_features; k++) {
+        sum += input[row * params.in_features + k] * weights[column * params.in_features + k];
+    }
+
+    output[index] = sum;
+}
+
+@compute @workgroup_size(64, 1, 1)
+fn relu(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let index = global_id.x;
+    if (index >= arrayLength(&output)) {
+        return;
+    }
+
+    output[index] = max(input[index], 0.0);
+}
+
+@compute @workgroup_size(64, 1, 1)
+fn sigmoid(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let index = global_id.x;
+    if (index >= arrayLength(&output)) {
+        return;
+    }
+
+    output[index] = 1.0 / (1.0 + exp(-input[index]));
+}
+
+// One invocation per row.
+@compute @workgroup_size(64, 1, 1)
+fn softmax(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let row = global_id.x;
+    let n = params.in_features;
+    if (row >= arrayLength(&output) / n) {
+        return;
+    }
+
+    let start = row * n;
+
+    // Subtracting the row's maximum keeps exp from overflowing.
+    var largest = input[start];
+    for (var k = 1u; k < n; k++) {
+        largest = max(largest, input[start + k]);
+    }
+
+    var total = 0.0;
+    for (var k = 0u; k < n; k++) {
+        let e = exp(input[start + k] - largest);
+        output[start + k] = e;
+        total += e;
+    }
+
+    for (var k = 0u; k < n; k++) {
+        output[start + k] /= total;
+    }
+}
+
+// One invocation per row.
+@compute @workgroup_size(64, 1, 1)
+fn layer_norm(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let row = global_id.x;
+    let n = params.in_features;
+    if (row >= arrayLength(&output) / n) {
+        return;
+    }
+
+    let start = row * n;
+
+    var mean = 0.0;
+    for (var k = 0u; k < n; k++) {
+        mean += input[start + k];
+    }
+    mean /= f32(n);
+
+    var variance = 0.0;
+    for (var k = 0u; k < n; k++) {
+        let d = input[start + k] - mean;
+        variance += d * d;
+    }
+    variance /= f32(n);
+
+    let scale = inverseSqrt(variance + params.epsilon);
+    for (var k = 0u; k < n; k++) {
+        output[start + k] = (input[start + k] - mean) * scale * weights[k] + bias[k];
+    }
+}
@@ -0,0 +1,671 @@
+//! Operations on whole VBuffers, run on the devices already holding them.
+//!
+//! [`map`], [`reduce`] and [`sort`] also run on a Workgroup without devices, on the host's
+//! cores, if it was built with
+//! [`cpu_fallback`](crate::workgroup::WorkgroupBuilder::cpu_fallback).
+
+use std::any::TypeId;
+use std::cmp::Ordering;
+use std::ops::Range;
+
+use wgpu::util::DeviceExt;
+
+use crate::collective::{Merge, ReduceOp, Reducible};
+use crate::error::WiscError;
+use crate::partition::PartitionMode;
+use crate::shader::Shader;
+use crate::task::{TaskBuilder, vbuffer_bytes};
+use crate::vbuffer::VBuffer;
+use crate::workgroup::{VBufferHandle, Workgroup};
+
+// Every word is mixed with its index in the whole buffer into two independent lanes, which
+// are summed. Sums don't care which device added what, so the hash is the same however
+// the buffer is split.
+const HASH_WGSL: &str = "
+struct Params {
+    offset: u32,
+    words: u32,
+    base: u32,
+}
+
+@group(0) @binding(0) var<storage, read> data: array<u32>;
+@group(0) @binding(1) var<uniform> params: Params;
+@group(0) @binding(2) var<storage, read_write> sums: array<atomic<u32>, 2>;
+
+fn fmix(x: u32) -> u32 {
+    var h = x;
+    h ^= h >> 16u;
+    h *= 0x85ebca6bu;
+    h ^= h >> 13u;
+    h *= 0xc2b2ae35u;
+    h ^= h >> 16u;
+    return h;
+}
+
+@compute @workgroup_size(256, 1, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>, @builtin(num_workgroups) n: vec3<u32>) {
+    let stride = n.x * 256u;
+    var low = 0u;
+    var high = 0u;
+
+    for (var i = id.x; i < params.words; i += stride) {
+        let word = data[params.offset + i];
+        let key = fmix(params.base + i + 0x9e3779b9u);
+
+        low += fmix(word ^ key);
+        high += fmix(word + fmix(key ^ 0x68e31da4u));
+    }
+
+    atomicAdd(&sums[0], low);
+    atomicAdd(&sums[1], high);
+}
+";
+
+const WORKGROUPS: u32 = 64;
+
+/// The same mixing as the hash kernel's `fmix`.
+fn fmix(mut h: u32) -> u32 {
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^= h >> 16;
+    h
+}
+
+/// The lanes of `words`, the first of them at `base` in the whole buffer, as the hash
+/// kernel sums them.
+fn hash_words(words: impl Iterator<Item = u32>, base: u32) -> [u32; 2] {
+    words.enumerate().fold([0u32; 2], |[low, high], (i, word)| {
+        let key = fmix(base.wrapping_add(i as u32).wrapping_add(0x9e37_79b9));
+
+        [
+            low.wrapping_add(fmix(word ^ key)),
+            high.wrapping_add(fmix(word.wrapping_add(fmix(key ^ 0x68e3_1da4)))),
+        ]
+    })
+}
+
+/// A fast, non-cryptographic 64-bit hash of a VBuffer's contents, for telling whether they
+/// changed (say, to skip uploading them again, or to key cached results) or whether two
+/// Workgroups computed the same thing.
+///
+/// Where the devices hold the buffer, each hashes the elements it owns and only the hashes
+/// are read back and combined; otherwise the host copy is hashed. Either way, and however
+/// the buffer is partitioned, equal contents hash equally.
+pub fn hash_buffer(workgroup: &Workgroup, handle: VBufferHandle) -> Result<u64, WiscError> {
+    let vbuffer = workgroup
+        .vbuffers
+        .get(handle)
+        .ok_or(WiscError::UnknownVBuffer)?;
+
+    let byte_len = (vbuffer.length * vbuffer.stride) as u64;
+
+    let lanes = match device_shares(vbuffer) {
+        Some(shares) => hash_on_devices(workgroup, vbuffer, &shares)?,
+        None => {
+            let pending = workgroup.pending_contents(vbuffer)?;
+
+            if vbuffer.assume_init.is_some() && pending.is_none() {
+                return Err(WiscError::Uninitialized);
+            }
+
+            let bytes = pending.as_deref().unwrap_or(vbuffer_bytes(vbuffer));
+            let words = bytes.chunks(4).map(|chunk| {
+                let mut word = [0u8; 4];
+                word[..chunk.len()].copy_from_slice(chunk);
+                u32::from_le_bytes(word)
+            });
+
+            hash_words(words, 0)
+        }
+    };
+
+    let [low, high] = lanes;
+
+    Ok(finish((high as u64) << 32 | low as u64, byte_len))
+}
+
+/// Folds the buffer's length into the summed lanes, and spreads them over all 64 bits.
+fn finish(lanes: u64, byte_len: u64) -> u64 {
+    let mut h = lanes ^ byte_len.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce9_b5e7);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    h ^ (h >> 31)
+}
+
+/// The elements each device hashes, if between them the devices own every element exactly
+/// once after overlaps are given to the first owner. `None` if the buffer should be hashed
+/// on the host instead: it isn't on the devices, or its elements aren't whole words.
+fn device_shares(vbuffer: &VBuffer) -> Option<Vec<Range<usize>>> {
+    let resident = vbuffer.residency.resident()?;
+
+    if !vbuffer.stride.is_multiple_of(4)
+        || resident
+            .buffers
+            .iter()
+            .any(|buffer| !buffer.usage().contains(wgpu::BufferUsages::STORAGE))
+    {
+        return None;
+    }
+
+    let mut order: Vec<usize> = (0..resident.owned.len()).collect();
+    order.sort_by_key(|vdi| resident.owned[*vdi].start);
+
+    let mut shares = vec![0..0; resident.owned.len()];
+    let mut covered = 0;
+
+    for vdi in order {
+        let owned = &resident.owned[vdi];
+        if owned.is_empty() {
+            continue;
+        }
+
+        if owned.start > covered {
+            return None;
+        }
+
+        if owned.end > covered {
+            shares[vdi] = covered..owned.end;
+            covered = owned.end;
+        }
+    }
+
+    (covered == vbuffer.length).then_some(shares)
+}
+
+/// Hashes each device's share where it is, and sums the lanes they read back.
+fn hash_on_devices(
+    workgroup: &Workgroup,
+    vbuffer: &VBuffer,
+    shares: &[Range<usize>],
+) -> Result<[u32; 2], WiscError> {
+    let resident = vbuffer.residency.resident().unwrap();
+    let words_per_element = vbuffer.stride / 4;
+    let mut pending = vec![];
+
+    for (vdi, vd) in workgroup.vdevices.iter().enumerate() {
+        let share = &shares[vdi];
+        if share.is_empty() {
+            continue;
+        }
+
+        let params = [
+            ((share.start - resident.ranges[vdi].start) * words_per_element) as u32,
+            (share.len() * words_per_element) as u32,
+            (share.start * words_per_element) as u32,
+            0,
+        ];
+
+        let module = vd.modules.module(
+            vd,
+            &wgpu::ShaderModuleDescriptor {
+                label: Some("WISC Hash"),
+                source: wgpu::ShaderSource::Wgsl(HASH_WGSL.into()),
+            },
+        );
+
+        let pipeline = vd
+            .device
+            .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("WISC Hash"),
+                layout: None,
+                module: &module,
+                entry_point: Some("main"),
+                compilation_options: Default::default(),
+                cache: None,
+            });
+
+        let params = vd
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(&format!("WISC Hash Params (VDevice {})", vd.label)),
+                contents: bytemuck::cast_slice(&params),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+
+        let sums = vd.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("WISC Hash Sums (VDevice {})", vd.label)),
+            size: 2 * std::mem::size_of::<u32>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = vd.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: resident.buffers[vdi].as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: params.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: sums.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = vd
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("WISC Hash"),
+            });
+
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: None,
+                timestamp_writes: None,
+            });
+
+            compute_pass.set_pipeline(&pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[]);
+            compute_pass.dispatch_workgroups(WORKGROUPS, 1, 1);
+        }
+
+        vd.queue.submit([encoder.finish()]);
+        pending.push((vd, sums));
+    }
+
+    // Every device hashes before any is waited on.
+    let mut lanes = [0u32; 2];
+
+    for (vd, sums) in pending {
+        let sums: [u32; 2] = bytemuck::pod_read_unaligned(&vd.read_buffer(&sums)?);
+
+        lanes[0] = lanes[0].wrapping_add(sums[0]);
+        lanes[1] = lanes[1].wrapping_add(sums[1]);
+    }
+
+    Ok(lanes)
+}
+
+/// A number type [`map`], [`reduce`] and [`sort`] work on, which their kernels name in
+/// WGSL.
+pub trait Scalar: Reducible + PartialOrd + Send + Sync {
+    const WGSL: &'static str;
+    /// No value is less; `-inf` for floats.
+    const LEAST: Self;
+    /// No value is greater; `inf` for floats.
+    const GREATEST: Self;
+    const ZERO: Self;
+    const ONE: Self;
+}
+
+macro_rules! scalar {
+    ($($t:ty => $wgsl:literal, $least:expr, $greatest:expr, $zero:literal, $one:literal);*) => {
+        $(
+            impl Scalar for $t {
+                const WGSL: &'static str = $wgsl;
+                const LEAST: Self = $least;
+                const GREATEST: Self = $greatest;
+                const ZERO: Self = $zero;
+                const ONE: Self = $one;
+            }
+        )*
+    };
+}
+
+scalar!(
+    u32 => "u32", u32::MIN, u32::MAX, 0, 1;
+    i32 => "i32", i32::MIN, i32::MAX, 0, 1;
+    f32 => "f32", f32::NEG_INFINITY, f32::INFINITY, 0.0, 1.0
+);
+
+/// The value that combining with leaves others unchanged.
+fn identity<T: Scalar>(op: ReduceOp) -> T {
+    match op {
+        ReduceOp::Sum => T::ZERO,
+        ReduceOp::Product => T::ONE,
+        ReduceOp::Min => T::GREATEST,
+        ReduceOp::Max => T::LEAST,
+    }
+}
+
+// Every element is passed through `apply`, which returns the expression the caller gave.
+const MAP_WGSL: &str = "
+@group(0) @binding(0) var<storage, read> src: array<T>;
+@group(0) @binding(1) var<storage, read_write> dst: array<T>;
+
+fn apply(x: T) -> T {
+    return EXPRESSION;
+}
+
+@compute @workgroup_size(256, 1, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= arrayLength(&dst)) {
+        return;
+    }
+
+    dst[id.x] = apply(src[id.x]);
+}
+";
+
+// Each invocation folds a strided share of its device's elements into one partial, and the
+// devices' partials are folded together as they are written back.
+const REDUCE_WGSL: &str = "
+struct Params {
+    identity: T,
+}
+
+@group(0) @binding(0) var<storage, read> data: array<T>;
+@group(0) @binding(1) var<uniform> params: Params;
+@group(0) @binding(2) var<storage, read_write> partials: array<T>;
+
+fn combine(a: T, b: T) -> T {
+    return COMBINE;
+}
+
+@compute @workgroup_size(256, 1, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>, @builtin(num_workgroups) n: vec3<u32>) {
+    let stride = n.x * 256u;
+    var acc = params.identity;
+
+    for (var i = id.x; i < arrayLength(&data); i += stride) {
+        acc = combine(acc, data[i]);
+    }
+
+    partials[id.x] = acc;
+}
+";
+
+// Each workgroup sorts its 256 elements with a bitonic network in workgroup memory, the
+// missing ones at the end of a device's share padded with the greatest value.
+const SORT_WGSL: &str = "
+struct Params {
+    greatest: T,
+}
+
+@group(0) @binding(0) var<storage, read> src: array<T>;
+@group(0) @binding(1) var<uniform> params: Params;
+@group(0) @binding(2) var<storage, read_write> dst: array<T>;
+
+var<workgroup> run: array<T, 256>;
+
+@compute @workgroup_size(256, 1, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>, @builtin(local_invocation_index) local: u32) {
+    let length = arrayLength(&dst);
+
+    if (id.x < length) {
+        run[local] = src[id.x];
+    } else {
+        run[local] = params.greatest;
+    }
+    workgroupBarrier();
+
+    for (var k = 2u; k <= 256u; k <<= 1u) {
+        for (var j = k >> 1u; j > 0u; j >>= 1u) {
+            if (local < 128u) {
+                let i = (local / j) * 2u * j + local % j;
+                let ascending = (i & k) == 0u;
+
+                if ((run[i] > run[i + j]) == ascending) {
+                    let swapped = run[i];
+                    run[i] = run[i + j];
+                    run[i + j] = swapped;
+                }
+            }
+            workgroupBarrier();
+        }
+    }
+
+    if (id.x < length) {
+        dst[id.x] = run[local];
+    }
+}
+";
+
+const PARTIALS: usize = WORKGROUPS as usize * 256;
+
+/// Applies `expression`, WGSL in terms of an element `x`, to every element of the VBuffer
+/// at `handle`, and returns a new VBuffer of the results. The devices each map their
+/// weighted share of the elements.
+///
+/// On the host, [`cpu_fallback`](crate::workgroup::WorkgroupBuilder::cpu_fallback) applies
+/// `on_host` instead, which should compute the same thing: `map(w, h, "x * 2.0", |x| x *
+/// 2.0)`.
+pub fn map<T, F>(
+    workgroup: &mut Workgroup,
+    handle: VBufferHandle,
+    expression: &str,
+    on_host: F,
+) -> Result<VBufferHandle, WiscError>
+where
+    T: Scalar,
+    F: Fn(T) -> T + Sync,
+{
+    workgroup.read_back_pending(handle)?;
+    let length = host_copy::<T>(workgroup, handle)?.len();
+
+    if on_host_instead(workgroup, length)? {
+        let mut data = host_copy::<T>(workgroup, handle)?.clone();
+
+        in_parallel(&mut data, |chunk| {
+            for x in chunk {
+                *x = on_host(*x);
+            }
+        });
+
+        return Ok(workgroup.create_vbuffer(data));
+    }
+
+    let source = kernel::<T>(&MAP_WGSL.replace("EXPRESSION", expression));
+    let mapped = workgroup.create_vbuffer_uninit::<T>(length);
+
+    TaskBuilder::new(workgroup, source)
+        .with_size_per_element(mapped)
+        .with_input_buffer_partitioned(0, handle, PartitionMode::Weighted)
+        .with_output_buffer_partitioned(1, mapped, PartitionMode::Weighted)
+        .build()?
+        .run()?;
+
+    Ok(mapped)
+}
+
+/// Combines every element of the VBuffer at `handle` with `op`, in no particular order. The
+/// devices each reduce their weighted share of the elements, and the host combines their
+/// partial results.
+pub fn reduce<T: Scalar>(
+    workgroup: &mut Workgroup,
+    handle: VBufferHandle,
+    op: ReduceOp,
+) -> Result<T, WiscError> {
+    workgroup.read_back_pending(handle)?;
+    let length = host_copy::<T>(workgroup, handle)?.len();
+
+    if on_host_instead(workgroup, length)? {
+        let data = host_copy::<T>(workgroup, handle)?;
+
+        return Ok(fold_in_parallel(data, op));
+    }
+
+    let combine = match op {
+        ReduceOp::Sum => "a + b",
+        ReduceOp::Product => "a * b",
+        ReduceOp::Min => "min(a, b)",
+        ReduceOp::Max => "max(a, b)",
+    };
+    let source = kernel::<T>(&REDUCE_WGSL.replace("COMBINE", combine));
+    let partials = workgroup.create_vbuffer_uninit::<T>(PARTIALS);
+
+    TaskBuilder::new(workgroup, source)
+        .with_size((WORKGROUPS, 1, 1))
+        .with_input_buffer_partitioned(0, handle, PartitionMode::Weighted)
+        .with_uniform_buffer(1, identity::<T>(op))
+        .with_merged_output_buffer(
+            2,
+            partials,
+            Merge::Custom(Box::new(move |a: T, b| a.reduce(b, op))),
+        )
+        .build()?
+        .run()?;
+
+    let partials = workgroup.take_vbuffer::<T>(partials)?;
+
+    Ok(partials
+        .into_iter()
+        .fold(identity(op), |acc, partial| acc.reduce(partial, op)))
+}
+
+/// Returns a new VBuffer of the elements of the one at `handle` in ascending order. The
+/// devices each sort runs of their weighted share of the elements, and the host merges the
+/// runs. Where NaNs end up is unspecified.
+pub fn sort<T: Scalar>(
+    workgroup: &mut Workgroup,
+    handle: VBufferHandle,
+) -> Result<VBufferHandle, WiscError> {
+    workgroup.read_back_pending(handle)?;
+    let length = host_copy::<T>(workgroup, handle)?.len();
+
+    if on_host_instead(workgroup, length)? {
+        let mut data = host_copy::<T>(workgroup, handle)?.clone();
+
+        in_parallel(&mut data, |chunk| {
+            chunk.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+        });
+
+        return Ok(workgroup.create_vbuffer(merge_runs(data)));
+    }
+
+    let source = kernel::<T>(SORT_WGSL);
+    let runs = workgroup.create_vbuffer_uninit::<T>(length);
+
+    TaskBuilder::new(workgroup, source)
+        .with_size_per_element(runs)
+        .with_input_buffer_partitioned(0, handle, PartitionMode::Weighted)
+        .with_uniform_buffer(1, T::GREATEST)
+        .with_output_buffer_partitioned(2, runs, PartitionMode::Weighted)
+        .build()?
+        .run()?;
+
+    let runs = workgroup.take_vbuffer::<T>(runs)?;
+
+    Ok(workgroup.create_vbuffer(merge_runs(runs)))
+}
+
+/// One of the kernels above, for elements of type `T`.
+fn kernel<T: Scalar>(source: &str) -> Shader<'static> {
+    Shader::wgsl(format!("alias T = {};\n{source}", T::WGSL))
+}
+
+/// The host copy of the VBuffer at `handle`, as `T`s.
+fn host_copy<T: Scalar>(
+    workgroup: &Workgroup,
+    handle: VBufferHandle,
+) -> Result<&Vec<T>, WiscError> {
+    let vbuffer = workgroup
+        .vbuffers
+        .get(handle)
+        .ok_or(WiscError::UnknownVBuffer)?;
+
+    if vbuffer.assume_init.is_some() {
+        return Err(WiscError::Uninitialized);
+    }
+
+    if vbuffer.typeid != TypeId::of::<T>() {
+        return Err(WiscError::TypeMismatch);
+    }
+
+    vbuffer
+        .inner
+        .downcast_ref::<Vec<T>>()
+        .ok_or(WiscError::TypeMismatch)
+}
+
+/// Whether an op over `length` elements should run on the host: there's nothing to
+/// dispatch, or no device to dispatch it to and the Workgroup falls back to the host.
+fn on_host_instead(workgroup: &Workgroup, length: usize) -> Result<bool, WiscError> {
+    if !workgroup.vdevices.is_empty() {
+        return Ok(length == 0);
+    }
+
+    match workgroup.cpu_fallback {
+        true => Ok(true),
+        false => Err(WiscError::NoDevices),
+    }
+}
+
+/// Calls `f` on contiguous chunks of `data`, one per available core, each on its own thread.
+fn in_parallel<T: Send, F: Fn(&mut [T]) + Sync>(data: &mut [T], f: F) {
+    let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+    let chunk = data.len().div_ceil(threads).max(1);
+
+    std::thread::scope(|scope| {
+        for part in data.chunks_mut(chunk) {
+            let f = &f;
+            scope.spawn(move || f(part));
+        }
+    });
+}
+
+/// Combines every element of `data` with `op`, a chunk per available core.
+fn fold_in_parallel<T: Scalar>(data: &[T], op: ReduceOp) -> T {
+    let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+    let chunk = data.len().div_ceil(threads).max(1);
+
+    std::thread::scope(|scope| {
+        let partials: Vec<_> = data
+            .chunks(chunk)
+            .map(|part| {
+                scope.spawn(move || {
+                    part.iter()
+                        .fold(identity(op), |acc: T, &x| acc.reduce(x, op))
+                })
+            })
+            .collect();
+
+        partials
+            .into_iter()
+            .map(|partial| partial.join().unwrap())
+            .fold(identity(op), |acc: T, partial| acc.reduce(partial, op))
+    })
+}
+
+/// Sorts `data`, made of ascending runs, by merging neighbouring runs until one is left.
+fn merge_runs<T: Scalar>(mut data: Vec<T>) -> Vec<T> {
+    // Where each run starts, and then where the last one ends.
+    let mut bounds = vec![0];
+    bounds.extend((1..data.len()).filter(|&i| data[i] < data[i - 1]));
+    bounds.push(data.len());
+
+    let mut merged = Vec::with_capacity(data.len());
+
+    while bounds.len() > 2 {
+        merged.clear();
+        let mut next = vec![0];
+
+        for i in (0..bounds.len() - 1).step_by(2) {
+            let (start, middle) = (bounds[i], bounds[i + 1]);
+            // An odd run out is carried over as it is.
+            let end = bounds.get(i + 2).copied().unwrap_or(middle);
+
+            merge_into(&mut merged, &data[start..middle], &data[middle..end]);
+            next.push(end);
+        }
+
+        std::mem::swap(&mut data, &mut merged);
+        bounds = next;
+    }
+
+    data
+}
+
+/// Appends the ascending `a` and `b` to `out` in ascending order, `a`'s first among equals.
+fn merge_into<T: Scalar>(out: &mut Vec<T>, mut a: &[T], mut b: &[T]) {
+    while let (Some(&x), Some(&y)) = (a.first(), b.first()) {
+        if y < x {
+            out.push(y);
+            b = &b[1..];
+        } else {
+            out.push(x);
+            a = &a[1..];
+        }
+    }
+
+    out.extend_from_slice(a);
+    out.extend_from_slice(b);
+}
@@ -0,0 +1,602 @@
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::error::WiscError;
+use crate::vbuffer::VBuffer;
+
+/// The binding in group 0 at which tasks supply each device's [`PartitionInfo`] as a
+/// uniform. It is bound automatically when a reflectable shader declares it, or on request
+/// with [`TaskBuilder::with_partition_info`](crate::task::TaskBuilder::with_partition_info).
+pub const PARTITION_INFO_BINDING: u32 = 999;
+
+/// A WGSL declaration of the partition uniform, to paste (or `//#include`) into kernels.
+pub const PARTITION_INFO_WGSL: &str = "\
+struct WiscPartition {
+    offset: u32,
+    len: u32,
+    device: u32,
+    devices: u32,
+}
+
+@group(0) @binding(999) var<uniform> wisc_partition: WiscPartition;
+";
+
+/// Where a device's chunk of a partitioned buffer sits in the whole buffer, so kernels can
+/// turn local indices into global ones (`wisc_partition.offset + local_index`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod, Zeroable)]
+pub struct PartitionInfo {
+    /// The global index of the device's first held element.
+    pub offset: u32,
+    /// How many elements the device holds.
+    pub len: u32,
+    /// The device's index in the Workgroup.
+    pub device: u32,
+    /// How many devices the Workgroup has.
+    pub devices: u32,
+}
+
+/// A user supplied partitioner. It is called with the buffer's length in elements and the
+/// Workgroup's normalized device weightings (strongest device first), and returns one
+/// element range per device.
+pub type PartitionFn = dyn Fn(usize, &[f32]) -> Vec<Range<usize>> + Send + Sync;
+
+/// How a VBuffer's elements are distributed across the devices of a Workgroup.
+///
+/// The modes that cut a buffer between the devices never give one more of it than its
+/// largest binding holds, or than its
+/// [memory cap](crate::workgroup::Workgroup::set_memory_cap) leaves room for; what it
+/// can't take goes to the others.
+#[derive(Clone, Default)]
+pub enum PartitionMode {
+    /// Every device receives the whole buffer.
+    #[default]
+    Unmanaged,
+    /// The buffer is cut into contiguous, equally sized chunks, one per device.
+    Split,
+    /// The buffer is cut into contiguous chunks sized in proportion to each device's
+    /// weighting, so stronger devices receive more elements.
+    Weighted,
+    /// The ranges come from a user supplied function, for workloads the built in modes
+    /// balance poorly, like the rows of a sparse matrix.
+    Custom(Arc<PartitionFn>),
+    /// Another mode's ranges, each widened by a halo of neighbouring elements on both sides
+    /// for stencil-style kernels. Build it with [`with_halo`](Self::with_halo).
+    Haloed(Box<PartitionMode>, usize),
+    /// Overlapping windows of `size` elements, each `stride` after the last, dealt out
+    /// whole to the devices by their weightings. Each device holds every element its
+    /// windows cover, so the elements where windows overlap go to both neighbours. For
+    /// inputs, usually bound with
+    /// [`with_sliding_windows`](crate::task::TaskBuilder::with_sliding_windows).
+    Windowed { size: usize, stride: usize },
+}
+
+/// The element ranges of a VBuffer that each device holds, and the (possibly narrower)
+/// ranges whose results it owns and writes back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Plan {
+    pub(crate) held: Vec<Range<usize>>,
+    pub(crate) owned: Vec<Range<usize>>,
+}
+
+impl PartitionMode {
+    pub fn custom<F>(partitioner: F) -> Self
+    where
+        F: Fn(usize, &[f32]) -> Vec<Range<usize>> + Send + Sync + 'static,
+    {
+        PartitionMode::Custom(Arc::new(partitioner))
+    }
+
+    /// Puts every element on the device at `device` in the Workgroup's order, and none on
+    /// the others, which sit out tasks that bind the buffer. For buffers that only live on
+    /// one device, like imported ones.
+    pub fn on_device(device: usize) -> Self {
+        PartitionMode::custom(move |len, weightings| {
+            (0..weightings.len())
+                .map(|d| if d == device { 0..len } else { 0..0 })
+                .collect()
+        })
+    }
+
+    /// Whether the mode cuts the buffer between the devices by their weightings, keeping to
+    /// what each can hold, as the built in modes but [`Unmanaged`](Self::Unmanaged) do.
+    pub(crate) fn is_split(&self) -> bool {
+        match self {
+            PartitionMode::Unmanaged | PartitionMode::Custom(_) => false,
+            PartitionMode::Split | PartitionMode::Weighted | PartitionMode::Windowed { .. } => true,
+            PartitionMode::Haloed(mode, _) => mode.is_split(),
+        }
+    }
+
+    /// Widens every device's range by `width` elements on each side (rounded out to whole
+    /// element groups and clamped to the buffer), so stencil kernels can read their
+    /// neighbours' edge elements. Inputs upload the widened range; outputs are computed
+    /// over it too, but only write back the device's own elements.
+    pub fn with_halo(self, width: usize) -> Self {
+        PartitionMode::Haloed(Box::new(self), width)
+    }
+
+    /// Plans the elements each device holds and owns, given the Workgroup's normalized
+    /// device weightings, and the largest fraction of the buffer each device can take
+    /// (see [`cap_shares`]), which the built in modes other than
+    /// [`Unmanaged`](Self::Unmanaged) keep to.
+    ///
+    /// Every mode must keep element groups of `group_size` whole, usually the buffer's
+    /// own, so each boundary it produces falls on a multiple of it. Fails if a custom
+    /// partitioner breaks that rule, goes out of bounds, or doesn't give every device a
+    /// range.
+    pub(crate) fn plan(
+        &self,
+        vbuffer: &VBuffer,
+        group_size: usize,
+        weightings: &[f32],
+        caps: &[f64],
+    ) -> Result<Plan, WiscError> {
+        if let PartitionMode::Haloed(mode, width) = self {
+            let owned = mode.plan(vbuffer, group_size, weightings, caps)?.owned;
+
+            let held = owned
+                .iter()
+                .map(|range| {
+                    if range.is_empty() {
+                        return range.clone();
+                    }
+
+                    let start = range.start.saturating_sub(*width) / group_size * group_size;
+                    let end = (range.end + width).next_multiple_of(group_size);
+
+                    start..end.min(vbuffer.length)
+                })
+                .collect();
+
+            return Ok(Plan { held, owned });
+        }
+
+        if let PartitionMode::Windowed { size, stride } = self {
+            let windows = window_count(vbuffer.length, *size, *stride)?;
+
+            let held: Vec<Range<usize>> =
+                split_by_shares(windows, 1, &cap_shares(weightings, caps))
+                    .into_iter()
+                    .map(|range| {
+                        if range.is_empty() {
+                            return 0..0;
+                        }
+
+                        range.start * stride..(range.end - 1) * stride + size
+                    })
+                    .collect();
+
+            return Ok(Plan {
+                held: held.clone(),
+                owned: held,
+            });
+        }
+
+        let ranges = self.ranges(vbuffer, group_size, weightings, caps)?;
+
+        Ok(Plan {
+            held: ranges.clone(),
+            owned: ranges,
+        })
+    }
+
+    fn ranges(
+        &self,
+        vbuffer: &VBuffer,
+        group_size: usize,
+        weightings: &[f32],
+        caps: &[f64],
+    ) -> Result<Vec<Range<usize>>, WiscError> {
+        let num_devices = weightings.len();
+
+        if let PartitionMode::Custom(partitioner) = self {
+            let ranges = partitioner(vbuffer.length, weightings);
+
+            if ranges.len() != num_devices {
+                return Err(WiscError::InvalidPartition(
+                    "the custom partitioner must give one range per device",
+                ));
+            }
+
+            if ranges
+                .iter()
+                .any(|range| range.start > range.end || range.end > vbuffer.length)
+            {
+                return Err(WiscError::InvalidPartition(
+                    "a custom range is out of the buffer's bounds",
+                ));
+            }
+
+            if ranges.iter().any(|range| {
+                !range.start.is_multiple_of(group_size) || !range.end.is_multiple_of(group_size)
+            }) {
+                return Err(WiscError::InvalidPartition(
+                    "a custom range splits an element group",
+                ));
+            }
+
+            return Ok(ranges);
+        }
+
+        // A lone device always owns the whole buffer with the built in modes.
+        if num_devices == 1 {
+            return Ok(vec![0..vbuffer.length; 1]);
+        }
+
+        let ranges = match self {
+            PartitionMode::Unmanaged => vec![0..vbuffer.length; num_devices],
+            PartitionMode::Split => {
+                // Devices given no share at all, like those that rejected the task's shader,
+                // still get nothing.
+                let shares: Vec<f32> = weightings
+                    .iter()
+                    .map(|weighting| if *weighting > 0.0 { 1.0 } else { 0.0 })
+                    .collect();
+
+                split_by_shares(vbuffer.length, group_size, &cap_shares(&shares, caps))
+            }
+            PartitionMode::Weighted => {
+                split_by_shares(vbuffer.length, group_size, &cap_shares(weightings, caps))
+            }
+            PartitionMode::Custom(_)
+            | PartitionMode::Haloed(..)
+            | PartitionMode::Windowed { .. } => {
+                unreachable!()
+            }
+        };
+
+        debug_assert!(
+            ranges
+                .iter()
+                .all(|range| { range.start % group_size == 0 && range.end % group_size == 0 })
+        );
+
+        Ok(ranges)
+    }
+}
+
+impl fmt::Debug for PartitionMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PartitionMode::Unmanaged => f.write_str("Unmanaged"),
+            PartitionMode::Split => f.write_str("Split"),
+            PartitionMode::Weighted => f.write_str("Weighted"),
+            PartitionMode::Custom(_) => f.write_str("Custom(..)"),
+            PartitionMode::Haloed(mode, width) => {
+                f.debug_tuple("Haloed").field(mode).field(width).finish()
+            }
+            PartitionMode::Windowed { size, stride } => f
+                .debug_struct("Windowed")
+                .field("size", size)
+                .field("stride", stride)
+                .finish(),
+        }
+    }
+}
+
+/// Rewrites `module` as WGSL in which the compute entry point `kernel` sees
+/// `global_invocation_id` shifted along x by the `offset` of the partition uniform, which
+/// it declares if the module doesn't already. The kernel's body becomes a function of its
+/// own, called with the shifted ID by a generated entry point of the same name.
+///
+/// Kernels that don't take the built-in are written back unchanged.
+pub(crate) fn offset_global_ids(module: &naga::Module, kernel: &str) -> Result<String, WiscError> {
+    let mut module = module.clone();
+
+    let entry_point = module
+        .entry_points
+        .iter()
+        .position(|entry_point| {
+            entry_point.name == kernel && entry_point.stage == naga::ShaderStage::Compute
+        })
+        .ok_or(WiscError::InvalidShader("the kernel isn't in the shader"))?;
+
+    let is_global_id = |binding: &Option<naga::Binding>| {
+        *binding == Some(naga::Binding::BuiltIn(naga::BuiltIn::GlobalInvocationId))
+    };
+
+    let arguments = &module.entry_points[entry_point].function.arguments;
+
+    // A struct of built-ins can't be shifted member by member without rebuilding it.
+    let in_struct = arguments.iter().any(|argument| {
+        matches!(
+            &module.types[argument.ty].inner,
+            naga::TypeInner::Struct { members, .. }
+                if members.iter().any(|member| is_global_id(&member.binding))
+        )
+    });
+
+    if in_struct {
+        return Err(WiscError::InvalidShader(
+            "global_invocation_id must be a kernel parameter of its own to be offset",
+        ));
+    }
+
+    if let Some(global_id) = arguments
+        .iter()
+        .position(|argument| is_global_id(&argument.binding))
+    {
+        wrap_with_offset(&mut module, entry_point, global_id);
+    }
+
+    let info = naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    )
+    .validate(&module)
+    .map_err(|error| WiscError::ShaderParse(error.emit_to_string("")))?;
+
+    naga::back::wgsl::write_string(&module, &info, naga::back::wgsl::WriterFlags::empty())
+        .map_err(|error| WiscError::ShaderParse(error.to_string()))
+}
+
+/// Moves the function of `module`'s entry point into a function of its own, and makes the
+/// entry point call it with argument `global_id` plus the partition offset.
+fn wrap_with_offset(module: &mut naga::Module, entry_point: usize, global_id: usize) {
+    let span = naga::Span::UNDEFINED;
+
+    let u32_ty = module.types.insert(
+        naga::Type {
+            name: None,
+            inner: naga::TypeInner::Scalar(naga::Scalar::U32),
+        },
+        span,
+    );
+    let vec3_ty = module.types.insert(
+        naga::Type {
+            name: None,
+            inner: naga::TypeInner::Vector {
+                size: naga::VectorSize::Tri,
+                scalar: naga::Scalar::U32,
+            },
+        },
+        span,
+    );
+
+    let declared = module.global_variables.iter().find_map(|(handle, global)| {
+        let binding = global.binding.as_ref()?;
+
+        (binding.group == 0 && binding.binding == PARTITION_INFO_BINDING).then_some(handle)
+    });
+
+    let partition = declared.unwrap_or_else(|| {
+        let members = ["offset", "len", "device", "devices"]
+            .iter()
+            .zip(0..)
+            .map(|(name, index)| naga::StructMember {
+                name: Some(name.to_string()),
+                ty: u32_ty,
+                binding: None,
+                offset: index * 4,
+            })
+            .collect();
+
+        let ty = module.types.insert(
+            naga::Type {
+                name: Some("WiscPartition".to_string()),
+                inner: naga::TypeInner::Struct { members, span: 16 },
+            },
+            span,
+        );
+
+        module.global_variables.append(
+            naga::GlobalVariable {
+                name: Some("wisc_partition".to_string()),
+                space: naga::AddressSpace::Uniform,
+                binding: Some(naga::ResourceBinding {
+                    group: 0,
+                    binding: PARTITION_INFO_BINDING,
+                }),
+                ty,
+                init: None,
+            },
+            span,
+        )
+    });
+
+    let name = module.entry_points[entry_point].name.clone();
+
+    let mut body = std::mem::take(&mut module.entry_points[entry_point].function);
+    body.name = Some(format!("wisc_{name}_body"));
+
+    let mut wrapper = naga::Function {
+        name: Some(name),
+        arguments: body.arguments.clone(),
+        ..Default::default()
+    };
+
+    for argument in &mut body.arguments {
+        argument.binding = None;
+    }
+
+    let expressions = &mut wrapper.expressions;
+
+    let mut arguments: Vec<_> = (0..wrapper.arguments.len() as u32)
+        .map(|index| expressions.append(naga::Expression::FunctionArgument(index), span))
+        .collect();
+    let global = expressions.append(naga::Expression::GlobalVariable(partition), span);
+    let zero = expressions.append(naga::Expression::Literal(naga::Literal::U32(0)), span);
+
+    let pointer = expressions.append(
+        naga::Expression::AccessIndex {
+            base: global,
+            index: 0,
+        },
+        span,
+    );
+    let offset = expressions.append(naga::Expression::Load { pointer }, span);
+    let shift = expressions.append(
+        naga::Expression::Compose {
+            ty: vec3_ty,
+            components: vec![offset, zero, zero],
+        },
+        span,
+    );
+    let shifted = expressions.append(
+        naga::Expression::Binary {
+            op: naga::BinaryOperator::Add,
+            left: arguments[global_id],
+            right: shift,
+        },
+        span,
+    );
+    arguments[global_id] = shifted;
+
+    wrapper.body.push(
+        naga::Statement::Emit(naga::Range::new_from_bounds(pointer, shifted)),
+        span,
+    );
+
+    let body = module.functions.append(body, span);
+
+    wrapper.body.push(
+        naga::Statement::Call {
+            function: body,
+            arguments,
+            result: None,
+        },
+        span,
+    );
+    wrapper
+        .body
+        .push(naga::Statement::Return { value: None }, span);
+
+    module.entry_points[entry_point].function = wrapper;
+}
+
+/// How many whole windows of `size` elements, each `stride` after the last, fit in
+/// `length` elements.
+pub(crate) fn window_count(length: usize, size: usize, stride: usize) -> Result<usize, WiscError> {
+    if size == 0 || stride == 0 {
+        return Err(WiscError::InvalidPartition(
+            "windows need a nonzero size and stride",
+        ));
+    }
+
+    Ok(match length.checked_sub(size) {
+        Some(rest) => rest / stride + 1,
+        None => 0,
+    })
+}
+
+/// Whether no element falls in more than one of `ranges`, so every device's write-back
+/// lands on its own elements.
+pub(crate) fn is_disjoint(ranges: &[Range<usize>]) -> bool {
+    let mut sorted: Vec<&Range<usize>> = ranges.iter().filter(|range| !range.is_empty()).collect();
+    sorted.sort_by_key(|range| range.start);
+
+    sorted.windows(2).all(|pair| pair[0].end <= pair[1].start)
+}
+
+/// Whether `ranges` together cover every one of `length` elements.
+pub(crate) fn covers(ranges: &[Range<usize>], length: usize) -> bool {
+    let mut sorted: Vec<&Range<usize>> = ranges.iter().collect();
+    sorted.sort_by_key(|range| range.start);
+
+    let mut covered = 0;
+
+    for range in sorted {
+        if range.start > covered {
+            return false;
+        }
+        covered = covered.max(range.end);
+    }
+
+    covered >= length
+}
+
+/// Evens out `shares` so that none is a larger fraction of their total than its device's
+/// cap, handing what is cut from a device to the others with room left, in proportion to
+/// their shares. Devices with no share get none. If the caps of the devices with a share
+/// can't hold everything between them, the shares are left as they are, for the buffers'
+/// size checks to fail on.
+pub(crate) fn cap_shares(shares: &[f32], caps: &[f64]) -> Vec<f32> {
+    let total: f64 = shares.iter().map(|share| *share as f64).sum();
+    let room: f64 = shares
+        .iter()
+        .zip(caps)
+        .filter(|(share, _)| **share > 0.0)
+        .map(|(_, cap)| cap.min(1.0))
+        .sum();
+
+    if total <= 0.0 || room < 1.0 {
+        return shares.to_vec();
+    }
+
+    let mut fractions: Vec<f64> = shares.iter().map(|share| *share as f64 / total).collect();
+    let mut capped = vec![false; shares.len()];
+
+    // Each pass caps one more device, until every device is within its cap.
+    let over = |fractions: &[f64], capped: &[bool]| {
+        (0..fractions.len()).find(|&d| !capped[d] && fractions[d] > caps[d])
+    };
+    while let Some(device) = over(&fractions, &capped) {
+        capped[device] = true;
+
+        let left = 1.0
+            - (0..fractions.len())
+                .filter(|&d| capped[d])
+                .map(|d| caps[d])
+                .sum::<f64>();
+        let uncapped: f64 = (0..shares.len())
+            .filter(|&d| !capped[d])
+            .map(|d| shares[d] as f64)
+            .sum();
+
+        for d in 0..fractions.len() {
+            fractions[d] = if capped[d] {
+                caps[d]
+            } else if uncapped > 0.0 {
+                left * shares[d] as f64 / uncapped
+            } else {
+                0.0
+            };
+        }
+    }
+
+    fractions
+        .into_iter()
+        .map(|fraction| fraction as f32)
+        .collect()
+}
+
+/// Cuts `length` elements into contiguous ranges proportional to `shares`, with every
+/// boundary on a multiple of `group_size`.
+pub(crate) fn split_by_shares(
+    length: usize,
+    group_size: usize,
+    shares: &[f32],
+) -> Vec<Range<usize>> {
+    let groups = length / group_size;
+    let total: f64 = shares.iter().map(|share| *share as f64).sum();
+
+    let mut boundaries = Vec::with_capacity(shares.len() + 1);
+    let mut cumulative = 0.0;
+    boundaries.push(0);
+
+    for share in shares {
+        cumulative += *share as f64;
+
+        let boundary = if total > 0.0 {
+            ((groups as f64 * cumulative / total).round() as usize).min(groups)
+        } else {
+            0
+        };
+
+        boundaries.push(boundary);
+    }
+
+    // Rounding can never leave elements behind.
+    if let Some(last) = boundaries.last_mut() {
+        *last = groups;
+    }
+
+    boundaries
+        .windows(2)
+        .map(|w| w[0] * group_size..w[1] * group_size)
+        .collect()
+}
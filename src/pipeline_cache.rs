@@ -0,0 +1,90 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::WiscError;
+use crate::vdevice::VDevice;
+use crate::workgroup::Workgroup;
+
+impl Workgroup {
+    /// Gives every device that supports it a pipeline cache, seeded from its file in `dir`
+    /// if there is one.
+    pub(crate) fn load_pipeline_cache(&mut self, dir: PathBuf) {
+        self.pipeline_caches = self
+            .vdevices
+            .iter()
+            .map(|vd| open_pipeline_cache(vd, &dir))
+            .collect();
+
+        self.pipeline_cache_dir.replace(dir);
+    }
+
+    /// Writes the devices' pipeline caches to the directory given to
+    /// [`WorkgroupBuilder::pipeline_cache_dir`](crate::workgroup::WorkgroupBuilder::pipeline_cache_dir),
+    /// creating it if needed, and returns how many files were written. Call it once the
+    /// application's tasks have been built, so the next run can load their pipelines.
+    ///
+    /// Does nothing without a cache directory, or devices that keep a cache.
+    pub fn save_pipeline_cache(&self) -> Result<usize, WiscError> {
+        let Some(dir) = &self.pipeline_cache_dir else {
+            return Ok(0);
+        };
+
+        let mut written = 0;
+
+        for (vd, cache) in self.vdevices.iter().zip(&self.pipeline_caches) {
+            let (Some(cache), Some(path)) = (cache, cache_path(vd, dir)) else {
+                continue;
+            };
+            let Some(data) = cache.get_data() else {
+                continue;
+            };
+
+            write_atomically(&path, &data)
+                .map_err(|error| WiscError::CacheFile(format!("{}: {error}", path.display())))?;
+            written += 1;
+        }
+
+        Ok(written)
+    }
+}
+
+/// A pipeline cache for `vd`, seeded from its file in `dir` if there is one, if its device
+/// supports them.
+pub(crate) fn open_pipeline_cache(vd: &VDevice, dir: &Path) -> Option<wgpu::PipelineCache> {
+    let path = cache_path(vd, dir)?;
+    // A missing or unreadable file just means starting from empty.
+    let data = fs::read(path).ok();
+
+    // SAFETY: the file is named after the adapter and driver that wrote it, and with
+    // `fallback` set, data the driver rejects (as it does data from another driver
+    // version) gives an empty cache instead.
+    Some(unsafe {
+        vd.device
+            .create_pipeline_cache(&wgpu::PipelineCacheDescriptor {
+                label: Some(&format!("WISC Pipeline Cache (VDevice {})", vd.label)),
+                data: data.as_deref(),
+                fallback: true,
+            })
+    })
+}
+
+/// Where the cache for `vd` is kept in `dir`, if its device has one.
+fn cache_path(vd: &VDevice, dir: &Path) -> Option<PathBuf> {
+    if !vd.features.contains(wgpu::Features::PIPELINE_CACHE) {
+        return None;
+    }
+
+    Some(dir.join(wgpu::util::pipeline_cache_key(&vd.info)?))
+}
+
+/// Replaces the file at `path` with `data`, so that an application stopped halfway (or a
+/// second one starting up) never reads a partial cache.
+pub(crate) fn write_atomically(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let temporary = path.with_extension(format!("{}.tmp", std::process::id()));
+    fs::write(&temporary, data)?;
+    fs::rename(&temporary, path)
+}
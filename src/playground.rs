@@ -0,0 +1,476 @@
+//! A window for trying out kernels on a Workgroup's devices: load a WGSL file, pick a
+//! kernel, and run it over generated buffers, then see how long it took, how its buffers
+//! were split between the devices, and what it wrote.
+//!
+//! Every storage buffer the kernel uses is generated: those it only reads are bound as
+//! inputs, and those it writes to as outputs, the task sized per element of the first.
+//! Kernels that use uniforms or textures need a real program.
+//!
+//! [`Playground::show`] opens the window, and the rest of the API does what its buttons
+//! do, for trying kernels without one.
+
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+use eframe::egui;
+use web_time::Instant;
+
+use crate::error::WiscError;
+use crate::history::Launch;
+use crate::partition::PARTITION_INFO_BINDING;
+use crate::reflect::{self, BindingKind};
+use crate::report::RunReport;
+use crate::shader::Shader;
+use crate::task::{self, TaskBuilder};
+use crate::workgroup::{VBufferHandle, Workgroup};
+
+/// How many elements of each output a [`Trial`] keeps.
+pub const PREVIEW_LENGTH: usize = 16;
+
+/// The type of every element of the generated buffers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Element {
+    #[default]
+    U32,
+    F32,
+}
+
+/// What the generated buffers are filled with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Fill {
+    Zeros,
+    Ones,
+    /// Each element's index.
+    #[default]
+    Indices,
+    /// The same pseudorandom values every time: any `u32`, or an `f32` in `[0, 1)`.
+    Random,
+}
+
+impl Fill {
+    fn generate(self, element: Element, length: usize) -> Vec<u32> {
+        (0..length)
+            .map(|index| match (self, element) {
+                (Fill::Zeros, _) => 0,
+                (Fill::Ones, Element::U32) => 1,
+                (Fill::Ones, Element::F32) => 1f32.to_bits(),
+                (Fill::Indices, Element::U32) => index as u32,
+                (Fill::Indices, Element::F32) => (index as f32).to_bits(),
+                (Fill::Random, Element::U32) => scramble(index as u32),
+                (Fill::Random, Element::F32) => {
+                    ((scramble(index as u32) >> 8) as f32 / (1 << 24) as f32).to_bits()
+                }
+            })
+            .collect()
+    }
+}
+
+/// One run of a kernel in the playground.
+#[derive(Debug, Clone)]
+pub struct Trial {
+    pub kernel: String,
+    /// How long building the task took, compiling its shader included.
+    pub build_time: Duration,
+    /// How long running it took, from upload to write-back.
+    pub run_time: Duration,
+    pub report: RunReport,
+    /// The dispatches it issued, one or more per device, with each binding's size on that
+    /// device.
+    pub launches: Vec<Launch>,
+    /// The first [`PREVIEW_LENGTH`] elements of each output, by binding.
+    pub outputs: Vec<(u32, Vec<u32>)>,
+    pub element: Element,
+}
+
+/// The state of the playground window, around the Workgroup it runs kernels on.
+pub struct Playground {
+    workgroup: Workgroup,
+    /// The WGSL source to run.
+    pub source: String,
+    /// The file the source was last loaded from.
+    pub path: String,
+    /// The entry point to run, or `None` for the shader's only one.
+    pub kernel: Option<String>,
+    /// How many elements every generated buffer has.
+    pub length: usize,
+    pub element: Element,
+    pub fill: Fill,
+    trials: Vec<Trial>,
+    // The last failure, shown until the next success.
+    message: Option<String>,
+}
+
+impl Playground {
+    pub fn new(workgroup: Workgroup) -> Self {
+        Self {
+            workgroup,
+            source: String::new(),
+            path: String::new(),
+            kernel: None,
+            length: 1024,
+            element: Element::default(),
+            fill: Fill::default(),
+            trials: vec![],
+            message: None,
+        }
+    }
+
+    /// Replaces the source with the contents of the WGSL file at `path`.
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<(), WiscError> {
+        let path = path.as_ref();
+
+        self.source = std::fs::read_to_string(path)
+            .map_err(|error| WiscError::ShaderFile(format!("{}: {error}", path.display())))?;
+        self.path = path.display().to_string();
+
+        Ok(())
+    }
+
+    /// The compute entry points of the source. Fails with the compiler's messages if it
+    /// doesn't parse.
+    pub fn kernels(&self) -> Result<Vec<String>, WiscError> {
+        Ok(self
+            .module()?
+            .map(|module| reflect::compute_entry_points(&module))
+            .unwrap_or_default())
+    }
+
+    /// Runs the kernel over freshly generated buffers, and keeps the outcome.
+    pub fn run(&mut self) -> Result<&Trial, WiscError> {
+        let module = self.module()?.ok_or(WiscError::InvalidShader(
+            "the playground only runs WGSL it can reflect",
+        ))?;
+        let kernel = task::resolve_kernel(Some(&module), self.kernel.clone())?;
+
+        let bindings: Vec<(u32, BindingKind)> = reflect::bindings(&module, &kernel)
+            .into_iter()
+            .filter(|binding| binding.used && binding.binding != PARTITION_INFO_BINDING)
+            .map(|binding| (binding.binding, binding.kind))
+            .collect();
+
+        if let Some(&(binding, _)) = bindings.iter().find(|(_, kind)| {
+            !matches!(
+                kind,
+                BindingKind::ReadOnlyStorage | BindingKind::ReadWriteStorage
+            )
+        }) {
+            return Err(WiscError::BindingMismatch {
+                binding,
+                reason: "the playground only generates storage buffers",
+            });
+        }
+
+        let data = self.fill.generate(self.element, self.length);
+        let handles: Vec<_> = bindings
+            .iter()
+            .map(|&(binding, kind)| {
+                let handle = match self.element {
+                    Element::U32 => self.workgroup.create_vbuffer(data.clone()),
+                    Element::F32 => self
+                        .workgroup
+                        .create_vbuffer::<f32>(bytemuck::cast_slice(&data).to_vec()),
+                };
+
+                (binding, kind, handle)
+            })
+            .collect();
+
+        let sized = handles
+            .iter()
+            .find(|(_, kind, _)| *kind == BindingKind::ReadWriteStorage)
+            .map(|&(_, _, handle)| handle);
+
+        let result = self.trial(&kernel, &handles, sized);
+
+        // The buffers go whatever happened, and only the outputs are worth keeping.
+        let mut outputs = vec![];
+
+        for (binding, kind, handle) in handles {
+            let Ok(mut values) = (match self.element {
+                Element::U32 => self.workgroup.take_vbuffer::<u32>(handle),
+                Element::F32 => self
+                    .workgroup
+                    .take_vbuffer::<f32>(handle)
+                    .map(bytemuck::cast_vec),
+            }) else {
+                continue;
+            };
+
+            if kind == BindingKind::ReadWriteStorage {
+                values.truncate(PREVIEW_LENGTH);
+                outputs.push((binding, values));
+            }
+        }
+
+        let (build_time, run_time, report, launches) = result?;
+
+        self.trials.push(Trial {
+            kernel,
+            build_time,
+            run_time,
+            report,
+            launches,
+            outputs,
+            element: self.element,
+        });
+
+        Ok(self.trials.last().unwrap())
+    }
+
+    /// The outcomes of every run so far, oldest first.
+    pub fn trials(&self) -> &[Trial] {
+        &self.trials
+    }
+
+    /// Opens the playground's window, and blocks until it is closed.
+    pub fn show(self) -> Result<(), eframe::Error> {
+        eframe::run_native(
+            "wisc playground",
+            eframe::NativeOptions::default(),
+            Box::new(|_| Ok(Box::new(self))),
+        )
+    }
+
+    fn module(&self) -> Result<Option<naga::Module>, WiscError> {
+        reflect::parse(&wgpu::ShaderSource::Wgsl(self.source.as_str().into()))
+    }
+
+    /// Builds and runs the task, timing each.
+    fn trial(
+        &mut self,
+        kernel: &str,
+        handles: &[(u32, BindingKind, VBufferHandle)],
+        sized: Option<VBufferHandle>,
+    ) -> Result<(Duration, Duration, RunReport, Vec<Launch>), WiscError> {
+        let sized = sized.ok_or(WiscError::InvalidBinding(
+            "the kernel writes to no storage buffer to size the task by",
+        ))?;
+
+        self.workgroup.record_launches(true);
+        self.workgroup.take_launch_history();
+
+        let started = Instant::now();
+
+        let mut builder = TaskBuilder::new(&mut self.workgroup, Shader::wgsl(self.source.clone()))
+            .with_kernel(kernel)
+            .with_size_per_element(sized);
+
+        for &(binding, kind, handle) in handles {
+            builder = match kind {
+                BindingKind::ReadWriteStorage => builder.with_output_buffer(binding, handle),
+                _ => builder.with_input_buffer(binding, handle),
+            };
+        }
+
+        let mut task = builder.build()?;
+        let build_time = started.elapsed();
+
+        let started = Instant::now();
+        let report = task.run()?;
+        let run_time = started.elapsed();
+
+        drop(task);
+
+        let launches = self.workgroup.take_launch_history();
+        self.workgroup.record_launches(false);
+
+        Ok((build_time, run_time, report, launches))
+    }
+
+    fn devices_panel(&self, ui: &mut egui::Ui) {
+        ui.heading("Devices");
+
+        for (vd, (_, weighting)) in self
+            .workgroup
+            .vdevices
+            .iter()
+            .zip(self.workgroup.vdevice_weightings())
+        {
+            ui.separator();
+            ui.strong(&vd.label);
+            ui.label(format!("{} on {:?}", vd.info.name, vd.info.backend));
+            ui.add(egui::ProgressBar::new(weighting).text(format!("weighting {weighting:.2}")));
+        }
+    }
+
+    fn controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("WGSL file");
+            ui.text_edit_singleline(&mut self.path);
+
+            if ui.button("Load").clicked() {
+                let path = self.path.clone();
+                self.message = self.load(path).err().map(|error| error.to_string());
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let kernels = self.kernels().unwrap_or_default();
+
+            egui::ComboBox::from_label("Kernel")
+                .selected_text(self.kernel.as_deref().unwrap_or("(only entry point)"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.kernel, None, "(only entry point)");
+
+                    for kernel in kernels {
+                        ui.selectable_value(&mut self.kernel, Some(kernel.clone()), kernel);
+                    }
+                });
+
+            ui.label("Elements");
+            ui.add(egui::DragValue::new(&mut self.length).range(1..=1 << 26));
+
+            egui::ComboBox::from_label("of")
+                .selected_text(format!("{:?}", self.element))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.element, Element::U32, "U32");
+                    ui.selectable_value(&mut self.element, Element::F32, "F32");
+                });
+
+            egui::ComboBox::from_label("filled with")
+                .selected_text(format!("{:?}", self.fill))
+                .show_ui(ui, |ui| {
+                    for fill in [Fill::Zeros, Fill::Ones, Fill::Indices, Fill::Random] {
+                        ui.selectable_value(&mut self.fill, fill, format!("{fill:?}"));
+                    }
+                });
+
+            if ui.button("Run").clicked() {
+                self.message = self.run().err().map(|error| error.to_string());
+            }
+        });
+    }
+
+    fn results(&self, ui: &mut egui::Ui) {
+        if let Some(message) = &self.message {
+            ui.colored_label(egui::Color32::LIGHT_RED, message);
+        }
+
+        let Some(trial) = self.trials.last() else {
+            return;
+        };
+
+        ui.heading(format!(
+            "{} on {} devices",
+            trial.kernel, trial.report.devices
+        ));
+        ui.label(format!(
+            "built in {:.2?}, ran in {:.2?}",
+            trial.build_time, trial.run_time
+        ));
+
+        if !trial.report.excluded.is_empty() {
+            ui.label(format!("devices sat out: {:?}", trial.report.excluded));
+        }
+
+        ui.collapsing("Partition plan", |ui| {
+            egui::Grid::new("plan").striped(true).show(ui, |ui| {
+                ui.label("device");
+                ui.label("workgroups");
+                ui.label("bytes bound, of every device's total");
+                ui.end_row();
+
+                for launch in &trial.launches {
+                    ui.label(launch.device.to_string());
+                    ui.label(match launch.workgroups {
+                        Some((x, y, z)) => format!("{x} × {y} × {z}"),
+                        None => "indirect".to_string(),
+                    });
+
+                    ui.vertical(|ui| {
+                        for &(binding, size) in &launch.bindings {
+                            let total: u64 = trial
+                                .launches
+                                .iter()
+                                .flat_map(|launch| &launch.bindings)
+                                .filter(|(other, _)| *other == binding)
+                                .map(|(_, size)| size)
+                                .sum();
+
+                            ui.add(
+                                egui::ProgressBar::new(size as f32 / total.max(1) as f32)
+                                    .text(format!("binding {binding}: {size} B")),
+                            );
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+        });
+
+        ui.collapsing("Outputs", |ui| {
+            for (binding, values) in &trial.outputs {
+                let values = Values(values, trial.element);
+                ui.monospace(format!("binding {binding}: {values}"));
+            }
+        });
+
+        ui.collapsing("Run times", |ui| {
+            let slowest = self
+                .trials
+                .iter()
+                .map(|trial| trial.run_time)
+                .max()
+                .unwrap_or_default();
+
+            for trial in self.trials.iter().rev() {
+                ui.add(
+                    egui::ProgressBar::new(trial.run_time.div_duration_f32(slowest))
+                        .text(format!("{}: {:.2?}", trial.kernel, trial.run_time)),
+                );
+            }
+        });
+    }
+}
+
+impl eframe::App for Playground {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::SidePanel::left("devices").show(ctx, |ui| self.devices_panel(ui));
+        egui::TopBottomPanel::top("controls").show(ctx, |ui| self.controls(ui));
+        egui::TopBottomPanel::bottom("results")
+            .resizable(true)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| self.results(ui));
+            });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.source)
+                        .code_editor()
+                        .desired_rows(24)
+                        .desired_width(f32::INFINITY),
+                );
+            });
+        });
+    }
+}
+
+/// Output elements as the kernel's type.
+struct Values<'v>(&'v [u32], Element);
+
+impl fmt::Display for Values<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, &bits) in self.0.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+
+            match self.1 {
+                Element::U32 => write!(f, "{bits}")?,
+                Element::F32 => write!(f, "{}", f32::from_bits(bits))?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A cheap integer hash, for the random fill.
+fn scramble(mut x: u32) -> u32 {
+    x = (x ^ 61) ^ (x >> 16);
+    x = x.wrapping_mul(9);
+    x ^= x >> 4;
+    x = x.wrapping_mul(0x27d4_eb2d);
+    x ^ (x >> 15)
+}
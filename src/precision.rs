@@ -0,0 +1,132 @@
+//! Keeping floating-point results consistent across devices.
+//!
+//! Drivers are free to contract multiplies and adds into fused ones, and to approximate
+//! transcendental functions, each in their own way, and wgpu offers no switch to turn
+//! that off. So when a buffer is split between devices from different vendors or
+//! drivers, neighbouring elements can disagree in their last bits. A [`Precision`] policy
+//! avoids that by choosing the devices, and [`cross_check`] measures how far apart they
+//! actually are.
+
+use crate::error::WiscError;
+use crate::vdevice::VDevice;
+use crate::workgroup::Workgroup;
+
+/// Which devices may compute the elements of one task's buffers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Precision {
+    /// Every device takes its share, however its driver rounds.
+    #[default]
+    Relaxed,
+    /// Only devices running the same driver on the same kind of adapter as the strongest
+    /// device that accepts the shader take part, so every element comes from the same
+    /// compiler. The others sit the task out, like devices that reject its shader, and
+    /// are listed in the report's [`excluded`](crate::report::RunReport::excluded).
+    Consistent,
+}
+
+/// The devices of `vdevices`, other than the already `excluded` ones, that don't match
+/// the first remaining device's adapter and driver.
+pub(crate) fn inconsistent_devices(vdevices: &[VDevice], excluded: &[usize]) -> Vec<usize> {
+    let implementation = |vd: &VDevice| {
+        (
+            vd.info.backend,
+            vd.info.vendor,
+            vd.info.device,
+            vd.info.driver.clone(),
+            vd.info.driver_info.clone(),
+        )
+    };
+
+    let mut candidates = (0..vdevices.len()).filter(|vdi| !excluded.contains(vdi));
+
+    let Some(leading) = candidates.next() else {
+        return vec![];
+    };
+    let leading = implementation(&vdevices[leading]);
+
+    candidates
+        .filter(|&vdi| implementation(&vdevices[vdi]) != leading)
+        .collect()
+}
+
+/// How far one device's results strayed from the first device's in a [`cross_check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deviation {
+    /// The device's index among those checked.
+    pub device: usize,
+    pub label: String,
+    /// The largest distance of any element from the first device's, in units in the last
+    /// place. A NaN where the other is a number counts as `u32::MAX`.
+    pub max_ulps: u32,
+    /// How many elements differed at all.
+    pub differing: usize,
+}
+
+/// Runs `job` on each of `devices` by itself, each in a Workgroup of its own, and compares
+/// the values it returns against the first device's. The first device's entry is always
+/// zero.
+///
+/// Use it on a representative task before splitting it across a mix of devices under
+/// [`Precision::Relaxed`], to see whether their differences matter.
+pub fn cross_check<F>(devices: &[VDevice], job: F) -> Result<Vec<Deviation>, WiscError>
+where
+    F: Fn(&mut Workgroup) -> Result<Vec<f32>, WiscError>,
+{
+    let results = devices
+        .iter()
+        .map(|vd| job(&mut Workgroup::from_devices(vec![vd.clone()])))
+        .collect::<Result<Vec<Vec<f32>>, WiscError>>()?;
+
+    let reference = results.first().ok_or(WiscError::NoDevices)?;
+
+    devices
+        .iter()
+        .zip(&results)
+        .enumerate()
+        .map(|(vdi, (vd, values))| {
+            if values.len() != reference.len() {
+                return Err(WiscError::ShapeMismatch(
+                    "the devices returned different numbers of values",
+                ));
+            }
+
+            let distances: Vec<u32> = values
+                .iter()
+                .zip(reference)
+                .map(|(&value, &expected)| ulps_between(value, expected))
+                .collect();
+
+            Ok(Deviation {
+                device: vdi,
+                label: vd.label.clone(),
+                max_ulps: distances.iter().copied().max().unwrap_or(0),
+                differing: distances.iter().filter(|&&distance| distance > 0).count(),
+            })
+        })
+        .collect()
+}
+
+/// How many representable `f32`s lie between `a` and `b`, saturating. Zeroes of either
+/// sign are equal, as are any two NaNs.
+fn ulps_between(a: f32, b: f32) -> u32 {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => return 0,
+        (true, false) | (false, true) => return u32::MAX,
+        _ => {}
+    }
+
+    // Reorders the bit patterns so that they count up with the values they stand for.
+    let ordered = |x: f32| {
+        let bits = x.to_bits() as i32;
+
+        if bits < 0 {
+            i32::MIN as i64 - bits as i64
+        } else {
+            bits as i64
+        }
+    };
+
+    (ordered(a) - ordered(b))
+        .unsigned_abs()
+        .min(u32::MAX as u64) as u32
+}
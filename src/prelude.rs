@@ -1,4 +1,14 @@
-pub use crate::task::TaskBuilder;
-pub use crate::vdevice::VDevice;
-pub use crate::workgroup::Workgroup;
-pub use wgpu::{include_spirv_raw, include_wgsl};
+//! The types most programs need, to glob-import with `use wisc::prelude::*`. Everything
+//! here is wisc's own, so it stays put when wisc moves to a new major version of wgpu;
+//! wgpu itself is re-exported as [`crate::wgpu`] for the rest.
+
+pub use crate::error::WiscError;
+#[cfg(feature = "spirv")]
+pub use crate::include_spirv;
+pub use crate::include_wgsl;
+pub use crate::partition::PartitionMode;
+pub use crate::report::RunReport;
+pub use crate::shader::Shader;
+pub use crate::task::{Task, TaskBuilder};
+pub use crate::vdevice::{Features, VDevice};
+pub use crate::workgroup::{VBufferHandle, Workgroup, WorkgroupBuilder};
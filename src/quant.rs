@@ -0,0 +1,237 @@
+//! Int8 quantized VBuffers, which move a quarter of the bytes of their `f32` values to and
+//! from the devices. Values are packed four to a `[i8; 4]` element (one `u32` word in WGSL)
+//! and map to `f32` through a per-buffer [`Quantization`].
+//!
+//! The [`dequantize`](Workgroup::dequantize) and [`quantize`](Workgroup::quantize) tasks
+//! convert on the devices, so that only the packed values cross the bus.
+
+use std::any::TypeId;
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::error::WiscError;
+use crate::partition::PartitionMode;
+use crate::prelude::Workgroup;
+use crate::report::RunReport;
+use crate::task::TaskBuilder;
+use crate::workgroup::VBufferHandle;
+
+const SHADER: &str = "wisc::quant";
+
+/// An affine mapping between `f32` values and `i8`s: `value = (q - zero_point) * scale`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantization {
+    pub scale: f32,
+    pub zero_point: i8,
+}
+
+impl Quantization {
+    pub fn new(scale: f32, zero_point: i8) -> Self {
+        Self { scale, zero_point }
+    }
+
+    /// The mapping that spreads `min..=max` over every `i8`. The range is widened to
+    /// include zero, so that zero is represented exactly.
+    pub fn for_range(min: f32, max: f32) -> Self {
+        let (min, max) = (min.min(0.0), max.max(0.0));
+
+        let scale = match (max - min) / 255.0 {
+            scale if scale > 0.0 => scale,
+            _ => 1.0,
+        };
+
+        let zero_point = (-128.0 - min / scale)
+            .round_ties_even()
+            .clamp(-128.0, 127.0) as i8;
+
+        Self { scale, zero_point }
+    }
+
+    /// The mapping that covers every one of `values`.
+    pub fn for_values(values: &[f32]) -> Self {
+        let (min, max) = values
+            .iter()
+            .fold((0.0f32, 0.0f32), |(min, max), v| (min.min(*v), max.max(*v)));
+
+        Self::for_range(min, max)
+    }
+
+    /// The nearest `i8` to `value`, saturating at the ends of the range. Rounds like the
+    /// quantize kernel does.
+    pub fn quantize(&self, value: f32) -> i8 {
+        ((value / self.scale).round_ties_even() + self.zero_point as f32).clamp(-128.0, 127.0) as i8
+    }
+
+    pub fn dequantize(&self, q: i8) -> f32 {
+        (q as i32 - self.zero_point as i32) as f32 * self.scale
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct QuantParams {
+    scale: f32,
+    zero_point: i32,
+}
+
+impl From<Quantization> for QuantParams {
+    fn from(quantization: Quantization) -> Self {
+        Self {
+            scale: quantization.scale,
+            zero_point: quantization.zero_point as i32,
+        }
+    }
+}
+
+impl Workgroup {
+    /// Quantizes `data` on the host and registers the packed values.
+    pub fn create_quantized_vbuffer(
+        &mut self,
+        data: &[f32],
+        quantization: Quantization,
+    ) -> VBufferHandle {
+        // The last word is padded with zeros.
+        let packed: Vec<[i8; 4]> = data
+            .chunks(4)
+            .map(|chunk| {
+                let mut word = [quantization.zero_point; 4];
+                for (q, value) in word.iter_mut().zip(chunk) {
+                    *q = quantization.quantize(*value);
+                }
+                word
+            })
+            .collect();
+
+        let handle = self.create_vbuffer(packed);
+        self.vbuffers[handle].quantized = Some((quantization, data.len()));
+
+        handle
+    }
+
+    /// Registers a quantized buffer of `length` values for a task (like
+    /// [`quantize`](Self::quantize)) to write.
+    pub fn create_quantized_vbuffer_uninit(
+        &mut self,
+        length: usize,
+        quantization: Quantization,
+    ) -> VBufferHandle {
+        let handle = self.create_vbuffer_uninit::<[i8; 4]>(length.div_ceil(4));
+        self.vbuffers[handle].quantized = Some((quantization, length));
+
+        handle
+    }
+
+    /// The mapping of a quantized buffer, or `None` if it isn't one.
+    pub fn quantization(&self, buffer_handle: VBufferHandle) -> Option<Quantization> {
+        let (quantization, _) = self.vbuffers.get(buffer_handle)?.quantized?;
+
+        Some(quantization)
+    }
+
+    /// Takes a quantized buffer out of the runtime, dequantized on the host.
+    pub fn take_quantized_vbuffer(
+        &mut self,
+        buffer_handle: VBufferHandle,
+    ) -> Result<Vec<f32>, WiscError> {
+        let (quantization, length) = self
+            .vbuffers
+            .get(buffer_handle)
+            .ok_or(WiscError::UnknownVBuffer)?
+            .quantized
+            .ok_or(WiscError::TypeMismatch)?;
+
+        let packed: Vec<[i8; 4]> = self.take_vbuffer(buffer_handle)?;
+
+        Ok(packed
+            .iter()
+            .flatten()
+            .take(length)
+            .map(|q| quantization.dequantize(*q))
+            .collect())
+    }
+
+    /// Expands the quantized buffer `quantized` into the `f32` buffer `output` on the
+    /// devices, each converting its share.
+    ///
+    /// Fails if `output` doesn't hold as many values, or they aren't a multiple of four,
+    /// since devices convert whole words.
+    pub fn dequantize(
+        &mut self,
+        quantized: VBufferHandle,
+        output: VBufferHandle,
+    ) -> Result<RunReport, WiscError> {
+        let quantization = self.check_conversion(quantized, output)?;
+
+        quant_task(self, "dequantize")
+            .with_size_per_element(quantized)
+            .with_input_buffer_partitioned(0, quantized, PartitionMode::Weighted)
+            .with_output_buffer_partitioned(3, output, PartitionMode::Weighted)
+            .with_uniform_buffer(4, QuantParams::from(quantization))
+            .build()?
+            .run()
+    }
+
+    /// Packs the `f32` buffer `input` into the quantized buffer `output` on the devices,
+    /// with `output`'s mapping, so that only the packed values are read back.
+    ///
+    /// Fails as [`dequantize`](Self::dequantize) does.
+    pub fn quantize(
+        &mut self,
+        input: VBufferHandle,
+        output: VBufferHandle,
+    ) -> Result<RunReport, WiscError> {
+        let quantization = self.check_conversion(output, input)?;
+
+        quant_task(self, "quantize")
+            .with_size_per_element(output)
+            .with_input_buffer_partitioned(1, input, PartitionMode::Weighted)
+            .with_output_buffer_partitioned(2, output, PartitionMode::Weighted)
+            .with_uniform_buffer(4, QuantParams::from(quantization))
+            .build()?
+            .run()
+    }
+
+    /// Checks that `values` is an `f32` buffer matching the quantized buffer `quantized`,
+    /// and has both split along whole words.
+    fn check_conversion(
+        &mut self,
+        quantized: VBufferHandle,
+        values: VBufferHandle,
+    ) -> Result<Quantization, WiscError> {
+        let (quantization, length) = self
+            .vbuffers
+            .get(quantized)
+            .ok_or(WiscError::UnknownVBuffer)?
+            .quantized
+            .ok_or(WiscError::TypeMismatch)?;
+
+        self.has_expected_types(&[(values, TypeId::of::<f32>())])?;
+
+        if self.vbuffers[values].length != length {
+            return Err(WiscError::ShapeMismatch(
+                "the buffers hold different numbers of values",
+            ));
+        }
+
+        if !length.is_multiple_of(4) {
+            return Err(WiscError::ShapeMismatch(
+                "devices convert whole words, so the length must be a multiple of four",
+            ));
+        }
+
+        // Four values to every word, so both buffers split at the same places.
+        self.set_element_group_size(values, 4)?;
+
+        Ok(quantization)
+    }
+}
+
+fn quant_task<'w>(workgroup: &'w mut Workgroup, kernel: &str) -> TaskBuilder<'w> {
+    if !workgroup.has_registered_shader(SHADER) {
+        workgroup.register_shader(SHADER, crate::include_wgsl!("quant.wgsl"));
+    }
+
+    TaskBuilder::from_workgroup(workgroup)
+        .with_registered_shader(SHADER)
+        .with_kernel(kernel)
+}
@@ -0,0 +1,48 @@
+// Int8 values packed four to a little-endian word, mapped to f32 by
+// (q - zero_point) * scale.
+
+struct QuantParams {
+    scale: f32,
+    zero_point: i32,
+}
+
+@group(0) @binding(0) var<storage, read> quantized_in: array<u32>;
+@group(0) @binding(1) var<storage, read> values_in: array<f32>;
+@group(0) @binding(2) var<storage, read_write> quantized_out: array<u32>;
+@group(0) @binding(3) var<storage, read_write> values_out: array<f32>;
+@group(0) @binding(4) var<uniform> params: QuantParams;
+
+// One invocation per word.
+@compute @workgroup_size(64, 1, 1)
+fn dequantize(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let index = global_id.x;
+    if (index >= arrayLength(&quantized_in)) {
+        return;
+    }
+
+    let word = bitcast<i32>(quantized_in[index]);
+
+    for (var k = 0u; k < 4u; k++) {
+        let q = extractBits(word, k * 8u, 8u);
+        values_out[index * 4u + k] = f32(q - params.zero_point) * params.scale;
+    }
+}
+
+// One invocation per word.
+@compute @workgroup_size(64, 1, 1)
+fn quantize(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let index = global_id.x;
+    if (index >= arrayLength(&quantized_out)) {
+        return;
+    }
+
+    var word = 0u;
+
+    for (var k = 0u; k < 4u; k++) {
+        let value = values_in[index * 4u + k];
+        let q = clamp(i32(round(value / params.scale)) + params.zero_point, -128, 127);
+        word |= (bitcast<u32>(q) & 0xffu) << (k * 8u);
+    }
+
+    quantized_out[index] = word;
+}
@@ -0,0 +1,126 @@
+//! Replacing lost devices, and running work that survives losing one.
+
+#[cfg(feature = "blocking")]
+use futures_lite::future;
+
+use crate::cache::BindingCache;
+use crate::error::WiscError;
+use crate::pipeline_cache::open_pipeline_cache;
+use crate::shader;
+use crate::vbuffer::Residency;
+use crate::vdevice::VDevice;
+use crate::workgroup::{RegisteredShader, Workgroup};
+
+impl Workgroup {
+    /// Replaces every lost device (see [`VDevice::is_lost`]) with a fresh one opened on the
+    /// same adapter, and returns the indices of those it replaced.
+    ///
+    /// Registered shaders are compiled again for the replacements; the few that can't be,
+    /// GLSL shaders naga can't write back out as WGSL, are unregistered. Whatever the lost devices held
+    /// is gone, so every VBuffer goes back to its host copy, aliases included, and tasks
+    /// built before must be built again. Results left on the devices rather than read back
+    /// are lost with them. Other Workgroups sharing a lost device keep
+    /// theirs until they recover it themselves.
+    ///
+    /// Fails if an adapter can't open a device anymore, as when it was unplugged.
+    #[cfg(feature = "blocking")]
+    pub fn recover_lost_devices(&mut self) -> Result<Vec<usize>, WiscError> {
+        future::block_on(self.recover_lost_devices_async())
+    }
+
+    /// Like [`recover_lost_devices`](Self::recover_lost_devices), but without blocking on
+    /// the adapters, as the browser requires.
+    pub async fn recover_lost_devices_async(&mut self) -> Result<Vec<usize>, WiscError> {
+        let lost: Vec<usize> = (0..self.vdevices.len())
+            .filter(|&vdi| self.vdevices[vdi].is_lost())
+            .collect();
+
+        for &vdi in &lost {
+            let vd = self.vdevices[vdi].reopen().await?;
+
+            self.shaders
+                .retain(|_, registered| recompile(registered, &vd, vdi));
+            self.binding_caches[vdi] = BindingCache::default();
+            self.pipeline_caches[vdi] = self
+                .pipeline_cache_dir
+                .as_ref()
+                .and_then(|dir| open_pipeline_cache(&vd, dir));
+
+            self.vdevices[vdi] = vd;
+        }
+
+        if !lost.is_empty() {
+            for vbuffer in self.vbuffers.values_mut() {
+                vbuffer.residency = Residency::Host;
+                vbuffer.pending_readback = false;
+            }
+
+            let shaders = &self.shaders;
+            self.watched_shaders
+                .retain(|name, _| shaders.contains_key(name));
+        }
+
+        Ok(lost)
+    }
+
+    /// Runs `job` on the Workgroup, and should it fail because a device was lost, recovers
+    /// the lost devices with [`recover_lost_devices`](Self::recover_lost_devices) and runs
+    /// it once more. Any other failure, or a second loss, is returned as it is.
+    ///
+    /// `job` should build the tasks it runs itself, since tasks built on a lost device
+    /// can't run on its replacement.
+    #[cfg(feature = "blocking")]
+    pub fn retry_on_device_loss<R, F>(&mut self, mut job: F) -> Result<R, WiscError>
+    where
+        F: FnMut(&mut Workgroup) -> Result<R, WiscError>,
+    {
+        match job(self) {
+            Err(WiscError::DeviceLost(_)) if self.vdevices.iter().any(VDevice::is_lost) => {
+                self.recover_lost_devices()?;
+
+                job(self)
+            }
+            result => result,
+        }
+    }
+
+    /// Fails with [`WiscError::DeviceLost`] if any device is known to be lost.
+    pub(crate) fn check_lost(&self) -> Result<(), WiscError> {
+        self.vdevices.iter().try_for_each(VDevice::check_lost)
+    }
+}
+
+/// Compiles `registered` for `vd`, the replacement of device `vdi`. Returns whether it
+/// could.
+fn recompile(registered: &mut RegisteredShader, vd: &VDevice, vdi: usize) -> bool {
+    let source = match (&registered.source, &registered.reflection) {
+        (Some(source), _) => source.clone(),
+        (None, Some(module)) => match written_as_wgsl(module) {
+            Some(code) => wgpu::ShaderSource::Wgsl(code.into()),
+            None => return false,
+        },
+        (None, None) => return false,
+    };
+
+    registered.modules[vdi] = shader::create_module(
+        vd,
+        &wgpu::ShaderModuleDescriptor {
+            label: None,
+            source,
+        },
+    );
+
+    true
+}
+
+/// The WGSL for a reflected module, if naga can write it.
+fn written_as_wgsl(module: &naga::Module) -> Option<String> {
+    let info = naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    )
+    .validate(module)
+    .ok()?;
+
+    naga::back::wgsl::write_string(module, &info, naga::back::wgsl::WriterFlags::empty()).ok()
+}
@@ -0,0 +1,206 @@
+use crate::error::WiscError;
+
+/// Parses a shader source into naga IR, if it is in a language we can read on the host.
+/// Fails with the front end's messages if the source doesn't parse.
+pub(crate) fn parse(source: &wgpu::ShaderSource) -> Result<Option<naga::Module>, WiscError> {
+    match source {
+        wgpu::ShaderSource::Wgsl(code) => naga::front::wgsl::parse_str(code)
+            .map(Some)
+            .map_err(|error| WiscError::ShaderParse(error.emit_to_string(code))),
+        // Drivers may take SPIR-V naga can't read, so that only loses reflection.
+        #[cfg(feature = "spirv")]
+        wgpu::ShaderSource::SpirV(words) => Ok(naga::front::spv::parse_u8_slice(
+            bytemuck::cast_slice(words),
+            &naga::front::spv::Options::default(),
+        )
+        .ok()),
+        #[cfg(feature = "glsl")]
+        wgpu::ShaderSource::Glsl {
+            shader,
+            stage,
+            defines,
+        } => {
+            if *stage != naga::ShaderStage::Compute {
+                return Err(WiscError::InvalidShader(
+                    "GLSL shaders must be for the compute stage",
+                ));
+            }
+
+            let options = naga::front::glsl::Options {
+                stage: *stage,
+                defines: defines
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect(),
+            };
+
+            naga::front::glsl::Frontend::default()
+                .parse(&options, shader)
+                .map(Some)
+                .map_err(|errors| WiscError::ShaderParse(errors.emit_to_string(shader)))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Lists the names of every compute entry point in `module`, in declaration order.
+pub(crate) fn compute_entry_points(module: &naga::Module) -> Vec<String> {
+    module
+        .entry_points
+        .iter()
+        .filter(|entry_point| entry_point.stage == naga::ShaderStage::Compute)
+        .map(|entry_point| entry_point.name.clone())
+        .collect()
+}
+
+/// The workgroup size declared by the compute entry point `kernel`, unless it is missing or
+/// its size depends on pipeline overrides.
+pub(crate) fn workgroup_size(module: &naga::Module, kernel: &str) -> Option<[u32; 3]> {
+    let entry_point = module.entry_points.iter().find(|entry_point| {
+        entry_point.stage == naga::ShaderStage::Compute && entry_point.name == kernel
+    })?;
+
+    if entry_point
+        .workgroup_size_overrides
+        .is_some_and(|overrides| overrides.iter().any(Option::is_some))
+    {
+        return None;
+    }
+
+    Some(entry_point.workgroup_size)
+}
+
+/// The keys pipeline constants can set `module`'s overrides by: each one's `@id` if it
+/// has one, or else its name.
+pub(crate) fn override_keys(module: &naga::Module) -> Vec<String> {
+    module
+        .overrides
+        .iter()
+        .filter_map(|(_, override_)| match override_.id {
+            Some(id) => Some(id.to_string()),
+            None => override_.name.clone(),
+        })
+        .collect()
+}
+
+/// Whether `module` declares a resource at `binding` of bind group `group`.
+pub(crate) fn declares_binding(module: &naga::Module, group: u32, binding: u32) -> bool {
+    module
+        .global_variables
+        .iter()
+        .any(|(_, global)| global.binding == Some(naga::ResourceBinding { group, binding }))
+}
+
+/// How a shader declares a buffer binding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BindingKind {
+    Uniform,
+    ReadOnlyStorage,
+    ReadWriteStorage,
+    /// Anything that isn't a buffer, like a texture or sampler.
+    Other,
+}
+
+/// A resource the shader declares in bind group 0.
+pub(crate) struct ShaderBinding {
+    pub(crate) binding: u32,
+    pub(crate) kind: BindingKind,
+    /// Whether the kernel (or anything it calls) touches it. Assumed when the module
+    /// doesn't validate.
+    pub(crate) used: bool,
+}
+
+/// Whether the entry point `kernel` (or anything it calls) touches each global variable of
+/// `module`. All of them are assumed to be when the module doesn't validate.
+fn global_uses(
+    module: &naga::Module,
+    kernel: &str,
+) -> impl Fn(naga::Handle<naga::GlobalVariable>) -> bool {
+    let info = naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    )
+    .validate(module)
+    .ok();
+
+    let entry_point = module
+        .entry_points
+        .iter()
+        .position(|entry_point| entry_point.name == kernel);
+
+    move |handle| match (&info, entry_point) {
+        (Some(info), Some(index)) => !info.get_entry_point(index)[handle].is_empty(),
+        _ => true,
+    }
+}
+
+/// The resources `module` declares in bind group 0, as seen by the entry point `kernel`.
+pub(crate) fn bindings(module: &naga::Module, kernel: &str) -> Vec<ShaderBinding> {
+    let uses = global_uses(module, kernel);
+
+    module
+        .global_variables
+        .iter()
+        .filter_map(|(handle, global)| {
+            let binding = global
+                .binding
+                .as_ref()
+                .filter(|binding| binding.group == 0)?;
+
+            let kind = match global.space {
+                naga::AddressSpace::Uniform => BindingKind::Uniform,
+                naga::AddressSpace::Storage { access }
+                    if access.contains(naga::StorageAccess::STORE) =>
+                {
+                    BindingKind::ReadWriteStorage
+                }
+                naga::AddressSpace::Storage { .. } => BindingKind::ReadOnlyStorage,
+                _ => BindingKind::Other,
+            };
+
+            Some(ShaderBinding {
+                binding: binding.binding,
+                kind,
+                used: uses(handle),
+            })
+        })
+        .collect()
+}
+
+/// The bytes of workgroup storage the entry point `kernel` uses, counted as WebGPU counts
+/// them against `max_compute_workgroup_storage_size`: each `var<workgroup>` rounded up to
+/// 16 bytes. `None` if the size of one depends on pipeline overrides.
+pub(crate) fn workgroup_storage_size(module: &naga::Module, kernel: &str) -> Option<u32> {
+    let uses = global_uses(module, kernel);
+
+    module
+        .global_variables
+        .iter()
+        .filter(|(handle, global)| global.space == naga::AddressSpace::WorkGroup && uses(*handle))
+        .map(|(_, global)| {
+            if sized_by_overrides(module, global.ty) {
+                return None;
+            }
+
+            module.types[global.ty]
+                .inner
+                .try_size(module.to_ctx())
+                .map(|size| size.next_multiple_of(16))
+        })
+        .sum()
+}
+
+/// Whether `ty` is, or holds, an array whose length is a pipeline override.
+fn sized_by_overrides(module: &naga::Module, ty: naga::Handle<naga::Type>) -> bool {
+    match &module.types[ty].inner {
+        naga::TypeInner::Array {
+            size: naga::ArraySize::Pending(_),
+            ..
+        } => true,
+        naga::TypeInner::Array { base, .. } => sized_by_overrides(module, *base),
+        naga::TypeInner::Struct { members, .. } => members
+            .iter()
+            .any(|member| sized_by_overrides(module, member.ty)),
+        _ => false,
+    }
+}
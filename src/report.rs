@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use crate::error::WiscError;
+
+/// What happened during a [`Task::run`](crate::task::Task::run).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunReport {
+    /// The number of devices the task ran on.
+    pub devices: usize,
+    /// Whether the task ran on exactly one device and so skipped partition planning,
+    /// worker threads, and multi-device write-back entirely.
+    pub single_device_fast_path: bool,
+    /// How many device copies of the outputs had their checksums verified, if the task
+    /// was built [`with_checksums`](crate::task::TaskBuilder::with_checksums).
+    pub checksums_verified: usize,
+    /// How many sub-dispatches a task built
+    /// [`with_time_slice`](crate::task::TaskBuilder::with_time_slice) was split into, across
+    /// all devices.
+    pub time_slices: usize,
+    /// How many tiles each device ran, in device order, for a task built
+    /// [`with_tiles`](crate::task::TaskBuilder::with_tiles).
+    pub tiles: Vec<usize>,
+    /// What was kept of each output bound
+    /// [`with_counted_output_buffer`](crate::task::TaskBuilder::with_counted_output_buffer),
+    /// in the order they were bound. What didn't fit in a device's share is dropped.
+    pub counted: Vec<Appended>,
+    /// What was appended to each append output, in the order they were bound.
+    pub appended: Vec<Appended>,
+    /// Whether the outputs were restored from the Workgroup's
+    /// [result cache](crate::workgroup::WorkgroupBuilder::result_cache) instead of being
+    /// computed.
+    pub from_cache: bool,
+    /// The devices that rejected the task's shader, or were lost before it was built, and
+    /// sat the run out, their share of the buffers spread over the others. Why is in the task's
+    /// [`compile_messages`](crate::task::Task::compile_messages). Under
+    /// [`Precision::Consistent`](crate::precision::Precision::Consistent), also the devices
+    /// that differ from the leading one.
+    pub excluded: Vec<usize>,
+    /// The devices that failed or were lost during the run, whose shares were run again on
+    /// another device instead. See [`Task::run`](crate::task::Task::run).
+    pub failed_over: Vec<usize>,
+    /// Where each device's time went, in device order, to tell which one held the run up.
+    /// All zero for the devices that sat it out, and for a run restored from the cache.
+    pub timings: Vec<DeviceTiming>,
+    /// How many times each device invoked the kernel, in device order, for a task built
+    /// [`with_pipeline_statistics`](crate::task::TaskBuilder::with_pipeline_statistics):
+    /// every invocation of every workgroup it dispatched, those past the end of its share
+    /// included. `None` for devices that can't count them, sat the run out, or failed.
+    pub invocations: Vec<Option<u64>>,
+}
+
+/// How long one device took over each stage of a run, as the host saw it and, where it
+/// can tell, as the device did, and how much it moved.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeviceTiming {
+    /// Recording the device's commands and submitting them.
+    pub submit: Duration,
+    /// From submitting the commands until the device finished them, copying its results
+    /// out included. Other work queued on a shared device counts too. Time slices and
+    /// tiles are waited on as they are submitted, so count as submitting.
+    pub execution: Duration,
+    /// Copying the device's results from its mapped staging buffers into the host copies.
+    pub readback: Duration,
+    /// How many bytes the run wrote to the device. Inputs are uploaded when the task is
+    /// built, so this is usually none.
+    pub bytes_uploaded: u64,
+    /// How many bytes the run read back from the device.
+    pub bytes_read_back: u64,
+    /// How long the task's compute pass took by the device's own clock, if it supports
+    /// [`TIMESTAMP_QUERY`](wgpu::Features::TIMESTAMP_QUERY). `None` otherwise, and for
+    /// time-sliced and tiled runs, which dispatch in many passes.
+    pub kernel: Option<Duration>,
+}
+
+/// What the devices appended to an output bound
+/// [`with_append_output`](crate::task::TaskBuilder::with_append_output), or counted in one
+/// bound [`with_counted_output_buffer`](crate::task::TaskBuilder::with_counted_output_buffer).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Appended {
+    /// How many elements each device contributed, in device order, which is the order they
+    /// were concatenated in.
+    pub lengths: Vec<usize>,
+    /// How many elements the devices tried to append beyond their capacity, which were
+    /// lost. A larger capacity keeps them.
+    pub dropped: usize,
+}
+
+/// What a [`Workgroup::shutdown`](crate::workgroup::Workgroup::shutdown) or
+/// [`Scheduler::shutdown`](crate::scheduler::Scheduler::shutdown) released, and what it
+/// had to leave behind.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShutdownReport {
+    /// How many jobs were still queued and were cancelled.
+    pub cancelled: usize,
+    /// How many device copies of VBuffers were destroyed.
+    pub buffers_destroyed: usize,
+    /// How many cached pipelines were released.
+    pub pipelines_released: usize,
+    /// The devices that were destroyed, no one else holding them.
+    pub devices_destroyed: Vec<usize>,
+    /// The devices left open because something else still holds them: another Workgroup
+    /// sharing them, a [`Scheduler`](crate::scheduler::Scheduler), a stream stage, or a
+    /// [`VDevice`](crate::vdevice::VDevice) kept by the caller. Whatever those built on
+    /// them lives on.
+    pub leaked_devices: Vec<usize>,
+    /// What the devices reported while finishing their work.
+    pub errors: Vec<WiscError>,
+}
@@ -0,0 +1,177 @@
+//! Memoized task results, kept on disk and keyed by a fingerprint of everything a run's
+//! outputs depend on, so that a run identical to an earlier one (as parameter sweeps
+//! often repeat) restores its outputs instead of dispatching anything.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::ops;
+use crate::pipeline_cache::write_atomically;
+use crate::workgroup::{VBufferHandle, Workgroup};
+
+const MAGIC: &[u8; 8] = b"WISCRST2";
+const EXTENSION: &str = "wiscresult";
+
+/// A directory of results, one file per fingerprint, trimmed to `max_bytes` by dropping
+/// the least recently used.
+pub(crate) struct ResultCache {
+    pub(crate) dir: PathBuf,
+    pub(crate) max_bytes: u64,
+}
+
+impl ResultCache {
+    // Fingerprints that share a file name only take turns in it, since every entry holds
+    // its whole fingerprint and is only loaded for that one.
+    fn path(&self, fingerprint: &[u8]) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        fingerprint.hash(&mut hasher);
+
+        self.dir
+            .join(format!("{:016x}.{EXTENSION}", hasher.finish()))
+    }
+
+    /// The bytes of every output stored for `fingerprint`, in binding order, if there are
+    /// any. Marks the entry as just used.
+    pub(crate) fn load(&self, fingerprint: &[u8]) -> Option<Vec<Vec<u8>>> {
+        let path = self.path(fingerprint);
+        let data = fs::read(&path).ok()?;
+
+        let rest = data.strip_prefix(MAGIC)?;
+        let (key_len, rest) = rest.split_first_chunk::<4>()?;
+        let (key, rest) = rest.split_at_checked(u32::from_le_bytes(*key_len) as usize)?;
+
+        if key != fingerprint {
+            return None;
+        }
+
+        // A failure only costs the entry its place in the eviction order.
+        let _ = fs::File::options()
+            .append(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now()));
+
+        let (count, mut rest) = rest.split_first_chunk::<4>()?;
+        let mut outputs = vec![];
+
+        for _ in 0..u32::from_le_bytes(*count) {
+            let (len, tail) = rest.split_first_chunk::<8>()?;
+            let len = usize::try_from(u64::from_le_bytes(*len)).ok()?;
+
+            if tail.len() < len {
+                return None;
+            }
+
+            let (bytes, tail) = tail.split_at(len);
+            outputs.push(bytes.to_vec());
+            rest = tail;
+        }
+
+        rest.is_empty().then_some(outputs)
+    }
+
+    /// Stores `outputs` for `fingerprint`, then drops the oldest entries until the cache
+    /// fits its bound again. Entries that can't be written are skipped; the run they came
+    /// from has already succeeded.
+    pub(crate) fn store(&self, fingerprint: &[u8], outputs: &[&[u8]]) {
+        let len = MAGIC.len()
+            + 4
+            + fingerprint.len()
+            + 4
+            + outputs.iter().map(|bytes| 8 + bytes.len()).sum::<usize>();
+
+        // An entry that could never fit would only push out everything else.
+        if len as u64 > self.max_bytes {
+            return;
+        }
+
+        let mut data = Vec::with_capacity(len);
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&(fingerprint.len() as u32).to_le_bytes());
+        data.extend_from_slice(fingerprint);
+        data.extend_from_slice(&(outputs.len() as u32).to_le_bytes());
+
+        for bytes in outputs {
+            data.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+            data.extend_from_slice(bytes);
+        }
+
+        if write_atomically(&self.path(fingerprint), &data).is_ok() {
+            self.evict();
+        }
+    }
+
+    fn evict(&self) {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+
+        let mut entries: Vec<(SystemTime, u64, PathBuf)> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != EXTENSION {
+                    return None;
+                }
+
+                let metadata = fs::metadata(&path).ok()?;
+                Some((metadata.modified().ok()?, metadata.len(), path))
+            })
+            .collect();
+
+        let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+        entries.sort_by_key(|(modified, _, _)| *modified);
+
+        for (_, len, path) in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+
+            if fs::remove_file(path).is_ok() {
+                total -= len;
+            }
+        }
+    }
+}
+
+/// Accumulates what a task's results depend on into a fingerprint. The fingerprint is
+/// all of it, byte for byte, rather than a hash of it, so that two runs only share an
+/// entry if they match exactly.
+#[derive(Clone, Default)]
+pub(crate) struct Fingerprint(Vec<u8>);
+
+impl Fingerprint {
+    pub(crate) fn add<T: Hash + ?Sized>(&mut self, value: &T) -> &mut Self {
+        value.hash(self);
+
+        self
+    }
+
+    /// Adds the contents of `handle`, hashed where they are. `None` if they can't be,
+    /// say because the buffer hasn't been written.
+    pub(crate) fn add_buffer(
+        &mut self,
+        workgroup: &Workgroup,
+        handle: VBufferHandle,
+    ) -> Option<&mut Self> {
+        let hash = ops::hash_buffer(workgroup, handle).ok()?;
+
+        Some(self.add(&hash))
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+// Values are added by their `Hash` impls, which write what identifies them here.
+impl Hasher for Fingerprint {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        unreachable!("a fingerprint is kept whole, not hashed")
+    }
+}
@@ -0,0 +1,138 @@
+//! Running tasks from async code. In the browser the devices finish work and resolve
+//! readbacks on their own, so awaiting a run yields to it until they do. Natively the
+//! devices have to be polled: with the `tokio` feature that happens on Tokio's blocking
+//! threads, leaving the runtime's workers free to serve other requests, and otherwise the
+//! awaiting task polls them itself whenever it is polled.
+
+#[cfg(not(feature = "tokio"))]
+use std::task::Poll;
+
+#[cfg(not(feature = "tokio"))]
+use futures_lite::future;
+use web_time::Instant;
+
+use crate::error::WiscError;
+use crate::report::RunReport;
+#[cfg(feature = "tokio")]
+use crate::task::finish;
+use crate::task::{PendingTask, Task, time_execution};
+use crate::vdevice::{Mapping, VDevice};
+use crate::workgroup::Workgroup;
+
+impl Task<'_> {
+    /// Runs the task like [`run`](Self::run), but awaits the results instead of blocking
+    /// on them. Only the commands are recorded and the results written back on the
+    /// calling thread.
+    ///
+    /// With the `tokio` feature, must be awaited inside a Tokio runtime.
+    pub async fn run_async(&mut self) -> Result<RunReport, WiscError> {
+        self.submit()?.wait_async().await
+    }
+}
+
+impl Workgroup {
+    /// Awaits the devices' being idle like [`wait_idle`](Self::wait_idle) blocks on it.
+    ///
+    /// With the `tokio` feature, must be awaited inside a Tokio runtime.
+    pub async fn wait_idle_async(&self) -> Result<(), WiscError> {
+        self.flush();
+
+        settle(self.vdevices.clone(), vec![]).await
+    }
+}
+
+impl PendingTask<'_, '_> {
+    /// Awaits the results like [`wait`](Self::wait) blocks on them.
+    ///
+    /// With the `tokio` feature, must be awaited inside a Tokio runtime. A run that had a
+    /// device lost before it was submitted is waited for the blocking way, to run its
+    /// share elsewhere; one that loses a device while awaited fails.
+    pub async fn wait_async(mut self) -> Result<RunReport, WiscError> {
+        let mut report = std::mem::take(&mut self.report);
+
+        let Some(submitted) = self.submitted.take() else {
+            return Ok(report);
+        };
+
+        if !submitted.failed.is_empty() {
+            let (failed, finished) = self.task.settle(submitted.mappings, submitted.failed)?;
+            time_execution(&mut report, &submitted.submitted_at, &finished);
+
+            return self
+                .task
+                .write_back(report, submitted.started, submitted.fingerprint, &failed);
+        }
+
+        // The devices are shared handles, so whoever polls them can have their own. Those
+        // that sat the task out have nothing to wait for.
+        let vdevices = self
+            .task
+            .workgroup
+            .vdevices
+            .iter()
+            .enumerate()
+            .filter(|(vdi, _)| !self.task.excluded.contains(vdi))
+            .map(|(_, vd)| vd.clone())
+            .collect();
+        settle(vdevices, submitted.mappings).await?;
+
+        // Awaiting the devices together, there is no telling which finished when.
+        let finished = vec![Instant::now(); self.task.workgroup.vdevices.len()];
+        time_execution(&mut report, &submitted.submitted_at, &finished);
+
+        self.task
+            .write_back(report, submitted.started, submitted.fingerprint, &[])
+    }
+}
+
+/// Waits for every device to finish and every mapping to resolve, on a blocking thread.
+#[cfg(feature = "tokio")]
+async fn settle(vdevices: Vec<VDevice>, mappings: Vec<Mapping>) -> Result<(), WiscError> {
+    tokio::task::spawn_blocking(move || finish(&vdevices, mappings))
+        .await
+        .map_err(|error| WiscError::DeviceLost(error.to_string()))?
+}
+
+/// Waits for every mapping to resolve, or with none to wait for, every device to finish.
+#[cfg(not(feature = "tokio"))]
+async fn settle(vdevices: Vec<VDevice>, mut mappings: Vec<Mapping>) -> Result<(), WiscError> {
+    future::poll_fn(|cx| {
+        let mut idle = true;
+        for vd in &vdevices {
+            match vd.poll() {
+                Ok(done) => idle &= done,
+                Err(error) => return Poll::Ready(Err(error)),
+            }
+        }
+
+        let ready = if mappings.is_empty() {
+            idle
+        } else {
+            // Every mapping is polled, not just those up to the first pending one, so that
+            // each registers the waker.
+            let mut ready = true;
+            for mapping in &mut mappings {
+                ready &= mapping.poll_ready(cx).is_ready();
+            }
+            ready
+        };
+
+        if ready {
+            return Poll::Ready(Ok(()));
+        }
+
+        // Nothing but polling moves native devices along, so come back to them soon.
+        if !cfg!(target_arch = "wasm32") {
+            cx.waker().wake_by_ref();
+        }
+
+        Poll::Pending
+    })
+    .await?;
+
+    for mapping in mappings {
+        mapping.finish()?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,346 @@
+//! A small runtime for many independent tasks: each device gets a worker thread with a
+//! queue of jobs and a single-device Workgroup of its own to run them on.
+
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::error::WiscError;
+use crate::report::ShutdownReport;
+use crate::vdevice::VDevice;
+use crate::workgroup::Workgroup;
+
+// Given no Workgroup, a job was cancelled and only reports so.
+type Job = Box<dyn FnOnce(Option<&mut Workgroup>) + Send>;
+
+/// How soon a job runs relative to the others waiting on its device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Priority {
+    /// Ahead of everything else, for interactive work someone is waiting on.
+    High,
+    #[default]
+    Normal,
+    /// Behind everything else, for long batch work.
+    Background,
+}
+
+impl Priority {
+    const LEVELS: usize = 3;
+
+    fn level(self) -> usize {
+        match self {
+            Priority::High => 0,
+            Priority::Normal => 1,
+            Priority::Background => 2,
+        }
+    }
+}
+
+/// How many times the job at the head of a queue can be passed over for jobs of a higher
+/// priority before it runs anyway.
+pub const STARVATION_LIMIT: u32 = 8;
+
+struct Queued {
+    job: Job,
+    // How many jobs of a higher priority have run ahead of it while it was first in line.
+    passed_over: u32,
+}
+
+/// One device's waiting jobs, a queue for each priority, highest first.
+#[derive(Default)]
+struct DeviceQueue([VecDeque<Queued>; Priority::LEVELS]);
+
+impl DeviceQueue {
+    fn len(&self) -> usize {
+        self.0.iter().map(VecDeque::len).sum()
+    }
+
+    /// How many jobs wait that would run before one of `priority` were it queued now.
+    fn ahead_of(&self, priority: Priority) -> usize {
+        self.0[..=priority.level()].iter().map(VecDeque::len).sum()
+    }
+
+    /// The job to run next: the first of the highest priority, unless the first of a lower
+    /// one has been passed over too often.
+    fn pop(&mut self) -> Option<Job> {
+        let starving = self.0.iter().position(|queue| {
+            queue
+                .front()
+                .is_some_and(|queued| queued.passed_over >= STARVATION_LIMIT)
+        });
+        let level = starving.or_else(|| self.0.iter().position(|queue| !queue.is_empty()))?;
+
+        let queued = self.0[level].pop_front()?;
+
+        for queue in &mut self.0[level + 1..] {
+            if let Some(waiting) = queue.front_mut() {
+                waiting.passed_over += 1;
+            }
+        }
+
+        Some(queued.job)
+    }
+}
+
+struct Queues {
+    per_device: Vec<DeviceQueue>,
+    // How many jobs each device is running right now, zero or one.
+    running: Vec<usize>,
+    closed: bool,
+}
+
+struct Shared {
+    queues: Mutex<Queues>,
+    ready: Condvar,
+}
+
+/// Runs submitted jobs across a set of devices, each job on whichever single device takes
+/// it. A job is a closure given that device's Workgroup, in which it creates its buffers
+/// and builds and runs its tasks; what it returns is handed back through the
+/// [`ScheduledTask`] that [`submit`](Self::submit) returns.
+///
+/// Jobs are queued on the device with the least work waiting ahead of them, and each
+/// device runs its queue in order of [`Priority`], and in order of submission within
+/// one. A device whose queue runs dry takes the next job waiting on the busiest other
+/// device, so one long job doesn't hold up the ones queued behind it.
+///
+/// Jobs of a higher priority only ever overtake waiting jobs, never interrupt running
+/// ones. So that a steady stream of them can't starve the rest, the job at the head of a
+/// lower priority's queue runs once it has been passed over [`STARVATION_LIMIT`] times.
+///
+/// Every device's Workgroup lives for as long as the Scheduler, so shaders registered in
+/// one job (see [`Workgroup::register_shader`]) are there for later jobs on that device.
+/// Dropping the Scheduler runs the jobs still queued, then stops its threads, each
+/// [shutting down](Workgroup::shutdown) its Workgroup; [`shutdown`](Self::shutdown)
+/// cancels the queued jobs instead, and reports what was released.
+pub struct Scheduler {
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<ShutdownReport>>,
+}
+
+impl Scheduler {
+    /// A Scheduler with a worker for each of `devices`, like the ones
+    /// [`Workgroup::shared_devices`] returns.
+    pub fn new(devices: Vec<VDevice>) -> Self {
+        let num_devices = devices.len();
+
+        let shared = Arc::new(Shared {
+            queues: Mutex::new(Queues {
+                per_device: (0..num_devices).map(|_| DeviceQueue::default()).collect(),
+                running: vec![0; num_devices],
+                closed: false,
+            }),
+            ready: Condvar::new(),
+        });
+
+        let workers = devices
+            .into_iter()
+            .enumerate()
+            .map(|(vdi, vd)| {
+                let shared = shared.clone();
+
+                thread::Builder::new()
+                    .name(format!("wisc scheduler ({})", vd.label))
+                    .spawn(move || work(&shared, vdi, vd))
+                    .expect("Failed to spawn a scheduler thread")
+            })
+            .collect();
+
+        Self { shared, workers }
+    }
+
+    /// Queues `job` to run on one of the devices at [`Priority::Normal`]. Fails if the
+    /// Scheduler has no devices.
+    pub fn submit<R, F>(&self, job: F) -> Result<ScheduledTask<R>, WiscError>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut Workgroup) -> Result<R, WiscError> + Send + 'static,
+    {
+        self.submit_with_priority(Priority::Normal, job)
+    }
+
+    /// Queues `job` like [`submit`](Self::submit), ahead of every waiting job of a lower
+    /// `priority`.
+    pub fn submit_with_priority<R, F>(
+        &self,
+        priority: Priority,
+        job: F,
+    ) -> Result<ScheduledTask<R>, WiscError>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut Workgroup) -> Result<R, WiscError> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+
+        let job: Job = Box::new(move |workgroup| {
+            let result = match workgroup {
+                Some(workgroup) => panic::catch_unwind(AssertUnwindSafe(|| job(workgroup))),
+                None => Ok(Err(WiscError::Cancelled)),
+            };
+
+            // Nobody may be waiting for the result anymore.
+            let _ = sender.send(result);
+        });
+
+        let mut queues = self.shared.queues.lock().unwrap();
+
+        let vdi = (0..queues.per_device.len())
+            .min_by_key(|&vdi| queues.per_device[vdi].ahead_of(priority) + queues.running[vdi])
+            .ok_or(WiscError::NoDevices)?;
+
+        queues.per_device[vdi].0[priority.level()].push_back(Queued {
+            job,
+            passed_over: 0,
+        });
+        drop(queues);
+
+        self.shared.ready.notify_all();
+
+        Ok(ScheduledTask { receiver })
+    }
+
+    /// How many jobs wait in each device's queue, in device order. Jobs already running
+    /// aren't counted.
+    pub fn queued(&self) -> Vec<usize> {
+        let queues = self.shared.queues.lock().unwrap();
+
+        queues.per_device.iter().map(DeviceQueue::len).collect()
+    }
+
+    /// Cancels the jobs still queued, whose [`ScheduledTask`]s then return
+    /// [`WiscError::Cancelled`], waits for the running ones to finish, and stops the
+    /// threads, each of which [shuts down](Workgroup::shutdown) its device's Workgroup as
+    /// it stops. What they released is reported together, by device.
+    pub fn shutdown(mut self) -> ShutdownReport {
+        let mut queues = self.shared.queues.lock().unwrap();
+        queues.closed = true;
+
+        let cancelled: Vec<Job> = queues
+            .per_device
+            .iter_mut()
+            .flat_map(|queue| queue.0.iter_mut().flat_map(|queue| queue.drain(..)))
+            .map(|queued| queued.job)
+            .collect();
+        drop(queues);
+
+        self.shared.ready.notify_all();
+
+        let mut report = ShutdownReport {
+            cancelled: cancelled.len(),
+            ..ShutdownReport::default()
+        };
+
+        for job in cancelled {
+            job(None);
+        }
+
+        for (vdi, worker) in self.workers.drain(..).enumerate() {
+            let Ok(released) = worker.join() else {
+                report.errors.push(WiscError::DeviceLost(
+                    "a scheduler thread stopped".to_string(),
+                ));
+                continue;
+            };
+
+            // Each worker's Workgroup has the one device.
+            report.buffers_destroyed += released.buffers_destroyed;
+            report.pipelines_released += released.pipelines_released;
+            if !released.devices_destroyed.is_empty() {
+                report.devices_destroyed.push(vdi);
+            }
+            if !released.leaked_devices.is_empty() {
+                report.leaked_devices.push(vdi);
+            }
+            report.errors.extend(released.errors);
+        }
+
+        report
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        self.shared.queues.lock().unwrap().closed = true;
+        self.shared.ready.notify_all();
+
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Runs device `vdi`'s jobs, and any it takes from the others, until the Scheduler is
+/// closed and nothing is left, then shuts the device's Workgroup down.
+fn work(shared: &Shared, vdi: usize, vd: VDevice) -> ShutdownReport {
+    let mut workgroup = Workgroup::from_devices(vec![vd]);
+
+    loop {
+        let mut queues = shared.queues.lock().unwrap();
+
+        let job = loop {
+            if let Some(job) = next_job(&mut queues, vdi) {
+                break job;
+            }
+
+            if queues.closed {
+                drop(queues);
+
+                return workgroup.shutdown();
+            }
+
+            queues = shared.ready.wait(queues).unwrap();
+        };
+
+        queues.running[vdi] += 1;
+        drop(queues);
+
+        job(Some(&mut workgroup));
+
+        shared.queues.lock().unwrap().running[vdi] -= 1;
+    }
+}
+
+/// The next job in device `vdi`'s queue, or else the next one waiting on the device with
+/// the most waiting.
+fn next_job(queues: &mut Queues, vdi: usize) -> Option<Job> {
+    if let Some(job) = queues.per_device[vdi].pop() {
+        return Some(job);
+    }
+
+    let busiest =
+        (0..queues.per_device.len()).max_by_key(|&other| queues.per_device[other].len())?;
+
+    queues.per_device[busiest].pop()
+}
+
+/// A job submitted to a [`Scheduler`], to join on for its result.
+pub struct ScheduledTask<R> {
+    receiver: Receiver<thread::Result<Result<R, WiscError>>>,
+}
+
+impl<R> ScheduledTask<R> {
+    /// Blocks until the job has run, and returns what it did. A job that panicked panics
+    /// here in turn.
+    pub fn join(self) -> Result<R, WiscError> {
+        match self.receiver.recv() {
+            Ok(result) => result.unwrap_or_else(|payload| panic::resume_unwind(payload)),
+            // Jobs always send their result, unless their worker died before running them.
+            Err(_) => Err(WiscError::DeviceLost(
+                "a scheduler thread stopped".to_string(),
+            )),
+        }
+    }
+
+    /// The job's result if it has run, without blocking; otherwise, the task back.
+    pub fn try_join(self) -> Result<Result<R, WiscError>, Self> {
+        match self.receiver.try_recv() {
+            Ok(result) => Ok(result.unwrap_or_else(|payload| panic::resume_unwind(payload))),
+            Err(TryRecvError::Empty) => Err(self),
+            Err(TryRecvError::Disconnected) => Ok(Err(WiscError::DeviceLost(
+                "a scheduler thread stopped".to_string(),
+            ))),
+        }
+    }
+}
@@ -0,0 +1,443 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "blocking")]
+use futures_lite::future;
+
+#[cfg(feature = "spirv")]
+use crate::error::WiscError;
+use crate::vdevice::VDevice;
+
+#[cfg(feature = "spirv")]
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+/// Reads the WGSL file at `path`, replacing every `//#include "file.wgsl"` line with the
+/// contents of that file, resolved relative to the file containing the directive.
+///
+/// Includes nest, and each file is pasted at most once, so helpers shared by several
+/// includes (or files that include each other) don't produce duplicate definitions.
+pub fn resolve_wgsl_includes<P: AsRef<Path>>(path: P) -> io::Result<String> {
+    ShaderSourceBuilder::new().resolve(path)
+}
+
+/// Builds a shader from the WGSL file at `path`, with its includes
+/// resolved by [`resolve_wgsl_includes`].
+pub fn wgsl_with_includes<P: AsRef<Path>>(path: P) -> io::Result<Shader<'static>> {
+    Ok(Shader::wgsl(resolve_wgsl_includes(path)?))
+}
+
+/// Assembles WGSL from several files and snippets, for kernel codebases split across
+/// files.
+///
+/// Files pull others in with `#include "file.wgsl"` (or `//#include "file.wgsl"`, which
+/// keeps them valid WGSL on their own) lines, found next to the including file or else in
+/// the search paths, in the order they were added. Snippets, like
+/// [`PARTITION_INFO_WGSL`](crate::partition::PARTITION_INFO_WGSL), go first.
+///
+/// ```no_run
+/// use wisc::{partition::PARTITION_INFO_WGSL, shader::ShaderSourceBuilder};
+///
+/// let source = ShaderSourceBuilder::new()
+///     .search_path("shaders/common")
+///     .snippet(PARTITION_INFO_WGSL)
+///     .build("shaders/blur.wgsl")
+///     .expect("Failed to assemble the shader");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ShaderSourceBuilder {
+    search_paths: Vec<PathBuf>,
+    snippets: Vec<String>,
+}
+
+impl ShaderSourceBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a directory to look for included files in, after any added before.
+    pub fn search_path<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.search_paths.push(path.into());
+
+        self
+    }
+
+    /// Adds WGSL to paste ahead of the shader, after any added before.
+    pub fn snippet<S: Into<String>>(mut self, code: S) -> Self {
+        self.snippets.push(code.into());
+
+        self
+    }
+
+    /// The snippets followed by the file at `path`, with its includes resolved.
+    pub fn resolve<P: AsRef<Path>>(&self, path: P) -> io::Result<String> {
+        let mut source = self.snippet_source();
+        let mut included = HashSet::new();
+
+        self.append_file(path.as_ref(), &mut source, &mut included)?;
+
+        Ok(source)
+    }
+
+    /// As [`resolve`](Self::resolve), along with every file the source was assembled from.
+    pub(crate) fn resolve_files<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> io::Result<(String, Vec<PathBuf>)> {
+        let mut source = self.snippet_source();
+        let mut included = HashSet::new();
+
+        self.append_file(path.as_ref(), &mut source, &mut included)?;
+
+        Ok((source, included.into_iter().collect()))
+    }
+
+    /// The snippets followed by `code`, whose includes are looked up in the search paths.
+    pub fn resolve_source(&self, code: &str) -> io::Result<String> {
+        let mut source = self.snippet_source();
+        let mut included = HashSet::new();
+
+        self.append_code(code, None, &mut source, &mut included)?;
+
+        Ok(source)
+    }
+
+    /// Builds a shader from the file at `path`, as
+    /// [`resolve`](Self::resolve) assembles it.
+    pub fn build<P: AsRef<Path>>(&self, path: P) -> io::Result<Shader<'static>> {
+        Ok(Shader::wgsl(self.resolve(path)?))
+    }
+
+    /// Builds a shader from `code`, as
+    /// [`resolve_source`](Self::resolve_source) assembles it.
+    pub fn build_source(&self, code: &str) -> io::Result<Shader<'static>> {
+        Ok(Shader::wgsl(self.resolve_source(code)?))
+    }
+
+    fn snippet_source(&self) -> String {
+        let mut source = String::new();
+
+        for snippet in &self.snippets {
+            source.push_str(snippet);
+
+            if !snippet.ends_with('\n') {
+                source.push('\n');
+            }
+        }
+
+        source
+    }
+
+    fn append_file(
+        &self,
+        path: &Path,
+        source: &mut String,
+        included: &mut HashSet<PathBuf>,
+    ) -> io::Result<()> {
+        let canonical = fs::canonicalize(path).map_err(|error| with_path(error, path))?;
+
+        if !included.insert(canonical.clone()) {
+            return Ok(());
+        }
+
+        let code = fs::read_to_string(&canonical).map_err(|error| with_path(error, path))?;
+        let directory = canonical.parent().unwrap_or(Path::new(""));
+
+        self.append_code(&code, Some(directory), source, included)
+    }
+
+    fn append_code(
+        &self,
+        code: &str,
+        directory: Option<&Path>,
+        source: &mut String,
+        included: &mut HashSet<PathBuf>,
+    ) -> io::Result<()> {
+        for line in code.lines() {
+            match include_target(line) {
+                Some(target) => {
+                    let path = self.find(target, directory);
+                    self.append_file(&path, source, included)?;
+                }
+                None => {
+                    source.push_str(line);
+                    source.push('\n');
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Where `target` is: next to the including file if it is there, or else in the first
+    /// search path that has it. Falls back to the first place looked, for the error.
+    fn find(&self, target: &str, directory: Option<&Path>) -> PathBuf {
+        let mut candidates = directory
+            .into_iter()
+            .chain(self.search_paths.iter().map(PathBuf::as_path))
+            .map(|directory| directory.join(target));
+
+        let first = candidates.next().unwrap_or_else(|| PathBuf::from(target));
+
+        if first.exists() {
+            return first;
+        }
+
+        candidates
+            .find(|candidate| candidate.exists())
+            .unwrap_or(first)
+    }
+}
+
+/// A shader to run: WGSL source, or with the `spirv` and `glsl` features, a SPIR-V binary
+/// or GLSL source, as the functions in this module build them.
+///
+/// Descriptors from wgpu, like the ones its `include_wgsl!` makes, convert into it. Code
+/// that only builds shaders through wisc doesn't touch wgpu's API at all, and so keeps
+/// compiling when wisc moves to wgpu's next major version.
+#[derive(Debug, Clone)]
+pub struct Shader<'a>(pub(crate) wgpu::ShaderModuleDescriptor<'a>);
+
+impl<'a> Shader<'a> {
+    /// A shader from WGSL source.
+    pub fn wgsl<S: Into<Cow<'a, str>>>(source: S) -> Self {
+        Shader(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        })
+    }
+
+    /// Names the shader in debuggers and the devices' error messages.
+    pub fn with_label(mut self, label: &'a str) -> Self {
+        self.0.label = Some(label);
+
+        self
+    }
+}
+
+impl<'a> From<wgpu::ShaderModuleDescriptor<'a>> for Shader<'a> {
+    fn from(descriptor: wgpu::ShaderModuleDescriptor<'a>) -> Self {
+        Shader(descriptor)
+    }
+}
+
+/// Builds a [`Shader`] from a WGSL file at compile time, labelled with its path. Like
+/// `include_str!`, the path is relative to the file the macro is used in.
+#[macro_export]
+macro_rules! include_wgsl {
+    ($path:literal) => {
+        $crate::shader::Shader::wgsl(include_str!($path)).with_label($path)
+    };
+}
+
+/// Builds a [`Shader`] from a SPIR-V binary included at compile time, checked the same way
+/// as [`spirv`]. Like `include_bytes!`, the path is relative to the file the macro is used
+/// in.
+#[cfg(feature = "spirv")]
+#[macro_export]
+macro_rules! include_spirv {
+    ($path:literal) => {
+        $crate::shader::spirv(include_bytes!($path)).map(|shader| shader.with_label($path))
+    };
+}
+
+/// Builds a shader from a compiled SPIR-V binary, like the output of
+/// glslang or rust-gpu, in either byte order. wgpu translates it for each device unless the
+/// Workgroup was built with
+/// [`spirv_passthrough`](crate::workgroup::WorkgroupBuilder::spirv_passthrough).
+#[cfg(feature = "spirv")]
+pub fn spirv(bytes: &[u8]) -> Result<Shader<'static>, WiscError> {
+    if !bytes.len().is_multiple_of(4) {
+        return Err(WiscError::InvalidShader(
+            "SPIR-V must be a whole number of words",
+        ));
+    }
+
+    let mut words: Vec<u32> = bytes
+        .chunks_exact(4)
+        .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+        .collect();
+
+    match words.first() {
+        Some(&SPIRV_MAGIC) => {}
+        Some(magic) if magic.swap_bytes() == SPIRV_MAGIC => {
+            words.iter_mut().for_each(|word| *word = word.swap_bytes());
+        }
+        _ => return Err(WiscError::InvalidShader("missing the SPIR-V magic number")),
+    }
+
+    Ok(Shader(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::SpirV(words.into()),
+    }))
+}
+
+/// Builds a shader from GLSL compute shader source, whose entry point is
+/// `main`. Errors in the source are reported when a task using it is built.
+#[cfg(feature = "glsl")]
+pub fn glsl(code: &str) -> Shader<'_> {
+    Shader(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::Glsl {
+            shader: code.into(),
+            stage: naga::ShaderStage::Compute,
+            defines: &[],
+        },
+    })
+}
+
+/// A message from compiling a task's shader for one device, like a warning from the
+/// backend's compiler. The same source can compile cleanly on one backend and warn on
+/// another, so each device reports its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileMessage {
+    /// The device's index in the Workgroup.
+    pub device: usize,
+    pub kind: CompileMessageKind,
+    pub message: String,
+    /// The line of the source it points at, if it points anywhere.
+    pub line: Option<u32>,
+}
+
+impl CompileMessage {
+    /// Whether the device rejected the shader over this message.
+    pub fn is_error(&self) -> bool {
+        self.kind == CompileMessageKind::Error
+    }
+}
+
+/// How serious a [`CompileMessage`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompileMessageKind {
+    /// The device rejected the shader.
+    Error,
+    Warning,
+    Info,
+}
+
+impl From<wgpu::CompilationMessageType> for CompileMessageKind {
+    fn from(kind: wgpu::CompilationMessageType) -> Self {
+        match kind {
+            wgpu::CompilationMessageType::Error => CompileMessageKind::Error,
+            wgpu::CompilationMessageType::Warning => CompileMessageKind::Warning,
+            wgpu::CompilationMessageType::Info => CompileMessageKind::Info,
+        }
+    }
+}
+
+/// Compiles a module for the device at `vdi` with `create`, returning what compiling it
+/// reported. Validation errors are caught and reported as well, instead of reaching the
+/// device's error handler.
+pub(crate) fn checked_compile(
+    vdi: usize,
+    vd: &VDevice,
+    create: impl FnOnce() -> wgpu::ShaderModule,
+) -> Vec<CompileMessage> {
+    let scope = vd.device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let module = create();
+    let error = resolve(scope.pop()).flatten();
+
+    let mut messages = compile_messages(vdi, &module);
+
+    if let Some(error) = error
+        && !messages.iter().any(CompileMessage::is_error)
+    {
+        messages.push(CompileMessage {
+            device: vdi,
+            kind: CompileMessageKind::Error,
+            message: error.to_string(),
+            line: None,
+        });
+    }
+
+    messages
+}
+
+/// What compiling `module` for the device at `vdi` reported.
+pub(crate) fn compile_messages(vdi: usize, module: &wgpu::ShaderModule) -> Vec<CompileMessage> {
+    resolve(module.get_compilation_info())
+        .map(|info| info.messages)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|message| CompileMessage {
+            device: vdi,
+            kind: message.message_type.into(),
+            message: message.message,
+            line: message.location.map(|location| location.line_number),
+        })
+        .collect()
+}
+
+/// What `future` resolves to, waiting for it.
+#[cfg(feature = "blocking")]
+fn resolve<F: Future>(future: F) -> Option<F::Output> {
+    Some(future::block_on(future))
+}
+
+/// What `future` resolves to, if it already has. Without the `blocking` feature, as in the
+/// browser, compiling reports only what native backends know at once, rather than waiting
+/// on the device for its messages.
+#[cfg(not(feature = "blocking"))]
+fn resolve<F: Future>(future: F) -> Option<F::Output> {
+    let future = std::pin::pin!(future);
+    let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+
+    match future.poll(&mut cx) {
+        std::task::Poll::Ready(output) => Some(output),
+        std::task::Poll::Pending => None,
+    }
+}
+
+/// Compiles `descriptor` on `vd`, passing SPIR-V through untranslated if the device allows.
+pub(crate) fn create_module(
+    vd: &VDevice,
+    descriptor: &wgpu::ShaderModuleDescriptor,
+) -> wgpu::ShaderModule {
+    #[cfg(feature = "spirv")]
+    if let wgpu::ShaderSource::SpirV(words) = &descriptor.source
+        && vd
+            .features
+            .contains(wgpu::Features::EXPERIMENTAL_PASSTHROUGH_SHADERS)
+    {
+        // SAFETY: devices only have the feature if the Workgroup was built with the unsafe
+        // `spirv_passthrough`, whose caller vouches for every SPIR-V module it is given.
+        return unsafe {
+            vd.device
+                .create_shader_module_passthrough(wgpu::ShaderModuleDescriptorPassthrough {
+                    label: descriptor.label,
+                    spirv: Some(words.clone()),
+                    ..Default::default()
+                })
+        };
+    }
+
+    vd.device.create_shader_module(descriptor.clone())
+}
+
+/// A copy of `source` that borrows nothing, for the kinds of source that can be copied.
+/// GLSL's defines borrow their strings, so it can't.
+pub(crate) fn owned_source(source: &wgpu::ShaderSource) -> Option<wgpu::ShaderSource<'static>> {
+    match source {
+        wgpu::ShaderSource::Wgsl(code) => Some(wgpu::ShaderSource::Wgsl(code.to_string().into())),
+        #[cfg(feature = "spirv")]
+        wgpu::ShaderSource::SpirV(words) => Some(wgpu::ShaderSource::SpirV(words.to_vec().into())),
+        _ => None,
+    }
+}
+
+/// The quoted file name of an `#include "file.wgsl"` or `//#include "file.wgsl"` line.
+fn include_target(line: &str) -> Option<&str> {
+    let line = line.trim();
+
+    line.strip_prefix("//#include")
+        .or_else(|| line.strip_prefix("#include"))?
+        .trim()
+        .strip_prefix('"')?
+        .strip_suffix('"')
+}
+
+fn with_path(error: io::Error, path: &Path) -> io::Error {
+    io::Error::new(error.kind(), format!("{}: {error}", path.display()))
+}
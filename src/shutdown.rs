@@ -0,0 +1,82 @@
+//! Tearing a Workgroup down in a known order, rather than whenever its parts are dropped.
+
+use std::sync::Arc;
+
+use crate::report::ShutdownReport;
+use crate::task::per_device_parallel;
+use crate::workgroup::Workgroup;
+
+impl Workgroup {
+    /// Finishes the devices' work, then releases everything the Workgroup built on them
+    /// before returning: the device copies of its VBuffers are destroyed, which unmaps any
+    /// still mapped, as are its cached uniforms, and its cached pipelines, bind groups and
+    /// shader modules are dropped. The devices themselves are destroyed unless something
+    /// else still holds them, which the report lists as leaked.
+    ///
+    /// Nothing is saved: call [`save_pipeline_cache`](Self::save_pipeline_cache) first to
+    /// keep the compiled pipelines, and take the VBuffers worth keeping. Errors the devices
+    /// report while finishing are collected in the report rather than stopping the
+    /// teardown.
+    ///
+    /// The Workgroup runs no threads of its own. A [`Scheduler`](crate::scheduler::Scheduler)
+    /// does, and has a [`shutdown`](crate::scheduler::Scheduler::shutdown) of its own.
+    pub fn shutdown(self) -> ShutdownReport {
+        let mut report = ShutdownReport::default();
+
+        self.flush();
+        report.errors = per_device_parallel(&self.vdevices, |_, vd| vd.wait())
+            .into_iter()
+            .filter_map(Result::err)
+            .collect();
+
+        // Aliases share their buffers, which are only counted once.
+        let mut destroyed: Vec<wgpu::Buffer> = vec![];
+        for vbuffer in self.vbuffers.values() {
+            // Imported memory belongs to the API it came from.
+            if vbuffer.imported {
+                continue;
+            }
+
+            for buffer in vbuffer
+                .residency
+                .resident()
+                .into_iter()
+                .flat_map(|r| &r.buffers)
+            {
+                if !destroyed.contains(buffer) {
+                    buffer.destroy();
+                    destroyed.push(buffer.clone());
+                }
+            }
+        }
+        report.buffers_destroyed = destroyed.len();
+
+        let Workgroup {
+            vdevices,
+            vbuffers,
+            shaders,
+            watched_shaders,
+            binding_caches,
+            pipeline_caches,
+            ..
+        } = self;
+
+        for cache in binding_caches {
+            report.pipelines_released += cache.release();
+        }
+        drop((vbuffers, shaders, watched_shaders, pipeline_caches));
+
+        for (vdi, vd) in vdevices.into_iter().enumerate() {
+            // Every clone of a VDevice shares its module cache, so this is the last one
+            // if no other holds it.
+            if Arc::strong_count(&vd.modules) > 1 {
+                report.leaked_devices.push(vdi);
+            } else {
+                vd.destroy();
+                report.devices_destroyed.push(vdi);
+            }
+        }
+
+        report
+    }
+}
@@ -0,0 +1,234 @@
+//! Snapshots of every VBuffer's host contents in a compact binary file, for checkpointing
+//! experiments or attaching the exact inputs that reproduce a problem to a bug report.
+//!
+//! A snapshot starts with `WISCSNAP`, a format version and the number of buffers, followed
+//! by each buffer: its element type name, stride, length, element group size, flags, its
+//! quantization if it is quantized, and then its elements, unless it hasn't been written.
+//! Every number is little-endian.
+
+use std::fs;
+use std::path::Path;
+
+use bytemuck::Pod;
+
+use crate::complex::{Complex32, Complex64};
+use crate::error::WiscError;
+use crate::pipeline_cache::write_atomically;
+use crate::quant::Quantization;
+use crate::vbuffer::VBuffer;
+use crate::workgroup::{VBufferHandle, Workgroup};
+
+const MAGIC: &[u8; 8] = b"WISCSNAP";
+const VERSION: u32 = 1;
+
+const INITIALIZED: u8 = 1;
+const QUANTIZED: u8 = 2;
+
+/// How to restore a buffer of one element type from its bytes.
+#[derive(Clone, Copy)]
+pub(crate) struct ElementType {
+    stride: usize,
+    restore: fn(&[u8]) -> VBuffer,
+    restore_uninit: fn(usize) -> VBuffer,
+}
+
+impl ElementType {
+    fn of<T: Pod>() -> Self {
+        Self {
+            stride: std::mem::size_of::<T>(),
+            restore: |bytes| VBuffer::new::<T>(bytemuck::pod_collect_to_vec(bytes)),
+            restore_uninit: VBuffer::new_uninit::<T>,
+        }
+    }
+}
+
+/// The element types every snapshot can be restored with, even by a Workgroup that hasn't
+/// created buffers of them.
+fn builtin_element_type(name: &str) -> Option<ElementType> {
+    macro_rules! lookup {
+        ($($t:ty),*) => {
+            $(
+                if name == std::any::type_name::<$t>() {
+                    return Some(ElementType::of::<$t>());
+                }
+            )*
+        };
+    }
+
+    lookup!(
+        u8, u16, u32, u64, i8, i16, i32, i64, f32, f64, Complex32, Complex64, [i8; 4]
+    );
+
+    None
+}
+
+impl Workgroup {
+    /// Lets snapshots restore buffers of `T`. Creating a buffer of `T` registers it too, as
+    /// do the integer, float and complex element types.
+    pub fn register_element_type<T: Pod>(&mut self) {
+        self.element_types
+            .entry(std::any::type_name::<T>())
+            .or_insert_with(ElementType::of::<T>);
+    }
+
+    /// Writes the host contents of every VBuffer to a snapshot at `path`, replacing any
+    /// file there, and returns their handles in the order they were written. Device copies
+    /// aren't saved, since the host copy holds everything tasks have written back.
+    pub fn save_buffers<P: AsRef<Path>>(&self, path: P) -> Result<Vec<VBufferHandle>, WiscError> {
+        let path = path.as_ref();
+        let mut file = vec![];
+
+        file.extend_from_slice(MAGIC);
+        file.extend_from_slice(&VERSION.to_le_bytes());
+        file.extend_from_slice(&(self.vbuffers.len() as u64).to_le_bytes());
+
+        for (_, vbuffer) in &self.vbuffers {
+            // What runs left on the devices is saved as if it had been read back.
+            let pending = self.pending_contents(vbuffer)?;
+            let initialized = vbuffer.assume_init.is_none() || pending.is_some();
+
+            file.extend_from_slice(&(vbuffer.type_name.len() as u32).to_le_bytes());
+            file.extend_from_slice(vbuffer.type_name.as_bytes());
+
+            for number in [vbuffer.stride, vbuffer.length, vbuffer.group_size] {
+                file.extend_from_slice(&(number as u64).to_le_bytes());
+            }
+
+            let mut flags = 0;
+            if initialized {
+                flags |= INITIALIZED;
+            }
+            if vbuffer.quantized.is_some() {
+                flags |= QUANTIZED;
+            }
+            file.push(flags);
+
+            if let Some((quantization, values)) = vbuffer.quantized {
+                file.extend_from_slice(&quantization.scale.to_le_bytes());
+                file.extend_from_slice(&quantization.zero_point.to_le_bytes());
+                file.extend_from_slice(&(values as u64).to_le_bytes());
+            }
+
+            if initialized {
+                file.extend_from_slice(
+                    pending
+                        .as_deref()
+                        .unwrap_or(crate::task::vbuffer_bytes(vbuffer)),
+                );
+            }
+        }
+
+        write_atomically(path, &file)
+            .map_err(|error| WiscError::SnapshotFile(format!("{}: {error}", path.display())))?;
+
+        Ok(self.vbuffers.keys().collect())
+    }
+
+    /// Registers every buffer in the snapshot at `path` as a new VBuffer, and returns their
+    /// handles in the order [`save_buffers`](Self::save_buffers) returned them. Buffers that
+    /// hadn't been written come back unwritten.
+    ///
+    /// Fails, registering nothing, if the file isn't a snapshot or holds a buffer of an
+    /// element type that isn't [registered](Self::register_element_type).
+    pub fn load_buffers<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<Vec<VBufferHandle>, WiscError> {
+        let path = path.as_ref();
+        let failed =
+            |reason: &str| WiscError::SnapshotFile(format!("{}: {reason}", path.display()));
+
+        let file = fs::read(path).map_err(|error| failed(&error.to_string()))?;
+        let mut reader = Reader { bytes: &file };
+
+        if reader.take(MAGIC.len()) != Some(MAGIC) {
+            return Err(failed("not a buffer snapshot"));
+        }
+
+        if reader.u32() != Some(VERSION) {
+            return Err(failed("written by an unsupported version"));
+        }
+
+        let truncated = || failed("truncated");
+        let count = reader.u64().ok_or_else(truncated)?;
+        let mut vbuffers = vec![];
+
+        for _ in 0..count {
+            let name_len = reader.u32().ok_or_else(truncated)? as usize;
+            let name = reader.take(name_len).ok_or_else(truncated)?;
+            let name = std::str::from_utf8(name).map_err(|_| failed("malformed type name"))?;
+
+            let element_type = self
+                .element_types
+                .get(name)
+                .copied()
+                .or_else(|| builtin_element_type(name))
+                .ok_or_else(|| failed(&format!("unregistered element type {name}")))?;
+
+            let stride = reader.u64().ok_or_else(truncated)? as usize;
+            let length = reader.u64().ok_or_else(truncated)? as usize;
+            let group_size = reader.u64().ok_or_else(truncated)? as usize;
+            let flags = reader.take(1).ok_or_else(truncated)?[0];
+
+            if stride != element_type.stride {
+                return Err(failed(&format!("{name} has changed size")));
+            }
+
+            let quantized = if flags & QUANTIZED != 0 {
+                let scale = f32::from_le_bytes(reader.array().ok_or_else(truncated)?);
+                let zero_point = i8::from_le_bytes(reader.array().ok_or_else(truncated)?);
+                let values = reader.u64().ok_or_else(truncated)? as usize;
+
+                Some((Quantization::new(scale, zero_point), values))
+            } else {
+                None
+            };
+
+            let mut vbuffer = if flags & INITIALIZED != 0 {
+                let byte_len = length.checked_mul(stride).ok_or_else(truncated)?;
+                (element_type.restore)(reader.take(byte_len).ok_or_else(truncated)?)
+            } else {
+                (element_type.restore_uninit)(length)
+            };
+
+            vbuffer.group_size = group_size;
+            vbuffer.quantized = quantized;
+            vbuffers.push(vbuffer);
+        }
+
+        Ok(vbuffers
+            .into_iter()
+            .map(|vbuffer| self.vbuffers.insert(vbuffer))
+            .collect())
+    }
+}
+
+/// Reads a snapshot front to back. Every read returns `None` past the end.
+struct Reader<'b> {
+    bytes: &'b [u8],
+}
+
+impl<'b> Reader<'b> {
+    fn take(&mut self, len: usize) -> Option<&'b [u8]> {
+        if len > self.bytes.len() {
+            return None;
+        }
+
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+
+        Some(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.take(N)?.try_into().ok()
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.array().map(u64::from_le_bytes)
+    }
+}
@@ -0,0 +1,79 @@
+use crate::vdevice::VDevice;
+
+/// The invocations one device counts over a task's compute pass, read back through a
+/// staging buffer with the task's results.
+pub(crate) struct PassStatistics {
+    query_set: wgpu::QuerySet,
+    resolved: wgpu::Buffer,
+    staging: wgpu::Buffer,
+}
+
+impl PassStatistics {
+    /// Statistics for a pass on `vd`, if it supports pipeline statistics queries.
+    pub(crate) fn new(vd: &VDevice) -> Option<Self> {
+        if !vd
+            .features
+            .contains(wgpu::Features::PIPELINE_STATISTICS_QUERY)
+        {
+            return None;
+        }
+
+        let query_set = vd.device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some(&format!("WISC Pipeline Statistics (VDevice {})", vd.label)),
+            ty: wgpu::QueryType::PipelineStatistics(
+                wgpu::PipelineStatisticsTypes::COMPUTE_SHADER_INVOCATIONS,
+            ),
+            count: 1,
+        });
+        let resolved = vd.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!(
+                "WISC Pipeline Statistics Resolved (VDevice {})",
+                vd.label
+            )),
+            size: 8,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging = vd.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!(
+                "WISC Pipeline Statistics Staging (VDevice {})",
+                vd.label
+            )),
+            size: 8,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Some(Self {
+            query_set,
+            resolved,
+            staging,
+        })
+    }
+
+    /// Starts counting, before the pass's first dispatch.
+    pub(crate) fn begin(&self, compute_pass: &mut wgpu::ComputePass) {
+        compute_pass.begin_pipeline_statistics_query(&self.query_set, 0);
+    }
+
+    /// Records copying the count to the staging buffer, once the pass has ended.
+    pub(crate) fn encode_readback(&self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.resolve_query_set(&self.query_set, 0..1, &self.resolved, 0);
+        encoder.copy_buffer_to_buffer(&self.resolved, 0, &self.staging, 0, 8);
+    }
+
+    /// The buffer to map once the readback has been submitted.
+    pub(crate) fn staging(&self) -> &wgpu::Buffer {
+        &self.staging
+    }
+
+    /// How many times the pass invoked the kernel, once the staging buffer is mapped.
+    /// Unmaps it.
+    pub(crate) fn read(&self) -> u64 {
+        let invocations: u64 =
+            bytemuck::pod_read_unaligned(&self.staging.slice(..).get_mapped_range());
+        self.staging.unmap();
+
+        invocations
+    }
+}
@@ -0,0 +1,816 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::Read;
+use std::sync::mpsc;
+
+use web_time::Instant;
+use wgpu::util::DeviceExt;
+
+use crate::dispatch::{Dispatch, DispatchSize};
+use crate::error::WiscError;
+use crate::history::{self, Launch};
+use crate::precision::Precision;
+use crate::prelude::Workgroup;
+use crate::reflect::BindingKind;
+use crate::task::{
+    InputBinding, TaskBuilder, check_binding_size, check_bindings, check_immediates,
+    check_overrides, create_pipeline, override_constants, per_device_parallel, resolve_dispatch,
+    resolve_kernel, storage_layout_entry, uniform_layout_entry, vbuffer_bytes,
+};
+use crate::throttle;
+use crate::vdevice::{self, Mapping, VDevice};
+
+/// A pull-based supplier of input bytes for streaming execution.
+///
+/// Streaming tasks only ask for the next chunk once a device is free to take it, so a
+/// source that blocks (a bounded channel, a socket, a decompressor) naturally applies
+/// backpressure to whatever is producing the data.
+pub trait BufferSource {
+    /// Returns the next chunk of input, or `None` once the source is exhausted.
+    ///
+    /// The chunk is only borrowed until the next call, so sources are free to reuse a
+    /// single internal buffer.
+    fn next_chunk(&mut self) -> Option<&[u8]>;
+}
+
+/// Splits an in-memory byte slice into fixed-size chunks.
+pub struct SliceSource<'s> {
+    chunks: std::slice::Chunks<'s, u8>,
+}
+
+impl<'s> SliceSource<'s> {
+    /// Fails if `chunk_size` is zero.
+    pub fn new(bytes: &'s [u8], chunk_size: usize) -> Result<Self, WiscError> {
+        check_chunk_size(chunk_size)?;
+
+        Ok(Self {
+            chunks: bytes.chunks(chunk_size),
+        })
+    }
+}
+
+impl BufferSource for SliceSource<'_> {
+    fn next_chunk(&mut self) -> Option<&[u8]> {
+        self.chunks.next()
+    }
+}
+
+/// Reads fixed-size chunks from any [`Read`]er, such as a file or a socket.
+///
+/// The final chunk may be shorter. A read error ends the stream.
+pub struct ReaderSource<R: Read> {
+    reader: R,
+    buffer: Vec<u8>,
+}
+
+impl<R: Read> ReaderSource<R> {
+    /// Fails if `chunk_size` is zero.
+    pub fn new(reader: R, chunk_size: usize) -> Result<Self, WiscError> {
+        check_chunk_size(chunk_size)?;
+
+        Ok(Self {
+            reader,
+            buffer: vec![0; chunk_size],
+        })
+    }
+}
+
+fn check_chunk_size(chunk_size: usize) -> Result<(), WiscError> {
+    if chunk_size == 0 {
+        return Err(WiscError::InvalidBinding(
+            "streamed chunks must hold at least one byte",
+        ));
+    }
+
+    Ok(())
+}
+
+impl<R: Read> BufferSource for ReaderSource<R> {
+    fn next_chunk(&mut self) -> Option<&[u8]> {
+        let mut filled = 0;
+
+        while filled < self.buffer.len() {
+            match self.reader.read(&mut self.buffer[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            }
+        }
+
+        (filled > 0).then(|| &self.buffer[..filled])
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl<R: Read> ReaderSource<zstd::stream::read::Decoder<'static, std::io::BufReader<R>>> {
+    /// Reads a zstd-compressed stream, decompressing it on the fly into chunks of
+    /// `chunk_size` uncompressed bytes. Fails if the stream has no zstd header, or
+    /// `chunk_size` is zero.
+    pub fn zstd(reader: R, chunk_size: usize) -> std::io::Result<Self> {
+        Self::new(zstd::stream::read::Decoder::new(reader)?, chunk_size)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))
+    }
+}
+
+#[cfg(feature = "lz4")]
+impl<R: Read> ReaderSource<lz4_flex::frame::FrameDecoder<R>> {
+    /// Reads an LZ4 frame stream, decompressing it on the fly into chunks of `chunk_size`
+    /// uncompressed bytes. Fails if `chunk_size` is zero.
+    pub fn lz4(reader: R, chunk_size: usize) -> Result<Self, WiscError> {
+        Self::new(lz4_flex::frame::FrameDecoder::new(reader), chunk_size)
+    }
+}
+
+/// The codec used by a [`DecompressSource`].
+#[cfg(any(feature = "zstd", feature = "lz4"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    #[cfg(feature = "zstd")]
+    Zstd,
+    #[cfg(feature = "lz4")]
+    Lz4,
+}
+
+/// Wraps a source whose chunks are each an independently compressed frame, yielding the
+/// decompressed chunks. A chunk that fails to decompress ends the stream.
+#[cfg(any(feature = "zstd", feature = "lz4"))]
+pub struct DecompressSource<S: BufferSource> {
+    inner: S,
+    compression: Compression,
+    buffer: Vec<u8>,
+}
+
+#[cfg(any(feature = "zstd", feature = "lz4"))]
+impl<S: BufferSource> DecompressSource<S> {
+    pub fn new(inner: S, compression: Compression) -> Self {
+        Self {
+            inner,
+            compression,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+#[cfg(any(feature = "zstd", feature = "lz4"))]
+impl<S: BufferSource> BufferSource for DecompressSource<S> {
+    fn next_chunk(&mut self) -> Option<&[u8]> {
+        let compressed = self.inner.next_chunk()?;
+
+        self.buffer.clear();
+
+        match self.compression {
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => {
+                zstd::stream::copy_decode(compressed, &mut self.buffer).ok()?;
+            }
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => {
+                let mut decoder = lz4_flex::frame::FrameDecoder::new(compressed);
+                std::io::copy(&mut decoder, &mut self.buffer).ok()?;
+            }
+        }
+
+        Some(&self.buffer)
+    }
+}
+
+/// Receives chunks sent by another thread. Pair it with [`mpsc::sync_channel`] to bound
+/// how far the producer can run ahead of the devices.
+pub struct ChannelSource {
+    receiver: mpsc::Receiver<Vec<u8>>,
+    current: Vec<u8>,
+}
+
+impl ChannelSource {
+    pub fn new(receiver: mpsc::Receiver<Vec<u8>>) -> Self {
+        Self {
+            receiver,
+            current: Vec::new(),
+        }
+    }
+}
+
+impl BufferSource for ChannelSource {
+    fn next_chunk(&mut self) -> Option<&[u8]> {
+        self.current = self.receiver.recv().ok()?;
+
+        Some(&self.current)
+    }
+}
+
+/// A task that runs its kernel once per chunk pulled from a [`BufferSource`].
+///
+/// Each chunk is bound as the streamed input, and the streamed output is sized to hold the
+/// same number of elements. Chunks are handed to the devices round-robin, with at most one
+/// chunk in flight per device, and results reach the sink in source order.
+pub struct StreamTask<'t> {
+    pub(crate) workgroup: &'t mut Workgroup,
+    pub(crate) stage: StreamStage,
+}
+
+/// A streaming kernel compiled for every device of a Workgroup, without holding on to the
+/// Workgroup itself, so that several can be combined in a [`StreamPipeline`].
+pub struct StreamStage {
+    pub(crate) kernel: String,
+    pub(crate) size: (u32, u32, u32),
+
+    // (binding, element stride) of the streamed input and output.
+    pub(crate) stream_input: (u32, usize),
+    pub(crate) stream_output: (u32, usize),
+
+    pub(crate) pipelines: Vec<(wgpu::BindGroupLayout, wgpu::ComputePipeline)>,
+    // The fixed input bindings, uploaded once and shared by every chunk.
+    pub(crate) fixed_buffers: Vec<Vec<(u32, wgpu::Buffer)>>,
+    pub(crate) immediates: Vec<u8>,
+}
+
+struct InFlight {
+    vdi: usize,
+    submitted: Instant,
+    output_len: usize,
+    // The staging buffer being mapped, or `None` for an empty chunk, which is never
+    // dispatched.
+    readback: Option<(wgpu::Buffer, Mapping)>,
+}
+
+impl InFlight {
+    /// Waits for the chunk to finish and passes its output bytes to `sink`.
+    fn finish<F: FnOnce(&[u8])>(self, vd: &VDevice, sink: F) -> Result<(), WiscError> {
+        let Some((staging_buffer, mapping)) = self.readback else {
+            sink(&[]);
+            return Ok(());
+        };
+
+        vd.wait()?;
+        mapping.finish()?;
+
+        {
+            let data = staging_buffer.slice(..).get_mapped_range();
+            sink(&data[..self.output_len]);
+        }
+        staging_buffer.unmap();
+
+        Ok(())
+    }
+}
+
+impl<'t> StreamTask<'t> {
+    pub(crate) fn from_builder(builder: TaskBuilder<'t>) -> Result<Self, WiscError> {
+        let (workgroup, stage) = StreamStage::from_builder(builder)?;
+
+        Ok(StreamTask { workgroup, stage })
+    }
+
+    /// Pulls chunks from `source` until it is exhausted, passing each chunk's output bytes to
+    /// `sink` in order. Returns the number of chunks processed.
+    pub fn run<F: FnMut(&[u8])>(
+        &mut self,
+        source: &mut dyn BufferSource,
+        mut sink: F,
+    ) -> Result<usize, WiscError> {
+        let vdevices = &self.workgroup.vdevices;
+        let num_devices = vdevices.len();
+        if num_devices == 0 {
+            return Ok(0);
+        }
+
+        let mut in_flight: VecDeque<InFlight> = VecDeque::with_capacity(num_devices);
+        let mut next_device = 0;
+        let mut processed = 0;
+        let mut exhausted = false;
+
+        loop {
+            // Keep every device busy, but never pull more than one chunk per device ahead.
+            while !exhausted && in_flight.len() < num_devices {
+                let Some(chunk) = source.next_chunk() else {
+                    exhausted = true;
+                    break;
+                };
+
+                in_flight.push_back(self.stage.submit_chunk(
+                    next_device,
+                    &vdevices[next_device],
+                    chunk,
+                    &mut self.workgroup.launches,
+                ));
+                next_device = (next_device + 1) % num_devices;
+            }
+
+            let Some(oldest) = in_flight.pop_front() else {
+                break;
+            };
+
+            let (vdi, submitted) = (oldest.vdi, oldest.submitted);
+            oldest.finish(&vdevices[vdi], &mut sink)?;
+            throttle::idle_after(self.workgroup.throttle, submitted);
+
+            processed += 1;
+        }
+
+        Ok(processed)
+    }
+}
+
+impl StreamStage {
+    pub(crate) fn from_builder(
+        builder: TaskBuilder<'_>,
+    ) -> Result<(&mut Workgroup, Self), WiscError> {
+        let TaskBuilder {
+            workgroup,
+            shader,
+            kernel,
+            size,
+            overrides,
+            input_buffers,
+            uniform_values,
+            output_buffers,
+            expected_types,
+            partition_info,
+            global_ids,
+            precision,
+            // Streams have no devices to leave out, and wgpu rejects an oversized kernel
+            // itself.
+            over_limit: _,
+            checksums,
+            pipeline_statistics,
+            immediates,
+            time_slice,
+            tiles,
+            sliding_windows,
+            // Counted outputs are output buffers too, which are turned away below.
+            counted_outputs: _,
+            append_outputs,
+            stream_input,
+            stream_output,
+            template,
+        } = builder;
+
+        workgroup.has_expected_types(&expected_types)?;
+        workgroup.check_lost()?;
+
+        // Chunks have no place in a partition.
+        if partition_info.is_some() || global_ids {
+            return Err(WiscError::InvalidBinding(
+                "streamed chunks have no partition info",
+            ));
+        }
+
+        if checksums {
+            return Err(WiscError::InvalidBinding(
+                "streamed chunks aren't checksummed",
+            ));
+        }
+
+        if precision != Precision::Relaxed {
+            return Err(WiscError::InvalidBinding(
+                "streamed chunks go to every device",
+            ));
+        }
+
+        if !sliding_windows.is_empty() {
+            return Err(WiscError::InvalidBinding(
+                "streamed chunks aren't split into windows",
+            ));
+        }
+
+        if !append_outputs.is_empty() {
+            return Err(WiscError::InvalidBinding(
+                "streamed chunks have no append outputs",
+            ));
+        }
+
+        if pipeline_statistics {
+            return Err(WiscError::InvalidDispatch("streamed chunks aren't counted"));
+        }
+
+        // Chunks are already a way of keeping each submission short.
+        if time_slice.is_some() {
+            return Err(WiscError::InvalidDispatch(
+                "streamed chunks aren't time-sliced",
+            ));
+        }
+
+        // Chunks are dealt out to the devices already.
+        if tiles.is_some() {
+            return Err(WiscError::InvalidDispatch("streamed chunks aren't tiled"));
+        }
+
+        let shader = shader.ok_or(WiscError::MissingShader)?;
+        let size = size.ok_or(WiscError::MissingSize)?.checked()?;
+        let (Some(stream_input), Some(stream_output)) = (stream_input, stream_output) else {
+            return Err(WiscError::InvalidBinding(
+                "a stream needs a streamed input and output",
+            ));
+        };
+
+        // Only the streamed output is read back per chunk.
+        if !output_buffers.is_empty() {
+            return Err(WiscError::InvalidBinding(
+                "a stream can't have output buffers besides the streamed one",
+            ));
+        }
+
+        shader.check_available(workgroup)?;
+        check_immediates(workgroup, &immediates)?;
+
+        let reflection = shader.reflect(workgroup)?;
+        let kernel = resolve_kernel(reflection.as_ref(), kernel)?;
+
+        if let Some(module) = &reflection {
+            check_overrides(module, &overrides)?;
+        }
+
+        if let Some(module) = &reflection {
+            let bound: Vec<(u32, BindingKind)> = input_buffers
+                .iter()
+                .map(|input| {
+                    let kind = if input.uniform {
+                        BindingKind::Uniform
+                    } else {
+                        BindingKind::ReadOnlyStorage
+                    };
+
+                    (input.id, kind)
+                })
+                .chain(
+                    uniform_values
+                        .iter()
+                        .map(|(id, _)| (*id, BindingKind::Uniform)),
+                )
+                .chain([
+                    (stream_input.0, BindingKind::ReadOnlyStorage),
+                    (stream_output.0, BindingKind::ReadWriteStorage),
+                ])
+                .collect();
+
+            check_bindings(module, &kernel, &bound)?;
+        }
+
+        // Every chunk is dispatched alike, so the size has to be known up front.
+        let fixed_size =
+            WiscError::InvalidDispatch("a stream needs the same dispatch for every chunk");
+        let size = match size {
+            DispatchSize::FromIndirect(_) => return Err(fixed_size),
+            size => match resolve_dispatch(
+                workgroup,
+                size,
+                reflection.as_ref(),
+                &kernel,
+                &HashMap::new(),
+            )?
+            .first()
+            {
+                Some(Dispatch::Direct(x, y, z)) => (*x, *y, *z),
+                _ => return Err(fixed_size),
+            },
+        };
+
+        let num_devices = workgroup.vdevices.len();
+
+        let mut layouts: Vec<Vec<wgpu::BindGroupLayoutEntry>> = vec![vec![]; num_devices];
+        let mut fixed_buffers: Vec<Vec<(u32, wgpu::Buffer)>> = vec![vec![]; num_devices];
+
+        for InputBinding {
+            id,
+            handle: key,
+            uniform,
+            ..
+        } in &input_buffers
+        {
+            workgroup.read_back_pending(*key)?;
+
+            let vbuffer = workgroup
+                .vbuffers
+                .get(*key)
+                .ok_or(WiscError::UnknownVBuffer)?;
+
+            if vbuffer.assume_init.is_some() {
+                return Err(WiscError::Uninitialized);
+            }
+
+            for (vdi, vd) in workgroup.vdevices.iter().enumerate() {
+                check_binding_size(vd, vdi, *id, vbuffer_bytes(vbuffer).len() as u64, *uniform)?;
+
+                let label = format!("WISC Input Buffer {} (VDevice {})", id, vd.label);
+
+                let wgpu_buffer = vd
+                    .device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some(&label),
+                        contents: vbuffer_bytes(vbuffer),
+                        usage: if *uniform {
+                            wgpu::BufferUsages::UNIFORM
+                        } else {
+                            wgpu::BufferUsages::STORAGE
+                        },
+                    });
+
+                layouts[vdi].push(if *uniform {
+                    uniform_layout_entry(*id)
+                } else {
+                    storage_layout_entry(*id, true)
+                });
+                fixed_buffers[vdi].push((*id, wgpu_buffer));
+            }
+        }
+
+        for (id, contents) in &uniform_values {
+            for (vdi, vd) in workgroup.vdevices.iter().enumerate() {
+                let label = format!("WISC Uniform Buffer {} (VDevice {})", id, vd.label);
+
+                layouts[vdi].push(uniform_layout_entry(*id));
+                fixed_buffers[vdi].push((
+                    *id,
+                    workgroup.binding_caches[vdi].uniform(vd, &label, contents),
+                ));
+            }
+        }
+
+        for layout in layouts.iter_mut() {
+            layout.push(storage_layout_entry(stream_input.0, true));
+            layout.push(storage_layout_entry(stream_output.0, false));
+        }
+
+        let override_constants = override_constants(&overrides);
+
+        let pipelines = per_device_parallel(&workgroup.vdevices, |vdi, vd| {
+            let module = shader.module(&workgroup.shaders, vdi, vd);
+
+            create_pipeline(
+                vd,
+                &workgroup.binding_caches[vdi],
+                &module,
+                &kernel,
+                &layouts[vdi],
+                &override_constants,
+                &immediates,
+                workgroup.pipeline_caches[vdi].as_ref(),
+                template,
+            )
+        });
+
+        Ok((
+            workgroup,
+            StreamStage {
+                kernel,
+                size,
+                stream_input,
+                stream_output,
+                pipelines,
+                fixed_buffers,
+                immediates,
+            },
+        ))
+    }
+
+    /// Dispatches the kernel on `chunk`, adding the dispatch to `history` if it is recorded.
+    fn submit_chunk(
+        &self,
+        vdi: usize,
+        vd: &VDevice,
+        chunk: &[u8],
+        history: &mut Option<Vec<Launch>>,
+    ) -> InFlight {
+        let (bind_group_layout, pipeline) = &self.pipelines[vdi];
+
+        let (input_id, input_stride) = self.stream_input;
+        let (output_id, output_stride) = self.stream_output;
+
+        let output_len = chunk.len() / input_stride * output_stride;
+        let submitted = Instant::now();
+
+        // wgpu can't bind an empty chunk, and there would be nothing to compute anyway.
+        if chunk.is_empty() || output_len == 0 {
+            return InFlight {
+                vdi,
+                submitted,
+                output_len: 0,
+                readback: None,
+            };
+        }
+        // Storage buffers must be a multiple of four bytes long.
+        let output_size = output_len.next_multiple_of(4) as wgpu::BufferAddress;
+
+        let mappable_primary = vd
+            .features
+            .contains(wgpu::Features::MAPPABLE_PRIMARY_BUFFERS);
+
+        let input_buffer = vd
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(&format!(
+                    "WISC Stream Input {} (VDevice {})",
+                    input_id, vd.label
+                )),
+                contents: chunk,
+                usage: wgpu::BufferUsages::STORAGE,
+            });
+
+        let output_buffer = vd.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!(
+                "WISC Stream Output {} (VDevice {})",
+                output_id, vd.label
+            )),
+            size: output_size,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | if mappable_primary {
+                    wgpu::BufferUsages::MAP_READ
+                } else {
+                    wgpu::BufferUsages::empty()
+                },
+            mapped_at_creation: false,
+        });
+
+        let staging_buffer = if mappable_primary {
+            output_buffer.clone()
+        } else {
+            vd.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&format!(
+                    "WISC Stream Staging {} (VDevice {})",
+                    output_id, vd.label
+                )),
+                size: output_size,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        };
+
+        let mut bind_group_entries: Vec<wgpu::BindGroupEntry> = self.fixed_buffers[vdi]
+            .iter()
+            .map(|(id, buffer)| wgpu::BindGroupEntry {
+                binding: *id,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect();
+        bind_group_entries.push(wgpu::BindGroupEntry {
+            binding: input_id,
+            resource: input_buffer.as_entire_binding(),
+        });
+        bind_group_entries.push(wgpu::BindGroupEntry {
+            binding: output_id,
+            resource: output_buffer.as_entire_binding(),
+        });
+
+        let bind_group = vd.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: bind_group_layout,
+            entries: &bind_group_entries,
+        });
+
+        let mut encoder = vd
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: None,
+                timestamp_writes: None,
+            });
+
+            compute_pass.set_pipeline(pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[]);
+
+            if !self.immediates.is_empty() {
+                compute_pass.set_immediates(0, &self.immediates);
+            }
+
+            let (x, y, z) = self.size;
+            compute_pass.dispatch_workgroups(x, y, z);
+        }
+
+        if !mappable_primary {
+            encoder.copy_buffer_to_buffer(&output_buffer, 0, &staging_buffer, 0, output_size);
+        }
+
+        vd.queue.submit([encoder.finish()]);
+
+        history::record(history, || {
+            let fixed = self.fixed_buffers[vdi]
+                .iter()
+                .map(|(id, buffer)| (*id, buffer.size()));
+
+            Launch::new(
+                vdi,
+                &self.kernel,
+                Some(self.size),
+                fixed.chain([(input_id, input_buffer.size()), (output_id, output_size)]),
+            )
+        });
+
+        let mapping = vdevice::map_read(&staging_buffer);
+
+        InFlight {
+            vdi,
+            submitted,
+            output_len,
+            readback: Some((staging_buffer, mapping)),
+        }
+    }
+}
+
+/// Runs two streaming stages back to back on different devices: while the producer device
+/// runs the first stage on chunk N, the consumer device runs the second stage on chunk
+/// N - 1. The first stage's output for each chunk is staged through the host and becomes
+/// the second stage's streamed input.
+///
+/// Pairing a strong device with a weak one this way hides the weak device's latency
+/// behind the strong device's work, as long as the stages take similar time on each.
+pub struct StreamPipeline<'t> {
+    workgroup: &'t mut Workgroup,
+    producer: StreamStage,
+    consumer: StreamStage,
+    devices: (usize, usize),
+}
+
+impl<'t> StreamPipeline<'t> {
+    /// By default the producer runs on the strongest device and the consumer on the next
+    /// strongest, or both on the same device if there is only one.
+    pub fn new(workgroup: &'t mut Workgroup, producer: StreamStage, consumer: StreamStage) -> Self {
+        let consumer_device = 1.min(workgroup.vdevices.len().saturating_sub(1));
+
+        Self {
+            workgroup,
+            producer,
+            consumer,
+            devices: (0, consumer_device),
+        }
+    }
+
+    /// Picks the devices, by index into the Workgroup, that run each stage. Fails if either
+    /// index is out of range.
+    pub fn with_devices(mut self, producer: usize, consumer: usize) -> Result<Self, WiscError> {
+        let num_devices = self.workgroup.vdevices.len();
+
+        if producer >= num_devices || consumer >= num_devices {
+            return Err(WiscError::OutOfBounds);
+        }
+
+        self.devices = (producer, consumer);
+
+        Ok(self)
+    }
+
+    /// Pulls chunks from `source` until it is exhausted, passing the second stage's output
+    /// for each chunk to `sink` in order. Returns the number of chunks processed.
+    pub fn run<F: FnMut(&[u8])>(
+        &mut self,
+        source: &mut dyn BufferSource,
+        mut sink: F,
+    ) -> Result<usize, WiscError> {
+        let (producer_vdi, consumer_vdi) = self.devices;
+        let Some(producer_vd) = self.workgroup.vdevices.get(producer_vdi) else {
+            return Ok(0);
+        };
+        let consumer_vd = &self.workgroup.vdevices[consumer_vdi];
+        let history = &mut self.workgroup.launches;
+
+        let mut first: Option<InFlight> = None;
+        let mut second: Option<InFlight> = None;
+        let mut processed = 0;
+        let mut exhausted = false;
+
+        loop {
+            let started = Instant::now();
+
+            if !exhausted {
+                match source.next_chunk() {
+                    Some(chunk) => {
+                        first = Some(self.producer.submit_chunk(
+                            producer_vdi,
+                            producer_vd,
+                            chunk,
+                            history,
+                        ))
+                    }
+                    None => exhausted = true,
+                }
+            }
+
+            if first.is_none() && second.is_none() {
+                break;
+            }
+
+            // The older chunk leaves the pipeline first, keeping the sink in source order.
+            if let Some(in_flight) = second.take() {
+                in_flight.finish(consumer_vd, &mut sink)?;
+                processed += 1;
+            }
+
+            if let Some(in_flight) = first.take() {
+                in_flight.finish(producer_vd, |staged| {
+                    second = Some(self.consumer.submit_chunk(
+                        consumer_vdi,
+                        consumer_vd,
+                        staged,
+                        history,
+                    ));
+                })?;
+            }
+
+            throttle::idle_after(self.workgroup.throttle, started);
+        }
+
+        Ok(processed)
+    }
+}
@@ -1,23 +1,79 @@
-use std::any::Any;
-use std::sync::mpsc;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::time::Duration;
 
+use bytemuck::Pod;
+use web_time::Instant;
 use wgpu::util::DeviceExt;
 
+use crate::cache::{self, BindingCache, PipelineStore};
+use crate::checksum;
+use crate::collective::{Merge, MergeKey, Merger, ReduceOp, Reducible, reduce_merger};
+use crate::counter::{self, DeviceCounter};
+use crate::dispatch::{self, Dispatch, DispatchSize};
+use crate::error::WiscError;
+use crate::failover::Recipe;
+use crate::history::{self, Launch};
+use crate::partition::{self, PartitionInfo, PartitionMode, Plan};
+use crate::precision::{self, Precision};
 use crate::prelude::Workgroup;
-use crate::vbuffer::VBuffer;
-use crate::workgroup::VBufferHandle;
+use crate::reflect::{self, BindingKind};
+use crate::report::{Appended, DeviceTiming, RunReport};
+use crate::result_cache::Fingerprint;
+use crate::shader::{self, CompileMessage, CompileMessageKind, Shader};
+use crate::statistics::PassStatistics;
+use crate::stream::{StreamStage, StreamTask};
+use crate::throttle;
+use crate::timeslice::{self, SlicedDispatch, Tiling};
+use crate::timestamp::PassTimestamps;
+use crate::trace;
+use crate::vbuffer::{self, Residency, Resident, VBuffer};
+use crate::vdevice::{self, Features, Mapping, VDevice};
+use crate::workgroup::{RegisteredShader, VBufferHandle};
 
 pub struct Task<'t> {
     pub(crate) workgroup: &'t mut Workgroup,
+    pub(crate) kernel: String,
 
     pub(crate) output_buffers: Vec<(u32, VBufferHandle)>,
+    // The elements of each output that each device holds and writes back.
+    pub(crate) output_partitions: Vec<Plan>,
+    // How each output's device results reach its host copy.
+    pub(crate) output_writebacks: Vec<Writeback>,
 
+    pub(crate) output_wgpu_buffers: Vec<Vec<wgpu::Buffer>>,
     pub(crate) staging_buffers: Vec<Vec<wgpu::Buffer>>,
-    pub(crate) command_buffers: Vec<wgpu::CommandBuffer>,
+    // Whether the last run left each output on the devices rather than reading it back.
+    pub(crate) deferred: Vec<bool>,
+    // The on-device checksum of each output, where readback is verified.
+    pub(crate) checksum_buffers: Vec<Vec<wgpu::Buffer>>,
+    // What each device records every run; `None` for devices that sit the task out.
+    pub(crate) device_commands: Vec<Option<DeviceCommands>>,
+    // Whether the run report counts each device's invocations.
+    pub(crate) pipeline_statistics: bool,
+    pub(crate) immediates: Vec<u8>,
+    // How long each sub-dispatch should take, and what to record for each device, when the
+    // dispatch is time-sliced. The device commands then only read the results back.
+    pub(crate) time_slice: Option<(Duration, Vec<Option<SlicedDispatch>>)>,
+    // The tiles the dispatch is dealt out in, when devices take them from a shared queue.
+    pub(crate) tiles: Option<Tiling>,
+    pub(crate) counted_outputs: Vec<CountedOutput>,
+    pub(crate) append_outputs: Vec<AppendOutput>,
+    // What compiling the shader reported on each device, in device order.
+    pub(crate) compile_messages: Vec<CompileMessage>,
+    // The devices that rejected the shader, and so sit every run out.
+    pub(crate) excluded: Vec<usize>,
+    // What each device's bindings hold, to run its share on another device should it fail;
+    // `None` for tasks whose shares can't be set up from the host.
+    pub(crate) recipes: Option<Vec<Vec<Recipe>>>,
+    // What the results depend on besides the outputs' contents before a run, if the
+    // Workgroup memoizes them and they can be.
+    pub(crate) fingerprint: Option<Fingerprint>,
 }
 
 impl<'t> Task<'t> {
-    pub(crate) fn from_builder(builder: TaskBuilder<'t>) -> Option<Self> {
+    pub(crate) fn from_builder(builder: TaskBuilder<'t>) -> Result<Self, WiscError> {
         let TaskBuilder {
             workgroup,
             shader,
@@ -25,92 +81,709 @@ impl<'t> Task<'t> {
             size,
             overrides,
             input_buffers,
+            uniform_values,
             output_buffers,
+            expected_types,
+            partition_info,
+            global_ids,
+            precision,
+            over_limit,
+            checksums,
+            pipeline_statistics,
+            immediates,
+            time_slice,
+            tiles,
+            sliding_windows,
+            counted_outputs,
+            append_outputs,
+            stream_input,
+            stream_output,
+            template,
         } = builder;
 
-        let kernel = kernel?;
-        let size = size?;
+        // Streamed bindings only make sense for a StreamTask.
+        if stream_input.is_some() || stream_output.is_some() {
+            return Err(WiscError::InvalidBinding(
+                "streamed bindings need build_stream or build_stage",
+            ));
+        }
+
+        workgroup.has_expected_types(&expected_types)?;
+
+        // Keep each window's results together, so they go to the device with its input. The
+        // windows only group the output for this task.
+        let mut window_groups: HashMap<VBufferHandle, usize> = HashMap::new();
+
+        for &(input, window_size, stride, output) in &sliding_windows {
+            let length = |handle| {
+                workgroup
+                    .vbuffers
+                    .get(handle)
+                    .map(|vbuffer: &VBuffer| vbuffer.length)
+                    .ok_or(WiscError::UnknownVBuffer)
+            };
+
+            let windows = partition::window_count(length(input)?, window_size, stride)?;
+            let output_length = length(output)?;
+
+            if windows == 0 || !output_length.is_multiple_of(windows) {
+                return Err(WiscError::ShapeMismatch(
+                    "the output must hold the same number of elements for every window",
+                ));
+            }
+
+            if output_length > 0 {
+                window_groups.insert(output, output_length / windows);
+            }
+        }
+
+        let group_size = |handle: VBufferHandle, vbuffer: &VBuffer| {
+            window_groups
+                .get(&handle)
+                .copied()
+                .unwrap_or(vbuffer.group_size)
+        };
+
+        // wgpu can't bind an empty buffer.
+        if append_outputs.iter().any(|append| append.capacity == 0) {
+            return Err(WiscError::InvalidBinding(
+                "an append output needs room for at least one element",
+            ));
+        }
+
+        let shader = shader.ok_or(WiscError::MissingShader)?;
+        let size = size.ok_or(WiscError::MissingSize)?.checked()?;
+
+        shader.check_available(workgroup)?;
+        check_immediates(workgroup, &immediates)?;
+
+        let reflection = shader.reflect(workgroup)?;
+        let kernel = resolve_kernel(reflection.as_ref(), kernel)?;
+
+        if let Some(module) = &reflection {
+            check_overrides(module, &overrides)?;
+        }
+
+        // The kernel is wrapped in an entry point that shifts its global IDs by the first
+        // element each device owns. That is a shader of its own, which a template's
+        // pipelines weren't made from.
+        let (shader, reflection, template) = if global_ids {
+            let module = reflection.ok_or(WiscError::InvalidShader(
+                "global IDs need a shader that can be reflected",
+            ))?;
+
+            let shader = TaskShader::Inline(wgpu::ShaderModuleDescriptor {
+                label: None,
+                source: wgpu::ShaderSource::Wgsl(
+                    partition::offset_global_ids(&module, &kernel)?.into(),
+                ),
+            });
+            let reflection = shader.reflect(workgroup)?;
+
+            (shader, reflection, None)
+        } else {
+            (shader, reflection, template)
+        };
+
+        // Kernels that declare the partition uniform get it for the chosen buffer, or else
+        // the first bound one.
+        let declares_partition_info = reflection.as_ref().is_some_and(|module| {
+            reflect::declares_binding(module, 0, partition::PARTITION_INFO_BINDING)
+        });
+        let bind_partition_info = partition_info.is_some() || declares_partition_info;
+
+        // Time-sliced kernels can only find their place through the slice uniform.
+        let declares_slice_info = reflection.as_ref().is_some_and(|module| {
+            reflect::declares_binding(module, 0, timeslice::SLICE_INFO_BINDING)
+        });
+        let bind_slice_info = time_slice.is_some() || tiles.is_some() || declares_slice_info;
+
+        if time_slice.is_some() && reflection.is_some() && !declares_slice_info {
+            return Err(WiscError::InvalidBinding(
+                "a time-sliced kernel must declare the slice info uniform",
+            ));
+        }
+
+        if let Some(workgroups) = tiles {
+            check_tiling(
+                workgroup,
+                workgroups,
+                size,
+                &output_buffers,
+                global_ids || time_slice.is_some(),
+                !counted_outputs.is_empty() || !append_outputs.is_empty(),
+            )?;
+
+            if reflection.is_some() && !declares_slice_info {
+                return Err(WiscError::InvalidBinding(
+                    "a tiled kernel must declare the slice info uniform",
+                ));
+            }
+        }
+
+        if let Some(module) = &reflection {
+            let bound: Vec<(u32, BindingKind)> = input_buffers
+                .iter()
+                .map(|input| {
+                    let kind = if input.uniform {
+                        BindingKind::Uniform
+                    } else {
+                        BindingKind::ReadOnlyStorage
+                    };
+
+                    (input.id, kind)
+                })
+                .chain(
+                    uniform_values
+                        .iter()
+                        .map(|(id, _)| (*id, BindingKind::Uniform)),
+                )
+                .chain(
+                    output_buffers
+                        .iter()
+                        .map(|out| (out.id, BindingKind::ReadWriteStorage)),
+                )
+                .chain(
+                    counted_outputs
+                        .iter()
+                        .map(|(_, counter_id, _)| (*counter_id, BindingKind::ReadWriteStorage)),
+                )
+                .chain(append_outputs.iter().flat_map(|append| {
+                    [
+                        (append.id, BindingKind::ReadWriteStorage),
+                        (append.counter_id, BindingKind::ReadWriteStorage),
+                    ]
+                }))
+                .chain(
+                    bind_partition_info
+                        .then_some((partition::PARTITION_INFO_BINDING, BindingKind::Uniform)),
+                )
+                .chain(
+                    bind_slice_info
+                        .then_some((timeslice::SLICE_INFO_BINDING, BindingKind::Uniform)),
+                )
+                .collect();
+
+            check_bindings(module, &kernel, &bound)?;
+        }
 
         let num_devices = workgroup.vdevices.len();
 
+        if num_devices > 0 && workgroup.vdevices.iter().all(VDevice::is_lost) {
+            workgroup.check_lost()?;
+        }
+
+        // A device can reject a shader that others accept, say for using a feature it lacks.
+        // It sits the task out, and the others split its share between them. So does a
+        // device that is already lost.
+        let mut compile_messages: Vec<Vec<CompileMessage>> =
+            per_device_parallel(&workgroup.vdevices, |vdi, vd| {
+                if let Err(error) = vd.check_lost() {
+                    return vec![CompileMessage {
+                        device: vdi,
+                        kind: CompileMessageKind::Error,
+                        message: error.to_string(),
+                        line: None,
+                    }];
+                }
+
+                shader::checked_compile(vdi, vd, || shader.module(&workgroup.shaders, vdi, vd))
+            });
+
+        // wgpu only checks workgroup storage when the pipeline is made, so reflect it here.
+        if let Some(size) = reflection
+            .as_ref()
+            .and_then(|module| reflect::workgroup_storage_size(module, &kernel))
+        {
+            for (vdi, vd) in workgroup.vdevices.iter().enumerate() {
+                let max = vd.device.limits().max_compute_workgroup_storage_size;
+                if size <= max {
+                    continue;
+                }
+
+                let error = WiscError::WorkgroupStorageTooLarge {
+                    kernel: kernel.clone(),
+                    device: vdi,
+                    size,
+                    max,
+                };
+
+                if over_limit == OverLimit::Fail {
+                    return Err(error);
+                }
+
+                compile_messages[vdi].push(CompileMessage {
+                    device: vdi,
+                    kind: CompileMessageKind::Error,
+                    message: error.to_string(),
+                    line: None,
+                });
+            }
+        }
+
+        let mut excluded: Vec<usize> = compile_messages
+            .iter()
+            .enumerate()
+            .filter(|(_, messages)| messages.iter().any(CompileMessage::is_error))
+            .map(|(vdi, _)| vdi)
+            .collect();
+
+        if excluded.len() == num_devices {
+            let reason = compile_messages
+                .iter()
+                .flatten()
+                .find(|message| message.is_error())
+                .map(|message| message.message.clone())
+                .unwrap_or_default();
+
+            return Err(WiscError::ShaderRejected(reason));
+        }
+
+        if precision == Precision::Consistent {
+            excluded.extend(precision::inconsistent_devices(
+                &workgroup.vdevices,
+                &excluded,
+            ));
+            excluded.sort_unstable();
+        }
+
+        // Kernels indexing by global IDs, and tiles that may go to any device, need every
+        // device that takes part to hold the whole of each buffer.
+        let whole = global_ids || tiles.is_some();
+
+        let mut weightings = workgroup.vdevice_weightings.clone();
+        for &vdi in &excluded {
+            weightings[vdi] = 0.0;
+        }
+
         let mut buffers: Vec<Vec<wgpu::Buffer>> = vec![vec![]; num_devices];
         let mut layouts: Vec<Vec<wgpu::BindGroupLayoutEntry>> = vec![vec![]; num_devices];
         let mut staging_buffers: Vec<Vec<wgpu::Buffer>> = vec![vec![]; num_devices];
         let mut output_wgpu_buffers: Vec<Vec<wgpu::Buffer>> = vec![vec![]; num_devices];
+        // The elements each device holds of every bound buffer, for sizing the dispatch.
+        let mut held_ranges: HashMap<VBufferHandle, Vec<Range<usize>>> = HashMap::new();
+        // The elements each device writes back, or would if the buffer were an output.
+        let mut owned_ranges: HashMap<VBufferHandle, Vec<Range<usize>>> = HashMap::new();
+
+        // What each device's bindings hold, in binding order, to set them up again on
+        // another device. Shares that can't be set up from the host alone rule that out.
+        let mut recipes: Vec<Vec<Recipe>> = vec![vec![]; num_devices];
+        let mut can_fail_over = time_slice.is_none()
+            && tiles.is_none()
+            && !checksums
+            && counted_outputs.is_empty()
+            && append_outputs.is_empty()
+            && output_buffers
+                .iter()
+                .all(|out| matches!(out.writeback, Writeback::Overwrite));
+
+        // A device that holds none of some bound buffer, as a weighted split can leave a weak
+        // device, sits the task out: wgpu can't bind an empty slice.
+        let mut idle = vec![false; num_devices];
+        for &vdi in &excluded {
+            idle[vdi] = true;
+        }
 
-        for (id, key) in &input_buffers {
-            let vbuffer = workgroup.vbuffers.get(*key)?;
+        // How much of the split buffers each device can take, so that what a device can't
+        // hold goes to the others rather than failing the task.
+        let mut bound = vec![];
+        for (handle, uniform, mode) in input_buffers
+            .iter()
+            .map(|input| (input.handle, input.uniform, &input.mode))
+            .chain(
+                output_buffers
+                    .iter()
+                    .map(|out| (out.handle, false, &out.mode)),
+            )
+        {
+            let vbuffer = workgroup
+                .vbuffers
+                .get(handle)
+                .ok_or(WiscError::UnknownVBuffer)?;
+
+            bound.push((
+                vbuffer,
+                group_size(handle, vbuffer),
+                !uniform && !whole && mode.is_split(),
+            ));
+        }
+        let caps = share_caps(&workgroup.vdevices, &workgroup.memory_caps, &bound);
+
+        // Sizes are checked before any buffer is created, since wgpu would only complain
+        // once they are bound.
+        for (id, handle, uniform, mode) in input_buffers
+            .iter()
+            .map(|input| (input.id, input.handle, input.uniform, &input.mode))
+            .chain(
+                output_buffers
+                    .iter()
+                    .map(|out| (out.id, out.handle, false, &out.mode)),
+            )
+        {
+            let vbuffer = workgroup
+                .vbuffers
+                .get(handle)
+                .ok_or(WiscError::UnknownVBuffer)?;
+
+            let held = if uniform {
+                vec![0..vbuffer.length; num_devices]
+            } else {
+                let group_size = group_size(handle, vbuffer);
+
+                plan_excluding(
+                    mode,
+                    vbuffer,
+                    group_size,
+                    &weightings,
+                    &caps,
+                    &excluded,
+                    whole,
+                )?
+                .held
+            };
+
+            for (idle, range) in idle.iter_mut().zip(&held) {
+                *idle |= range.is_empty();
+            }
+
+            for (vdi, (vd, range)) in workgroup.vdevices.iter().zip(&held).enumerate() {
+                check_binding_size(vd, vdi, id, (range.len() * vbuffer.stride) as u64, uniform)?;
+            }
+        }
+
+        for append in &append_outputs {
+            let stride = workgroup
+                .vbuffers
+                .get(append.handle)
+                .ok_or(WiscError::UnknownVBuffer)?
+                .stride;
 
             for (vdi, vd) in workgroup.vdevices.iter().enumerate() {
-                let byte_slice: &[u8] = vbuffer_bytes(vbuffer);
+                check_binding_size(vd, vdi, append.id, (append.capacity * stride) as u64, false)?;
+            }
+        }
+
+        for InputBinding {
+            id,
+            handle: key,
+            uniform,
+            mode,
+            broadcast,
+        } in &input_buffers
+        {
+            let vbuffer = workgroup
+                .vbuffers
+                .get(*key)
+                .ok_or(WiscError::UnknownVBuffer)?;
+
+            // There is nothing to read from a buffer that was never written.
+            if vbuffer.assume_init.is_some()
+                && !vbuffer.pending_readback
+                && !matches!(vbuffer.residency, Residency::Aliased(_))
+            {
+                return Err(WiscError::Uninitialized);
+            }
 
-                let label = format!("WISC Input Buffer {} (VDevice {})", id, vd.label);
+            // An aliased buffer can't be bound read-only and read-write in the same dispatch.
+            if let Residency::Aliased(_) = vbuffer.residency
+                && output_buffers.iter().any(|out| out.handle == *key)
+            {
+                return Err(WiscError::InvalidBinding(
+                    "an aliased buffer can't also be bound as an output",
+                ));
+            }
 
-                let wgpu_buffer = vd
-                    .device
-                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                        label: Some(&label),
-                        contents: byte_slice,
-                        usage: wgpu::BufferUsages::STORAGE,
-                    });
+            // Uniforms are small parameters that every device needs all of.
+            let Plan {
+                held: partition,
+                owned,
+            } = if *uniform {
+                Plan {
+                    held: vec![0..vbuffer.length; num_devices],
+                    owned: vec![0..vbuffer.length; num_devices],
+                }
+            } else {
+                let group_size = group_size(*key, vbuffer);
+
+                plan_excluding(
+                    mode,
+                    vbuffer,
+                    group_size,
+                    &weightings,
+                    &caps,
+                    &excluded,
+                    whole,
+                )?
+            };
+
+            // The host copy is only uploaded once it holds what a run left on the devices.
+            if vbuffer.pending_readback && device_copies(vbuffer, &partition, *broadcast).is_none()
+            {
+                workgroup.read_back_pending(*key)?;
+            }
+
+            let vbuffer = &workgroup.vbuffers[*key];
+            let aliased = device_copies(vbuffer, &partition, *broadcast);
+            let upload = aliased.is_none();
+
+            for (vdi, vd) in workgroup.vdevices.iter().enumerate() {
+                if idle[vdi] {
+                    continue;
+                }
+
+                let wgpu_buffer = if let Some(resident) = aliased {
+                    resident.buffers[vdi].clone()
+                } else {
+                    let byte_slice: &[u8] = partition_bytes(vbuffer, &partition[vdi]);
+                    let _span = trace::span!(
+                        "create_buffer",
+                        device = vd.label.as_str(),
+                        binding = *id,
+                        bytes = byte_slice.len()
+                    );
+
+                    let label = format!("WISC Input Buffer {} (VDevice {})", id, vd.label);
+
+                    vd.device
+                        .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                            label: Some(&label),
+                            contents: byte_slice,
+                            usage: if *uniform {
+                                wgpu::BufferUsages::UNIFORM
+                            } else {
+                                wgpu::BufferUsages::STORAGE
+                            },
+                        })
+                };
 
-                let layout_entry = wgpu::BindGroupLayoutEntry {
-                    binding: *id,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
+                let layout_entry = if *uniform {
+                    uniform_layout_entry(*id)
+                } else {
+                    storage_layout_entry(*id, true)
                 };
 
                 buffers[vdi].push(wgpu_buffer);
                 layouts[vdi].push(layout_entry);
+                recipes[vdi].push(Recipe::Elements(*key, partition[vdi].clone()));
+            }
+
+            // Imported memory is only current on the device that holds it.
+            can_fail_over &= !vbuffer.imported;
+
+            // Keep a fresh broadcast on the devices, unless one of them sat it out.
+            if *broadcast && upload && !idle.contains(&true) {
+                let uploaded = Resident {
+                    buffers: buffers
+                        .iter()
+                        .map(|buffers| buffers.last().unwrap().clone())
+                        .collect(),
+                    ranges: partition.clone(),
+                    owned: partition.clone(),
+                };
+
+                let vbuffer = &mut workgroup.vbuffers[*key];
+                if let Residency::Host = vbuffer.residency {
+                    vbuffer.residency = Residency::Uploaded(uploaded);
+                }
             }
-        }
 
-        for (id, key) in &output_buffers {
-            let vbuffer = workgroup.vbuffers.get(*key)?;
+            held_ranges.insert(*key, partition);
+            owned_ranges.insert(*key, owned);
+        }
 
+        for (id, contents) in &uniform_values {
             for (vdi, vd) in workgroup.vdevices.iter().enumerate() {
-                let mappable_primary = vd
-                    .features
-                    .contains(wgpu::Features::MAPPABLE_PRIMARY_BUFFERS);
+                if idle[vdi] {
+                    continue;
+                }
+
+                let label = format!("WISC Uniform Buffer {} (VDevice {})", id, vd.label);
+
+                buffers[vdi].push(workgroup.binding_caches[vdi].uniform(vd, &label, contents));
+                layouts[vdi].push(uniform_layout_entry(*id));
+                recipes[vdi].push(Recipe::Bytes(contents.clone()));
+            }
+        }
+
+        let mut output_partitions: Vec<Plan> = Vec::with_capacity(output_buffers.len());
+
+        for OutputBinding {
+            id,
+            handle: key,
+            mode,
+            writeback,
+        } in &output_buffers
+        {
+            let vbuffer = workgroup
+                .vbuffers
+                .get(*key)
+                .ok_or(WiscError::UnknownVBuffer)?;
+
+            match writeback {
+                Writeback::Overwrite => {}
+                Writeback::Merge(typeid, ..) | Writeback::Accumulate(typeid, ..)
+                    if *typeid != vbuffer.typeid =>
+                {
+                    return Err(WiscError::TypeMismatch);
+                }
+                Writeback::Merge(..) => {}
+                // Accumulating folds results into what the host copy already holds.
+                Writeback::Accumulate(..)
+                    if vbuffer.assume_init.is_some() && !vbuffer.pending_readback =>
+                {
+                    return Err(WiscError::Uninitialized);
+                }
+                Writeback::Accumulate(..) => {}
+            }
 
-                let byte_slice: &[u8] = vbuffer_bytes(vbuffer);
+            let group_size = group_size(*key, vbuffer);
+            let plan = plan_excluding(
+                mode,
+                vbuffer,
+                group_size,
+                &weightings,
+                &caps,
+                &excluded,
+                whole,
+            )?;
+
+            // What a run left on the devices is read back, unless the device copies holding
+            // it are bound again as they are.
+            if vbuffer.pending_readback
+                && vbuffer
+                    .residency
+                    .resident()
+                    .is_none_or(|resident| resident.ranges != plan.held)
+            {
+                workgroup.read_back_pending(*key)?;
+            }
+
+            let vbuffer = &workgroup.vbuffers[*key];
+
+            // Devices writing back the same element would race, unless they all hold the
+            // whole buffer (the unmanaged case), devices sitting the task out aside. A buffer
+            // with no host contents yet must be written in full.
+            let unmanaged = plan
+                .owned
+                .iter()
+                .filter(|range| !range.is_empty())
+                .all(|range| *range == (0..vbuffer.length));
+
+            if !unmanaged && !partition::is_disjoint(&plan.owned) {
+                return Err(WiscError::InvalidPartition(
+                    "devices would write back overlapping elements",
+                ));
+            }
+
+            if vbuffer.assume_init.is_some()
+                && !vbuffer.pending_readback
+                && !partition::covers(&plan.owned, vbuffer.length)
+            {
+                return Err(WiscError::InvalidPartition(
+                    "an uninitialized output must be written in full",
+                ));
+            }
+
+            // Devices compute over everything they hold, halo included.
+            let partition = &plan.held;
+
+            // Device copies left behind by an earlier task are reused when they cover the same
+            // elements, which keeps the bind groups of consecutive tasks identical.
+            let resident = vbuffer.residency.resident().filter(|resident| {
+                resident.ranges == *partition
+                    && (vbuffer.assume_init.is_none()
+                        || vbuffer.imported
+                        || vbuffer.pending_readback)
+            });
+
+            // Imported memory is always written in place, since that is where its owner
+            // looks for the results.
+            if vbuffer.imported && resident.is_none() {
+                return Err(WiscError::InvalidPartition(
+                    "imported memory must be bound wholly on the device that holds it",
+                ));
+            }
 
+            for (vdi, vd) in workgroup.vdevices.iter().enumerate() {
                 let label = format!("WISC Output Buffer {} (VDevice {})", id, vd.label);
 
-                let wgpu_buffer = vd
-                    .device
-                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                        label: Some(&label),
-                        contents: byte_slice,
-                        usage: wgpu::BufferUsages::STORAGE
-                            | wgpu::BufferUsages::COPY_SRC
-                            | if mappable_primary {
-                                wgpu::BufferUsages::MAP_READ
-                            } else {
-                                wgpu::BufferUsages::empty()
-                            },
-                    });
+                // An idle device computes nothing, but still needs something to stand for
+                // its (empty) copy once the output is retained.
+                if idle[vdi] {
+                    output_wgpu_buffers[vdi].push(vd.device.create_buffer(
+                        &wgpu::BufferDescriptor {
+                            label: Some(&label),
+                            size: 0,
+                            usage: wgpu::BufferUsages::COPY_SRC,
+                            mapped_at_creation: false,
+                        },
+                    ));
+                    continue;
+                }
 
-                let layout_entry = wgpu::BindGroupLayoutEntry {
-                    binding: *id,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: false },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
+                // Imported memory can't be mapped, so it is read back through staging.
+                let mappable_primary = vd
+                    .features
+                    .contains(wgpu::Features::MAPPABLE_PRIMARY_BUFFERS)
+                    && !vbuffer.imported;
+
+                let byte_len = partition[vdi].len() * vbuffer.stride;
+                let _span = trace::span!(
+                    "create_buffer",
+                    device = vd.label.as_str(),
+                    binding = *id,
+                    bytes = byte_len
+                );
+
+                // Outputs may later be aliased as storage or uniform inputs, or supply the
+                // workgroup counts of an indirect dispatch.
+                let usage = wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::UNIFORM
+                    | wgpu::BufferUsages::INDIRECT
+                    | wgpu::BufferUsages::COPY_SRC
+                    | wgpu::BufferUsages::COPY_DST
+                    | if mappable_primary {
+                        wgpu::BufferUsages::MAP_READ
+                    } else {
+                        wgpu::BufferUsages::empty()
+                    };
+
+                // Buffers without host contents yet start out zeroed on the device, so
+                // there is nothing to upload.
+                let wgpu_buffer = if let Some(buffer) = resident
+                    .map(|resident| &resident.buffers[vdi])
+                    .filter(|buffer| buffer.usage().contains(usage))
+                {
+                    // The device copy may have drifted from the host copy, which is what
+                    // an output starts out as. Imported memory is what it starts out as, as
+                    // is a device copy holding results the host copy hasn't caught up with.
+                    if !vbuffer.imported && !vbuffer.pending_readback {
+                        vd.queue
+                            .write_buffer(buffer, 0, partition_bytes(vbuffer, &partition[vdi]));
+                    }
+
+                    buffer.clone()
+                } else if vbuffer.assume_init.is_some() {
+                    vd.device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some(&label),
+                        size: byte_len as wgpu::BufferAddress,
+                        usage,
+                        mapped_at_creation: false,
+                    })
+                } else {
+                    vd.device
+                        .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                            label: Some(&label),
+                            contents: partition_bytes(vbuffer, &partition[vdi]),
+                            usage,
+                        })
                 };
 
+                let layout_entry = storage_layout_entry(*id, false);
+
                 let staging_buffer = if mappable_primary {
                     wgpu_buffer.clone()
                 } else {
-                    let byte_len = vbuffer.length * vbuffer.stride;
                     vd.device.create_buffer(&wgpu::BufferDescriptor {
                         label: Some(&format!(
                             "WISC Staging Buffer {} (VDevice {})",
@@ -126,236 +799,2886 @@ impl<'t> Task<'t> {
                 layouts[vdi].push(layout_entry);
                 output_wgpu_buffers[vdi].push(wgpu_buffer);
                 staging_buffers[vdi].push(staging_buffer);
+                recipes[vdi].push(Recipe::Output(*key, partition[vdi].clone()));
             }
+
+            can_fail_over &= !vbuffer.imported;
+
+            held_ranges.insert(*key, plan.held.clone());
+            owned_ranges.insert(*key, plan.owned.clone());
+            output_partitions.push(plan);
         }
 
-        let mut command_buffers: Vec<wgpu::CommandBuffer> = Vec::with_capacity(num_devices);
+        // Each device counts how many elements of its share of a counted output it filled.
+        let mut counted: Vec<CountedOutput> = Vec::with_capacity(counted_outputs.len());
 
-        for (vdi, vd) in workgroup.vdevices.iter().enumerate() {
-            let bind_group_layout =
-                vd.device
-                    .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                        label: None,
-                        entries: &layouts[vdi],
-                    });
+        for (handle, counter_id, resize) in counted_outputs {
+            let output_index = output_buffers
+                .iter()
+                .position(|out| out.handle == handle)
+                .ok_or(WiscError::UnknownVBuffer)?;
+            let length = workgroup.vbuffers[handle].length;
+            let plan = &output_partitions[output_index];
+
+            // Counts are of the elements a device writes back, which it must not share.
+            if plan.held != plan.owned || !partition::is_disjoint(&plan.owned) {
+                return Err(WiscError::InvalidPartition(
+                    "every device must write back a share of a counted output of its own",
+                ));
+            }
 
-            let bind_group_entries: Vec<wgpu::BindGroupEntry> = layouts[vdi]
+            let counters = workgroup
+                .vdevices
                 .iter()
-                .zip(buffers[vdi].iter())
-                .map(|(entry, buffer)| wgpu::BindGroupEntry {
-                    binding: entry.binding,
-                    resource: buffer.as_entire_binding(),
+                .enumerate()
+                .map(|(vdi, vd)| {
+                    if idle[vdi] {
+                        return None;
+                    }
+
+                    let counter = DeviceCounter::new(vd, counter_id);
+
+                    buffers[vdi].push(counter.buffer.clone());
+                    layouts[vdi].push(storage_layout_entry(counter_id, false));
+
+                    Some(counter)
                 })
                 .collect();
 
-            let bind_group = vd.device.create_bind_group(&wgpu::BindGroupDescriptor {
-                label: None,
-                layout: &bind_group_layout,
-                entries: &bind_group_entries,
+            counted.push(CountedOutput {
+                output_index,
+                length,
+                resize,
+                counters,
             });
+        }
 
-            let pipeline_layout =
-                vd.device
-                    .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                        label: None,
-                        bind_group_layouts: &[&bind_group_layout],
-                        immediate_size: 0,
-                    });
+        // Every device appends to a buffer of its own, counting its results in a counter.
+        let mut appends: Vec<AppendOutput> = Vec::with_capacity(append_outputs.len());
+
+        for AppendBinding {
+            id,
+            counter_id,
+            handle,
+            capacity,
+            replace,
+        } in append_outputs
+        {
+            let stride = workgroup
+                .vbuffers
+                .get(handle)
+                .ok_or(WiscError::UnknownVBuffer)?
+                .stride;
 
-            let override_string_buffer: Vec<String> =
-                overrides.iter().map(|(id, _)| id.to_string()).collect();
+            // Copies are made in whole words.
+            let byte_len = (capacity * stride).next_multiple_of(4) as wgpu::BufferAddress;
 
-            let override_constants: Vec<(&str, f64)> = overrides
+            let devices = workgroup
+                .vdevices
                 .iter()
                 .enumerate()
-                .map(|(i, (_, val))| (override_string_buffer[i].as_str(), *val))
+                .map(|(vdi, vd)| {
+                    if idle[vdi] {
+                        return None;
+                    }
+
+                    let results = vd.device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some(&format!("WISC Append Buffer {} (VDevice {})", id, vd.label)),
+                        size: byte_len,
+                        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                        mapped_at_creation: false,
+                    });
+                    let counter = DeviceCounter::new(vd, counter_id);
+
+                    buffers[vdi].push(results.clone());
+                    layouts[vdi].push(storage_layout_entry(id, false));
+                    buffers[vdi].push(counter.buffer.clone());
+                    layouts[vdi].push(storage_layout_entry(counter_id, false));
+
+                    Some(AppendDevice { results, counter })
+                })
                 .collect();
 
-            let pipeline = vd
-                .device
-                .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-                    label: None,
-                    layout: Some(&pipeline_layout),
-                    module: &vd.device.create_shader_module(shader.clone()),
-                    entry_point: Some(kernel.as_str()),
-                    compilation_options: wgpu::PipelineCompilationOptions {
-                        constants: override_constants.as_ref(),
-                        zero_initialize_workgroup_memory: true,
-                    },
-                    cache: None,
-                });
+            appends.push(AppendOutput {
+                handle,
+                capacity,
+                stride,
+                replace,
+                devices,
+            });
+        }
 
-            let mut encoder = vd
-                .device
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        // Kernels shifted to global IDs are placed, and dispatched, by the elements each
+        // device owns rather than the whole buffers it holds.
+        let dispatch_ranges = if global_ids {
+            &owned_ranges
+        } else {
+            &held_ranges
+        };
+
+        if bind_partition_info {
+            let handle = partition_info.or_else(|| {
+                output_buffers
+                    .first()
+                    .map(|out| out.handle)
+                    .or_else(|| input_buffers.first().map(|input| input.handle))
+            });
 
-            {
-                let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                    label: None,
-                    timestamp_writes: None,
-                });
+            let ranges = match handle {
+                Some(handle) => match dispatch_ranges.get(&handle) {
+                    Some(ranges) => ranges.clone(),
+                    None => {
+                        let vbuffer = workgroup
+                            .vbuffers
+                            .get(handle)
+                            .ok_or(WiscError::UnknownVBuffer)?;
+
+                        vec![0..vbuffer.length; num_devices]
+                    }
+                },
+                None => vec![0..0; num_devices],
+            };
 
-                compute_pass.set_pipeline(&pipeline);
-                compute_pass.set_bind_group(0, &bind_group, &[]);
+            for (vdi, vd) in workgroup.vdevices.iter().enumerate() {
+                if idle[vdi] {
+                    continue;
+                }
+
+                let info = PartitionInfo {
+                    offset: u32::try_from(ranges[vdi].start).map_err(|_| WiscError::OutOfBounds)?,
+                    len: u32::try_from(ranges[vdi].len()).map_err(|_| WiscError::OutOfBounds)?,
+                    device: vdi as u32,
+                    devices: num_devices as u32,
+                };
 
-                let (x, y, z) = size;
-                compute_pass.dispatch_workgroups(x, y, z);
+                let label = format!("WISC Partition Info (VDevice {})", vd.label);
+
+                buffers[vdi].push(workgroup.binding_caches[vdi].uniform(
+                    vd,
+                    &label,
+                    bytemuck::bytes_of(&info),
+                ));
+                layouts[vdi].push(uniform_layout_entry(partition::PARTITION_INFO_BINDING));
+                recipes[vdi].push(Recipe::Bytes(bytemuck::bytes_of(&info).to_vec()));
             }
+        }
 
-            let mappable_primary = vd
-                .features
-                .contains(wgpu::Features::MAPPABLE_PRIMARY_BUFFERS);
+        // A time-sliced task binds each sub-dispatch's own slice uniform as it runs; any
+        // other kernel that declares it runs as a single slice.
+        if bind_slice_info {
+            let contents = [0u8; 16];
 
-            if !mappable_primary {
-                for (output_buffer, staging_buffer) in output_wgpu_buffers[vdi]
-                    .iter()
-                    .zip(staging_buffers[vdi].iter())
-                {
-                    encoder.copy_buffer_to_buffer(
-                        output_buffer,
-                        0,
-                        staging_buffer,
-                        0,
-                        output_buffer.size(),
-                    );
+            for (vdi, vd) in workgroup.vdevices.iter().enumerate() {
+                if idle[vdi] {
+                    continue;
                 }
-            }
 
-            command_buffers.push(encoder.finish());
+                if time_slice.is_none() && tiles.is_none() {
+                    let label = format!("WISC Slice Info (VDevice {})", vd.label);
+
+                    buffers[vdi].push(workgroup.binding_caches[vdi].uniform(vd, &label, &contents));
+                    recipes[vdi].push(Recipe::Bytes(contents.to_vec()));
+                }
+                layouts[vdi].push(uniform_layout_entry(timeslice::SLICE_INFO_BINDING));
+            }
         }
 
-        Some(Task {
+        let dispatches = resolve_dispatch(
             workgroup,
+            size,
+            reflection.as_ref(),
+            &kernel,
+            dispatch_ranges,
+        )?;
 
-            output_buffers,
-
-            staging_buffers,
-            command_buffers,
-        })
-    }
-
-    pub fn run(self) {
-        for (device, command_buffer) in self
-            .workgroup
-            .vdevices
-            .iter()
-            .zip(self.command_buffers.into_iter())
+        if (time_slice.is_some() || tiles.is_some())
+            && dispatches
+                .iter()
+                .any(|dispatch| matches!(dispatch, Dispatch::Indirect(_)))
         {
-            device.queue.submit([command_buffer]);
+            return Err(WiscError::InvalidDispatch(
+                "an indirect dispatch can't be time-sliced",
+            ));
         }
 
-        let mut receivers = Vec::new();
+        for (vdi, dispatch) in dispatches.iter().enumerate() {
+            let Dispatch::Direct(x, y, z) = dispatch else {
+                continue;
+            };
 
-        for (device_id, _device) in self.workgroup.vdevices.iter().enumerate() {
-            for staging_buffer in self.staging_buffers[device_id].iter() {
-                let buffer_slice = staging_buffer.slice(..);
-                let (tx, rx) = mpsc::channel();
-                buffer_slice.map_async(wgpu::MapMode::Read, move |_| {
-                    let _ = tx.send(());
-                });
-                receivers.push(rx);
+            let limit = workgroup.vdevices[vdi]
+                .device
+                .limits()
+                .max_compute_workgroups_per_dimension;
+
+            if *y > limit || *z > limit {
+                return Err(WiscError::InvalidDispatch(
+                    "more workgroups along y or z than the device allows",
+                ));
             }
-        }
 
-        for device in self.workgroup.vdevices.iter() {
-            device
-                .device
-                .poll(wgpu::PollType::wait_indefinitely())
-                .unwrap();
+            // Longer runs along x are split into several dispatches, which the kernel can
+            // only tell apart by the offset in its slice uniform.
+            if *x > limit && !bind_slice_info {
+                return Err(WiscError::InvalidDispatch(
+                    "more workgroups along x than the device allows, and no slice uniform to split them by",
+                ));
+            }
         }
 
-        for rx in receivers {
-            let _ = rx.recv();
-        }
+        let fingerprint = workgroup
+            .result_cache
+            .as_ref()
+            .filter(|_| counted.is_empty() && appends.is_empty())
+            .and_then(|_| {
+                let source_hash = match &shader {
+                    TaskShader::Inline(descriptor) => cache::source_hash(&descriptor.source),
+                    TaskShader::Registered(name) => workgroup.shaders[name].source_hash,
+                }?;
+
+                let mut fingerprint = Fingerprint::default();
+                fingerprint
+                    .add(&source_hash)
+                    .add(&kernel)
+                    .add(&immediates)
+                    .add(&uniform_values)
+                    .add(&num_devices);
+
+                for (key, value) in &overrides {
+                    fingerprint.add(key).add(&value.to_bits());
+                }
 
-        for (device_id, _device) in self.workgroup.vdevices.iter().enumerate() {
-            for (output_index, staging_buffer) in self.staging_buffers[device_id].iter().enumerate()
-            {
-                let Some((_, handle)) = self.output_buffers.get(output_index) else {
-                    continue;
-                };
-                let buffer_slice = staging_buffer.slice(..);
-                let data = buffer_slice.get_mapped_range();
-                let bytes: &[u8] = &data;
+                for input in &input_buffers {
+                    fingerprint
+                        .add(&input.id)
+                        .add(&input.uniform)
+                        .add(&held_ranges[&input.handle])
+                        .add_buffer(workgroup, input.handle)?;
+                }
 
-                if let Some(vbuffer) = self.workgroup.vbuffers.get_mut(*handle) {
-                    let dst = vbuffer_bytes_mut(vbuffer);
-                    let copy_len = dst.len().min(bytes.len());
-                    dst[..copy_len].copy_from_slice(&bytes[..copy_len]);
+                for (output, plan) in output_buffers.iter().zip(&output_partitions) {
+                    fingerprint.add(&output.id).add(&plan.held).add(&plan.owned);
+
+                    match output.writeback {
+                        Writeback::Overwrite => fingerprint.add(&0u8),
+                        // Nothing tells one custom merge from another.
+                        Writeback::Merge(_, _, MergeKey::Custom) => return None,
+                        Writeback::Merge(_, _, key) => fingerprint.add(&1u8).add(&key),
+                        Writeback::Accumulate(_, _, op) => fingerprint.add(&2u8).add(&op),
+                    };
                 }
 
-                drop(data);
-                staging_buffer.unmap();
-            }
-        }
-    }
-}
+                for dispatch in &dispatches {
+                    let Dispatch::Direct(x, y, z) = dispatch else {
+                        return None;
+                    };
 
-pub struct TaskBuilder<'b> {
-    pub(crate) workgroup: &'b mut Workgroup,
-    pub(crate) shader: wgpu::ShaderModuleDescriptor<'b>,
-    pub(crate) kernel: Option<String>,
-    pub(crate) size: Option<(u32, u32, u32)>,
+                    fingerprint.add(&(x, y, z));
+                }
 
-    pub(crate) overrides: Vec<(u32, f64)>,
-    pub(crate) input_buffers: Vec<(u32, VBufferHandle)>,
-    pub(crate) output_buffers: Vec<(u32, VBufferHandle)>,
-}
+                Some(fingerprint)
+            });
 
-impl<'b> TaskBuilder<'b> {
-    pub fn new(workgroup: &'b mut Workgroup, shader: wgpu::ShaderModuleDescriptor<'b>) -> Self {
-        Self {
-            workgroup,
-            shader,
+        let override_constants = override_constants(&overrides);
+
+        // Every device compiles its own shader module and pipeline, and they don't depend on
+        // each other, so compile them all at once rather than one device after another.
+        let pipelines: Vec<Option<(wgpu::BindGroupLayout, wgpu::ComputePipeline)>> =
+            per_device_parallel(&workgroup.vdevices, |vdi, vd| {
+                if idle[vdi] {
+                    return None;
+                }
+
+                let module = shader.module(&workgroup.shaders, vdi, vd);
+
+                Some(create_pipeline(
+                    vd,
+                    &workgroup.binding_caches[vdi],
+                    &module,
+                    &kernel,
+                    &layouts[vdi],
+                    &override_constants,
+                    &immediates,
+                    workgroup.pipeline_caches[vdi].as_ref(),
+                    template,
+                ))
+            });
+
+        let sliced = || -> Vec<Option<SlicedDispatch>> {
+            pipelines
+                .iter()
+                .zip(&dispatches)
+                .enumerate()
+                .map(|(vdi, (pipeline, dispatch))| {
+                    let ((layout, pipeline), Dispatch::Direct(x, y, z)) =
+                        (pipeline.as_ref()?, dispatch)
+                    else {
+                        return None;
+                    };
+
+                    Some(SlicedDispatch {
+                        layout: layout.clone(),
+                        pipeline: pipeline.clone(),
+                        entries: layouts[vdi].clone(),
+                        buffers: buffers[vdi].clone(),
+                        workgroups: (*x, *y, *z),
+                        immediates: immediates.clone(),
+                    })
+                })
+                .collect()
+        };
+
+        let time_slice = time_slice.map(|duration| (duration, sliced()));
+
+        let tiles = match (tiles, size) {
+            (Some(workgroups_per_tile), DispatchSize::PerBuffer(handle)) => Some(Tiling {
+                workgroups_per_tile,
+                elements_per_workgroup: reflection
+                    .as_ref()
+                    .and_then(|module| reflect::workgroup_size(module, &kernel))
+                    .map_or(1, |size| size.iter().product::<u32>() as usize),
+                length: workgroup.vbuffers[handle].length,
+                dispatches: sliced(),
+                done: vec![vec![]; num_devices],
+            }),
+            _ => None,
+        };
+
+        let mut device_commands: Vec<Option<DeviceCommands>> = pipelines
+            .into_iter()
+            .zip(layouts.into_iter().zip(buffers))
+            .zip(dispatches.into_iter().zip(&workgroup.vdevices))
+            .map(|((pipeline, (entries, buffers)), (dispatch, vd))| {
+                let (bind_group_layout, pipeline) = pipeline?;
+
+                // Time-sliced and tiled tasks record their dispatches as they run.
+                let dispatch = (time_slice.is_none() && tiles.is_none()).then_some(dispatch);
+
+                Some(DeviceCommands {
+                    bind_group_layout,
+                    pipeline,
+                    entries,
+                    buffers,
+                    timestamps: dispatch.as_ref().and_then(|_| PassTimestamps::new(vd)),
+                    statistics: dispatch
+                        .as_ref()
+                        .filter(|_| pipeline_statistics)
+                        .and_then(|_| PassStatistics::new(vd)),
+                    dispatch,
+                    checksums: vec![],
+                })
+            })
+            .collect();
+
+        // Each device checksums its outputs where they were computed, to compare with what
+        // reaches the host.
+        let mut checksum_buffers: Vec<Vec<wgpu::Buffer>> = vec![vec![]; num_devices];
+
+        if checksums {
+            for (vdi, vd) in workgroup.vdevices.iter().enumerate() {
+                if idle[vdi] {
+                    continue;
+                }
+
+                let mappable_primary = vd
+                    .features
+                    .contains(wgpu::Features::MAPPABLE_PRIMARY_BUFFERS);
+
+                let mut device_checksums = vec![];
+                let mut staging = vec![];
+
+                for _ in &output_buffers {
+                    let checksum = vd.device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some(&format!("WISC Checksum (VDevice {})", vd.label)),
+                        size: checksum::CHECKSUM_SIZE,
+                        usage: wgpu::BufferUsages::STORAGE
+                            | wgpu::BufferUsages::COPY_SRC
+                            | wgpu::BufferUsages::COPY_DST
+                            | if mappable_primary {
+                                wgpu::BufferUsages::MAP_READ
+                            } else {
+                                wgpu::BufferUsages::empty()
+                            },
+                        mapped_at_creation: false,
+                    });
+
+                    staging.push(if mappable_primary {
+                        checksum.clone()
+                    } else {
+                        vd.device.create_buffer(&wgpu::BufferDescriptor {
+                            label: Some(&format!("WISC Checksum Staging (VDevice {})", vd.label)),
+                            size: checksum::CHECKSUM_SIZE,
+                            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                            mapped_at_creation: false,
+                        })
+                    });
+                    device_checksums.push(checksum);
+                }
+
+                if let Some(commands) = &mut device_commands[vdi] {
+                    commands.checksums = device_checksums;
+                }
+                checksum_buffers[vdi] = staging;
+            }
+        }
+
+        let (output_buffers, output_writebacks) = output_buffers
+            .into_iter()
+            .map(|out| ((out.id, out.handle), out.writeback))
+            .unzip();
+
+        Ok(Task {
+            workgroup,
+            kernel,
+
+            output_buffers,
+            output_partitions,
+            output_writebacks,
+
+            output_wgpu_buffers,
+            staging_buffers,
+            deferred: vec![],
+            checksum_buffers,
+            device_commands,
+            pipeline_statistics,
+            immediates,
+            time_slice,
+            tiles,
+            counted_outputs: counted,
+            append_outputs: appends,
+            recipes: can_fail_over.then_some(recipes),
+            compile_messages: compile_messages.concat(),
+            excluded,
+            fingerprint,
+        })
+    }
+
+    /// Adds this run's dispatches to the Workgroup's launch history, if it is recorded.
+    fn record_launches(&mut self) {
+        for (vdi, commands) in self.device_commands.iter().enumerate() {
+            let Some(commands) = commands else {
+                continue;
+            };
+
+            history::record(&mut self.workgroup.launches, || {
+                let sliced = self
+                    .time_slice
+                    .as_ref()
+                    .and_then(|(_, sliced)| sliced[vdi].as_ref())
+                    .or_else(|| {
+                        let tiling = self.tiles.as_ref()?;
+                        tiling.dispatches[vdi].as_ref()
+                    });
+
+                let workgroups = match (&commands.dispatch, sliced) {
+                    (Some(Dispatch::Direct(x, y, z)), _) => Some((*x, *y, *z)),
+                    (None, Some(sliced)) => Some(sliced.workgroups),
+                    _ => None,
+                };
+
+                let bindings = commands
+                    .entries
+                    .iter()
+                    .zip(&commands.buffers)
+                    .map(|(entry, buffer)| (entry.binding, buffer.size()));
+
+                Launch::new(vdi, &self.kernel, workgroups, bindings)
+            });
+        }
+    }
+
+    /// Submits the task to every device and writes the results back to the output VBuffers,
+    /// blocking until they arrive.
+    ///
+    /// A task can be run any number of times, recording its commands again each time but
+    /// keeping its pipelines, bind groups and device buffers. Inputs keep what was uploaded
+    /// when it was built, while outputs start each run as the last one left them on the
+    /// devices, so a loop can run it without building it again.
+    ///
+    /// Should a device fail or be lost during the run, its share is run again on the
+    /// strongest device left, and listed in the report's
+    /// [`failed_over`](RunReport::failed_over), rather than failing the run. That takes a
+    /// share the host can set up again: not one of a time-sliced or tiled task, one with
+    /// checksums, counted, append, merged or accumulated outputs, imported memory or an
+    /// indirect dispatch. A device that was lost before the task was built sits it out,
+    /// like one that rejects its shader.
+    pub fn run(&mut self) -> Result<RunReport, WiscError> {
+        self.submit()?.wait()
+    }
+
+    /// Runs the task like [`run`](Self::run), but gives up on any device that hasn't
+    /// finished within `timeout` of the call, rather than waiting on a wedged driver forever.
+    /// See [`PendingTask::wait_with_timeout`].
+    pub fn run_with_timeout(&mut self, timeout: Duration) -> Result<RunReport, WiscError> {
+        let deadline = Instant::now() + timeout;
+
+        self.submit()?.wait_until(deadline)
+    }
+
+    /// Submits the task like [`submit`](Self::submit), and calls `hook` with the run's
+    /// result once it is written back. See [`Completion`] for how to drive it.
+    pub fn run_with_callback<'p, F: FnOnce(TaskResult) + 'p>(
+        &'p mut self,
+        hook: F,
+    ) -> Result<Completion<'p, 't>, WiscError> {
+        Ok(self.submit()?.on_complete(hook))
+    }
+
+    /// Submits the task to every device like [`run`](Self::run), but returns as soon as
+    /// its commands are queued instead of waiting for the results, so the host can get on
+    /// with other work while the devices compute. The results are written back by
+    /// [`PendingTask::wait`].
+    ///
+    /// A time-sliced task has already run by the time this returns, since its slices are
+    /// waited for one by one; only its results remain to be read back.
+    pub fn submit(&mut self) -> Result<PendingTask<'_, 't>, WiscError> {
+        // Devices lost since the task was built have their shares run on the others once
+        // the results are in, if the task allows it.
+        let lost: Vec<usize> = (0..self.workgroup.vdevices.len())
+            .filter(|&vdi| {
+                self.device_commands[vdi].is_some()
+                    && self.workgroup.vdevices[vdi].check_lost().is_err()
+            })
+            .collect();
+
+        if !lost.is_empty() && !self.can_fail_over(&lost) {
+            self.workgroup.check_lost()?;
+        }
+
+        // An output aliased as an input stays on the devices for the next task to read,
+        // and is only read back once its host copy is, if nothing else needs it on the host
+        // first: not a memoized, tiled or checksummed run, nor a merge or count, and not
+        // part of the output that a device's share leaves to the host copy.
+        self.deferred = self
+            .output_buffers
+            .iter()
+            .enumerate()
+            .map(|(output_index, (_, handle))| {
+                lost.is_empty()
+                    && self.fingerprint.is_none()
+                    && self.tiles.is_none()
+                    && self.checksum_buffers.iter().all(Vec::is_empty)
+                    && matches!(self.output_writebacks[output_index], Writeback::Overwrite)
+                    && !self
+                        .counted_outputs
+                        .iter()
+                        .any(|counted| counted.output_index == output_index)
+                    && self.workgroup.vbuffers.get(*handle).is_some_and(|vbuffer| {
+                        matches!(vbuffer.residency, Residency::Aliased(_))
+                            && partition::covers(
+                                &self.output_partitions[output_index].owned,
+                                vbuffer.length,
+                            )
+                    })
+            })
+            .collect();
+
+        // The rest are written back over their host copies, which must first hold what an
+        // earlier run left on the devices.
+        for (output_index, (_, handle)) in self.output_buffers.iter().enumerate() {
+            if !self.deferred[output_index] {
+                self.workgroup.read_back_pending(*handle)?;
+            }
+        }
+
+        let fingerprint = self.run_fingerprint();
+
+        if let Some(fingerprint) = &fingerprint
+            && self.restore_cached(fingerprint)
+        {
+            let report = RunReport {
+                devices: self.workgroup.vdevices.len(),
+                single_device_fast_path: self.workgroup.vdevices.len() == 1,
+                from_cache: true,
+                excluded: self.excluded.clone(),
+                timings: vec![DeviceTiming::default(); self.workgroup.vdevices.len()],
+                invocations: vec![],
+                ..Default::default()
+            };
+
+            return Ok(PendingTask {
+                task: self,
+                report,
+                submitted: None,
+            });
+        }
+
+        // The last run shortened counted outputs to what the devices counted.
+        for counted in &self.counted_outputs {
+            let (_, handle) = self.output_buffers[counted.output_index];
+
+            if let Some(vbuffer) = self.workgroup.vbuffers.get_mut(handle)
+                && vbuffer.length != counted.length
+            {
+                (counted.resize)(vbuffer.inner.as_mut(), counted.length);
+                vbuffer.length = counted.length;
+            }
+        }
+
+        // Counters start every run from zero, before any (time-sliced) dispatch.
+        for (vdi, vd) in self.workgroup.vdevices.iter().enumerate() {
+            if let Some(command_buffer) = counter::encode_reset(vd, self.counters(vdi)) {
+                vd.queue.submit([command_buffer]);
+            }
+        }
+
+        self.record_launches();
+
+        let mut report = RunReport {
+            devices: self.workgroup.vdevices.len(),
+            single_device_fast_path: self.workgroup.vdevices.len() == 1,
+            checksums_verified: 0,
+            time_slices: 0,
+            tiles: vec![],
+            counted: vec![],
+            appended: vec![],
+            from_cache: false,
+            excluded: self.excluded.clone(),
+            failed_over: vec![],
+            timings: vec![DeviceTiming::default(); self.workgroup.vdevices.len()],
+            invocations: vec![],
+        };
+
+        if let Some((duration, sliced)) = &self.time_slice {
+            report.time_slices = timeslice::run_sliced(
+                &self.workgroup.vdevices,
+                &self.workgroup.binding_caches,
+                sliced,
+                *duration,
+                self.workgroup.throttle,
+            )?;
+        }
+
+        if let Some(tiling) = &mut self.tiles {
+            // Each device's copy of the outputs holds only the tiles it ran last time; the
+            // host copy holds them all.
+            if tiling.done.iter().any(|done| !done.is_empty()) {
+                for (output_index, (_, handle)) in self.output_buffers.iter().enumerate() {
+                    let vbuffer = &self.workgroup.vbuffers[*handle];
+                    let held = &self.output_partitions[output_index].held;
+
+                    for (vdi, vd) in self.workgroup.vdevices.iter().enumerate() {
+                        if self.device_commands[vdi].is_some() {
+                            let bytes = partition_bytes(vbuffer, &held[vdi]);
+                            vd.queue.write_buffer(
+                                &self.output_wgpu_buffers[vdi][output_index],
+                                0,
+                                bytes,
+                            );
+                            report.timings[vdi].bytes_uploaded += bytes.len() as u64;
+                        }
+                    }
+                }
+            }
+
+            report.tiles = tiling.run(
+                &self.workgroup.vdevices,
+                &self.workgroup.binding_caches,
+                self.workgroup.throttle,
+            )?;
+        }
+
+        // Time slices and tiles leave their own gaps.
+        let started = Instant::now();
+
+        // Encoders are per-device, so each device records and submits its commands on its
+        // own thread, and starts on them as soon as they are submitted rather than once
+        // every device's are recorded. wgpu gives each device a single queue, so copies
+        // can't run beside the pass on a transfer queue; instead the dispatch is submitted
+        // on its own, and the device computes while the readback copies are recorded.
+        let submitted: Vec<Option<(Instant, Duration)>> = {
+            // The outputs each device copies to staging buffers, and the buffers.
+            let read_back: Vec<(Vec<wgpu::Buffer>, Vec<wgpu::Buffer>)> =
+                (0..self.staging_buffers.len())
+                    .map(|vdi| {
+                        self.read_back(vdi)
+                            .map(|(output_index, staging)| {
+                                let output = &self.output_wgpu_buffers[vdi][output_index];
+
+                                (output.clone(), staging.clone())
+                            })
+                            .unzip()
+                    })
+                    .collect();
+            let (caches, counted, appends) = (
+                &self.workgroup.binding_caches,
+                &self.counted_outputs,
+                &self.append_outputs,
+            );
+
+            per_device_parallel(&self.workgroup.vdevices, |vdi, vd| {
+                let commands = self.device_commands[vdi].as_ref()?;
+
+                if lost.contains(&vdi) {
+                    return None;
+                }
+
+                let _span = trace::span!("submit", device = vd.label.as_str());
+                let recording = Instant::now();
+
+                vd.queue
+                    .submit([commands.encode(vd, &caches[vdi], &self.immediates)]);
+                let submitted_at = Instant::now();
+
+                let (outputs, staging) = &read_back[vdi];
+                let mut command_buffers =
+                    commands.encode_readback(vd, outputs, staging, &self.checksum_buffers[vdi]);
+
+                // Only the counters of append outputs are read back with the results. How
+                // much of each append buffer to read back isn't known until they arrive.
+                command_buffers.extend(counter::encode_readback(
+                    vd,
+                    device_counters(counted, appends, vdi),
+                ));
+
+                vd.queue.submit(command_buffers);
+
+                Some((submitted_at, recording.elapsed()))
+            })
+        };
+
+        let mut submitted_at = vec![started; self.workgroup.vdevices.len()];
+
+        for (vdi, submitted) in submitted.into_iter().enumerate() {
+            if let Some((at, submitting)) = submitted {
+                submitted_at[vdi] = at;
+                report.timings[vdi].submit = submitting;
+            }
+        }
+
+        let mappings: Vec<Mapping> = self.staging_except(&lost).map(vdevice::map_read).collect();
+
+        Ok(PendingTask {
+            task: self,
+            report,
+            submitted: Some(Submitted {
+                mappings,
+                started,
+                submitted_at,
+                fingerprint,
+                failed: lost,
+            }),
+        })
+    }
+
+    /// Writes back the results of a run submitted at `started`, once every staging buffer
+    /// of the devices that didn't fail is mapped. The shares of the `failed` devices are
+    /// run again on the others.
+    pub(crate) fn write_back(
+        &mut self,
+        mut report: RunReport,
+        started: Instant,
+        fingerprint: Option<Vec<u8>>,
+        failed: &[usize],
+    ) -> Result<RunReport, WiscError> {
+        throttle::idle_after(self.workgroup.throttle, started);
+
+        // Nothing is written back unless every checksum matches.
+        for (device_id, checksums) in self.checksum_buffers.iter().enumerate() {
+            for (output_index, checksum_buffer) in checksums.iter().enumerate() {
+                let expected: [u32; 2] =
+                    bytemuck::pod_read_unaligned(&checksum_buffer.slice(..).get_mapped_range());
+                let staged = self.staging_buffers[device_id][output_index].slice(..);
+
+                if checksum::checksum(&staged.get_mapped_range()) != expected {
+                    // Leave nothing mapped, so the task can run again.
+                    for buffer in self.staging() {
+                        buffer.unmap();
+                    }
+
+                    return Err(WiscError::ChecksumMismatch {
+                        binding: self.output_buffers[output_index].0,
+                        device: device_id,
+                        elements: self.output_partitions[output_index].held[device_id].clone(),
+                    });
+                }
+
+                report.checksums_verified += 1;
+            }
+        }
+
+        for checksum_buffer in self.checksum_buffers.iter().flatten() {
+            checksum_buffer.unmap();
+        }
+
+        // Whether each output has been written by a device yet. The first device to read a
+        // merged output back overwrites the host copy, and the others merge into it; which
+        // device that is depends on which sat the run out.
+        let mut written = vec![false; self.output_buffers.len()];
+
+        for (device_id, device) in self.workgroup.vdevices.iter().enumerate() {
+            if failed.contains(&device_id) {
+                continue;
+            }
+
+            let _span = trace::span!("readback", device = device.label.as_str());
+            let reading = Instant::now();
+
+            for (output_index, staging_buffer) in self.staging_buffers[device_id]
+                .iter()
+                .enumerate()
+                .filter(|(output_index, _)| !self.deferred[*output_index])
+            {
+                let Some((_, handle)) = self.output_buffers.get(output_index) else {
+                    continue;
+                };
+                let buffer_slice = staging_buffer.slice(..);
+                let data = buffer_slice.get_mapped_range();
+                let bytes: &[u8] = &data;
+
+                if let Some(vbuffer) = self.workgroup.vbuffers.get_mut(*handle) {
+                    let plan = &self.output_partitions[output_index];
+                    let held = &plan.held[device_id];
+
+                    // Every device of an unmanaged output computes all of it, so only the
+                    // first to read back is accumulated.
+                    let replicated = self.tiles.is_none() && !partition::is_disjoint(&plan.owned);
+
+                    // A tiled device owns the tiles it happened to run.
+                    let owned = match &self.tiles {
+                        Some(tiling) => tiling.done[device_id].clone(),
+                        None => vec![plan.owned[device_id].clone()],
+                    };
+
+                    for owned in &owned {
+                        // Only the owned elements go back; the halo around them is discarded.
+                        let skip = ((owned.start - held.start) * vbuffer.stride).min(bytes.len());
+                        let copy_len = (owned.len() * vbuffer.stride).min(bytes.len() - skip);
+                        let owned_bytes = &bytes[skip..skip + copy_len];
+                        let byte_offset = owned.start * vbuffer.stride;
+
+                        match &self.output_writebacks[output_index] {
+                            Writeback::Merge(_, merger, _) if written[output_index] => {
+                                vbuffer_merge(vbuffer, byte_offset, owned_bytes, merger)
+                            }
+                            Writeback::Accumulate(..) if replicated && written[output_index] => {}
+                            Writeback::Accumulate(_, merger, _) => {
+                                vbuffer_merge(vbuffer, byte_offset, owned_bytes, merger)
+                            }
+                            _ => vbuffer_write(vbuffer, byte_offset, owned_bytes),
+                        }
+                    }
+
+                    written[output_index] = true;
+                }
+
+                drop(data);
+                staging_buffer.unmap();
+
+                report.timings[device_id].bytes_read_back += staging_buffer.size();
+            }
+
+            // A failed device's share of the outputs left on the devices is run again into
+            // their host copies, which then need the rest from the devices that didn't fail.
+            if !failed.is_empty() {
+                for (output_index, (_, handle)) in self.output_buffers.iter().enumerate() {
+                    let plan = &self.output_partitions[output_index];
+                    let (held, owned) = (&plan.held[device_id], &plan.owned[device_id]);
+
+                    if !self.deferred[output_index] || owned.is_empty() {
+                        continue;
+                    }
+
+                    let vbuffer = &mut self.workgroup.vbuffers[*handle];
+                    let offset = (owned.start - held.start) * vbuffer.stride;
+                    let bytes = device.read_buffer_range(
+                        &self.output_wgpu_buffers[device_id][output_index],
+                        offset..offset + owned.len() * vbuffer.stride,
+                    )?;
+
+                    vbuffer_write(vbuffer, owned.start * vbuffer.stride, &bytes);
+                    report.timings[device_id].bytes_read_back += bytes.len() as u64;
+                }
+            }
+
+            report.timings[device_id].readback = reading.elapsed();
+
+            if let Some(timestamps) = self.device_commands[device_id]
+                .as_ref()
+                .and_then(|commands| commands.timestamps.as_ref())
+            {
+                report.timings[device_id].kernel = Some(timestamps.read(device));
+            }
+        }
+
+        if self.pipeline_statistics {
+            report.invocations = self
+                .device_commands
+                .iter()
+                .enumerate()
+                .map(|(vdi, commands)| {
+                    let statistics = commands.as_ref()?.statistics.as_ref()?;
+
+                    (!failed.contains(&vdi)).then(|| statistics.read())
+                })
+                .collect();
+        }
+
+        for &vdi in failed {
+            if let Some(on) = self.stand_in(vdi, failed) {
+                self.run_share_on(vdi, on)?;
+            }
+        }
+        report.failed_over = failed.to_vec();
+
+        // Keep the device copies of every output alive so they can be aliased as the input
+        // of a later task without another upload.
+        for (output_index, (_, handle)) in self.output_buffers.iter().enumerate() {
+            if let Some(vbuffer) = self.workgroup.vbuffers.get_mut(*handle) {
+                let deferred = self.deferred[output_index] && failed.is_empty();
+
+                // The partitions cover the whole buffer, so its host copy is now written,
+                // unless the results were left on the devices.
+                if !deferred && let Some(assume_init) = vbuffer.assume_init.take() {
+                    assume_init(vbuffer.inner.as_mut(), vbuffer.length);
+                }
+                vbuffer.pending_readback = deferred;
+
+                // A merged or accumulated result exists only on the host; no device copy
+                // holds it.
+                // So does a tiled one, pieced together from every device's tiles, and one
+                // that a failed device's copy is missing from.
+                let host_only = match &self.output_writebacks[output_index] {
+                    _ if self.tiles.is_some() || !failed.is_empty() => true,
+                    Writeback::Overwrite => false,
+                    Writeback::Merge(..) => self.workgroup.vdevices.len() > 1,
+                    Writeback::Accumulate(..) => true,
+                };
+
+                if host_only {
+                    vbuffer.residency = Residency::Host;
+                    continue;
+                }
+
+                let buffers = self
+                    .output_wgpu_buffers
+                    .iter()
+                    .map(|buffers| buffers[output_index].clone())
+                    .collect();
+
+                let resident = Resident {
+                    buffers,
+                    ranges: self.output_partitions[output_index].held.clone(),
+                    owned: self.output_partitions[output_index].owned.clone(),
+                };
+
+                // Imported memory stays bound in place of the host copy.
+                vbuffer.residency = if vbuffer.imported {
+                    Residency::Aliased(resident)
+                } else {
+                    Residency::Retained(resident)
+                };
+            }
+        }
+
+        // Counted outputs keep only what each device counted, moved up to follow on from
+        // the devices before it.
+        for counted in &self.counted_outputs {
+            let (_, handle) = self.output_buffers[counted.output_index];
+            let owned = &self.output_partitions[counted.output_index].owned;
+
+            let mut produced = Appended::default();
+            let mut kept = vec![];
+
+            for (counter, owned) in counted.counters.iter().zip(owned) {
+                let count = counter.as_ref().map_or(0, DeviceCounter::read);
+                let len = count.min(owned.len());
+
+                produced.lengths.push(len);
+                produced.dropped += count - len;
+                kept.push(owned.start..owned.start + len);
+            }
+
+            if let Some(vbuffer) = self.workgroup.vbuffers.get_mut(handle) {
+                vbuffer_compact(vbuffer, &kept, counted.resize);
+                vbuffer.residency = Residency::Host;
+            }
+
+            report.counted.push(produced);
+        }
+
+        for append in &self.append_outputs {
+            let (bytes, appended) = append.read_back(&self.workgroup.vdevices)?;
+
+            if let Some(vbuffer) = self.workgroup.vbuffers.get_mut(append.handle) {
+                (append.replace)(vbuffer, &bytes);
+            }
+
+            report.appended.push(appended);
+        }
+
+        if let (Some(fingerprint), Some(cache)) = (fingerprint, &self.workgroup.result_cache) {
+            let outputs: Vec<&[u8]> = self
+                .output_buffers
+                .iter()
+                .map(|(_, handle)| vbuffer_bytes(&self.workgroup.vbuffers[*handle]))
+                .collect();
+
+            cache.store(&fingerprint, &outputs);
+        }
+
+        Ok(report)
+    }
+
+    /// The fingerprint of this run in the result cache, which adds what the outputs hold
+    /// now to what the task was built with. `None` if the run can't be memoized.
+    fn run_fingerprint(&self) -> Option<Vec<u8>> {
+        let mut fingerprint = self.fingerprint.clone()?;
+
+        for (_, handle) in &self.output_buffers {
+            let vbuffer = self.workgroup.vbuffers.get(*handle)?;
+
+            match vbuffer.assume_init {
+                Some(_) => fingerprint.add(&false),
+                None => fingerprint.add(&true).add_buffer(self.workgroup, *handle)?,
+            };
+        }
+
+        Some(fingerprint.into_bytes())
+    }
+
+    /// Writes the outputs stored for `fingerprint` to the host copies, returning whether
+    /// there were any that fit them.
+    fn restore_cached(&mut self, fingerprint: &[u8]) -> bool {
+        let Some(outputs) = self
+            .workgroup
+            .result_cache
+            .as_ref()
+            .and_then(|cache| cache.load(fingerprint))
+        else {
+            return false;
+        };
+
+        let fits = outputs.len() == self.output_buffers.len()
+            && self
+                .output_buffers
+                .iter()
+                .zip(&outputs)
+                .all(|((_, handle), bytes)| {
+                    let vbuffer = &self.workgroup.vbuffers[*handle];
+                    vbuffer.length * vbuffer.stride == bytes.len()
+                });
+
+        if !fits {
+            return false;
+        }
+
+        for ((_, handle), bytes) in self.output_buffers.iter().zip(&outputs) {
+            let vbuffer = &mut self.workgroup.vbuffers[*handle];
+
+            vbuffer_write(vbuffer, 0, bytes);
+            if let Some(assume_init) = vbuffer.assume_init.take() {
+                assume_init(vbuffer.inner.as_mut(), vbuffer.length);
+            }
+
+            // The device copies still hold whatever they held before.
+            vbuffer.residency = Residency::Host;
+            vbuffer.pending_readback = false;
+        }
+
+        true
+    }
+
+    /// Everything compiling the task's shader reported, device by device: warnings the
+    /// backend's compiler raised on one device but not another, say. Usually empty.
+    pub fn compile_messages(&self) -> &[CompileMessage] {
+        &self.compile_messages
+    }
+
+    /// The workgroup the task runs on, to read results from between runs.
+    pub fn workgroup(&self) -> &Workgroup {
+        self.workgroup
+    }
+
+    fn counters(&self, vdi: usize) -> impl Iterator<Item = &DeviceCounter> {
+        device_counters(&self.counted_outputs, &self.append_outputs, vdi)
+    }
+
+    /// The staging buffers of device `vdi` that runs map to read outputs back, by output
+    /// index: those of every output the last run didn't leave on the devices.
+    pub(crate) fn read_back(&self, vdi: usize) -> impl Iterator<Item = (usize, &wgpu::Buffer)> {
+        self.staging_buffers[vdi]
+            .iter()
+            .enumerate()
+            .filter(|(output_index, _)| !self.deferred.get(*output_index).is_some_and(|d| *d))
+    }
+
+    /// Every buffer that is mapped to read back a run's results.
+    pub(crate) fn staging(&self) -> impl Iterator<Item = &wgpu::Buffer> {
+        self.staging_except(&[])
+    }
+
+    /// Like [`staging`](Self::staging), without the staging buffers of the `skipped`
+    /// devices.
+    fn staging_except<'s>(
+        &'s self,
+        skipped: &'s [usize],
+    ) -> impl Iterator<Item = &'s wgpu::Buffer> {
+        (0..self.staging_buffers.len())
+            .filter(move |vdi| !skipped.contains(vdi))
+            .flat_map(|vdi| self.read_back(vdi).map(|(_, buffer)| buffer))
+            .chain(self.checksum_buffers.iter().flatten())
+            .chain(
+                (0..self.workgroup.vdevices.len())
+                    .flat_map(|vdi| self.counters(vdi))
+                    .map(DeviceCounter::staging),
+            )
+            .chain(
+                self.device_commands
+                    .iter()
+                    .enumerate()
+                    .filter(move |(vdi, _)| !skipped.contains(vdi))
+                    .filter_map(|(_, commands)| commands.as_ref())
+                    .flat_map(|commands| {
+                        let timestamps = commands.timestamps.as_ref().map(PassTimestamps::staging);
+                        let statistics = commands.statistics.as_ref().map(PassStatistics::staging);
+
+                        timestamps.into_iter().chain(statistics)
+                    }),
+            )
+    }
+}
+
+/// A run of a [`Task`] that has been [submitted](Task::submit) to the devices and whose
+/// results haven't been written back yet.
+///
+/// Dropping it without [`wait`](Self::wait)ing still blocks until the devices finish, but
+/// discards the results, leaving the outputs' host copies as they were. To abandon the run
+/// without waiting for it, [`cancel`](Self::cancel) it.
+pub struct PendingTask<'p, 't> {
+    pub(crate) task: &'p mut Task<'t>,
+    pub(crate) report: RunReport,
+    // `None` if the results came from the result cache and nothing was submitted.
+    pub(crate) submitted: Option<Submitted>,
+}
+
+/// What a submitted run waits on.
+pub(crate) struct Submitted {
+    pub(crate) mappings: Vec<Mapping>,
+    pub(crate) started: Instant,
+    // When each device's commands were submitted.
+    pub(crate) submitted_at: Vec<Instant>,
+    pub(crate) fingerprint: Option<Vec<u8>>,
+    // The devices that were lost before the run could be submitted to them.
+    pub(crate) failed: Vec<usize>,
+}
+
+impl<'p, 't> PendingTask<'p, 't> {
+    /// Has `hook` called with the run's result once it is written back, for applications
+    /// that can't block waiting for it, like those running an event loop. Drive the
+    /// returned [`Completion`] from the loop.
+    pub fn on_complete<F: FnOnce(TaskResult) + 'p>(self, hook: F) -> Completion<'p, 't> {
+        Completion {
+            pending: Some(self),
+            hook: Some(Box::new(hook)),
+        }
+    }
+
+    /// Whether the results have arrived, so that [`wait`](Self::wait) won't block. Never
+    /// blocks itself.
+    pub fn poll(&mut self) -> Result<bool, WiscError> {
+        let Some(submitted) = &mut self.submitted else {
+            return Ok(true);
+        };
+
+        let mut idle = true;
+        for vd in &self.task.workgroup.vdevices {
+            match vd.poll() {
+                Ok(done) => idle &= done,
+                // `wait` runs a failed device's share elsewhere, if the task can.
+                Err(_) if self.task.recipes.is_some() => {}
+                Err(error) => return Err(error),
+            }
+        }
+
+        // Other work may keep a shared device busy long after this run's results are in.
+        if submitted.mappings.is_empty() {
+            return Ok(idle);
+        }
+
+        Ok(submitted.mappings.iter_mut().all(Mapping::is_ready))
+    }
+
+    /// Blocks until the results arrive, then writes them back to the output VBuffers like
+    /// [`Task::run`].
+    pub fn wait(mut self) -> Result<RunReport, WiscError> {
+        let mut report = std::mem::take(&mut self.report);
+
+        let Some(submitted) = self.submitted.take() else {
+            return Ok(report);
+        };
+
+        let (failed, finished) = self.task.settle(submitted.mappings, submitted.failed)?;
+        time_execution(&mut report, &submitted.submitted_at, &finished);
+
+        self.task
+            .write_back(report, submitted.started, submitted.fingerprint, &failed)
+    }
+
+    /// Like [`wait`](Self::wait), but fails with [`WiscError::Timeout`], naming the first
+    /// device that stalled, if the results haven't arrived within `timeout`. The run is
+    /// then abandoned as if [cancelled](Self::cancel).
+    ///
+    /// The deadline covers waiting for the results only: a time-sliced or tiled task has
+    /// waited on its slices or tiles by the time it was submitted.
+    pub fn wait_with_timeout(self, timeout: Duration) -> Result<RunReport, WiscError> {
+        self.wait_until(Instant::now() + timeout)
+    }
+
+    fn wait_until(mut self, deadline: Instant) -> Result<RunReport, WiscError> {
+        if self.submitted.is_some() {
+            let waited = per_device_parallel(&self.task.workgroup.vdevices, |vdi, vd| {
+                vd.wait_until(vdi, deadline)
+            });
+
+            // Devices that failed outright rather than stalled are left for `wait` to run
+            // the shares of elsewhere, where it can.
+            let can_fail_over = self.task.recipes.is_some();
+
+            if let Some(error) = waited
+                .into_iter()
+                .filter_map(Result::err)
+                .find(|error| matches!(error, WiscError::Timeout { .. }) || !can_fail_over)
+            {
+                // Waiting on the stalled device again when dropped would hang.
+                self.abandon();
+
+                return Err(error);
+            }
+        }
+
+        // Every device is idle, so this doesn't block.
+        self.wait()
+    }
+
+    /// Abandons the run without waiting for it: its results are never read back, and the
+    /// outputs' host copies stay as they were.
+    ///
+    /// Commands already queued on a device can't be recalled, so the devices still finish
+    /// them in the background, and the outputs' device copies, which the next run starts
+    /// from, may hold any part of them. The task can run again straight away.
+    pub fn cancel(mut self) {
+        self.abandon();
+    }
+
+    fn abandon(&mut self) {
+        if self.submitted.take().is_none() {
+            return;
+        }
+
+        // Unmapping a buffer whose mapping is still pending aborts the mapping, which
+        // frees its staging buffer for the next run.
+        for buffer in self.task.staging() {
+            buffer.unmap();
+        }
+    }
+}
+
+impl Drop for PendingTask<'_, '_> {
+    fn drop(&mut self) {
+        let Some(submitted) = self.submitted.take() else {
+            return;
+        };
+
+        // Leave nothing mapped, so the task can run again.
+        if finish(&self.task.workgroup.vdevices, submitted.mappings).is_ok() {
+            for buffer in self.task.staging() {
+                buffer.unmap();
+            }
+        }
+    }
+}
+
+/// What a run of a task comes to.
+pub type TaskResult = Result<RunReport, WiscError>;
+
+/// A [submitted](Task::submit) run with a hook to call once its results land, made by
+/// [`PendingTask::on_complete`] or [`Task::run_with_callback`].
+///
+/// Nothing moves it along on its own: [`poll`](Self::poll) it from the application's
+/// event loop, and the hook is called on that thread once the results are written back.
+/// Dropping it before then blocks until they are, and calls the hook anyway.
+pub struct Completion<'p, 't> {
+    pending: Option<PendingTask<'p, 't>>,
+    hook: Option<Box<dyn FnOnce(TaskResult) + 'p>>,
+}
+
+impl Completion<'_, '_> {
+    /// Writes the results back and calls the hook if they have arrived, without blocking.
+    /// Returns whether the hook has been called, by this poll or an earlier one.
+    pub fn poll(&mut self) -> bool {
+        let Some(pending) = &mut self.pending else {
+            return true;
+        };
+
+        match pending.poll() {
+            Ok(false) => false,
+            Ok(true) => {
+                self.complete();
+                true
+            }
+            Err(error) => {
+                // The run is lost with the device; there are no results to wait for.
+                if let Some(hook) = self.hook.take() {
+                    hook(Err(error));
+                }
+                self.pending = None;
+                true
+            }
+        }
+    }
+
+    fn complete(&mut self) {
+        if let (Some(pending), Some(hook)) = (self.pending.take(), self.hook.take()) {
+            hook(pending.wait());
+        }
+    }
+}
+
+impl Drop for Completion<'_, '_> {
+    fn drop(&mut self) {
+        self.complete();
+    }
+}
+
+/// How `mode` partitions `vbuffer`, with nothing on the `excluded` devices. `weightings`
+/// should give them no share, so the built in modes spread the rest over the others.
+fn plan_excluding(
+    mode: &PartitionMode,
+    vbuffer: &VBuffer,
+    group_size: usize,
+    weightings: &[f32],
+    caps: &[f64],
+    excluded: &[usize],
+    whole: bool,
+) -> Result<Plan, WiscError> {
+    let mut plan = mode.plan(vbuffer, group_size, weightings, caps)?;
+
+    for &vdi in excluded {
+        plan.held[vdi] = 0..0;
+        plan.owned[vdi] = 0..0;
+    }
+
+    // Kernels that index by global IDs need the whole buffer on every device that takes
+    // part, though each still writes back only its own elements.
+    if whole {
+        for (held, owned) in plan.held.iter_mut().zip(&plan.owned) {
+            if !owned.is_empty() {
+                *held = 0..vbuffer.length;
+            }
+        }
+    }
+
+    Ok(plan)
+}
+
+/// The largest fraction of the split buffers among `bound` that each of `vdevices` can
+/// take: what fits in its largest binding, and what fits in its memory cap, if it has one,
+/// besides the buffers it holds whole. Each buffer is paired with the element groups it is
+/// split in, and whether it is split.
+fn share_caps(
+    vdevices: &[VDevice],
+    memory_caps: &[Option<u64>],
+    bound: &[(&VBuffer, usize, bool)],
+) -> Vec<f64> {
+    vdevices
+        .iter()
+        .zip(memory_caps)
+        .map(|(vd, memory_cap)| {
+            let limits = vd.device.limits();
+            let largest = limits
+                .max_buffer_size
+                .min(limits.max_storage_buffer_binding_size as u64);
+
+            let mut cap: f64 = 1.0;
+            let (mut split_bytes, mut whole_bytes) = (0, 0);
+
+            for &(vbuffer, group_size, split) in bound {
+                let bytes = (vbuffer.length * vbuffer.stride) as u64;
+
+                if !split {
+                    whole_bytes += bytes;
+                    continue;
+                }
+
+                // Rounding to whole element groups can give a device one group more.
+                let margin = (group_size * vbuffer.stride) as u64;
+                split_bytes += bytes;
+
+                if bytes > 0 {
+                    cap = cap.min(largest.saturating_sub(margin) as f64 / bytes as f64);
+                }
+            }
+
+            if let Some(memory_cap) = memory_cap
+                && split_bytes > 0
+            {
+                let margins: u64 = bound
+                    .iter()
+                    .filter(|(_, _, split)| *split)
+                    .map(|(vbuffer, group_size, _)| (group_size * vbuffer.stride) as u64)
+                    .sum();
+                let room = memory_cap.saturating_sub(whole_bytes + margins);
+
+                cap = cap.min(room as f64 / split_bytes as f64);
+            }
+
+            cap
+        })
+        .collect()
+}
+
+/// Records in `report` how long each device that was submitted to took from then until it
+/// `finished`.
+pub(crate) fn time_execution(
+    report: &mut RunReport,
+    submitted_at: &[Instant],
+    finished: &[Instant],
+) {
+    for ((timing, submitted_at), finished) in
+        report.timings.iter_mut().zip(submitted_at).zip(finished)
+    {
+        if timing.submit > Duration::ZERO {
+            timing.execution = finished.saturating_duration_since(*submitted_at);
+        }
+    }
+}
+
+/// Blocks until every device is idle and every mapping has resolved. The devices are
+/// waited on together, so this takes as long as the slowest of them.
+pub(crate) fn finish(vdevices: &[VDevice], mappings: Vec<Mapping>) -> Result<(), WiscError> {
+    finish_timed(vdevices, mappings, &[]).map(|_| ())
+}
+
+/// Like [`finish`], but returns when each device became idle, and doesn't wait on the
+/// `skipped` ones, which took no part.
+pub(crate) fn finish_timed(
+    vdevices: &[VDevice],
+    mappings: Vec<Mapping>,
+    skipped: &[usize],
+) -> Result<Vec<Instant>, WiscError> {
+    let finished = per_device_parallel(vdevices, |vdi, vd| {
+        if skipped.contains(&vdi) {
+            return Ok(Instant::now());
+        }
+
+        vd.wait().map(|()| Instant::now())
+    })
+    .into_iter()
+    .collect::<Result<Vec<Instant>, WiscError>>()?;
+
+    for mapping in mappings {
+        mapping.finish()?;
+    }
+
+    Ok(finished)
+}
+
+/// The counters of device `vdi`, of counted outputs and then append outputs.
+fn device_counters<'c>(
+    counted: &'c [CountedOutput],
+    appends: &'c [AppendOutput],
+    vdi: usize,
+) -> impl Iterator<Item = &'c DeviceCounter> {
+    counted
+        .iter()
+        .filter_map(move |counted| counted.counters[vdi].as_ref())
+        .chain(
+            appends
+                .iter()
+                .filter_map(move |append| append.devices[vdi].as_ref())
+                .map(|device| &device.counter),
+        )
+}
+
+/// What one device records every time a task runs.
+pub(crate) struct DeviceCommands {
+    pub(crate) bind_group_layout: wgpu::BindGroupLayout,
+    pub(crate) pipeline: wgpu::ComputePipeline,
+    pub(crate) entries: Vec<wgpu::BindGroupLayoutEntry>,
+    pub(crate) buffers: Vec<wgpu::Buffer>,
+    // `None` when the dispatch is time-sliced, and recorded as it runs.
+    pub(crate) dispatch: Option<Dispatch>,
+    // The on-device checksum of each output, if readback is verified.
+    pub(crate) checksums: Vec<wgpu::Buffer>,
+    // Where the device times its pass, if it can and the dispatch is recorded once.
+    pub(crate) timestamps: Option<PassTimestamps>,
+    // Where it counts the pass's invocations, likewise, if the task asked.
+    pub(crate) statistics: Option<PassStatistics>,
+}
+
+impl DeviceCommands {
+    /// Records the dispatch.
+    fn encode(&self, vd: &VDevice, cache: &BindingCache, immediates: &[u8]) -> wgpu::CommandBuffer {
+        encode_commands(
+            vd,
+            cache,
+            &self.bind_group_layout,
+            &self.pipeline,
+            &self.entries,
+            &self.buffers,
+            self.dispatch.as_ref(),
+            self.timestamps.as_ref(),
+            self.statistics.as_ref(),
+            immediates,
+        )
+    }
+
+    /// Records the readback of `output_buffers` into `staging_buffers`, and the checksums,
+    /// if any, into `checksum_staging`.
+    fn encode_readback(
+        &self,
+        vd: &VDevice,
+        output_buffers: &[wgpu::Buffer],
+        staging_buffers: &[wgpu::Buffer],
+        checksum_staging: &[wgpu::Buffer],
+    ) -> Vec<wgpu::CommandBuffer> {
+        let mut command_buffers: Vec<wgpu::CommandBuffer> =
+            encode_readback(vd, output_buffers, staging_buffers)
+                .into_iter()
+                .collect();
+
+        if !self.checksums.is_empty() {
+            command_buffers.push(checksum::encode(
+                vd,
+                output_buffers,
+                &self.checksums,
+                checksum_staging,
+            ));
+        }
+
+        command_buffers
+    }
+}
+
+pub(crate) struct InputBinding {
+    pub(crate) id: u32,
+    pub(crate) handle: VBufferHandle,
+    pub(crate) uniform: bool,
+    pub(crate) mode: PartitionMode,
+    // Whether the devices keep the upload for later tasks binding the buffer the same way.
+    pub(crate) broadcast: bool,
+}
+
+pub(crate) struct OutputBinding {
+    pub(crate) id: u32,
+    pub(crate) handle: VBufferHandle,
+    pub(crate) mode: PartitionMode,
+    pub(crate) writeback: Writeback,
+}
+
+pub(crate) struct AppendBinding {
+    pub(crate) id: u32,
+    pub(crate) counter_id: u32,
+    pub(crate) handle: VBufferHandle,
+    pub(crate) capacity: usize,
+    // Replaces the host copy with the concatenated results, as the bound element type.
+    pub(crate) replace: fn(&mut VBuffer, &[u8]),
+}
+
+/// An append output's buffers on one device.
+pub(crate) struct AppendDevice {
+    pub(crate) results: wgpu::Buffer,
+    pub(crate) counter: DeviceCounter,
+}
+
+/// An output of which only the elements each device counted are kept.
+pub(crate) struct CountedOutput {
+    pub(crate) output_index: usize,
+    // The output's whole length, which every run writes back before shortening it.
+    pub(crate) length: usize,
+    // Resizes the host copy, as the bound element type.
+    pub(crate) resize: vbuffer::Resize,
+    // `None` for devices that sit the task out.
+    pub(crate) counters: Vec<Option<DeviceCounter>>,
+}
+
+pub(crate) struct AppendOutput {
+    pub(crate) handle: VBufferHandle,
+    pub(crate) capacity: usize,
+    pub(crate) stride: usize,
+    pub(crate) replace: fn(&mut VBuffer, &[u8]),
+    // `None` for devices that sit the task out.
+    pub(crate) devices: Vec<Option<AppendDevice>>,
+}
+
+impl AppendOutput {
+    /// Reads back as many results from every device as its (mapped) counter says it
+    /// appended, concatenated in device order.
+    fn read_back(&self, vdevices: &[VDevice]) -> Result<(Vec<u8>, Appended), WiscError> {
+        let mut appended = Appended::default();
+        let mut copies = vec![];
+
+        for (device, vd) in self.devices.iter().zip(vdevices) {
+            let Some(device) = device else {
+                appended.lengths.push(0);
+                continue;
+            };
+
+            let count = device.counter.read();
+
+            // The counter goes on counting past the end of the buffer.
+            let len = count.min(self.capacity);
+            appended.lengths.push(len);
+            appended.dropped += count - len;
+
+            if len == 0 {
+                continue;
+            }
+
+            let byte_len = len * self.stride;
+            let copy_len = byte_len.next_multiple_of(4) as wgpu::BufferAddress;
+
+            let staging = vd.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&format!(
+                    "WISC Append Staging Buffer (VDevice {})",
+                    vd.label
+                )),
+                size: copy_len,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+
+            let mut encoder = vd
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+            encoder.copy_buffer_to_buffer(&device.results, 0, &staging, 0, copy_len);
+            vd.queue.submit([encoder.finish()]);
+
+            copies.push((staging, byte_len));
+        }
+
+        let mappings: Vec<Mapping> = copies
+            .iter()
+            .map(|(staging, _)| vdevice::map_read(staging))
+            .collect();
+
+        for vd in vdevices {
+            vd.wait()?;
+        }
+
+        for mapping in mappings {
+            mapping.finish()?;
+        }
+
+        let mut bytes = vec![];
+
+        for (staging, byte_len) in copies {
+            bytes.extend_from_slice(&staging.slice(..).get_mapped_range()[..byte_len]);
+            staging.unmap();
+        }
+
+        Ok((bytes, appended))
+    }
+}
+
+/// How an output's device results reach its host copy. The merging variants carry the
+/// element type they were built for.
+pub(crate) enum Writeback {
+    Overwrite,
+    /// Replicated copies are merged with each other.
+    Merge(TypeId, Merger, MergeKey),
+    /// Every device's results are folded into the host copy as it was before the run.
+    Accumulate(TypeId, Merger, ReduceOp),
+}
+
+pub(crate) enum TaskShader<'b> {
+    Inline(wgpu::ShaderModuleDescriptor<'b>),
+    Registered(String),
+}
+
+impl TaskShader<'_> {
+    /// Fails if a registered shader isn't, after recompiling it if it is watched and its
+    /// files have changed.
+    pub(crate) fn check_available(&self, workgroup: &mut Workgroup) -> Result<(), WiscError> {
+        if let TaskShader::Registered(name) = self {
+            workgroup.reload_if_changed(name)?;
+        }
+
+        match self {
+            TaskShader::Registered(name) if !workgroup.shaders.contains_key(name) => {
+                Err(WiscError::UnknownShader(name.clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The shader's IR, or `None` if its source can't be reflected. Fails if an inline
+    /// shader doesn't parse.
+    pub(crate) fn reflect(&self, workgroup: &Workgroup) -> Result<Option<naga::Module>, WiscError> {
+        match self {
+            TaskShader::Inline(descriptor) => reflect::parse(&descriptor.source),
+            TaskShader::Registered(name) => Ok(workgroup
+                .shaders
+                .get(name)
+                .and_then(|shader| shader.reflection.clone())),
+        }
+    }
+}
+
+/// The kernel named with [`TaskBuilder::with_kernel`], or else the shader's only compute
+/// entry point. Fails with the candidate names if the named kernel isn't one of them, or
+/// no kernel was named and the shader has several.
+pub(crate) fn resolve_kernel(
+    reflection: Option<&naga::Module>,
+    kernel: Option<String>,
+) -> Result<String, WiscError> {
+    let candidates = reflection
+        .map(reflect::compute_entry_points)
+        .unwrap_or_default();
+
+    if let Some(kernel) = kernel {
+        // Shaders that can't be reflected are left for wgpu to check.
+        if reflection.is_some() && !candidates.contains(&kernel) {
+            return Err(WiscError::UnknownKernel { kernel, candidates });
+        }
+
+        return Ok(kernel);
+    }
+
+    match candidates.as_slice() {
+        [only] => Ok(only.clone()),
+        _ => Err(WiscError::MissingKernel { candidates }),
+    }
+}
+
+/// Checks the task's `bound` buffers against the bindings the shader declares, so that a
+/// mismatch is reported here rather than as a validation error when the pipeline is made.
+pub(crate) fn check_bindings(
+    module: &naga::Module,
+    kernel: &str,
+    bound: &[(u32, BindingKind)],
+) -> Result<(), WiscError> {
+    let declared = reflect::bindings(module, kernel);
+
+    for &(binding, kind) in bound {
+        let mismatch = |reason| WiscError::BindingMismatch { binding, reason };
+
+        let Some(shader_binding) = declared.iter().find(|declared| declared.binding == binding)
+        else {
+            return Err(mismatch("the shader doesn't declare it"));
+        };
+
+        match (kind, shader_binding.kind) {
+            (BindingKind::Uniform, BindingKind::Uniform)
+            | (BindingKind::ReadOnlyStorage, BindingKind::ReadOnlyStorage)
+            | (BindingKind::ReadWriteStorage, BindingKind::ReadWriteStorage) => {}
+            (_, BindingKind::Other) => return Err(mismatch("the shader declares a non-buffer")),
+            (BindingKind::Uniform, _) => {
+                return Err(mismatch(
+                    "bound as a uniform, but the shader declares storage",
+                ));
+            }
+            (_, BindingKind::Uniform) => {
+                return Err(mismatch(
+                    "bound as storage, but the shader declares a uniform",
+                ));
+            }
+            (BindingKind::ReadOnlyStorage, _) => {
+                return Err(mismatch("bound as an input, but the shader writes to it"));
+            }
+            _ => {
+                return Err(mismatch(
+                    "bound as an output, but the shader declares it read-only",
+                ));
+            }
+        }
+    }
+
+    if let Some(unbound) = declared.iter().find(|declared| {
+        declared.used
+            && !bound
+                .iter()
+                .any(|(binding, _)| *binding == declared.binding)
+    }) {
+        return Err(WiscError::BindingMismatch {
+            binding: unbound.binding,
+            reason: "the kernel uses it, but nothing is bound there",
+        });
+    }
+
+    Ok(())
+}
+
+impl TaskShader<'_> {
+    /// The module for device `vdi`: registered, compiled for an earlier task, or else
+    /// compiled now.
+    pub(crate) fn module(
+        &self,
+        shaders: &HashMap<String, RegisteredShader>,
+        vdi: usize,
+        vd: &VDevice,
+    ) -> wgpu::ShaderModule {
+        match self {
+            TaskShader::Inline(descriptor) => vd.modules.module(vd, descriptor),
+            TaskShader::Registered(name) => shaders[name].modules[vdi].clone(),
+        }
+    }
+}
+
+/// What a task does with a device whose limits its kernel exceeds, found out when it is
+/// built rather than as a pipeline error on that device alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverLimit {
+    /// The device sits the task out, like a device that rejects its shader, with the
+    /// reason in the task's [`compile_messages`](Task::compile_messages). The build fails
+    /// only if no device is left.
+    #[default]
+    Exclude,
+    /// The build fails, naming the first such device.
+    Fail,
+}
+
+pub struct TaskBuilder<'b> {
+    pub(crate) workgroup: &'b mut Workgroup,
+    pub(crate) shader: Option<TaskShader<'b>>,
+    pub(crate) kernel: Option<String>,
+    pub(crate) size: Option<DispatchSize>,
+
+    // Pipeline constants by the key wgpu looks them up with: the `@id`, or else the name.
+    pub(crate) overrides: Vec<(String, f64)>,
+    pub(crate) input_buffers: Vec<InputBinding>,
+    // The bytes of each `with_uniform_buffer` value, padded for a uniform binding.
+    pub(crate) uniform_values: Vec<(u32, Vec<u8>)>,
+    pub(crate) output_buffers: Vec<OutputBinding>,
+    // Element types that bound VBuffers were declared with, checked at build time.
+    pub(crate) expected_types: Vec<(VBufferHandle, TypeId)>,
+    pub(crate) partition_info: Option<VBufferHandle>,
+    pub(crate) global_ids: bool,
+    pub(crate) precision: Precision,
+    pub(crate) over_limit: OverLimit,
+    pub(crate) checksums: bool,
+    pub(crate) pipeline_statistics: bool,
+    // Padded to whole words; empty if the task has none.
+    pub(crate) immediates: Vec<u8>,
+    pub(crate) time_slice: Option<Duration>,
+    // How many workgroups along x each tile of a tiled dispatch holds.
+    pub(crate) tiles: Option<u32>,
+    // Each windowed input, with its window size and stride, and the output holding its
+    // per-window results.
+    pub(crate) sliding_windows: Vec<(VBufferHandle, usize, usize, VBufferHandle)>,
+    // Each counted output, with the binding of its counter and how to shorten it.
+    pub(crate) counted_outputs: Vec<(VBufferHandle, u32, vbuffer::Resize)>,
+    pub(crate) append_outputs: Vec<AppendBinding>,
+
+    pub(crate) stream_input: Option<(u32, usize)>,
+    pub(crate) stream_output: Option<(u32, usize)>,
+    // The pipelines of the template the task was instantiated from, if any.
+    pub(crate) template: Option<&'b PipelineStore>,
+}
+
+impl<'b> TaskBuilder<'b> {
+    pub fn new<S: Into<Shader<'b>>>(workgroup: &'b mut Workgroup, shader: S) -> Self {
+        Self::from_workgroup(workgroup).with_shader(shader)
+    }
+
+    /// Starts a task without a shader; one must be supplied with
+    /// [`with_shader`](Self::with_shader) or [`with_registered_shader`](Self::with_registered_shader).
+    pub fn from_workgroup(workgroup: &'b mut Workgroup) -> Self {
+        Self {
+            workgroup,
+            shader: None,
             kernel: None,
             size: None,
 
             overrides: vec![],
             input_buffers: vec![],
+            uniform_values: vec![],
             output_buffers: vec![],
+            expected_types: vec![],
+            partition_info: None,
+            global_ids: false,
+            precision: Precision::Relaxed,
+            over_limit: OverLimit::Exclude,
+            checksums: false,
+            pipeline_statistics: false,
+            immediates: vec![],
+            time_slice: None,
+            tiles: None,
+            sliding_windows: vec![],
+            counted_outputs: vec![],
+            append_outputs: vec![],
+
+            stream_input: None,
+            stream_output: None,
+            template: None,
         }
     }
 
-    pub fn build(self) -> Option<Task<'b>> {
+    pub fn build(self) -> Result<Task<'b>, WiscError> {
         Task::from_builder(self)
     }
 
+    /// Builds a [`StreamTask`] instead, which requires a streamed input and output binding.
+    pub fn build_stream(self) -> Result<StreamTask<'b>, WiscError> {
+        StreamTask::from_builder(self)
+    }
+
+    /// Builds a [`StreamStage`] for a [`StreamPipeline`](crate::stream::StreamPipeline),
+    /// releasing the Workgroup so the other stage can be built from it too.
+    pub fn build_stage(self) -> Result<StreamStage, WiscError> {
+        StreamStage::from_builder(self).map(|(_, stage)| stage)
+    }
+
+    pub fn with_shader<S: Into<Shader<'b>>>(mut self, shader: S) -> Self {
+        self.shader.replace(TaskShader::Inline(shader.into().0));
+
+        self
+    }
+
+    /// Uses a shader previously compiled with [`Workgroup::register_shader`].
+    pub fn with_registered_shader<S: Into<String>>(mut self, name: S) -> Self {
+        self.shader.replace(TaskShader::Registered(name.into()));
+
+        self
+    }
+
+    /// Names the entry point to run. May be omitted when the shader has exactly one compute
+    /// entry point; otherwise building fails with [`WiscError::MissingKernel`], which lists
+    /// the ones to pick from.
     pub fn with_kernel<S: Into<String>>(mut self, id: S) -> Self {
         self.kernel.replace(id.into());
 
         self
     }
 
-    pub fn with_size(mut self, size: (u32, u32, u32)) -> Self {
-        assert!(size.0 > 0, "Workgroup size must be greater than zero.");
-        assert!(size.1 > 0, "Workgroup size must be greater than zero.");
-        assert!(size.2 > 0, "Workgroup size must be greater than zero.");
+    /// Sets how many workgroups to dispatch, either as an exact `(x, y, z)` or as any other
+    /// [`DispatchSize`]. Building fails with [`WiscError::InvalidDispatch`] if an exact
+    /// size has a dimension of zero.
+    pub fn with_size<D: Into<DispatchSize>>(mut self, size: D) -> Self {
+        self.size.replace(size.into());
+
+        self
+    }
+
+    /// Dispatches one invocation per element of `handle` on each device, so with a
+    /// partitioned binding every device only launches enough workgroups for its own chunk.
+    /// Shorthand for [`DispatchSize::PerBuffer`].
+    pub fn with_size_per_element(self, handle: VBufferHandle) -> Self {
+        self.with_size(DispatchSize::PerBuffer(handle))
+    }
+
+    /// Dispatches the fewest workgroups of the kernel's reflected `@workgroup_size` that
+    /// give each of `count` elements an invocation. Shorthand for
+    /// [`DispatchSize::ForElements`] with one element per invocation.
+    pub fn with_size_for_elements(self, count: usize) -> Self {
+        self.with_size(DispatchSize::ForElements {
+            count,
+            per_invocation: 1,
+        })
+    }
+
+    /// Binds each device's [`PartitionInfo`] for `handle` at
+    /// [`PARTITION_INFO_BINDING`](partition::PARTITION_INFO_BINDING). Kernels written in WGSL
+    /// that declare the binding get it without this, for the first bound output (or input).
+    pub fn with_partition_info(mut self, handle: VBufferHandle) -> Self {
+        self.partition_info.replace(handle);
+
+        self
+    }
+
+    /// Lets a kernel written for one GPU run unmodified on partitioned buffers: its
+    /// `global_invocation_id` counts up across devices, as it would in a single dispatch,
+    /// rather than starting over on each.
+    ///
+    /// Every device that takes part then holds the whole of each partitioned buffer, so the
+    /// kernel can index them by those IDs, though it still writes back only the elements
+    /// it owns. The offset comes from the [`PartitionInfo`] of the buffer chosen as for
+    /// [`with_partition_info`](Self::with_partition_info), so the task should be sized
+    /// with [`with_size_per_element`](Self::with_size_per_element) over that buffer. The
+    /// shader must be reflectable, and the kernel must take the ID as a parameter of its
+    /// own rather than in a struct.
+    pub fn with_global_ids(mut self) -> Self {
+        self.global_ids = true;
+
+        self
+    }
+
+    /// Sets which devices may compute the task's elements, so results that are meant to
+    /// agree bit for bit can be kept to one driver. See [`Precision`].
+    pub fn with_precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+
+        self
+    }
+
+    /// Sets what happens when the kernel needs more of a device's resources than it has,
+    /// like more workgroup storage than its `max_compute_workgroup_storage_size`. See
+    /// [`OverLimit`].
+    pub fn with_over_limit(mut self, over_limit: OverLimit) -> Self {
+        self.over_limit = over_limit;
+
+        self
+    }
+
+    /// Runs the kernel over overlapping windows of `input`, `window_size` elements long and
+    /// `stride` apart, writing each window's results to an equal share of `output`. The
+    /// windows are dealt out whole to the devices, each of which gets a copy of every input
+    /// element its windows cover, so the kernel sees its first window at local index 0
+    /// and can index windows locally: window `w` reads from `w * stride` and writes its
+    /// share of results from `w * (output length / windows)`.
+    ///
+    /// Building fails if not even one window fits in `input`, or `output` can't be shared
+    /// equally between the windows.
+    pub fn with_sliding_windows(
+        mut self,
+        input_id: u32,
+        input: VBufferHandle,
+        window_size: usize,
+        stride: usize,
+        output_id: u32,
+        output: VBufferHandle,
+    ) -> Self {
+        self.sliding_windows
+            .push((input, window_size, stride, output));
+
+        self.with_input_buffer_partitioned(
+            input_id,
+            input,
+            PartitionMode::Windowed {
+                size: window_size,
+                stride,
+            },
+        )
+        .with_output_buffer_partitioned(output_id, output, PartitionMode::Weighted)
+    }
+
+    /// Has every device checksum its outputs after the kernel runs, and checks them against
+    /// what reaches the host before anything is written back. Catches results corrupted
+    /// in transfer, at the cost of an extra pass over the outputs, which long batch jobs
+    /// may find worth paying.
+    pub fn with_checksums(mut self) -> Self {
+        self.checksums = true;
+
+        self
+    }
+
+    /// Has every device that supports
+    /// [`PIPELINE_STATISTICS_QUERY`](wgpu::Features::PIPELINE_STATISTICS_QUERY) count the
+    /// kernel invocations it runs, for the [report](RunReport::invocations) to show
+    /// whether each device's share of the dispatch covered the elements it should.
+    /// Time-sliced and tiled tasks, which dispatch in many passes, count nothing.
+    pub fn with_pipeline_statistics(mut self) -> Self {
+        self.pipeline_statistics = true;
+
+        self
+    }
+
+    /// Like [`with_input_buffer`](Self::with_input_buffer), but the build fails unless the
+    /// VBuffer holds elements of type `T`.
+    pub fn with_typed_input_buffer<T: Pod>(mut self, id: u32, handle: VBufferHandle) -> Self {
+        self.expected_types.push((handle, TypeId::of::<T>()));
+
+        self.with_input_buffer(id, handle)
+    }
+
+    /// Like [`with_output_buffer`](Self::with_output_buffer), but the build fails unless the
+    /// VBuffer holds elements of type `T`.
+    pub fn with_typed_output_buffer<T: Pod>(mut self, id: u32, handle: VBufferHandle) -> Self {
+        self.expected_types.push((handle, TypeId::of::<T>()));
+
+        self.with_output_buffer(id, handle)
+    }
+
+    pub fn with_input_buffer(self, id: u32, handle: VBufferHandle) -> Self {
+        self.with_input_buffer_partitioned(id, handle, PartitionMode::Unmanaged)
+    }
+
+    /// Binds the whole of a VBuffer to every device, like a lookup table every work item
+    /// may read from. The devices keep their copies, so later tasks broadcasting the same
+    /// buffer bind them again instead of uploading it anew, until a task writes it.
+    pub fn with_broadcast_input(mut self, id: u32, handle: VBufferHandle) -> Self {
+        self.input_buffers.push(InputBinding {
+            id,
+            handle,
+            uniform: false,
+            mode: PartitionMode::Unmanaged,
+            broadcast: true,
+        });
+
+        self
+    }
+
+    /// Binds an input cut into equal chunks, one per device, like the work items of a
+    /// task whose tables are [broadcast](Self::with_broadcast_input).
+    pub fn with_split_input(self, id: u32, handle: VBufferHandle) -> Self {
+        self.with_input_buffer_partitioned(id, handle, PartitionMode::Split)
+    }
+
+    /// Binds an input whose elements are distributed across the devices according to `mode`.
+    pub fn with_input_buffer_partitioned(
+        mut self,
+        id: u32,
+        handle: VBufferHandle,
+        mode: PartitionMode,
+    ) -> Self {
+        self.input_buffers.push(InputBinding {
+            id,
+            handle,
+            uniform: false,
+            mode,
+            broadcast: false,
+        });
+
+        self
+    }
+
+    /// Binds the whole of a VBuffer to every device as a `var<uniform>`.
+    pub fn with_uniform_input(mut self, id: u32, handle: VBufferHandle) -> Self {
+        self.input_buffers.push(InputBinding {
+            id,
+            handle,
+            uniform: true,
+            mode: PartitionMode::Unmanaged,
+            broadcast: false,
+        });
+
+        self
+    }
+
+    /// Binds `value` to every device as a `var<uniform>`, for small parameters (lengths,
+    /// scale factors) that don't warrant a VBuffer. `T` must match the shader's layout for
+    /// the uniform, including any padding it requires.
+    pub fn with_uniform_buffer<T: Pod>(mut self, id: u32, value: T) -> Self {
+        let mut contents = bytemuck::bytes_of(&value).to_vec();
+
+        // Uniform structs are laid out in 16-byte units, so cover a whole number of them.
+        contents.resize(contents.len().next_multiple_of(16).max(16), 0);
+
+        self.uniform_values.push((id, contents));
+
+        self
+    }
+
+    /// Sets the shader's `var<immediate>` data to `value` for every dispatch, without a
+    /// buffer. Immediates are tiny (often 128 bytes at most) and need every device to
+    /// support [`Features::IMMEDIATES`], or the build fails.
+    pub fn with_immediates<T: Pod>(mut self, value: T) -> Self {
+        let mut immediates = bytemuck::bytes_of(&value).to_vec();
+
+        // Immediates are set in whole four-byte words.
+        immediates.resize(immediates.len().next_multiple_of(4), 0);
+
+        self.immediates = immediates;
+
+        self
+    }
+
+    /// Splits the dispatch into sub-dispatches along x that each take about `duration`,
+    /// waiting for each to finish and yielding before the next. Heavy compute on the GPU
+    /// that drives a display then leaves room for the application's rendering, at some cost
+    /// in throughput.
+    ///
+    /// The kernel must declare the [slice uniform](crate::timeslice::SLICE_INFO_WGSL) and
+    /// offset its workgroup index by it. Indirect dispatches can't be sliced.
+    pub fn with_time_slice(mut self, duration: Duration) -> Self {
+        self.time_slice.replace(duration);
+
+        self
+    }
+
+    /// Deals the dispatch out in tiles of `workgroups` workgroups along x, from a queue the
+    /// devices share. Each device takes the next tile as soon as it has finished its last,
+    /// so the faster ones end up doing more of the work, whatever their weightings; a mix
+    /// of discrete, integrated and software adapters is balanced by how they actually
+    /// perform. How many tiles each device ran is in the report's
+    /// [`tiles`](crate::report::RunReport::tiles).
+    ///
+    /// Since any tile may go to any device, every device holds the whole of each bound
+    /// buffer and writes back the elements of the tiles it ran. The task must be sized
+    /// with [`with_size_per_element`](Self::with_size_per_element), and each output must be
+    /// as long as the buffer it is sized by. Like a time-sliced kernel, the kernel must
+    /// declare the [slice uniform](crate::timeslice::SLICE_INFO_WGSL) and offset its
+    /// workgroup index by it.
+    pub fn with_tiles(mut self, workgroups: u32) -> Self {
+        self.tiles.replace(workgroups);
+
+        self
+    }
+
+    pub fn with_output_buffer(self, id: u32, handle: VBufferHandle) -> Self {
+        self.with_output_buffer_partitioned(id, handle, PartitionMode::Unmanaged)
+    }
+
+    /// Binds an output cut into equal chunks, one per device, each writing back its own.
+    pub fn with_split_output(self, id: u32, handle: VBufferHandle) -> Self {
+        self.with_output_buffer_partitioned(id, handle, PartitionMode::Split)
+    }
+
+    /// Binds an output whose elements are distributed across the devices according to
+    /// `mode`. Each device's results are written back to its own elements of the VBuffer.
+    pub fn with_output_buffer_partitioned(
+        mut self,
+        id: u32,
+        handle: VBufferHandle,
+        mode: PartitionMode,
+    ) -> Self {
+        self.output_buffers.push(OutputBinding {
+            id,
+            handle,
+            mode,
+            writeback: Writeback::Overwrite,
+        });
+
+        self
+    }
+
+    /// Binds an output that every device computes in full, combining the devices' copies
+    /// with `merge` instead of keeping whichever is read back last. `T` must be the
+    /// VBuffer's element type.
+    ///
+    /// With more than one device the merged result only lives on the host, so it can't be
+    /// aliased as the input of a later task without another upload.
+    pub fn with_merged_output_buffer<T: Reducible>(
+        mut self,
+        id: u32,
+        handle: VBufferHandle,
+        merge: Merge<T>,
+    ) -> Self {
+        let key = merge.key();
+
+        self.output_buffers.push(OutputBinding {
+            id,
+            handle,
+            mode: PartitionMode::Unmanaged,
+            writeback: Writeback::Merge(TypeId::of::<T>(), merge.into_merger(), key),
+        });
+
+        self
+    }
+
+    /// Binds an output whose results are folded into the VBuffer's current contents with
+    /// `op` on every run, instead of replacing them, so repeated runs accumulate (say, the
+    /// samples of a progressive Monte Carlo render). `T` must be the VBuffer's element
+    /// type, and the VBuffer must be initialized.
+    ///
+    /// The shader sees the accumulated values in the output binding, but what it writes
+    /// there is treated as this run's contribution. Under [`PartitionMode::Unmanaged`],
+    /// where every device computes the whole output, only the first device to read back
+    /// contributes. The accumulated result only lives on the host.
+    pub fn with_accumulated_output_buffer<T: Reducible>(
+        mut self,
+        id: u32,
+        handle: VBufferHandle,
+        mode: PartitionMode,
+        op: ReduceOp,
+    ) -> Self {
+        self.output_buffers.push(OutputBinding {
+            id,
+            handle,
+            mode,
+            writeback: Writeback::Accumulate(TypeId::of::<T>(), reduce_merger::<T>(op), op),
+        });
+
+        self
+    }
+
+    /// Binds an output that the kernel fills with however many `T`s it produces, counting
+    /// them in the `atomic<u32>` bound at `counter_id` (usually by claiming each slot with
+    /// an `atomicAdd`). Every device counts from the start of its own share of the
+    /// VBuffer, and only the elements counted are kept: after the run the VBuffer holds
+    /// each device's results one after another, so
+    /// [`take_vbuffer`](Workgroup::take_vbuffer) returns just those instead of the whole
+    /// allocation. The run's [`RunReport::counted`] says how many came from each device.
+    ///
+    /// Building fails if devices would share elements of the output, as a halo or an
+    /// unmanaged binding on several devices does.
+    pub fn with_counted_output_buffer<T: Pod>(
+        mut self,
+        id: u32,
+        counter_id: u32,
+        handle: VBufferHandle,
+        mode: PartitionMode,
+    ) -> Self {
+        self.expected_types.push((handle, TypeId::of::<T>()));
+        self.counted_outputs
+            .push((handle, counter_id, vbuffer::resize::<T>));
+
+        self.with_output_buffer_partitioned(id, handle, mode)
+    }
 
-        self.size.replace(size);
+    /// Binds an output that the kernel appends any number of `T`s to, in any order, by
+    /// claiming slots with an `atomicAdd` on the `atomic<u32>` bound at `counter_id`. Each
+    /// device appends to a buffer of its own with room for `capacity` elements, and
+    /// only the part it filled is read back. The VBuffer is replaced by every device's
+    /// results one after another, whatever its length was, and the run's
+    /// [`RunReport::appended`] says how many came from each device.
+    ///
+    /// The kernel should check each slot against `arrayLength` before writing it; whatever
+    /// doesn't fit is counted as dropped.
+    pub fn with_append_output<T: Pod>(
+        mut self,
+        id: u32,
+        counter_id: u32,
+        handle: VBufferHandle,
+        capacity: usize,
+    ) -> Self {
+        self.expected_types.push((handle, TypeId::of::<T>()));
+
+        self.append_outputs.push(AppendBinding {
+            id,
+            counter_id,
+            handle,
+            capacity,
+            replace: vbuffer::replace_contents::<T>,
+        });
 
         self
     }
 
-    pub fn with_input_buffer(mut self, id: u32, handle: VBufferHandle) -> Self {
-        self.input_buffers.push((id, handle));
+    /// Binds `id` to each chunk of a [`StreamTask`]'s source, interpreted as elements of `T`.
+    pub fn with_stream_input<T: Pod>(mut self, id: u32) -> Self {
+        self.stream_input.replace((id, std::mem::size_of::<T>()));
 
         self
     }
 
-    pub fn with_output_buffer(mut self, id: u32, handle: VBufferHandle) -> Self {
-        self.output_buffers.push((id, handle));
+    /// Binds `id` to a per-chunk output holding one `T` for each streamed input element.
+    pub fn with_stream_output<T: Pod>(mut self, id: u32) -> Self {
+        self.stream_output.replace((id, std::mem::size_of::<T>()));
 
         self
     }
 
-    pub fn with_override<N: Into<f64>>(mut self, id: u32, value: N) -> Self {
-        self.overrides.push((id, value.into()));
+    /// Sets a WGSL `override` constant when the pipeline is created, so values like a
+    /// workgroup or tile size needn't be templated into the shader source. `key` is the
+    /// constant's `@id` if it declares one (like `0`), or else its name (like `"TILE"`).
+    pub fn with_override<K: ToString, N: Into<f64>>(mut self, key: K, value: N) -> Self {
+        self.overrides.push((key.to_string(), value.into()));
 
         self
     }
 }
 
-fn vbuffer_bytes(vbuffer: &VBuffer) -> &[u8] {
+/// Runs `f` for every device on its own scoped thread and collects the results in
+/// device order. In the browser, which has no threads to spawn, the devices take turns.
+pub(crate) fn per_device_parallel<T, F>(vdevices: &[VDevice], f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(usize, &VDevice) -> T + Sync,
+{
+    // Nothing to overlap with a single device, so don't pay for a thread.
+    if let [vd] = vdevices {
+        return vec![f(0, vd)];
+    }
+
+    if cfg!(target_arch = "wasm32") {
+        return vdevices
+            .iter()
+            .enumerate()
+            .map(|(vdi, vd)| f(vdi, vd))
+            .collect();
+    }
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = vdevices
+            .iter()
+            .enumerate()
+            .map(|(vdi, vd)| {
+                let f = &f;
+                scope.spawn(move || f(vdi, vd))
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("VDevice worker thread panicked"))
+            .collect()
+    })
+}
+
+pub(crate) fn storage_layout_entry(binding: u32, read_only: bool) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+pub(crate) fn uniform_layout_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+pub(crate) fn override_constants(overrides: &[(String, f64)]) -> Vec<(&str, f64)> {
+    overrides
+        .iter()
+        .map(|(key, val)| (key.as_str(), *val))
+        .collect()
+}
+
+/// Checks that every override key names a constant the shader declares, since wgpu
+/// would otherwise ignore it.
+pub(crate) fn check_overrides(
+    module: &naga::Module,
+    overrides: &[(String, f64)],
+) -> Result<(), WiscError> {
+    let candidates = reflect::override_keys(module);
+
+    for (key, _) in overrides {
+        if !candidates.contains(key) {
+            return Err(WiscError::UnknownOverride {
+                key: key.clone(),
+                candidates,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that every device can take `immediates`.
+pub(crate) fn check_immediates(workgroup: &Workgroup, immediates: &[u8]) -> Result<(), WiscError> {
+    if immediates.is_empty() {
+        return Ok(());
+    }
+
+    for vd in &workgroup.vdevices {
+        if !vd.features.contains(wgpu::Features::IMMEDIATES) {
+            return Err(WiscError::MissingFeature(Features::IMMEDIATES));
+        }
+
+        if immediates.len() > vd.device.limits().max_immediate_size as usize {
+            return Err(WiscError::OutOfBounds);
+        }
+    }
+
+    Ok(())
+}
+
+/// Fails unless a task dealt out in tiles of `workgroups` can be: sized per element of a
+/// buffer every output is as long as, and not combined with anything else that places
+/// the kernel's workgroups, or with outputs whose length depends on which device ran what.
+fn check_tiling(
+    workgroup: &Workgroup,
+    workgroups: u32,
+    size: DispatchSize,
+    output_buffers: &[OutputBinding],
+    placed_otherwise: bool,
+    counts_outputs: bool,
+) -> Result<(), WiscError> {
+    if workgroups == 0 {
+        return Err(WiscError::InvalidDispatch(
+            "a tile needs at least one workgroup",
+        ));
+    }
+
+    if placed_otherwise {
+        return Err(WiscError::InvalidDispatch(
+            "a tiled dispatch can't also be time-sliced or shifted to global IDs",
+        ));
+    }
+
+    if counts_outputs {
+        return Err(WiscError::InvalidBinding(
+            "a tiled task can't count or append to its outputs",
+        ));
+    }
+
+    let DispatchSize::PerBuffer(handle) = size else {
+        return Err(WiscError::InvalidDispatch(
+            "a tiled task must be sized per element of a buffer",
+        ));
+    };
+
+    let length = |handle| {
+        workgroup
+            .vbuffers
+            .get(handle)
+            .map(|vbuffer: &VBuffer| vbuffer.length)
+            .ok_or(WiscError::UnknownVBuffer)
+    };
+    let length_of_size = length(handle)?;
+
+    for out in output_buffers {
+        if let Writeback::Merge(..) = out.writeback {
+            return Err(WiscError::InvalidBinding(
+                "a tiled output can't be merged, since no device holds all of it",
+            ));
+        }
+
+        if length(out.handle)? != length_of_size {
+            return Err(WiscError::ShapeMismatch(
+                "every output of a tiled task must be as long as the buffer it is sized by",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn create_pipeline(
+    vd: &VDevice,
+    cache: &BindingCache,
+    module: &wgpu::ShaderModule,
+    kernel: &str,
+    layout_entries: &[wgpu::BindGroupLayoutEntry],
+    constants: &[(&str, f64)],
+    immediates: &[u8],
+    pipeline_cache: Option<&wgpu::PipelineCache>,
+    template: Option<&PipelineStore>,
+) -> (wgpu::BindGroupLayout, wgpu::ComputePipeline) {
+    let _span = trace::span!(
+        "create_pipeline",
+        device = vd.label.as_str(),
+        kernel = kernel
+    );
+    let bind_group_layout = cache.layout(vd, layout_entries);
+    let immediate_size = immediates.len() as u32;
+
+    let create = || {
+        let pipeline_layout = vd
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[&bind_group_layout],
+                immediate_size,
+            });
+
+        vd.device
+            .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: None,
+                layout: Some(&pipeline_layout),
+                module,
+                entry_point: Some(kernel),
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants,
+                    zero_initialize_workgroup_memory: true,
+                },
+                cache: pipeline_cache,
+            })
+    };
+
+    // wgpu's GL backend shares one program between the live pipelines of a module and
+    // entry point, whatever their override constants, so a cached pipeline would hand its
+    // constants to the next.
+    let pipeline = if vd.info.backend == wgpu::Backend::Gl && !constants.is_empty() {
+        create()
+    } else {
+        // A template's pipelines outlive the cache's, which only keeps the latest.
+        template.unwrap_or(&cache.pipelines).pipeline(
+            module,
+            kernel,
+            layout_entries,
+            constants,
+            immediate_size,
+            create,
+        )
+    };
+
+    (bind_group_layout, pipeline)
+}
+
+/// Fails if a buffer of `size` bytes can't be created on `vd`, or bound at `binding` as a
+/// uniform or storage buffer.
+pub(crate) fn check_binding_size(
+    vd: &VDevice,
+    vdi: usize,
+    binding: u32,
+    size: u64,
+    uniform: bool,
+) -> Result<(), WiscError> {
+    let limits = vd.device.limits();
+    // Buffers are created in whole words.
+    let size = size.next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT);
+
+    let (limit, max) = if uniform {
+        (
+            "max_uniform_buffer_binding_size",
+            limits.max_uniform_buffer_binding_size as u64,
+        )
+    } else {
+        (
+            "max_storage_buffer_binding_size",
+            limits.max_storage_buffer_binding_size as u64,
+        )
+    };
+
+    for (limit, max) in [(limit, max), ("max_buffer_size", limits.max_buffer_size)] {
+        if size > max {
+            return Err(WiscError::BindingTooLarge {
+                binding,
+                device: vdi,
+                size,
+                limit,
+                max,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolves `size` into each device's dispatch, given the elements each device holds of
+/// the task's bound buffers.
+pub(crate) fn resolve_dispatch(
+    workgroup: &Workgroup,
+    size: DispatchSize,
+    reflection: Option<&naga::Module>,
+    kernel: &str,
+    held_ranges: &HashMap<VBufferHandle, Vec<Range<usize>>>,
+) -> Result<Vec<Dispatch>, WiscError> {
+    let num_devices = workgroup.vdevices.len();
+    let workgroup_size = || {
+        reflection
+            .and_then(|module| reflect::workgroup_size(module, kernel))
+            .ok_or(WiscError::InvalidDispatch(
+                "the kernel's workgroup size can't be reflected",
+            ))
+    };
+    let too_many = WiscError::InvalidDispatch("too many workgroups for one dispatch");
+
+    match size {
+        DispatchSize::Exact(x, y, z) => Ok((0..num_devices)
+            .map(|_| Dispatch::Direct(x, y, z))
+            .collect()),
+        DispatchSize::ForElements {
+            count,
+            per_invocation,
+        } => {
+            let workgroups = dispatch::workgroups_for(count, per_invocation, workgroup_size()?)
+                .ok_or(too_many.clone())?;
+
+            workgroup
+                .vdevices
+                .iter()
+                .map(|vd| {
+                    let limit = vd.device.limits().max_compute_workgroups_per_dimension;
+
+                    dispatch::spread(workgroups, limit)
+                        .map(|(x, y, z)| Dispatch::Direct(x, y, z))
+                        .ok_or(too_many.clone())
+                })
+                .collect()
+        }
+        DispatchSize::PerBuffer(handle) => {
+            let workgroup_size = workgroup_size()?;
+            let ranges = match held_ranges.get(&handle) {
+                Some(ranges) => ranges.clone(),
+                None => {
+                    let vbuffer = workgroup
+                        .vbuffers
+                        .get(handle)
+                        .ok_or(WiscError::UnknownVBuffer)?;
+
+                    vec![0..vbuffer.length; num_devices]
+                }
+            };
+
+            ranges
+                .iter()
+                .map(|range| {
+                    dispatch::workgroups_for(range.len(), 1, workgroup_size)
+                        .map(|x| Dispatch::Direct(x, 1, 1))
+                        .ok_or(too_many.clone())
+                })
+                .collect()
+        }
+        DispatchSize::FromIndirect(handle) => {
+            const ARGS_LEN: usize = 3 * std::mem::size_of::<u32>();
+
+            let vbuffer = workgroup
+                .vbuffers
+                .get(handle)
+                .ok_or(WiscError::UnknownVBuffer)?;
+
+            if vbuffer.length * vbuffer.stride < ARGS_LEN {
+                return Err(WiscError::InvalidDispatch(
+                    "indirect arguments need three u32 workgroup counts",
+                ));
+            }
+
+            // Counts written by an earlier task are read where they already are.
+            let resident = vbuffer.residency.resident().filter(|resident| {
+                resident
+                    .ranges
+                    .iter()
+                    .all(|range| range.start == 0 && range.len() * vbuffer.stride >= ARGS_LEN)
+                    && resident
+                        .buffers
+                        .iter()
+                        .all(|buffer| buffer.usage().contains(wgpu::BufferUsages::INDIRECT))
+            });
+
+            if let Some(resident) = resident {
+                return Ok(resident
+                    .buffers
+                    .iter()
+                    .map(|buffer| Dispatch::Indirect(buffer.clone()))
+                    .collect());
+            }
+
+            let pending = workgroup.pending_contents(vbuffer)?;
+
+            if vbuffer.assume_init.is_some() && pending.is_none() {
+                return Err(WiscError::Uninitialized);
+            }
+
+            let args = &pending.as_deref().unwrap_or(vbuffer_bytes(vbuffer))[..ARGS_LEN];
+
+            Ok(workgroup
+                .vdevices
+                .iter()
+                .map(|vd| {
+                    Dispatch::Indirect(vd.device.create_buffer_init(
+                        &wgpu::util::BufferInitDescriptor {
+                            label: Some(&format!("WISC Indirect Args (VDevice {})", vd.label)),
+                            contents: args,
+                            usage: wgpu::BufferUsages::INDIRECT,
+                        },
+                    ))
+                })
+                .collect())
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn encode_commands(
+    vd: &VDevice,
+    cache: &BindingCache,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    pipeline: &wgpu::ComputePipeline,
+    layout_entries: &[wgpu::BindGroupLayoutEntry],
+    buffers: &[wgpu::Buffer],
+    dispatch: Option<&Dispatch>,
+    timestamps: Option<&PassTimestamps>,
+    statistics: Option<&PassStatistics>,
+    immediates: &[u8],
+) -> wgpu::CommandBuffer {
+    let mut encoder = vd
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+    if let Some(dispatch) = dispatch {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: None,
+            timestamp_writes: timestamps.map(PassTimestamps::writes),
+        });
+
+        compute_pass.set_pipeline(pipeline);
+
+        if !immediates.is_empty() {
+            compute_pass.set_immediates(0, immediates);
+        }
+
+        if let Some(statistics) = statistics {
+            statistics.begin(&mut compute_pass);
+        }
+
+        let limit = vd.device.limits().max_compute_workgroups_per_dimension;
+
+        match dispatch {
+            // Too many workgroups for one dispatch; building made sure the slice uniform is
+            // bound, last, to tell each part its offset.
+            Dispatch::Direct(x, y, z) if *x > limit => {
+                let mut buffers = buffers.to_vec();
+
+                for offset in (0..*x).step_by(limit as usize) {
+                    *buffers.last_mut().unwrap() = cache.uniform(
+                        vd,
+                        &format!("WISC Slice Info (VDevice {})", vd.label),
+                        &timeslice::slice_uniform(offset),
+                    );
+
+                    let bind_group =
+                        cache.bind_group(vd, bind_group_layout, layout_entries, &buffers);
+
+                    compute_pass.set_bind_group(0, &bind_group, &[]);
+                    compute_pass.dispatch_workgroups(limit.min(x - offset), *y, *z);
+                }
+            }
+            _ => {
+                let bind_group = cache.bind_group(vd, bind_group_layout, layout_entries, buffers);
+
+                compute_pass.set_bind_group(0, &bind_group, &[]);
+                dispatch.record(&mut compute_pass);
+            }
+        }
+
+        if statistics.is_some() {
+            compute_pass.end_pipeline_statistics_query();
+        }
+        drop(compute_pass);
+
+        if let Some(timestamps) = timestamps {
+            timestamps.encode_readback(&mut encoder);
+        }
+        if let Some(statistics) = statistics {
+            statistics.encode_readback(&mut encoder);
+        }
+    }
+
+    encoder.finish()
+}
+
+/// Records the copies of `output_buffers` into `staging_buffers` for reading back, or
+/// nothing if every output is mapped directly.
+pub(crate) fn encode_readback(
+    vd: &VDevice,
+    output_buffers: &[wgpu::Buffer],
+    staging_buffers: &[wgpu::Buffer],
+) -> Option<wgpu::CommandBuffer> {
+    let copies: Vec<(&wgpu::Buffer, &wgpu::Buffer)> = output_buffers
+        .iter()
+        .zip(staging_buffers)
+        .filter(|(output_buffer, staging_buffer)| output_buffer != staging_buffer)
+        .collect();
+
+    if copies.is_empty() {
+        return None;
+    }
+
+    let mut encoder = vd
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("WISC Readback"),
+        });
+
+    for (output_buffer, staging_buffer) in copies {
+        encoder.copy_buffer_to_buffer(output_buffer, 0, staging_buffer, 0, output_buffer.size());
+    }
+
+    Some(encoder.finish())
+}
+
+/// The device copies of an input that can stand in for uploading the elements each device
+/// holds of it in `partition`: they must hold the same elements. Otherwise the host copy,
+/// which runs write back, is just as current.
+fn device_copies<'v>(
+    vbuffer: &'v VBuffer,
+    partition: &[Range<usize>],
+    broadcast: bool,
+) -> Option<&'v Resident> {
+    match &vbuffer.residency {
+        Residency::Aliased(resident) if resident.ranges == partition => Some(resident),
+        Residency::Uploaded(resident) if broadcast && resident.ranges == partition => {
+            Some(resident)
+        }
+        _ => None,
+    }
+}
+
+/// The bytes of the elements in `range`.
+pub(crate) fn partition_bytes<'v>(vbuffer: &'v VBuffer, range: &Range<usize>) -> &'v [u8] {
+    &vbuffer_bytes(vbuffer)[range.start * vbuffer.stride..range.end * vbuffer.stride]
+}
+
+pub(crate) fn vbuffer_bytes(vbuffer: &VBuffer) -> &[u8] {
     let byte_length = vbuffer.length * vbuffer.stride;
 
     unsafe {
@@ -365,12 +3688,54 @@ fn vbuffer_bytes(vbuffer: &VBuffer) -> &[u8] {
     }
 }
 
-fn vbuffer_bytes_mut(vbuffer: &mut VBuffer) -> &mut [u8] {
+/// Folds `bytes` into the host copy of `vbuffer` starting at `byte_offset` with `merger`.
+/// The range must already have been written.
+fn vbuffer_merge(vbuffer: &mut VBuffer, byte_offset: usize, bytes: &[u8], merger: &Merger) {
+    let byte_length = vbuffer.length * vbuffer.stride;
+    assert!(byte_offset + bytes.len() <= byte_length);
+
+    unsafe {
+        let vec = &mut *(vbuffer.inner.as_mut() as *mut dyn Any as *mut Vec<u8>);
+        let data_ptr = vec.as_mut_ptr().add(byte_offset);
+        merger(std::slice::from_raw_parts_mut(data_ptr, bytes.len()), bytes);
+    }
+}
+
+/// Moves the elements in each of `ranges`, which must be in order and not overlap, to
+/// the front of the host copy of `vbuffer` one after another, and drops the rest.
+fn vbuffer_compact(vbuffer: &mut VBuffer, ranges: &[Range<usize>], resize: vbuffer::Resize) {
+    let stride = vbuffer.stride;
+    let mut length = 0;
+
+    for range in ranges {
+        assert!(length <= range.start && range.end <= vbuffer.length);
+
+        unsafe {
+            let vec = &mut *(vbuffer.inner.as_mut() as *mut dyn Any as *mut Vec<u8>);
+            let data_ptr = vec.as_mut_ptr();
+            std::ptr::copy(
+                data_ptr.add(range.start * stride),
+                data_ptr.add(length * stride),
+                range.len() * stride,
+            );
+        }
+
+        length += range.len();
+    }
+
+    resize(vbuffer.inner.as_mut(), length);
+    vbuffer.length = length;
+}
+
+/// Copies `bytes` into the host copy of `vbuffer` starting at `byte_offset`, without ever
+/// forming a reference to memory that may not be initialized yet.
+pub(crate) fn vbuffer_write(vbuffer: &mut VBuffer, byte_offset: usize, bytes: &[u8]) {
     let byte_length = vbuffer.length * vbuffer.stride;
+    assert!(byte_offset + bytes.len() <= byte_length);
 
     unsafe {
         let vec = &mut *(vbuffer.inner.as_mut() as *mut dyn Any as *mut Vec<u8>);
-        let data_ptr = vec.as_mut_ptr();
-        std::slice::from_raw_parts_mut(data_ptr, byte_length)
+        let data_ptr = vec.as_mut_ptr().add(byte_offset);
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), data_ptr, bytes.len());
     }
 }
@@ -0,0 +1,80 @@
+//! Task templates, which hold everything about a task but its buffers and size, so that
+//! parameter sweeps running the same kernel thousands of times only compile it once.
+
+use bytemuck::Pod;
+
+use crate::cache::PipelineStore;
+use crate::task::TaskBuilder;
+use crate::workgroup::Workgroup;
+
+/// A registered shader's kernel, with its override constants and immediates, and the
+/// pipelines created for it. Each [`instantiate`](Self::instantiate) starts a task from
+/// the template, to which only the buffers and dispatch size remain to be bound.
+///
+/// The first instance built with a given binding layout on each device creates its
+/// pipeline; every later instance with the same layout reuses it, however many other
+/// tasks have been built in between.
+pub struct TaskTemplate {
+    shader: String,
+    kernel: Option<String>,
+    overrides: Vec<(String, f64)>,
+    immediates: Vec<u8>,
+    pipelines: PipelineStore,
+}
+
+impl TaskTemplate {
+    /// A template running the shader registered as `shader` with
+    /// [`Workgroup::register_shader`].
+    pub fn new<S: Into<String>>(shader: S) -> Self {
+        Self {
+            shader: shader.into(),
+            kernel: None,
+            overrides: vec![],
+            immediates: vec![],
+            pipelines: PipelineStore::unbounded(),
+        }
+    }
+
+    /// Names the entry point to run, as [`TaskBuilder::with_kernel`] does.
+    pub fn with_kernel<S: Into<String>>(mut self, id: S) -> Self {
+        self.kernel.replace(id.into());
+
+        self
+    }
+
+    /// Sets a WGSL `override` constant, as [`TaskBuilder::with_override`] does.
+    pub fn with_override<K: ToString, N: Into<f64>>(mut self, key: K, value: N) -> Self {
+        self.overrides.push((key.to_string(), value.into()));
+
+        self
+    }
+
+    /// Sets the shader's immediates, as [`TaskBuilder::with_immediates`] does.
+    pub fn with_immediates<T: Pod>(mut self, value: T) -> Self {
+        let mut immediates = bytemuck::bytes_of(&value).to_vec();
+        immediates.resize(immediates.len().next_multiple_of(4), 0);
+
+        self.immediates = immediates;
+
+        self
+    }
+
+    /// Starts a task from the template on `workgroup`. Bind its buffers and size, then
+    /// build it as usual.
+    pub fn instantiate<'b>(&'b self, workgroup: &'b mut Workgroup) -> TaskBuilder<'b> {
+        let mut builder =
+            TaskBuilder::from_workgroup(workgroup).with_registered_shader(self.shader.as_str());
+
+        builder.kernel = self.kernel.clone();
+        builder.overrides = self.overrides.clone();
+        builder.immediates = self.immediates.clone();
+        builder.template = Some(&self.pipelines);
+
+        builder
+    }
+
+    /// How many pipelines the template holds, across every device and binding layout.
+    pub fn pipeline_count(&self) -> usize {
+        self.pipelines.len()
+    }
+}
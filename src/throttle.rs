@@ -0,0 +1,16 @@
+use web_time::Instant;
+
+/// Leaves the devices idle after the work since `started` long enough that, over time,
+/// wisc keeps them busy for no more than `fraction` of it. Does nothing without a throttle.
+pub(crate) fn idle_after(fraction: Option<f32>, started: Instant) {
+    let Some(fraction) = fraction.filter(|fraction| *fraction < 1.0) else {
+        return;
+    };
+
+    let busy = started.elapsed().as_secs_f64();
+    let fraction = fraction as f64;
+
+    std::thread::sleep(std::time::Duration::from_secs_f64(
+        busy * (1.0 - fraction) / fraction,
+    ));
+}
@@ -0,0 +1,245 @@
+use std::ops::Range;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use bytemuck::{Pod, Zeroable};
+use web_time::Instant;
+
+use crate::cache::BindingCache;
+use crate::error::WiscError;
+use crate::task::per_device_parallel;
+use crate::throttle;
+use crate::vdevice::VDevice;
+
+/// The binding in group 0 at which tasks supply each sub-dispatch's [`SliceInfo`] as a
+/// uniform. Kernels of a task built
+/// [`with_time_slice`](crate::task::TaskBuilder::with_time_slice) must declare it, as must
+/// kernels dispatching more workgroups along x than a device allows at once, which are
+/// split into several dispatches. Other kernels that declare it see an offset of zero.
+pub const SLICE_INFO_BINDING: u32 = 998;
+
+/// A WGSL declaration of the slice uniform, to paste (or `//#include`) into kernels.
+pub const SLICE_INFO_WGSL: &str = "\
+struct WiscSlice {
+    workgroup_offset: u32,
+}
+
+@group(0) @binding(998) var<uniform> wisc_slice: WiscSlice;
+";
+
+/// Where a sub-dispatch of a time-sliced task starts, so kernels can recover the workgroup
+/// they would have been in a single dispatch (`wisc_slice.workgroup_offset + workgroup_id.x`).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod, Zeroable)]
+pub struct SliceInfo {
+    /// The x workgroup index of the sub-dispatch's first workgroup.
+    pub workgroup_offset: u32,
+}
+
+/// The contents of the slice uniform for a dispatch starting at workgroup `offset`, padded
+/// for a uniform binding.
+pub(crate) fn slice_uniform(offset: u32) -> Vec<u8> {
+    let info = SliceInfo {
+        workgroup_offset: offset,
+    };
+
+    let mut contents = bytemuck::bytes_of(&info).to_vec();
+    contents.resize(16, 0);
+
+    contents
+}
+
+/// Everything needed to record a device's sub-dispatches while the task runs.
+pub(crate) struct SlicedDispatch {
+    pub(crate) layout: wgpu::BindGroupLayout,
+    pub(crate) pipeline: wgpu::ComputePipeline,
+    pub(crate) entries: Vec<wgpu::BindGroupLayoutEntry>,
+    // The bound buffers, less the slice uniform, which comes last.
+    pub(crate) buffers: Vec<wgpu::Buffer>,
+    pub(crate) workgroups: (u32, u32, u32),
+    pub(crate) immediates: Vec<u8>,
+}
+
+impl SlicedDispatch {
+    /// Submits workgroups `offset..offset + count` along x.
+    pub(crate) fn submit(&self, vd: &VDevice, cache: &BindingCache, offset: u32, count: u32) {
+        let mut buffers = self.buffers.clone();
+        buffers.push(cache.uniform(
+            vd,
+            &format!("WISC Slice Info (VDevice {})", vd.label),
+            &slice_uniform(offset),
+        ));
+
+        let bind_group = cache.bind_group(vd, &self.layout, &self.entries, &buffers);
+
+        let mut encoder = vd
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: None,
+                timestamp_writes: None,
+            });
+
+            compute_pass.set_pipeline(&self.pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[]);
+
+            if !self.immediates.is_empty() {
+                compute_pass.set_immediates(0, &self.immediates);
+            }
+
+            let (_, y, z) = self.workgroups;
+            compute_pass.dispatch_workgroups(count, y, z);
+        }
+
+        vd.queue.submit([encoder.finish()]);
+    }
+}
+
+/// Runs every device's dispatch as a series of short sub-dispatches along x, waiting for
+/// each to finish and yielding before the next, so other work on the same GPU (like an
+/// application's rendering) gets a turn. Each device's slices are resized as they go to
+/// take about `duration`, and followed by an idle gap under a `throttle`. Returns how many
+/// sub-dispatches were submitted.
+pub(crate) fn run_sliced(
+    vdevices: &[VDevice],
+    caches: &[BindingCache],
+    dispatches: &[Option<SlicedDispatch>],
+    duration: Duration,
+    throttle: Option<f32>,
+) -> Result<usize, WiscError> {
+    let mut next = vec![0u32; vdevices.len()];
+    // Start small, since nothing is known yet about how long a workgroup takes.
+    let mut per_slice = vec![1u32; vdevices.len()];
+    let mut submitted = 0;
+
+    loop {
+        let started = Instant::now();
+        let mut running = vec![];
+
+        for (vdi, dispatch) in dispatches.iter().enumerate() {
+            let Some(dispatch) = dispatch else {
+                continue;
+            };
+
+            let remaining = dispatch.workgroups.0 - next[vdi];
+            if remaining == 0 {
+                continue;
+            }
+
+            let limit = vdevices[vdi]
+                .device
+                .limits()
+                .max_compute_workgroups_per_dimension;
+            let count = per_slice[vdi].min(remaining).min(limit);
+            dispatch.submit(&vdevices[vdi], &caches[vdi], next[vdi], count);
+
+            next[vdi] += count;
+            running.push((vdi, count));
+        }
+
+        if running.is_empty() {
+            return Ok(submitted);
+        }
+
+        submitted += running.len();
+
+        // Each device's slice is timed to when it finished, not to when the devices
+        // waited on before it did.
+        let mut finished = per_device_parallel(vdevices, |vdi, vd| {
+            running
+                .iter()
+                .any(|(running, _)| *running == vdi)
+                .then(|| vd.wait().map(|()| started.elapsed()))
+        });
+
+        for (vdi, count) in running {
+            let elapsed = finished[vdi].take().unwrap()?;
+
+            // Aim the next slice at the target, but grow it at most fourfold at a time so
+            // one fast measurement can't make it overshoot badly.
+            let elapsed = elapsed.as_secs_f64().max(1e-6);
+            let scaled = count as f64 * duration.as_secs_f64() / elapsed;
+            per_slice[vdi] = (scaled as u32).clamp(1, count.saturating_mul(4));
+        }
+
+        throttle::idle_after(throttle, started);
+        std::thread::yield_now();
+    }
+}
+
+/// A dispatch dealt out in tiles of workgroups along x from a queue the devices share,
+/// each device taking the next tile as soon as it has finished its last.
+pub(crate) struct Tiling {
+    pub(crate) workgroups_per_tile: u32,
+    pub(crate) elements_per_workgroup: usize,
+    // The length of the buffer the dispatch is sized by, which the last tile may overrun.
+    pub(crate) length: usize,
+    pub(crate) dispatches: Vec<Option<SlicedDispatch>>,
+    // The elements of the tiles each device ran last, in device order.
+    pub(crate) done: Vec<Vec<Range<usize>>>,
+}
+
+impl Tiling {
+    /// Runs every tile on whichever device is free to take it, and returns how many tiles
+    /// each device ran.
+    pub(crate) fn run(
+        &mut self,
+        vdevices: &[VDevice],
+        caches: &[BindingCache],
+        throttle: Option<f32>,
+    ) -> Result<Vec<usize>, WiscError> {
+        let total = self
+            .dispatches
+            .iter()
+            .flatten()
+            .map(|dispatch| dispatch.workgroups.0)
+            .next()
+            .unwrap_or(0);
+        let next = AtomicU32::new(0);
+
+        let ran = per_device_parallel(vdevices, |vdi, vd| {
+            let Some(dispatch) = &self.dispatches[vdi] else {
+                return Ok(vec![]);
+            };
+
+            let limit = vd.device.limits().max_compute_workgroups_per_dimension;
+            let per_tile = self.workgroups_per_tile.min(limit);
+            let mut ran = vec![];
+
+            loop {
+                let started = Instant::now();
+
+                let offset = next.fetch_add(per_tile, Ordering::Relaxed);
+                if offset >= total {
+                    return Ok(ran);
+                }
+
+                let count = per_tile.min(total - offset);
+                dispatch.submit(vd, &caches[vdi], offset, count);
+                vd.wait()?;
+
+                ran.push(offset..offset + count);
+                throttle::idle_after(throttle, started);
+            }
+        });
+
+        self.done = ran
+            .into_iter()
+            .map(|ran| {
+                Ok(ran?
+                    .into_iter()
+                    .map(|workgroups: Range<u32>| {
+                        let start = workgroups.start as usize * self.elements_per_workgroup;
+                        let end = workgroups.end as usize * self.elements_per_workgroup;
+
+                        start.min(self.length)..end.min(self.length)
+                    })
+                    .collect())
+            })
+            .collect::<Result<_, WiscError>>()?;
+
+        Ok(self.done.iter().map(Vec::len).collect())
+    }
+}
@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use crate::vdevice::VDevice;
+
+/// The timestamps one device writes at the start and end of a task's compute pass, read
+/// back through a staging buffer with the task's results.
+pub(crate) struct PassTimestamps {
+    query_set: wgpu::QuerySet,
+    resolved: wgpu::Buffer,
+    staging: wgpu::Buffer,
+}
+
+impl PassTimestamps {
+    /// Timestamps for a pass on `vd`, if it supports timestamp queries.
+    pub(crate) fn new(vd: &VDevice) -> Option<Self> {
+        if !vd.features.contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+
+        let query_set = vd.device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some(&format!("WISC Timestamps (VDevice {})", vd.label)),
+            ty: wgpu::QueryType::Timestamp,
+            count: 2,
+        });
+        let resolved = vd.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("WISC Timestamps Resolved (VDevice {})", vd.label)),
+            size: 16,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging = vd.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("WISC Timestamps Staging (VDevice {})", vd.label)),
+            size: 16,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Some(Self {
+            query_set,
+            resolved,
+            staging,
+        })
+    }
+
+    /// Where the pass writes its timestamps.
+    pub(crate) fn writes(&self) -> wgpu::ComputePassTimestampWrites<'_> {
+        wgpu::ComputePassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: Some(0),
+            end_of_pass_write_index: Some(1),
+        }
+    }
+
+    /// Records copying the timestamps to the staging buffer, once the pass has ended.
+    pub(crate) fn encode_readback(&self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.resolve_query_set(&self.query_set, 0..2, &self.resolved, 0);
+        encoder.copy_buffer_to_buffer(&self.resolved, 0, &self.staging, 0, 16);
+    }
+
+    /// The buffer to map once the readback has been submitted.
+    pub(crate) fn staging(&self) -> &wgpu::Buffer {
+        &self.staging
+    }
+
+    /// How long the pass took by `vd`'s clock, once the staging buffer is mapped. Unmaps it.
+    pub(crate) fn read(&self, vd: &VDevice) -> Duration {
+        let [start, end]: [u64; 2] =
+            bytemuck::pod_read_unaligned(&self.staging.slice(..).get_mapped_range());
+        self.staging.unmap();
+
+        let ticks = end.saturating_sub(start);
+
+        Duration::from_nanos((ticks as f64 * vd.queue.get_timestamp_period() as f64) as u64)
+    }
+}
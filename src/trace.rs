@@ -0,0 +1,38 @@
+//! Spans around the work wisc does on the devices, for applications that follow it with
+//! `tracing`. Without the `tracing` feature, they compile to nothing.
+
+/// Enters a span that lasts until what it returns is dropped, with the given fields.
+#[cfg(feature = "tracing")]
+macro_rules! span {
+    ($name:literal $(, $field:ident = $value:expr)* $(,)?) => {
+        tracing::info_span!($name $(, $field = $value)*).entered()
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! span {
+    ($name:literal $(, $field:ident = $value:expr)* $(,)?) => {
+        $crate::trace::Disabled
+    };
+}
+
+/// Has `future` run in a span with the given fields whenever it is polled.
+#[cfg(feature = "tracing")]
+macro_rules! instrument {
+    ($future:expr, $name:literal $(, $field:ident = $value:expr)* $(,)?) => {
+        tracing::Instrument::instrument($future, tracing::info_span!($name $(, $field = $value)*))
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! instrument {
+    ($future:expr, $name:literal $(, $field:ident = $value:expr)* $(,)?) => {
+        $future
+    };
+}
+
+pub(crate) use {instrument, span};
+
+/// What [`span!`] enters without the `tracing` feature.
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct Disabled;
@@ -1,9 +1,134 @@
 use std::any::{Any, TypeId};
+use std::ops::Range;
+
+use bytemuck::Pod;
+
+use crate::quant::Quantization;
 
 pub(crate) struct VBuffer {
     pub(crate) inner: Box<dyn Any>,
     pub(crate) typeid: TypeId,
+    // Recorded in snapshots, which can't keep a `TypeId`.
+    pub(crate) type_name: &'static str,
 
     pub(crate) stride: usize,
     pub(crate) length: usize,
+    // The number of consecutive elements that must never be split between devices.
+    pub(crate) group_size: usize,
+
+    pub(crate) residency: Residency,
+    // Set while the device copies hold results of a run that left them there rather than
+    // reading them back, so the host copy is behind. Reading it reads them back first.
+    pub(crate) pending_readback: bool,
+
+    // Set while the host copy is allocated but not yet written. Called with the length once
+    // a run has filled every element.
+    pub(crate) assume_init: Option<fn(&mut dyn Any, usize)>,
+
+    // Set for int8 quantized buffers, along with how many values they hold, since the
+    // last word may be padding.
+    pub(crate) quantized: Option<(Quantization, usize)>,
+
+    // Set for buffers whose device copy is memory imported from another API, which tasks
+    // write their results into in place.
+    pub(crate) imported: bool,
+}
+
+impl VBuffer {
+    pub(crate) fn new<T: Pod>(data: Vec<T>) -> Self {
+        let length = data.len();
+
+        Self {
+            inner: Box::new(data),
+            assume_init: None,
+            ..Self::new_uninit::<T>(length)
+        }
+    }
+
+    /// A buffer of `length` elements whose host memory is allocated but not written.
+    pub(crate) fn new_uninit<T: Pod>(length: usize) -> Self {
+        Self {
+            inner: Box::new(Vec::<T>::with_capacity(length)),
+            typeid: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>(),
+            stride: std::mem::size_of::<T>(),
+            length,
+            group_size: 1,
+            residency: Residency::Host,
+            pending_readback: false,
+            assume_init: Some(assume_init::<T>),
+            quantized: None,
+            imported: false,
+        }
+    }
+}
+
+pub(crate) fn assume_init<T: 'static>(inner: &mut dyn Any, length: usize) {
+    let vec = inner
+        .downcast_mut::<Vec<T>>()
+        .expect("VBuffer type mismatch");
+
+    assert!(length <= vec.capacity());
+
+    // SAFETY: The caller has written all `length` elements into the spare capacity.
+    unsafe { vec.set_len(length) }
+}
+
+/// Resizes the host copy of a VBuffer to a length, as its element type. Added elements are
+/// zeroed.
+pub(crate) type Resize = fn(&mut dyn Any, usize);
+
+pub(crate) fn resize<T: Pod>(inner: &mut dyn Any, length: usize) {
+    inner
+        .downcast_mut::<Vec<T>>()
+        .expect("VBuffer type mismatch")
+        .resize(length, T::zeroed());
+}
+
+/// Replaces the contents of a VBuffer of `T`s with the elements in `bytes`. Only the host
+/// copy holds them.
+pub(crate) fn replace_contents<T: Pod>(vbuffer: &mut VBuffer, bytes: &[u8]) {
+    let contents: Vec<T> = bytemuck::pod_collect_to_vec(bytes);
+
+    vbuffer.length = contents.len();
+    vbuffer.inner = Box::new(contents);
+    vbuffer.assume_init = None;
+    vbuffer.residency = Residency::Host;
+    vbuffer.pending_readback = false;
+}
+
+/// Where the most recent copy of a VBuffer's contents lives on the devices.
+pub(crate) enum Residency {
+    // Only the host copy exists; tasks upload it on every use.
+    Host,
+    // The per-device buffers written by the last task that output this VBuffer.
+    // Kept alive so they can be aliased, but not bound as inputs.
+    Retained(Resident),
+    // As above, but later tasks bind these buffers directly as inputs instead of
+    // uploading the host copy.
+    Aliased(Resident),
+    // Copies of the host copy uploaded for a broadcast input, which later tasks
+    // broadcasting it bind instead of uploading it again.
+    Uploaded(Resident),
+}
+
+impl Residency {
+    pub(crate) fn resident(&self) -> Option<&Resident> {
+        match self {
+            Residency::Retained(resident)
+            | Residency::Aliased(resident)
+            | Residency::Uploaded(resident) => Some(resident),
+            Residency::Host => None,
+        }
+    }
+}
+
+/// A VBuffer's copies on each device.
+pub(crate) struct Resident {
+    pub(crate) buffers: Vec<wgpu::Buffer>,
+    // The elements of the VBuffer that each device's buffer holds, starting at offset zero.
+    pub(crate) ranges: Vec<Range<usize>>,
+    // The part of each device's range that it computed itself and is authoritative for.
+    // Anything else it holds is a halo copied from a neighbour.
+    pub(crate) owned: Vec<Range<usize>>,
 }
@@ -1,140 +1,727 @@
 use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
 
+#[cfg(feature = "blocking")]
 use futures_lite::future;
+use web_time::Instant;
 use wgpu;
 
-const REQUESTED_FEATURES: wgpu::Features = wgpu::Features::MAPPABLE_PRIMARY_BUFFERS;
+use crate::cache::ModuleCache;
+use crate::error::WiscError;
+use crate::trace;
 
-#[derive(Debug)]
+const REQUESTED_FEATURES: wgpu::Features = wgpu::Features::MAPPABLE_PRIMARY_BUFFERS
+    .union(wgpu::Features::IMMEDIATES)
+    .union(wgpu::Features::PIPELINE_CACHE)
+    .union(wgpu::Features::TIMESTAMP_QUERY)
+    .union(wgpu::Features::PIPELINE_STATISTICS_QUERY);
+
+/// Optional device capabilities a task can need, as a set. Converts to and from
+/// [`wgpu::Features`] for the ones not named here.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Features(wgpu::Features);
+
+impl Features {
+    /// Immediate data, which [`crate::task::TaskBuilder::with_immediates`] uses.
+    pub const IMMEDIATES: Self = Self(wgpu::Features::IMMEDIATES);
+    /// `f16` in shaders.
+    pub const SHADER_F16: Self = Self(wgpu::Features::SHADER_F16);
+    /// `f64` in shaders.
+    pub const SHADER_F64: Self = Self(wgpu::Features::SHADER_F64);
+    /// `i64` and `u64` in shaders.
+    pub const SHADER_INT64: Self = Self(wgpu::Features::SHADER_INT64);
+    /// Subgroup operations in shaders.
+    pub const SUBGROUP: Self = Self(wgpu::Features::SUBGROUP);
+    /// GPU timestamps.
+    pub const TIMESTAMP_QUERY: Self = Self(wgpu::Features::TIMESTAMP_QUERY);
+    /// Caching compiled pipelines across runs.
+    pub const PIPELINE_CACHE: Self = Self(wgpu::Features::PIPELINE_CACHE);
+    /// Mapping device-local buffers directly, as integrated GPUs can.
+    pub const MAPPABLE_PRIMARY_BUFFERS: Self = Self(wgpu::Features::MAPPABLE_PRIMARY_BUFFERS);
+
+    pub const fn empty() -> Self {
+        Self(wgpu::Features::empty())
+    }
+
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0.union(other.0))
+    }
+
+    pub fn contains(&self, other: Self) -> bool {
+        self.0.contains(other.0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl std::ops::BitOr for Features {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        self.union(other)
+    }
+}
+
+impl std::ops::BitOrAssign for Features {
+    fn bitor_assign(&mut self, other: Self) {
+        *self = self.union(other);
+    }
+}
+
+impl std::fmt::Debug for Features {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<wgpu::Features> for Features {
+    fn from(features: wgpu::Features) -> Self {
+        Self(features)
+    }
+}
+
+impl From<Features> for wgpu::Features {
+    fn from(features: Features) -> Self {
+        features.0
+    }
+}
+
+/// A graphics API wgpu can reach a device through, for
+/// [`WorkgroupBuilder::backend_preference`](crate::workgroup::WorkgroupBuilder::backend_preference).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Backend {
+    Vulkan,
+    Metal,
+    Dx12,
+    Gl,
+    /// WebGPU in the browser.
+    BrowserWebGpu,
+}
+
+impl From<Backend> for wgpu::Backend {
+    fn from(backend: Backend) -> Self {
+        match backend {
+            Backend::Vulkan => wgpu::Backend::Vulkan,
+            Backend::Metal => wgpu::Backend::Metal,
+            Backend::Dx12 => wgpu::Backend::Dx12,
+            Backend::Gl => wgpu::Backend::Gl,
+            Backend::BrowserWebGpu => wgpu::Backend::BrowserWebGpu,
+        }
+    }
+}
+
+/// One GPU, opened once. Clones are further handles to the same device, queue and
+/// compiled modules, so several independent Workgroups can run on one adapter without
+/// each opening a device of its own. wgpu synchronizes their submissions internally.
+#[derive(Debug, Clone)]
 pub struct VDevice {
     pub(crate) label: String,
     pub(crate) info: wgpu::AdapterInfo,
     pub(crate) limits: wgpu::Limits,
     pub(crate) features: wgpu::Features,
     pub(crate) device: wgpu::Device,
+    // wgpu exposes a single queue per device, even on backends with dedicated transfer
+    // queues, so copies and compute passes share it. Runs submit every device's pass
+    // ahead of the readback copies so that the devices start on them sooner.
     pub(crate) queue: wgpu::Queue,
+    // The modules of inline shaders compiled for earlier tasks, shared between clones.
+    pub(crate) modules: Arc<ModuleCache>,
+    // Kept to request a replacement should the device be lost.
+    adapter: wgpu::Adapter,
+    experimental: wgpu::ExperimentalFeatures,
+    // What the device reported through its callbacks, shared between clones.
+    faults: Arc<Faults>,
+}
+
+/// What a device reported outside of any error scope, through the callbacks a VDevice
+/// registers on it instead of wgpu's defaults, which panic.
+#[derive(Debug, Default)]
+struct Faults {
+    // Why the device was lost, once it has been.
+    lost: Mutex<Option<String>>,
+    // The first uncaught error since it was last surfaced.
+    uncaptured: Mutex<Option<String>>,
+}
+
+impl Faults {
+    fn watch(device: &wgpu::Device) -> Arc<Self> {
+        let faults = Arc::new(Self::default());
+
+        let lost = faults.clone();
+        device.set_device_lost_callback(move |_, message| {
+            lost.lost.lock().unwrap().get_or_insert(message);
+        });
+
+        let uncaptured = faults.clone();
+        device.on_uncaptured_error(Arc::new(move |error| {
+            uncaptured
+                .uncaptured
+                .lock()
+                .unwrap()
+                .get_or_insert(error.to_string());
+        }));
+
+        faults
+    }
 }
 
 impl VDevice {
+    #[cfg(feature = "blocking")]
     pub fn best() -> Option<Self> {
-        Self::best_with_features(REQUESTED_FEATURES, wgpu::Features::empty())
+        future::block_on(Self::best_async())
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn best_with_features<F: Into<Features>, G: Into<Features>>(
+        requested: F,
+        required: G,
+    ) -> Option<Self> {
+        future::block_on(Self::best_with_features_async(requested, required))
+    }
+
+    /// Like [`best`](Self::best), but without blocking on the adapter, as the browser
+    /// requires.
+    pub async fn best_async() -> Option<Self> {
+        Self::best_with_features_async(REQUESTED_FEATURES, Features::empty()).await
+    }
+
+    pub async fn best_with_features_async<F: Into<Features>, G: Into<Features>>(
+        requested: F,
+        required: G,
+    ) -> Option<Self> {
+        let requested = wgpu::Features::from(requested.into());
+        let required = wgpu::Features::from(required.into());
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+
+        let adapter = trace::instrument!(
+            instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                ..Default::default()
+            }),
+            "request_adapter"
+        )
+        .await
+        .ok()?;
+
+        let downlevel_capabilities = adapter.get_downlevel_capabilities();
+        if !downlevel_capabilities
+            .flags
+            .contains(wgpu::DownlevelFlags::COMPUTE_SHADERS)
+        {
+            return None;
+        }
+
+        let label = format!("WISC VDevice {}", adapter.get_info().device);
+
+        let (device, queue) = trace::instrument!(
+            adapter.request_device(&wgpu::DeviceDescriptor {
+                label: Some(&label),
+                required_features: adapter.features().intersection(requested).union(required),
+                required_limits: with_immediates(wgpu::Limits::downlevel_defaults(), &adapter),
+                memory_hints: wgpu::MemoryHints::Performance,
+                trace: wgpu::Trace::Off,
+                experimental_features: wgpu::ExperimentalFeatures::disabled(),
+            }),
+            "request_device",
+            device = label.as_str()
+        )
+        .await
+        .ok()?;
+
+        Some(Self::new(
+            label,
+            &adapter,
+            adapter.limits(),
+            device,
+            queue,
+            wgpu::ExperimentalFeatures::disabled(),
+        ))
+    }
+
+    fn new(
+        label: String,
+        adapter: &wgpu::Adapter,
+        limits: wgpu::Limits,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        experimental: wgpu::ExperimentalFeatures,
+    ) -> Self {
+        Self {
+            label,
+            info: adapter.get_info(),
+            limits,
+            features: device.features(),
+            faults: Faults::watch(&device),
+            device,
+            queue,
+            modules: Arc::default(),
+            adapter: adapter.clone(),
+            experimental,
+        }
+    }
+
+    /// Whether the device has been lost, to a driver reset, a removed adapter or
+    /// [`destroy`](Self::destroy). Work on a lost device fails with
+    /// [`WiscError::DeviceLost`]; [`Workgroup::recover_lost_devices`](crate::workgroup::Workgroup::recover_lost_devices)
+    /// replaces it.
+    pub fn is_lost(&self) -> bool {
+        // wgpu reports the loss as it next handles the device's finished work.
+        let _ = self.device.poll(wgpu::PollType::Poll);
+
+        self.faults.lost.lock().unwrap().is_some()
+    }
+
+    /// Destroys the device now, rather than when its last handle is dropped, freeing its
+    /// memory. Every clone of it is lost from then on.
+    pub fn destroy(&self) {
+        self.device.destroy();
+
+        self.faults
+            .lost
+            .lock()
+            .unwrap()
+            .get_or_insert_with(|| "the device was destroyed".to_string());
+    }
+
+    /// Opens a fresh device on the same adapter, with the same features and limits, to
+    /// stand in for this one once it is lost. Shares nothing with it.
+    pub(crate) async fn reopen(&self) -> Result<Self, WiscError> {
+        let (device, queue) = self
+            .adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                label: Some(&self.label),
+                required_features: self.features,
+                required_limits: self.device.limits(),
+                memory_hints: wgpu::MemoryHints::Performance,
+                trace: wgpu::Trace::Off,
+                experimental_features: self.experimental,
+            })
+            .await
+            .map_err(|error| WiscError::DeviceLost(error.to_string()))?;
+
+        Ok(Self::new(
+            self.label.clone(),
+            &self.adapter,
+            self.limits.clone(),
+            device,
+            queue,
+            self.experimental,
+        ))
+    }
+
+    /// Fails with [`WiscError::DeviceLost`] if the device is known to be lost. wgpu treats
+    /// some misuse of a lost device's resources as fatal, so work is checked before it is
+    /// set up.
+    pub(crate) fn check_lost(&self) -> Result<(), WiscError> {
+        match self.faults.lost.lock().unwrap().clone() {
+            Some(reason) => Err(WiscError::DeviceLost(reason)),
+            None => Ok(()),
+        }
+    }
+
+    /// Fails with what the device reported since it was last checked: that it was lost,
+    /// or else the first error no scope caught.
+    fn check_faults(&self) -> Result<(), WiscError> {
+        self.check_lost()?;
+
+        match self.faults.uncaptured.lock().unwrap().take() {
+            Some(error) => Err(WiscError::DeviceError(error)),
+            None => Ok(()),
+        }
+    }
+
+    /// Blocks until all work submitted to this device has finished.
+    pub(crate) fn wait(&self) -> Result<(), WiscError> {
+        let _span = trace::span!("poll", device = self.label.as_str());
+        let polled = self.device.poll(wgpu::PollType::wait_indefinitely());
+        self.check_faults()?;
+
+        polled
+            .map(|_| ())
+            .map_err(|error| WiscError::DeviceLost(error.to_string()))
+    }
+
+    /// Like [`wait`](Self::wait), but gives up at `deadline`. Fails with
+    /// [`WiscError::Timeout`], naming this device as device `vdi`, if the work isn't done
+    /// by then.
+    pub(crate) fn wait_until(&self, vdi: usize, deadline: Instant) -> Result<(), WiscError> {
+        let _span = trace::span!("poll", device = self.label.as_str());
+        let timeout = deadline.saturating_duration_since(Instant::now());
+
+        let polled = self.device.poll(wgpu::PollType::Wait {
+            submission_index: None,
+            timeout: Some(timeout),
+        });
+        self.check_faults()?;
+
+        match polled {
+            Ok(_) => Ok(()),
+            Err(wgpu::PollError::Timeout) => Err(WiscError::Timeout {
+                device: vdi,
+                label: self.label.clone(),
+            }),
+            Err(error) => Err(WiscError::DeviceLost(error.to_string())),
+        }
+    }
+
+    /// Handles whatever work on this device has finished, without blocking. Returns whether
+    /// all of it has.
+    pub(crate) fn poll(&self) -> Result<bool, WiscError> {
+        let _span = trace::span!("poll", device = self.label.as_str());
+        let polled = self.device.poll(wgpu::PollType::Poll);
+        self.check_faults()?;
+
+        polled
+            .map(|status| status.is_queue_empty())
+            .map_err(|error| WiscError::DeviceLost(error.to_string()))
+    }
+
+    /// Copies a buffer on this device back to the host, blocking until it arrives. The
+    /// buffer must have `COPY_SRC` usage.
+    pub(crate) fn read_buffer(&self, buffer: &wgpu::Buffer) -> Result<Vec<u8>, WiscError> {
+        self.read_buffer_range(buffer, 0..buffer.size() as usize)
+    }
+
+    /// Like [`read_buffer`](Self::read_buffer), but only the bytes in `range`. Copies are
+    /// made in whole four-byte words, so the range is widened to word boundaries on the
+    /// device and trimmed again on the host.
+    pub(crate) fn read_buffer_range(
+        &self,
+        buffer: &wgpu::Buffer,
+        range: Range<usize>,
+    ) -> Result<Vec<u8>, WiscError> {
+        self.map_buffer_range(buffer, range, <[u8]>::to_vec)
+    }
+
+    /// Like [`read_buffer_range`](Self::read_buffer_range), but calls `read` with the
+    /// bytes while they are still mapped, rather than copying them out first.
+    pub(crate) fn map_buffer_range<R>(
+        &self,
+        buffer: &wgpu::Buffer,
+        range: Range<usize>,
+        read: impl FnOnce(&[u8]) -> R,
+    ) -> Result<R, WiscError> {
+        let _span = trace::span!("readback", device = self.label.as_str());
+        let align = wgpu::COPY_BUFFER_ALIGNMENT as usize;
+        let start = range.start / align * align;
+        let end = range
+            .end
+            .next_multiple_of(align)
+            .min(buffer.size() as usize);
+
+        if start >= end {
+            return Ok(read(&[]));
+        }
+
+        let staging_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("WISC Readback Buffer (VDevice {})", self.label)),
+            size: (end - start) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(
+            buffer,
+            start as wgpu::BufferAddress,
+            &staging_buffer,
+            0,
+            (end - start) as wgpu::BufferAddress,
+        );
+        self.queue.submit([encoder.finish()]);
+
+        let mapping = map_read(&staging_buffer);
+
+        self.wait()?;
+        mapping.finish()?;
+
+        let offset = range.start - start;
+        let result =
+            read(&staging_buffer.slice(..).get_mapped_range()[offset..offset + range.len()]);
+        staging_buffer.unmap();
+
+        Ok(result)
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn all() -> Vec<Self> {
+        future::block_on(Self::all_async())
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn all_with_features<F: Into<Features>, G: Into<Features>>(
+        requested: F,
+        required: G,
+    ) -> Vec<Self> {
+        future::block_on(Self::all_with_features_async(requested, required))
+    }
+
+    /// Like [`all`](Self::all), but without blocking on the adapters, as the browser
+    /// requires.
+    pub async fn all_async() -> Vec<Self> {
+        Self::all_with_features_async(REQUESTED_FEATURES, Features::empty()).await
+    }
+
+    pub async fn all_with_features_async<F: Into<Features>, G: Into<Features>>(
+        requested: F,
+        required: G,
+    ) -> Vec<Self> {
+        DeviceSelection {
+            requested: requested.into().into(),
+            required: required.into().into(),
+            ..Default::default()
+        }
+        .enumerate()
+        .await
+    }
+}
+
+/// Which limits to request from each adapter when creating its device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LimitsPolicy {
+    /// The conservative limits every downlevel adapter supports.
+    Downlevel,
+    /// Everything the adapter reports it can do.
+    #[default]
+    AdapterMax,
+}
+
+impl LimitsPolicy {
+    fn limits(&self, adapter: &wgpu::Adapter) -> wgpu::Limits {
+        match self {
+            LimitsPolicy::Downlevel => wgpu::Limits::downlevel_defaults(),
+            LimitsPolicy::AdapterMax => adapter.limits(),
+        }
+    }
+}
+
+/// `limits`, raised to allow as much immediate data as the adapter supports, since the
+/// downlevel defaults allow none.
+fn with_immediates(limits: wgpu::Limits, adapter: &wgpu::Adapter) -> wgpu::Limits {
+    wgpu::Limits {
+        max_immediate_size: adapter.limits().max_immediate_size,
+        ..limits
+    }
+}
+
+/// The options used to pick and open one VDevice per physical adapter.
+#[derive(Debug, Clone)]
+pub(crate) struct DeviceSelection {
+    pub(crate) requested: wgpu::Features,
+    pub(crate) required: wgpu::Features,
+    pub(crate) limits: LimitsPolicy,
+    // When one physical device is exposed through several backends, the first of these wins.
+    pub(crate) backends: Vec<wgpu::Backend>,
+    // Adapters whose name contains any of these (case-insensitively) are skipped.
+    pub(crate) deny: Vec<String>,
+    pub(crate) experimental: wgpu::ExperimentalFeatures,
+    // Open only Vulkan devices that can import memory from other APIs.
+    #[cfg(all(feature = "vulkan-interop", unix))]
+    pub(crate) external_memory: bool,
+}
+
+impl Default for DeviceSelection {
+    fn default() -> Self {
+        Self {
+            requested: REQUESTED_FEATURES,
+            required: wgpu::Features::empty(),
+            limits: LimitsPolicy::default(),
+            backends: vec![
+                wgpu::Backend::Vulkan,
+                wgpu::Backend::Dx12,
+                wgpu::Backend::Metal,
+                wgpu::Backend::Gl,
+            ],
+            deny: vec![],
+            experimental: wgpu::ExperimentalFeatures::disabled(),
+            #[cfg(all(feature = "vulkan-interop", unix))]
+            external_memory: false,
+        }
+    }
+}
+
+impl DeviceSelection {
+    pub(crate) fn is_denied(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+
+        self.deny
+            .iter()
+            .any(|denied| name.contains(&denied.to_lowercase()))
     }
 
-    pub fn best_with_features(requested: wgpu::Features, required: wgpu::Features) -> Option<Self> {
-        future::block_on(async {
-            let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+    pub(crate) async fn enumerate(&self) -> Vec<VDevice> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+        let adapters = trace::instrument!(
+            instance.enumerate_adapters(wgpu::Backends::all()),
+            "enumerate_adapters"
+        )
+        .await;
+
+        let mut physical_groups: HashMap<(u32, u32), Vec<wgpu::Adapter>> = HashMap::new();
+        for adapter in adapters {
+            let info = adapter.get_info();
+
+            if self.is_denied(&info.name) {
+                continue;
+            }
+
+            physical_groups
+                .entry((info.vendor, info.device))
+                .or_default()
+                .push(adapter);
+        }
+
+        let mut results = Vec::new();
 
-            let adapter = instance
-                .request_adapter(&wgpu::RequestAdapterOptions {
-                    power_preference: wgpu::PowerPreference::HighPerformance,
-                    ..Default::default()
-                })
-                .await
-                .ok()?;
+        for (_, mut adapters) in physical_groups {
+            adapters.sort_by_key(|a| {
+                let backend = a.get_info().backend;
 
-            let downlevel_capabilities = adapter.get_downlevel_capabilities();
-            if !downlevel_capabilities
+                self.backends
+                    .iter()
+                    .position(|preferred| *preferred == backend)
+                    .unwrap_or(self.backends.len())
+            });
+
+            let adapter = &adapters[0];
+
+            if !adapter
+                .get_downlevel_capabilities()
                 .flags
                 .contains(wgpu::DownlevelFlags::COMPUTE_SHADERS)
             {
-                return None;
+                continue;
             }
 
             let label = format!("WISC VDevice {}", adapter.get_info().device);
 
-            let (device, queue) = adapter
-                .request_device(&wgpu::DeviceDescriptor {
-                    label: Some(&label),
-                    required_features: adapter.features().intersection(requested).union(required),
-                    required_limits: wgpu::Limits::downlevel_defaults(),
-                    memory_hints: wgpu::MemoryHints::Performance,
-                    trace: wgpu::Trace::Off,
-                    experimental_features: wgpu::ExperimentalFeatures::disabled(),
-                })
-                .await
-                .ok()?;
-
-            Some(Self {
-                label,
-                info: adapter.get_info(),
-                limits: adapter.limits(),
-                features: device.features(),
-                device,
-                queue,
-            })
-        })
+            let descriptor = wgpu::DeviceDescriptor {
+                label: Some(&label),
+                required_features: adapter
+                    .features()
+                    .intersection(self.requested)
+                    .union(self.required),
+                required_limits: with_immediates(self.limits.limits(adapter), adapter),
+                memory_hints: wgpu::MemoryHints::Performance,
+                experimental_features: self.experimental,
+                ..Default::default()
+            };
+
+            let request_device = trace::instrument!(
+                adapter.request_device(&descriptor),
+                "request_device",
+                device = label.as_str()
+            );
+
+            #[cfg(all(feature = "vulkan-interop", unix))]
+            let device_result = if self.external_memory {
+                crate::interop::open_device(adapter, &descriptor)
+            } else {
+                request_device.await.ok()
+            };
+            #[cfg(not(all(feature = "vulkan-interop", unix)))]
+            let device_result = request_device.await.ok();
+
+            if let Some((device, queue)) = device_result {
+                let limits = device.limits();
+                results.push(VDevice::new(
+                    label,
+                    adapter,
+                    limits,
+                    device,
+                    queue,
+                    self.experimental,
+                ));
+            }
+        }
+
+        results
     }
+}
 
-    pub fn all() -> Vec<Self> {
-        Self::all_with_features(REQUESTED_FEATURES, wgpu::Features::empty())
+/// A pending [`map_read`] of a buffer.
+pub(crate) struct Mapping {
+    receiver: std::sync::mpsc::Receiver<Result<(), wgpu::BufferAsyncError>>,
+    // What the mapping resolved to, once `is_ready` has seen it.
+    resolved: Option<Result<(), wgpu::BufferAsyncError>>,
+    // The task awaiting the mapping, if one is, to wake once it resolves. Tokio waits on
+    // its blocking threads instead.
+    #[cfg_attr(feature = "tokio", allow(dead_code))]
+    waker: Arc<Mutex<Option<Waker>>>,
+}
+
+impl Mapping {
+    /// Whether the mapping has resolved, without blocking.
+    pub(crate) fn is_ready(&mut self) -> bool {
+        if self.resolved.is_none() {
+            self.resolved = self.receiver.try_recv().ok();
+        }
+
+        self.resolved.is_some()
     }
 
-    pub fn all_with_features(requested: wgpu::Features, required: wgpu::Features) -> Vec<Self> {
-        future::block_on(async {
-            let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-                backends: wgpu::Backends::all(),
-                ..Default::default()
-            });
-            let adapters = instance.enumerate_adapters(wgpu::Backends::all()).await;
-
-            let mut physical_groups: HashMap<(u32, u32), Vec<wgpu::Adapter>> = HashMap::new();
-            for adapter in adapters {
-                let info = adapter.get_info();
-                physical_groups
-                    .entry((info.vendor, info.device))
-                    .or_default()
-                    .push(adapter);
-            }
+    /// Like [`is_ready`](Self::is_ready), but wakes the awaiting task once the mapping
+    /// resolves if it hasn't yet. Outside the browser, the device must still be polled for
+    /// that to happen.
+    #[cfg_attr(feature = "tokio", allow(dead_code))]
+    pub(crate) fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.is_ready() {
+            return Poll::Ready(());
+        }
+
+        self.waker.lock().unwrap().replace(cx.waker().clone());
+
+        // It may have resolved before the waker was in place.
+        if self.is_ready() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    /// Blocks until the mapping resolves. The device must be polled for that to happen.
+    pub(crate) fn finish(self) -> Result<(), WiscError> {
+        let resolved = match self.resolved {
+            Some(resolved) => resolved,
+            None => self
+                .receiver
+                .recv()
+                .map_err(|_| WiscError::MapFailed("the mapping was dropped".into()))?,
+        };
+
+        resolved.map_err(|error| WiscError::MapFailed(error.to_string()))
+    }
+}
+
+/// Starts mapping the whole of `buffer` for reading.
+pub(crate) fn map_read(buffer: &wgpu::Buffer) -> Mapping {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let waker: Arc<Mutex<Option<Waker>>> = Arc::default();
+
+    buffer.slice(..).map_async(wgpu::MapMode::Read, {
+        let waker = waker.clone();
+
+        move |result| {
+            let _ = tx.send(result);
 
-            let mut results = Vec::new();
-
-            for (_, mut adapters) in physical_groups {
-                adapters.sort_by_key(|a| match a.get_info().backend {
-                    wgpu::Backend::Vulkan => 0,
-                    wgpu::Backend::Dx12 => 1,
-                    wgpu::Backend::Metal => 2,
-                    wgpu::Backend::Gl => 4,
-                    _ => 5,
-                });
-
-                let adapter = &adapters[0];
-
-                if !adapter
-                    .get_downlevel_capabilities()
-                    .flags
-                    .contains(wgpu::DownlevelFlags::COMPUTE_SHADERS)
-                {
-                    continue;
-                }
-
-                let label = format!("WISC VDevice {}", adapter.get_info().device);
-
-                let device_result = adapter
-                    .request_device(&wgpu::DeviceDescriptor {
-                        label: Some(&label),
-                        required_features: adapter
-                            .features()
-                            .intersection(requested)
-                            .union(required),
-                        required_limits: adapter.limits(),
-                        memory_hints: wgpu::MemoryHints::Performance,
-                        ..Default::default()
-                    })
-                    .await;
-
-                if let Ok((device, queue)) = device_result {
-                    results.push(Self {
-                        label,
-                        info: adapter.get_info(),
-                        limits: adapter.limits(),
-                        features: device.features(),
-                        device,
-                        queue,
-                    });
-                }
+            if let Some(waker) = waker.lock().unwrap().take() {
+                waker.wake();
             }
+        }
+    });
 
-            results
-        })
+    Mapping {
+        receiver: rx,
+        resolved: None,
+        waker,
     }
 }
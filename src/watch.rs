@@ -0,0 +1,129 @@
+//! Hot reloading of registered shaders, for iterating on kernels without restarting.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::error::WiscError;
+use crate::shader::{self, ShaderSourceBuilder};
+use crate::workgroup::Workgroup;
+
+/// A shader registered from a WGSL file, with every file its source was assembled from
+/// and when each was last modified.
+pub(crate) struct WatchedShader {
+    path: PathBuf,
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl WatchedShader {
+    fn changed(&self) -> bool {
+        self.files
+            .iter()
+            .any(|(path, modified)| modified_time(path) != *modified)
+    }
+}
+
+impl Workgroup {
+    /// Registers the WGSL file at `path` (with its includes resolved, as
+    /// [`ShaderSourceBuilder::resolve`] does) under `name`, and keeps watching it: the
+    /// next task built with the shader after the file or one of its includes changes
+    /// recompiles it first, so edits show up without restarting.
+    ///
+    /// Fails if the file can't be read or doesn't validate. An edit that doesn't validate
+    /// fails the build of the task that would have picked it up, and leaves the last good
+    /// version registered until it is fixed.
+    pub fn watch_shader<S: Into<String>, P: AsRef<Path>>(
+        &mut self,
+        name: S,
+        path: P,
+    ) -> Result<(), WiscError> {
+        let name = name.into();
+
+        let watched = self.compile_watched(&name, path.as_ref().to_path_buf())?;
+        self.watched_shaders.insert(name, watched);
+
+        Ok(())
+    }
+
+    /// Stops recompiling `name` when its files change, leaving it registered as it is.
+    /// Returns whether it was being watched.
+    pub fn unwatch_shader(&mut self, name: &str) -> bool {
+        self.watched_shaders.remove(name).is_some()
+    }
+
+    /// Recompiles every watched shader whose files have changed, and returns their names.
+    /// Tasks do this for their own shader as they are built, so this is only needed to
+    /// pick up (or report problems with) edits ahead of time.
+    pub fn reload_shaders(&mut self) -> Result<Vec<String>, WiscError> {
+        let names: Vec<String> = self.watched_shaders.keys().cloned().collect();
+        let mut reloaded = vec![];
+
+        for name in names {
+            if self.reload_if_changed(&name)? {
+                reloaded.push(name);
+            }
+        }
+
+        Ok(reloaded)
+    }
+
+    /// Recompiles `name` if it is watched and its files have changed since it was last
+    /// compiled. Returns whether it was.
+    pub(crate) fn reload_if_changed(&mut self, name: &str) -> Result<bool, WiscError> {
+        let Some(watched) = self.watched_shaders.get(name) else {
+            return Ok(false);
+        };
+
+        if !watched.changed() {
+            return Ok(false);
+        }
+
+        let watched = self.compile_watched(name, watched.path.clone())?;
+        self.watched_shaders.insert(name.to_string(), watched);
+
+        Ok(true)
+    }
+
+    /// Reads, validates and registers the shader at `path` as `name`.
+    fn compile_watched(&mut self, name: &str, path: PathBuf) -> Result<WatchedShader, WiscError> {
+        let (source, included) = ShaderSourceBuilder::new()
+            .resolve_files(&path)
+            .map_err(|error| WiscError::ShaderFile(error.to_string()))?;
+
+        let files = included
+            .into_iter()
+            .map(|file| {
+                let modified = modified_time(&file);
+                (file, modified)
+            })
+            .collect();
+
+        // wgpu treats an invalid module as fatal, and a half-finished edit shouldn't take
+        // the process down with it.
+        validate(&source)?;
+
+        self.register_shader(name, shader::Shader::wgsl(source));
+
+        Ok(WatchedShader { path, files })
+    }
+}
+
+fn validate(source: &str) -> Result<(), WiscError> {
+    let module = naga::front::wgsl::parse_str(source)
+        .map_err(|error| WiscError::ShaderParse(error.emit_to_string(source)))?;
+
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    )
+    .validate(&module)
+    .map_err(|error| WiscError::ShaderParse(error.emit_to_string(source)))?;
+
+    Ok(())
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
@@ -1,9 +1,28 @@
 use std::any::TypeId;
+use std::collections::HashMap;
+use std::mem::MaybeUninit;
+use std::path::PathBuf;
 
 use bytemuck::Pod;
+#[cfg(feature = "blocking")]
+use futures_lite::future;
 use slotmap::SlotMap;
 
-use crate::{vbuffer::VBuffer, vdevice::VDevice};
+use crate::{
+    cache::{self, BindingCache},
+    calibration::{default_calibration_file, load_weights},
+    element::WiscElement,
+    error::WiscError,
+    history::Launch,
+    reflect,
+    result_cache::ResultCache,
+    shader::{self, Shader},
+    snapshot::ElementType,
+    task::{per_device_parallel, vbuffer_write},
+    vbuffer::{Residency, VBuffer},
+    vdevice::{Backend, DeviceSelection, Features, LimitsPolicy, VDevice},
+    watch::WatchedShader,
+};
 
 slotmap::new_key_type! { pub struct VBufferHandle; }
 
@@ -13,6 +32,33 @@ pub struct Workgroup {
 
     // The owned I/O buffers that implement pod, as enforced by constructor.
     pub(crate) vbuffers: SlotMap<VBufferHandle, VBuffer>,
+
+    // Shader modules compiled ahead of time, one per VDevice, by registered name.
+    pub(crate) shaders: HashMap<String, RegisteredShader>,
+    // The registered shaders that are recompiled when their files change.
+    pub(crate) watched_shaders: HashMap<String, WatchedShader>,
+
+    // Bind group layouts and bind groups shared between tasks, one cache per VDevice.
+    pub(crate) binding_caches: Vec<BindingCache>,
+    // Compiled pipelines kept by the driver, for the VDevices that support it, and the
+    // directory they are loaded from and saved to.
+    pub(crate) pipeline_caches: Vec<Option<wgpu::PipelineCache>>,
+    pub(crate) pipeline_cache_dir: Option<PathBuf>,
+    // The fraction of each device's time tasks may occupy, if they are throttled.
+    pub(crate) throttle: Option<f32>,
+
+    // The element types that snapshots can restore buffers of, by type name.
+    pub(crate) element_types: HashMap<&'static str, ElementType>,
+    // Every dispatch issued since recording started, if it has.
+    pub(crate) launches: Option<Vec<Launch>>,
+    // Where the results of earlier runs are memoized, if they are.
+    pub(crate) result_cache: Option<ResultCache>,
+    // Whether the ops compute on the host when there are no devices, rather than fail.
+    pub(crate) cpu_fallback: bool,
+    // Where calibrations are saved, if anywhere.
+    pub(crate) calibration_file: Option<PathBuf>,
+    // How many bytes of its buffers each device may hold for one task, if it is capped.
+    pub(crate) memory_caps: Vec<Option<u64>>,
 }
 
 impl Workgroup {
@@ -24,47 +70,105 @@ impl Workgroup {
             .collect()
     }
 
-    pub fn from_devices(devices: Vec<VDevice>) -> Self {
-        // If we have multiple devices, we weight them based on estimates of their
-        // compute power.
-        //
-        // TODO: This estimation is very crude, so in the future this might
-        // be configurable by the user.
+    /// Sets the weightings of the `devices` named, by index or label, which decide how tasks
+    /// built afterwards divide their buffers. A label names every device that has it. The
+    /// devices not named keep theirs, and the whole is normalized to add up to one, so
+    /// `[(0, 0.8), (1, 0.2)]` splits a pair of devices 80/20 whatever their weightings
+    /// were. A device weighted zero sits tasks out.
+    ///
+    /// Fails, changing nothing, if a device isn't found, or a weighting is negative or not
+    /// finite, or they would all be zero.
+    pub fn set_vdevice_weightings<'d, D>(&mut self, devices: &[(D, f32)]) -> Result<(), WiscError>
+    where
+        D: Into<DeviceRef<'d>> + Clone,
+    {
+        let mut weightings = self.vdevice_weightings.clone();
 
-        let device_weights: Vec<f32> = devices
+        for (device, weighting) in devices {
+            if !weighting.is_finite() || *weighting < 0.0 {
+                return Err(WiscError::InvalidWeighting(
+                    "weightings must be finite and not negative",
+                ));
+            }
+
+            for vdi in self.find_devices(device.clone().into())? {
+                weightings[vdi] = *weighting;
+            }
+        }
+
+        let total: f32 = weightings.iter().sum();
+        if total <= 0.0 {
+            return Err(WiscError::InvalidWeighting(
+                "every device would be weighted zero",
+            ));
+        }
+
+        self.vdevice_weightings = weightings
             .iter()
-            .map(|vd| {
-                let base = vd.limits.max_compute_invocations_per_workgroup as f32;
-
-                let memory_proxy = if vd.info.device_type == wgpu::DeviceType::Cpu {
-                    1.0
-                } else {
-                    (vd.limits.max_buffer_size as f32 / 1_048_576.0)
-                        .log2()
-                        .max(1.0)
-                };
-
-                let type_multiplier = match vd.info.device_type {
-                    wgpu::DeviceType::DiscreteGpu => 10.0,
-                    wgpu::DeviceType::IntegratedGpu => 3.0,
-                    wgpu::DeviceType::VirtualGpu => 2.0,
-                    wgpu::DeviceType::Cpu => 1.0,
-                    wgpu::DeviceType::Other => 1.0,
-                };
-
-                base * memory_proxy * type_multiplier
-            })
+            .map(|weighting| weighting / total)
             .collect();
 
+        Ok(())
+    }
+
+    /// Caps how many bytes of a task's buffers `device`, by index or label, may hold, or
+    /// lifts the cap with `None`. wgpu doesn't report how much memory an adapter has, so
+    /// where the application knows better than the device's limits, as from the platform's
+    /// own APIs, it can say so here.
+    ///
+    /// Tasks built afterwards give a capped device no more of their split buffers than
+    /// fits besides the buffers it holds whole, handing the rest to the other devices.
+    /// Every device is capped by its limits anyway: none is given more of a buffer than
+    /// its largest binding holds.
+    pub fn set_memory_cap<'d, D: Into<DeviceRef<'d>>>(
+        &mut self,
+        device: D,
+        bytes: Option<u64>,
+    ) -> Result<(), WiscError> {
+        for vdi in self.find_devices(device.into())? {
+            self.memory_caps[vdi] = bytes;
+        }
+
+        Ok(())
+    }
+
+    /// The indices of the devices `device` names. Fails if there are none.
+    fn find_devices(&self, device: DeviceRef) -> Result<Vec<usize>, WiscError> {
+        let vdis: Vec<usize> = match device {
+            DeviceRef::Index(vdi) => (vdi < self.vdevices.len())
+                .then_some(vdi)
+                .into_iter()
+                .collect(),
+            DeviceRef::Label(label) => (0..self.vdevices.len())
+                .filter(|&vdi| self.vdevices[vdi].label == label)
+                .collect(),
+        };
+
+        if vdis.is_empty() {
+            return Err(WiscError::UnknownDevice(device.to_string()));
+        }
+
+        Ok(vdis)
+    }
+
+    pub fn from_devices(devices: Vec<VDevice>) -> Self {
+        WorkgroupBuilder::new().assemble(devices)
+    }
+
+    /// Handles to this Workgroup's devices, for another Workgroup to share. Each keeps its
+    /// own buffers, shaders and caches; only the devices and their queues are common.
+    pub fn shared_devices(&self) -> Vec<VDevice> {
+        self.vdevices.clone()
+    }
+
+    pub(crate) fn from_weighted_devices(devices: Vec<VDevice>, device_weights: Vec<f32>) -> Self {
         let total_weight: f32 = device_weights.iter().sum();
         let device_weights_normalized: Vec<f32> =
             device_weights.iter().map(|w| w / total_weight).collect();
 
         // Sort devices from strongest to weakest
-        let mut device_weight_pairs: Vec<(VDevice, f32)> = devices
-            .into_iter()
-            .zip(device_weights_normalized.into_iter())
-            .collect();
+        let mut device_weight_pairs: Vec<(VDevice, f32)> =
+            devices.into_iter().zip(device_weights_normalized).collect();
 
         device_weight_pairs
             .sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
@@ -73,33 +177,847 @@ impl Workgroup {
             device_weight_pairs.into_iter().unzip();
 
         Self {
+            binding_caches: devices.iter().map(|_| BindingCache::default()).collect(),
+            pipeline_caches: devices.iter().map(|_| None).collect(),
+            memory_caps: vec![None; devices.len()],
+            pipeline_cache_dir: None,
             vdevices: devices,
             vdevice_weightings: device_weights_normalized,
             vbuffers: SlotMap::default(),
+            shaders: HashMap::new(),
+            watched_shaders: HashMap::new(),
+            element_types: HashMap::new(),
+            throttle: None,
+            launches: None,
+            result_cache: None,
+            cpu_fallback: false,
+            calibration_file: None,
         }
     }
 
+    /// Compiles `source` on every device up front and stores it under `name`, so tasks
+    /// built with [`TaskBuilder::with_registered_shader`](crate::task::TaskBuilder::with_registered_shader)
+    /// skip shader compilation. Registering a name again replaces the old modules, and
+    /// stops [watching](Self::watch_shader) the files of any shader it was before.
+    pub fn register_shader<'a, S: Into<String>, D: Into<Shader<'a>>>(
+        &mut self,
+        name: S,
+        source: D,
+    ) {
+        let name = name.into();
+        let shader = RegisteredShader::compile(&self.vdevices, source.into().0);
+
+        self.watched_shaders.remove(&name);
+        self.shaders.insert(name, shader);
+    }
+
+    /// Registers many shaders at once, compiling each on its own thread. In the browser,
+    /// which has no threads to spawn, they are compiled one after another.
+    pub fn register_shaders<'a, S: Into<String> + Send, D: Into<Shader<'a>> + Send>(
+        &mut self,
+        shaders: Vec<(S, D)>,
+    ) {
+        if cfg!(target_arch = "wasm32") {
+            for (name, source) in shaders {
+                self.register_shader(name, source);
+            }
+
+            return;
+        }
+
+        let vdevices = &self.vdevices;
+
+        let compiled: Vec<(String, RegisteredShader)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = shaders
+                .into_iter()
+                .map(|(name, source)| {
+                    scope.spawn(move || {
+                        (
+                            name.into(),
+                            RegisteredShader::compile(vdevices, source.into().0),
+                        )
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("Shader compilation thread panicked"))
+                .collect()
+        });
+
+        self.shaders.extend(compiled);
+    }
+
+    pub fn has_registered_shader(&self, name: &str) -> bool {
+        self.shaders.contains_key(name)
+    }
+
+    /// Submits whatever the devices hold queued but unsubmitted, like uploads into device
+    /// copies of VBuffers that would otherwise wait for the next task, so it gets under way
+    /// now. Doesn't wait for any of it.
+    pub fn flush(&self) {
+        for vd in &self.vdevices {
+            vd.queue.submit([]);
+        }
+    }
+
+    /// Flushes the devices, then blocks until every one of them has finished all its work,
+    /// including that of other Workgroups sharing them. The devices are waited on together.
+    pub fn wait_idle(&self) -> Result<(), WiscError> {
+        self.flush();
+
+        per_device_parallel(&self.vdevices, |_, vd| vd.wait())
+            .into_iter()
+            .collect()
+    }
+
+    /// Whether every VBuffer exists and holds elements of the paired type.
+    pub(crate) fn has_expected_types(
+        &self,
+        expected: &[(VBufferHandle, TypeId)],
+    ) -> Result<(), WiscError> {
+        for (handle, typeid) in expected {
+            let vbuffer = self
+                .vbuffers
+                .get(*handle)
+                .ok_or(WiscError::UnknownVBuffer)?;
+
+            if vbuffer.typeid != *typeid {
+                return Err(WiscError::TypeMismatch);
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn create_vbuffer<T: Pod>(&mut self, data: Vec<T>) -> VBufferHandle {
-        let length = data.len();
-        let stride = std::mem::size_of::<T>();
-        let tbox = Box::new(data);
-        self.vbuffers.insert(VBuffer {
-            inner: tbox,
-            typeid: TypeId::of::<T>(),
-            stride,
-            length,
-        })
-    }
-
-    pub fn take_vbuffer<T: Pod>(&mut self, buffer_handle: VBufferHandle) -> Option<Vec<T>> {
-        let typeid = self.vbuffers.get(buffer_handle)?.typeid;
-
-        if TypeId::of::<T>() == typeid {
-            let vbuffer = self.vbuffers.remove(buffer_handle)?;
-            let anybox = vbuffer.inner;
-            anybox.downcast::<Vec<T>>().ok().map(|anybox| *anybox)
-        } else {
-            None
+        self.register_element_type::<T>();
+        self.vbuffers.insert(VBuffer::new(data))
+    }
+
+    /// Registers a buffer of `length` elements without initializing its host memory.
+    ///
+    /// The buffer can only be used as an output until a task has written it; its device copy
+    /// starts out zeroed and nothing is uploaded. This avoids the cost of filling very large
+    /// result buffers with placeholder values that are about to be overwritten.
+    pub fn create_vbuffer_uninit<T: Pod>(&mut self, length: usize) -> VBufferHandle {
+        self.register_element_type::<T>();
+        self.vbuffers.insert(VBuffer::new_uninit::<T>(length))
+    }
+
+    /// Copies the buffer's contents into caller-provided memory, such as an arena
+    /// allocation, without requiring it to be initialized first.
+    ///
+    /// Results a run left on the devices, as it does for an
+    /// [aliased](Self::alias_output_as_input) output, are copied straight out of the mapped
+    /// staging buffers, without passing through the host copy; otherwise the host copy,
+    /// which holds them already, is copied. Rust's allocator API is still unstable, so
+    /// rather than taking an allocator this takes memory allocated from one, such as the
+    /// spare capacity of a `Vec`.
+    ///
+    /// Returns the initialized slice. Fails if the types or lengths don't match or the
+    /// buffer has not been written yet.
+    pub fn read_vbuffer_into<'d, T: Pod>(
+        &self,
+        buffer_handle: VBufferHandle,
+        dst: &'d mut [MaybeUninit<T>],
+    ) -> Result<&'d mut [T], WiscError> {
+        let vbuffer = self
+            .vbuffers
+            .get(buffer_handle)
+            .ok_or(WiscError::UnknownVBuffer)?;
+
+        if vbuffer.typeid != TypeId::of::<T>() {
+            return Err(WiscError::TypeMismatch);
+        }
+
+        if vbuffer.length != dst.len() {
+            return Err(WiscError::OutOfBounds);
+        }
+
+        // SAFETY: Any `T` can be viewed as its bytes, which may be uninitialized too.
+        let dst_bytes = unsafe {
+            std::slice::from_raw_parts_mut(
+                dst.as_mut_ptr() as *mut MaybeUninit<u8>,
+                std::mem::size_of_val(dst),
+            )
+        };
+
+        if !self.read_pending_into(vbuffer, dst_bytes)? {
+            if vbuffer.assume_init.is_some() {
+                return Err(WiscError::Uninitialized);
+            }
+
+            let src = vbuffer
+                .inner
+                .downcast_ref::<Vec<T>>()
+                .ok_or(WiscError::TypeMismatch)?;
+
+            // SAFETY: `src` and `dst` have the same length and element type.
+            unsafe {
+                std::ptr::copy_nonoverlapping(src.as_ptr(), dst.as_mut_ptr() as *mut T, dst.len())
+            };
+        }
+
+        // SAFETY: Every element of `dst` has been written.
+        unsafe {
+            Ok(std::slice::from_raw_parts_mut(
+                dst.as_mut_ptr() as *mut T,
+                dst.len(),
+            ))
+        }
+    }
+
+    /// Registers a buffer of [`WiscElement`]s, stored as their `Pod` representation.
+    pub fn create_element_vbuffer<E: WiscElement>(&mut self, data: Vec<E>) -> VBufferHandle {
+        self.create_vbuffer(data.into_iter().map(E::to_repr).collect::<Vec<_>>())
+    }
+
+    /// Takes ownership of a buffer of [`WiscElement`]s.
+    ///
+    /// Fails, leaving the buffer registered, if the representation type doesn't match or
+    /// any element is not a valid `E`.
+    pub fn take_element_vbuffer<E: WiscElement>(
+        &mut self,
+        buffer_handle: VBufferHandle,
+    ) -> Result<Vec<E>, WiscError> {
+        self.read_back_pending(buffer_handle)?;
+
+        let vbuffer = self
+            .vbuffers
+            .get(buffer_handle)
+            .ok_or(WiscError::UnknownVBuffer)?;
+
+        if vbuffer.assume_init.is_some() {
+            return Err(WiscError::Uninitialized);
         }
+
+        let elements: Vec<E> = vbuffer
+            .inner
+            .downcast_ref::<Vec<E::Repr>>()
+            .ok_or(WiscError::TypeMismatch)?
+            .iter()
+            .map(|repr| E::from_repr(*repr))
+            .collect::<Option<_>>()
+            .ok_or(WiscError::TypeMismatch)?;
+
+        self.vbuffers.remove(buffer_handle);
+
+        Ok(elements)
+    }
+
+    /// Declares that the buffer's elements come in groups of `group_size` (for example the
+    /// four components of a `vec4<f32>` stored as `f32`s), which partitioning never splits
+    /// across devices.
+    ///
+    /// Fails if `group_size` is zero or the buffer's length is not a whole number of groups.
+    pub fn set_element_group_size(
+        &mut self,
+        buffer_handle: VBufferHandle,
+        group_size: usize,
+    ) -> Result<VBufferHandle, WiscError> {
+        if group_size == 0 {
+            return Err(WiscError::InvalidPartition(
+                "element group size must be greater than zero",
+            ));
+        }
+
+        let vbuffer = self
+            .vbuffers
+            .get_mut(buffer_handle)
+            .ok_or(WiscError::UnknownVBuffer)?;
+
+        if vbuffer.length % group_size != 0 {
+            return Err(WiscError::InvalidPartition(
+                "the buffer's length is not a whole number of groups",
+            ));
+        }
+
+        vbuffer.group_size = group_size;
+
+        Ok(buffer_handle)
+    }
+
+    /// Takes the buffer's host contents out of the runtime. Fails, leaving the buffer
+    /// registered, if `T` is not its element type or it has not been written yet.
+    pub fn take_vbuffer<T: Pod>(
+        &mut self,
+        buffer_handle: VBufferHandle,
+    ) -> Result<Vec<T>, WiscError> {
+        self.read_back_pending(buffer_handle)?;
+
+        let vbuffer = self
+            .vbuffers
+            .get(buffer_handle)
+            .ok_or(WiscError::UnknownVBuffer)?;
+
+        // An uninitialized buffer stays registered until a task writes it.
+        if vbuffer.assume_init.is_some() {
+            return Err(WiscError::Uninitialized);
+        }
+
+        if vbuffer.typeid != TypeId::of::<T>() {
+            return Err(WiscError::TypeMismatch);
+        }
+
+        let vbuffer = self
+            .vbuffers
+            .remove(buffer_handle)
+            .ok_or(WiscError::UnknownVBuffer)?;
+
+        vbuffer
+            .inner
+            .downcast::<Vec<T>>()
+            .map(|anybox| *anybox)
+            .map_err(|_| WiscError::TypeMismatch)
+    }
+
+    /// Binds the device-resident copy of `buffer_handle`, as left behind by the last task
+    /// that used it as an output, as the input of any later task that reads it.
+    ///
+    /// Later tasks skip the host upload entirely and read the data where it already lives
+    /// on each device. Submissions to a device's queue execute in order and wgpu inserts
+    /// the required barriers between them, so the later task always observes the
+    /// completed writes of the earlier one.
+    ///
+    /// Fails if no task has written this buffer yet. The alias lasts until a task writes
+    /// the buffer again or it is taken out of the runtime. That task leaves what it writes
+    /// on the devices too, unless it needs it on the host, and it is only read back once
+    /// the host copy is read, by [`take_vbuffer`](Self::take_vbuffer) say.
+    pub fn alias_output_as_input(
+        &mut self,
+        buffer_handle: VBufferHandle,
+    ) -> Result<VBufferHandle, WiscError> {
+        let vbuffer = self
+            .vbuffers
+            .get_mut(buffer_handle)
+            .ok_or(WiscError::UnknownVBuffer)?;
+
+        vbuffer.residency = match std::mem::replace(&mut vbuffer.residency, Residency::Host) {
+            Residency::Retained(resident)
+            | Residency::Aliased(resident)
+            | Residency::Uploaded(resident) => Residency::Aliased(resident),
+            Residency::Host => return Err(WiscError::Uninitialized),
+        };
+
+        Ok(buffer_handle)
+    }
+
+    /// Reads the results a run left on the devices back into the host copy of
+    /// `buffer_handle`, if there are any.
+    pub(crate) fn read_back_pending(
+        &mut self,
+        buffer_handle: VBufferHandle,
+    ) -> Result<(), WiscError> {
+        let vbuffer = self
+            .vbuffers
+            .get(buffer_handle)
+            .ok_or(WiscError::UnknownVBuffer)?;
+
+        let Some(bytes) = self.pending_contents(vbuffer)? else {
+            return Ok(());
+        };
+
+        let vbuffer = &mut self.vbuffers[buffer_handle];
+
+        vbuffer_write(vbuffer, 0, &bytes);
+        if let Some(assume_init) = vbuffer.assume_init.take() {
+            assume_init(vbuffer.inner.as_mut(), vbuffer.length);
+        }
+        vbuffer.pending_readback = false;
+
+        Ok(())
+    }
+
+    /// What the host copy of `vbuffer` holds once the results a run left on the devices
+    /// are read back into it, or `None` if there are none.
+    pub(crate) fn pending_contents(&self, vbuffer: &VBuffer) -> Result<Option<Vec<u8>>, WiscError> {
+        let byte_length = vbuffer.length * vbuffer.stride;
+        let mut bytes = Vec::with_capacity(byte_length);
+
+        if !self.read_pending_into(vbuffer, &mut bytes.spare_capacity_mut()[..byte_length])? {
+            return Ok(None);
+        }
+
+        // SAFETY: Every byte has been written.
+        unsafe { bytes.set_len(byte_length) };
+
+        Ok(Some(bytes))
+    }
+
+    /// Copies the results a run left on the devices into `dst`, which holds all of
+    /// `vbuffer`'s bytes, as they are mapped. Returns whether there were any, writing
+    /// every byte if there were and none otherwise.
+    fn read_pending_into(
+        &self,
+        vbuffer: &VBuffer,
+        dst: &mut [MaybeUninit<u8>],
+    ) -> Result<bool, WiscError> {
+        let Some(resident) = vbuffer
+            .residency
+            .resident()
+            .filter(|_| vbuffer.pending_readback)
+        else {
+            return Ok(false);
+        };
+
+        let stride = vbuffer.stride;
+
+        // Runs only leave results on the devices when what they own covers the buffer.
+        for (vdi, vd) in self.vdevices.iter().enumerate() {
+            let (held, owned) = (&resident.ranges[vdi], &resident.owned[vdi]);
+
+            // The devices of an unmanaged output each hold the same result.
+            if owned.is_empty() || resident.owned[..vdi].contains(owned) {
+                continue;
+            }
+
+            let offset = (owned.start - held.start) * stride;
+            let dst = &mut dst[owned.start * stride..owned.end * stride];
+
+            vd.map_buffer_range(
+                &resident.buffers[vdi],
+                offset..offset + dst.len(),
+                |bytes| {
+                    // SAFETY: `bytes` is as long as the range of `dst` it was read for.
+                    unsafe {
+                        std::ptr::copy_nonoverlapping(
+                            bytes.as_ptr(),
+                            dst.as_mut_ptr() as *mut u8,
+                            bytes.len(),
+                        )
+                    }
+                },
+            )?;
+        }
+
+        Ok(true)
+    }
+}
+
+/// A device of a Workgroup, by its index or its label, as
+/// [`Workgroup::vdevice_weightings`] lists them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceRef<'a> {
+    Index(usize),
+    Label(&'a str),
+}
+
+impl From<usize> for DeviceRef<'_> {
+    fn from(index: usize) -> Self {
+        DeviceRef::Index(index)
+    }
+}
+
+impl<'a> From<&'a str> for DeviceRef<'a> {
+    fn from(label: &'a str) -> Self {
+        DeviceRef::Label(label)
+    }
+}
+
+impl<'a> From<&'a String> for DeviceRef<'a> {
+    fn from(label: &'a String) -> Self {
+        DeviceRef::Label(label)
+    }
+}
+
+impl std::fmt::Display for DeviceRef<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeviceRef::Index(index) => write!(f, "{index}"),
+            DeviceRef::Label(label) => write!(f, "`{label}`"),
+        }
+    }
+}
+
+/// How a Workgroup weights its devices when dividing work between them.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Weighting {
+    /// Estimate each device's compute power from its limits and type, unless the Workgroup
+    /// was given a [calibration file](WorkgroupBuilder::calibration_file) with a
+    /// [profile](crate::calibration) for every device to go by.
+    #[default]
+    Estimated,
+    /// Go by the devices' [calibration](crate::calibration) profiles, kept in the user's
+    /// cache directory unless the Workgroup was given a
+    /// [file](WorkgroupBuilder::calibration_file) of its own, and if any device has none,
+    /// [calibrate](Workgroup::calibrate) them all when the Workgroup is built, which
+    /// blocks while they are measured. Should calibrating fail, the devices are weighted by
+    /// estimate.
+    Calibrated,
+    /// Give every device the same share.
+    Uniform,
+    /// Use these weights, in the order the devices were supplied or enumerated, before
+    /// any were [denied](WorkgroupBuilder::deny). How many devices there will be can't be
+    /// known up front, so devices past the end of the list get the mean of the weights
+    /// given, and weights past the last device are ignored.
+    ///
+    /// Weights that are negative or not finite, or that leave every remaining device
+    /// weighted zero, are no use, and the devices are weighted by estimate instead.
+    Manual(Vec<f32>),
+}
+
+impl Weighting {
+    /// Weights for `devices`, where `manual` holds the [`Manual`](Weighting::Manual)
+    /// weights of each, if that is the weighting.
+    fn weigh(&self, devices: &[VDevice], manual: Vec<f32>) -> Vec<f32> {
+        match self {
+            Weighting::Uniform => vec![1.0; devices.len()],
+            Weighting::Manual(_) if usable_weights(&manual) => manual,
+            _ => devices.iter().map(estimate_weight).collect(),
+        }
+    }
+
+    /// The weight of each of `count` devices under [`Manual`](Weighting::Manual)
+    /// weighting, or nothing for the others.
+    fn manual_weights(&self, count: usize) -> Vec<f32> {
+        let Weighting::Manual(weights) = self else {
+            return vec![0.0; count];
+        };
+
+        let mean = match weights.len() {
+            0 => 1.0,
+            given => weights.iter().sum::<f32>() / given as f32,
+        };
+
+        (0..count)
+            .map(|index| weights.get(index).copied().unwrap_or(mean))
+            .collect()
+    }
+}
+
+/// Whether `weights` can be normalized into weightings: none negative or not finite, and
+/// not all zero. No weights at all, for no devices, are fine.
+fn usable_weights(weights: &[f32]) -> bool {
+    weights
+        .iter()
+        .all(|weight| weight.is_finite() && *weight >= 0.0)
+        && (weights.is_empty() || weights.iter().sum::<f32>() > 0.0)
+}
+
+// If we have multiple devices, we weight them based on estimates of their
+// compute power.
+//
+// This estimation is very crude. Users who know better can weight the devices
+// themselves, with Weighting::Manual or Workgroup::set_vdevice_weightings, or
+// measure them with Workgroup::calibrate.
+fn estimate_weight(vd: &VDevice) -> f32 {
+    let base = vd.limits.max_compute_invocations_per_workgroup as f32;
+
+    let memory_proxy = if vd.info.device_type == wgpu::DeviceType::Cpu {
+        1.0
+    } else {
+        (vd.limits.max_buffer_size as f32 / 1_048_576.0)
+            .log2()
+            .max(1.0)
+    };
+
+    let type_multiplier = match vd.info.device_type {
+        wgpu::DeviceType::DiscreteGpu => 10.0,
+        wgpu::DeviceType::IntegratedGpu => 3.0,
+        wgpu::DeviceType::VirtualGpu => 2.0,
+        wgpu::DeviceType::Cpu => 1.0,
+        wgpu::DeviceType::Other => 1.0,
+    };
+
+    base * memory_proxy * type_multiplier
+}
+
+/// A shader compiled on every device of a [`Workgroup`], with its IR when the source could
+/// be reflected on the host.
+pub(crate) struct RegisteredShader {
+    pub(crate) modules: Vec<wgpu::ShaderModule>,
+    pub(crate) reflection: Option<naga::Module>,
+    // Identifies the source in result cache fingerprints, for kinds of source that have one.
+    pub(crate) source_hash: Option<u64>,
+    // Kept to compile the shader again for a device that replaces a lost one.
+    pub(crate) source: Option<wgpu::ShaderSource<'static>>,
+}
+
+impl RegisteredShader {
+    fn compile(vdevices: &[VDevice], source: wgpu::ShaderModuleDescriptor) -> Self {
+        // A source that doesn't parse fails to compile below, with wgpu's own report.
+        let reflection = reflect::parse(&source.source).ok().flatten();
+
+        let modules = vdevices
+            .iter()
+            .map(|vd| shader::create_module(vd, &source))
+            .collect();
+
+        Self {
+            modules,
+            reflection,
+            source_hash: cache::source_hash(&source.source),
+            source: shader::owned_source(&source.source),
+        }
+    }
+}
+
+/// Collects the device selection, weighting, and limit options for a [`Workgroup`] in one
+/// place.
+///
+/// ```no_run
+/// use wisc::prelude::*;
+/// use wisc::vdevice::Backend;
+/// use wisc::workgroup::Weighting;
+///
+/// let workgroup = WorkgroupBuilder::new()
+///     .weighting(Weighting::Uniform)
+///     .backend_preference([Backend::Vulkan, Backend::Metal])
+///     .deny(["llvmpipe"])
+///     .build();
+/// ```
+#[derive(Default)]
+pub struct WorkgroupBuilder {
+    devices: Option<Vec<VDevice>>,
+    selection: DeviceSelection,
+    weighting: Weighting,
+    pipeline_cache_dir: Option<PathBuf>,
+    throttle: Option<f32>,
+    result_cache: Option<(PathBuf, u64)>,
+    shaders: Vec<(String, Shader<'static>)>,
+    cpu_fallback: bool,
+    // None for the weighting's default, Some(None) for no file at all.
+    calibration_file: Option<Option<PathBuf>>,
+}
+
+impl WorkgroupBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses these devices instead of enumerating the system's adapters. Device selection
+    /// options other than [`deny`](Self::deny) then have no effect.
+    pub fn devices(mut self, devices: Vec<VDevice>) -> Self {
+        self.devices.replace(devices);
+
+        self
+    }
+
+    pub fn weighting(mut self, weighting: Weighting) -> Self {
+        self.weighting = weighting;
+
+        self
+    }
+
+    pub fn limits_policy(mut self, limits: LimitsPolicy) -> Self {
+        self.selection.limits = limits;
+
+        self
+    }
+
+    /// Sets which backend to prefer when one physical device is exposed through several.
+    pub fn backend_preference<I: IntoIterator<Item = Backend>>(mut self, backends: I) -> Self {
+        self.selection.backends = backends.into_iter().map(Into::into).collect();
+
+        self
+    }
+
+    /// Skips every adapter whose name contains one of `names`, ignoring case.
+    pub fn deny<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.selection.deny = names.into_iter().map(Into::into).collect();
+
+        self
+    }
+
+    pub fn features<F: Into<Features>, G: Into<Features>>(
+        mut self,
+        requested: F,
+        required: G,
+    ) -> Self {
+        self.selection.requested = requested.into().into();
+        self.selection.required = required.into().into();
+
+        self
+    }
+
+    /// Hands SPIR-V shader modules (see [`crate::shader::spirv`]) straight to the driver on
+    /// devices that support it, skipping wgpu's translation and validation. Devices that
+    /// don't still translate them as usual.
+    ///
+    /// # Safety
+    ///
+    /// Nothing checks passed-through modules, so every SPIR-V module used with the
+    /// Workgroup must be valid for the devices it runs on, and must match how tasks bind
+    /// its resources; otherwise the driver may misbehave or crash. This also opts into
+    /// wgpu's experimental features, which may themselves have bugs.
+    #[cfg(feature = "spirv")]
+    pub unsafe fn spirv_passthrough(mut self) -> Self {
+        self.selection.requested |= wgpu::Features::EXPERIMENTAL_PASSTHROUGH_SHADERS;
+        // SAFETY: the caller accepts the risks of experimental features, as above.
+        self.selection.experimental = unsafe { wgpu::ExperimentalFeatures::enabled() };
+
+        self
+    }
+
+    /// Keeps the driver's compiled pipelines in `dir`, one file per kind of adapter, so
+    /// that tasks built in a later run of the application skip most of the compilation.
+    /// Caches found there are loaded now, and written back by
+    /// [`Workgroup::save_pipeline_cache`].
+    ///
+    /// Only devices that support [`wgpu::Features::PIPELINE_CACHE`] (for now, Vulkan
+    /// ones) keep a cache; the rest compile as usual.
+    pub fn pipeline_cache_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.pipeline_cache_dir.replace(dir.into());
+
+        self
+    }
+
+    /// Opens only Vulkan devices that can import memory allocated by other APIs, with
+    /// [`Workgroup::import_external_memory`], preferring their Vulkan adapters to other
+    /// backends. Devices that can't are skipped.
+    #[cfg(all(feature = "vulkan-interop", unix))]
+    pub fn external_memory(mut self) -> Self {
+        self.selection.external_memory = true;
+        self.selection
+            .backends
+            .retain(|backend| *backend != wgpu::Backend::Vulkan);
+        self.selection.backends.insert(0, wgpu::Backend::Vulkan);
+
+        self
+    }
+
+    /// Keeps every device busy for no more than about `fraction` of the time (clamped to
+    /// between 0.01 and 1), for batch jobs sharing a machine with someone's desktop. Tasks
+    /// and streamed chunks are followed by idle gaps in proportion to how long they took,
+    /// as are the sub-dispatches of time-sliced tasks, which make the gaps finer-grained.
+    pub fn throttle(mut self, fraction: f32) -> Self {
+        self.throttle.replace(fraction.clamp(0.01, 1.0));
+
+        self
+    }
+
+    /// Memoizes task results in `dir`, keyed by a fingerprint of the shader, kernel,
+    /// constants, dispatch, partitioning and the contents of every bound buffer, so that
+    /// running a task identical to an earlier one restores its outputs (and reports
+    /// [`from_cache`](crate::report::RunReport::from_cache)) without dispatching it. The
+    /// least recently used results are dropped to keep `dir` under about `max_bytes`.
+    ///
+    /// Tasks with counted or append outputs, custom merges, indirect dispatches, or shaders
+    /// that can't be hashed always run. Kernels must be deterministic for their results to
+    /// be reused.
+    pub fn result_cache<P: Into<PathBuf>>(mut self, dir: P, max_bytes: u64) -> Self {
+        self.result_cache.replace((dir.into(), max_bytes));
+
+        self
+    }
+
+    /// Lets the [ops](crate::ops) ([`map`](crate::ops::map), [`reduce`](crate::ops::reduce)
+    /// and [`sort`](crate::ops::sort)) compute on the host's cores if the Workgroup ends up
+    /// with no devices, rather than fail with [`WiscError::NoDevices`]. Libraries built on
+    /// them then work on machines without a GPU, and use one where there is.
+    pub fn cpu_fallback(mut self) -> Self {
+        self.cpu_fallback = true;
+
+        self
+    }
+
+    /// Keeps calibration profiles in the file at `path`. With the default
+    /// [`Weighting::Estimated`] or with [`Weighting::Calibrated`], devices that all have a
+    /// profile there are weighted by it, and [`Workgroup::calibrate`] saves to it.
+    ///
+    /// Without one, only [`Weighting::Calibrated`] uses profiles, from the user's cache
+    /// directory; otherwise the devices are weighted the same on every machine, whatever
+    /// was calibrated there before.
+    pub fn calibration_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.calibration_file.replace(Some(path.into()));
+
+        self
+    }
+
+    /// Neither loads calibration profiles nor saves them, even with
+    /// [`Weighting::Calibrated`], so calibrating touches no file.
+    pub fn without_calibration_file(mut self) -> Self {
+        self.calibration_file.replace(None);
+
+        self
+    }
+
+    /// Registers a shader under `name` once the Workgroup is built, as
+    /// [`Workgroup::register_shader`] would. Call it once per shader; they are compiled
+    /// together, each on its own thread.
+    pub fn shader<S: Into<String>, D: Into<Shader<'static>>>(mut self, name: S, source: D) -> Self {
+        self.shaders.push((name.into(), source.into()));
+
+        self
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn build(mut self) -> Workgroup {
+        let devices = match self.devices.take() {
+            Some(devices) => devices,
+            None => future::block_on(self.selection.enumerate()),
+        };
+
+        self.assemble(devices)
+    }
+
+    /// Like [`build`](Self::build), but without blocking on the adapters, as the browser
+    /// requires.
+    pub async fn build_async(mut self) -> Workgroup {
+        let devices = match self.devices.take() {
+            Some(devices) => devices,
+            None => self.selection.enumerate().await,
+        };
+
+        self.assemble(devices)
+    }
+
+    fn assemble(self, devices: Vec<VDevice>) -> Workgroup {
+        // Manual weights go with the devices as supplied or enumerated, so they are paired
+        // up before any are denied.
+        let manual = self.weighting.manual_weights(devices.len());
+        let (devices, manual): (Vec<VDevice>, Vec<f32>) = devices
+            .into_iter()
+            .zip(manual)
+            .filter(|(vd, _)| !self.selection.is_denied(&vd.info.name))
+            .unzip();
+
+        // Profiles are only read from the user's cache directory when asked for, so that
+        // nothing calibrated there earlier changes how work is split.
+        let calibration_file = match self.calibration_file {
+            Some(file) => file,
+            None if self.weighting == Weighting::Calibrated => default_calibration_file(),
+            None => None,
+        };
+
+        // A profile measured on these devices is a better estimate than their limits.
+        let calibrated = match (&self.weighting, &calibration_file) {
+            (Weighting::Estimated | Weighting::Calibrated, Some(path)) => {
+                load_weights(path, &devices)
+            }
+            _ => None,
+        };
+        let uncalibrated = self.weighting == Weighting::Calibrated && calibrated.is_none();
+        let weights = calibrated.unwrap_or_else(|| self.weighting.weigh(&devices, manual));
+
+        let mut workgroup = Workgroup::from_weighted_devices(devices, weights);
+        workgroup.calibration_file = calibration_file;
+
+        if uncalibrated {
+            // The estimates stay if it fails.
+            let _ = workgroup.calibrate();
+        }
+
+        workgroup.throttle = self.throttle;
+        workgroup.cpu_fallback = self.cpu_fallback;
+        workgroup.result_cache = self
+            .result_cache
+            .map(|(dir, max_bytes)| ResultCache { dir, max_bytes });
+
+        if let Some(dir) = self.pipeline_cache_dir {
+            workgroup.load_pipeline_cache(dir);
+        }
+
+        if !self.shaders.is_empty() {
+            workgroup.register_shaders(self.shaders);
+        }
+
+        workgroup
     }
 }
@@ -0,0 +1,122 @@
+use wisc::prelude::*;
+
+#[test]
+fn alias_output_as_input() {
+    // Get all the hardware devices available to our system.
+    let devices = VDevice::all();
+
+    // Create a Workgroup out of our device(s).
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    // Register our buffers with the runtime.
+    let ibuf1 = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let obuf1 = workgroup.create_vbuffer(vec![0u32; 1024]);
+    let obuf2 = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    // Nothing has written the output yet, so there is nothing to alias.
+    assert!(workgroup.alias_output_as_input(obuf1).is_err());
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_kernel("main")
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, ibuf1)
+        .with_input_buffer(1, ibuf2)
+        .with_output_buffer(2, obuf1)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    // Feed the device-resident result straight into the next stage.
+    let intermediate = workgroup
+        .alias_output_as_input(obuf1)
+        .expect("Output should be resident after a run");
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_kernel("main")
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, intermediate)
+        .with_input_buffer(1, ibuf2)
+        .with_output_buffer(2, obuf2)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let obuf2: Vec<u32> = workgroup.take_vbuffer(obuf2).unwrap();
+
+    assert_eq!(obuf2, vec![8u32; 1024]);
+}
+
+#[test]
+fn alias_rejects_read_write_hazard() {
+    let devices = VDevice::all();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let ibuf = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let obuf = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_kernel("main")
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, ibuf)
+        .with_input_buffer(1, ibuf)
+        .with_output_buffer(2, obuf)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let aliased = workgroup.alias_output_as_input(obuf).unwrap();
+
+    // Reading and writing the same device buffer in one dispatch is a hazard.
+    let task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_kernel("main")
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, aliased)
+        .with_input_buffer(1, ibuf)
+        .with_output_buffer(2, aliased)
+        .build();
+
+    assert!(task.is_err());
+}
+
+#[test]
+fn aliased_outputs_are_read_back_once_taken() {
+    // The same devices twice over, so the buffers are split even on a single GPU.
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let ping = workgroup.create_vbuffer(vec![1u32; 1024]);
+    let pong = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    let double = |workgroup: &mut Workgroup, input, output| {
+        TaskBuilder::new(workgroup, include_wgsl!("./double.wgsl"))
+            .with_size_per_element(output)
+            .with_input_buffer_partitioned(0, input, PartitionMode::Weighted)
+            .with_output_buffer_partitioned(1, output, PartitionMode::Weighted)
+            .build()
+            .expect("Failed to build task")
+            .run()
+            .expect("Failed to run task")
+    };
+
+    for step in 0..4 {
+        let report = double(&mut workgroup, ping, pong);
+        let pong = workgroup.alias_output_as_input(pong).unwrap();
+        let read_back: u64 = report.timings.iter().map(|t| t.bytes_read_back).sum();
+
+        // Only the first run writes the output back; later ones leave it aliased.
+        assert_eq!(read_back == 0, step > 0);
+
+        double(&mut workgroup, pong, ping);
+        workgroup.alias_output_as_input(ping).unwrap();
+    }
+
+    let ping: Vec<u32> = workgroup.take_vbuffer(ping).unwrap();
+    let pong: Vec<u32> = workgroup.take_vbuffer(pong).unwrap();
+
+    assert_eq!(ping, vec![256u32; 1024]);
+    assert_eq!(pong, vec![128u32; 1024]);
+}
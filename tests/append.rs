@@ -0,0 +1,75 @@
+use wisc::{partition::PartitionMode, prelude::*};
+
+fn two_devices() -> Workgroup {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    Workgroup::from_devices(devices)
+}
+
+#[test]
+fn append_output_concatenates_each_devices_results() {
+    let mut workgroup = two_devices();
+
+    let input = workgroup.create_vbuffer((0..1000u32).collect());
+    let evens = workgroup.create_vbuffer_uninit::<u32>(0);
+
+    let report = TaskBuilder::new(&mut workgroup, include_wgsl!("./append_evens.wgsl"))
+        .with_size_per_element(input)
+        .with_input_buffer_partitioned(0, input, PartitionMode::Split)
+        .with_append_output::<u32>(1, 2, evens, 1000)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    // Each device found the evens in its own half.
+    assert_eq!(report.appended.len(), 1);
+    assert_eq!(report.appended[0].lengths, vec![250, 250]);
+    assert_eq!(report.appended[0].dropped, 0);
+
+    let mut evens: Vec<u32> = workgroup.take_vbuffer(evens).unwrap();
+
+    // Appends land in any order within each device's share.
+    assert!(evens[..250].iter().all(|value| *value < 500));
+    evens.sort();
+    assert_eq!(evens, (0..1000u32).step_by(2).collect::<Vec<_>>());
+}
+
+#[test]
+fn append_output_counts_what_overflows() {
+    let mut workgroup = two_devices();
+
+    let input = workgroup.create_vbuffer((0..1000u32).collect());
+    let evens = workgroup.create_vbuffer_uninit::<u32>(0);
+
+    let report = TaskBuilder::new(&mut workgroup, include_wgsl!("./append_evens.wgsl"))
+        .with_size_per_element(input)
+        .with_input_buffer_partitioned(0, input, PartitionMode::Split)
+        .with_append_output::<u32>(1, 2, evens, 100)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    assert_eq!(report.appended[0].lengths, vec![100, 100]);
+    assert_eq!(report.appended[0].dropped, 300);
+
+    let evens: Vec<u32> = workgroup.take_vbuffer(evens).unwrap();
+    assert_eq!(evens.len(), 200);
+    assert!(evens.iter().all(|value| value % 2 == 0));
+}
+
+#[test]
+fn append_output_needs_capacity() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let input = workgroup.create_vbuffer((0..64u32).collect());
+    let evens = workgroup.create_vbuffer_uninit::<u32>(0);
+
+    let result = TaskBuilder::new(&mut workgroup, include_wgsl!("./append_evens.wgsl"))
+        .with_size_per_element(input)
+        .with_input_buffer(0, input)
+        .with_append_output::<u32>(1, 2, evens, 0)
+        .build();
+
+    assert!(matches!(result, Err(WiscError::InvalidBinding(_))));
+}
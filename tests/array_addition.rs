@@ -15,7 +15,7 @@ fn array_addition() {
     let obuf1: VBufferHandle = workgroup.create_vbuffer(vec![0u32; 1024]);
 
     // Define our task and input our buffers.
-    let task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
         .with_kernel("main")
         // The shader is defined with workgroup size (256, 1, 1), so 4 * 256 invocations is
         // enough to cover the length of our data (1024)
@@ -27,7 +27,7 @@ fn array_addition() {
         .expect("Failed to build task");
 
     // Block the current thread while the task runs.
-    task.run();
+    task.run().expect("Failed to run task");
 
     // Take ownership of the buffer from the runtime.
     let obuf1: Vec<u32> = workgroup.take_vbuffer(obuf1).unwrap();
@@ -51,7 +51,7 @@ fn array_addition_safety() {
     let obuf1 = workgroup.create_vbuffer(vec![0u32; 1024]);
 
     // Define our task and input our buffers.
-    let task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
         .with_kernel("main")
         .with_size((4, 1, 1))
         .with_input_buffer(0, ibuf1)
@@ -61,21 +61,121 @@ fn array_addition_safety() {
         .expect("Failed to build task");
 
     // Block the current thread while the task runs.
-    task.run();
+    task.run().expect("Failed to run task");
 
     // Take ownership of the buffer from the runtime.
-    // Only the correct type decoding will yield Some(_), allowing access.
-    let maybe_obuf1_a: Option<Vec<f32>> = workgroup.take_vbuffer(obuf1);
-    let maybe_obuf1_b: Option<Vec<u64>> = workgroup.take_vbuffer(obuf1);
-    let maybe_obuf1_c: Option<Vec<u8>> = workgroup.take_vbuffer(obuf1);
+    // Only the correct type decoding will yield Ok(_), allowing access.
+    let maybe_obuf1_a: Result<Vec<f32>, WiscError> = workgroup.take_vbuffer(obuf1);
+    let maybe_obuf1_b: Result<Vec<u64>, WiscError> = workgroup.take_vbuffer(obuf1);
+    let maybe_obuf1_c: Result<Vec<u8>, WiscError> = workgroup.take_vbuffer(obuf1);
 
-    let maybe_obuf1_d: Option<Vec<u32>> = workgroup.take_vbuffer(obuf1);
+    let maybe_obuf1_d: Result<Vec<u32>, WiscError> = workgroup.take_vbuffer(obuf1);
 
-    assert!(maybe_obuf1_a.is_none());
-    assert!(maybe_obuf1_b.is_none());
-    assert!(maybe_obuf1_c.is_none());
+    assert_eq!(maybe_obuf1_a, Err(WiscError::TypeMismatch));
+    assert!(maybe_obuf1_b.is_err());
+    assert!(maybe_obuf1_c.is_err());
 
-    assert!(maybe_obuf1_d.is_some());
+    assert!(maybe_obuf1_d.is_ok());
 
     // Any buffers still managed by the runtime are freed automatically.
 }
+
+#[test]
+fn array_addition_typed_bindings() {
+    let devices = VDevice::all();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let ibuf1 = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let obuf1 = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    // A binding declared with the wrong element type is caught when the task is built.
+    let task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size((4, 1, 1))
+        .with_typed_input_buffer::<u32>(0, ibuf1)
+        .with_typed_input_buffer::<f32>(1, ibuf2)
+        .with_typed_output_buffer::<u32>(2, obuf1)
+        .build();
+
+    assert!(task.is_err());
+
+    let task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size((4, 1, 1))
+        .with_typed_input_buffer::<u32>(0, ibuf1)
+        .with_typed_input_buffer::<u32>(1, ibuf2)
+        .with_typed_output_buffer::<u32>(2, obuf1)
+        .build();
+
+    assert!(task.is_ok());
+}
+
+#[test]
+fn array_addition_repeated_builds() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let mut values = vec![1u32; 1024];
+
+    // Tasks built again from the same sources reuse their compiled modules; interleaving
+    // two shaders checks that each still gets its own.
+    for _ in 0..4 {
+        let a = workgroup.create_vbuffer(values.clone());
+        let b = workgroup.create_vbuffer(vec![1u32; 1024]);
+        let sum = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+        TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+            .with_size_per_element(sum)
+            .with_input_buffer(0, a)
+            .with_input_buffer(1, b)
+            .with_output_buffer(2, sum)
+            .build()
+            .expect("Failed to build task")
+            .run()
+            .expect("Failed to run task");
+
+        let doubled = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+        TaskBuilder::new(&mut workgroup, include_wgsl!("./double.wgsl"))
+            .with_size_per_element(doubled)
+            .with_input_buffer(0, sum)
+            .with_output_buffer(1, doubled)
+            .build()
+            .expect("Failed to build task")
+            .run()
+            .expect("Failed to run task");
+
+        values = workgroup.take_vbuffer(doubled).unwrap();
+    }
+
+    // 1 -> 4 -> 10 -> 22 -> 46
+    assert_eq!(values, vec![46u32; 1024]);
+}
+
+#[test]
+fn array_addition_rerun() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let a = workgroup.create_vbuffer((0..1024u32).collect());
+    let b = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let sum = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size_per_element(sum)
+        .with_input_buffer(0, a)
+        .with_input_buffer(1, b)
+        .with_output_buffer(2, sum)
+        .build()
+        .expect("Failed to build task");
+
+    // The same task runs again and again, and its results can be read in between.
+    let mut results = vec![std::mem::MaybeUninit::<u32>::uninit(); 1024];
+
+    for _ in 0..8 {
+        task.run().expect("Failed to run task");
+
+        let read = task
+            .workgroup()
+            .read_vbuffer_into(sum, &mut results)
+            .unwrap();
+        assert_eq!(read, (3..1027u32).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,45 @@
+use futures_lite::future;
+use wisc::prelude::*;
+
+#[test]
+fn async_device_constructors() {
+    let devices = future::block_on(VDevice::all_async());
+    let blocking = VDevice::all();
+    assert_eq!(devices.len(), blocking.len());
+
+    let workgroup = future::block_on(WorkgroupBuilder::new().build_async());
+    assert_eq!(workgroup.vdevice_weightings().len(), blocking.len());
+}
+
+// Tokio waits for the devices on its own blocking threads, which need a runtime.
+#[cfg(not(feature = "tokio"))]
+#[test]
+fn run_async_without_a_runtime() {
+    let devices = future::block_on(VDevice::all_async());
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let a = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let b = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let c = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, a)
+        .with_input_buffer(1, b)
+        .with_output_buffer(2, c)
+        .build()
+        .expect("Failed to build task");
+
+    future::block_on(task.run_async()).expect("Failed to run task");
+
+    drop(task);
+    assert_eq!(workgroup.take_vbuffer::<u32>(c).unwrap(), vec![5u32; 1024]);
+}
+
+#[cfg(not(feature = "tokio"))]
+#[test]
+fn wait_idle_async_without_a_runtime() {
+    let workgroup = Workgroup::from_devices(VDevice::all());
+
+    future::block_on(workgroup.wait_idle_async()).expect("Failed to wait for the devices");
+}
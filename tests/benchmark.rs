@@ -0,0 +1,37 @@
+#![cfg(feature = "benchmarks")]
+
+use wisc::{prelude::*, workgroup::Weighting};
+
+#[test]
+fn benchmark_suite_scores_devices() {
+    let devices: Vec<VDevice> = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let device_count = devices.len();
+    let workgroup = Workgroup::from_devices(devices);
+
+    let suite = workgroup.benchmark_suite().expect("Failed to benchmark");
+    assert_eq!(suite.devices.len(), device_count);
+
+    for device in &suite.devices {
+        assert_eq!(device.kernels.len(), 4);
+        assert!(device.score > 0.0 && device.score <= 1.0);
+
+        for kernel in &device.kernels {
+            assert!(kernel.throughput > 0.0);
+            assert!(kernel.normalized > 0.0 && kernel.normalized <= 1.0);
+        }
+    }
+
+    // Some device ran each kernel fastest.
+    for k in 0..4 {
+        assert!(
+            suite
+                .devices
+                .iter()
+                .any(|device| device.kernels[k].normalized == 1.0)
+        );
+    }
+
+    assert!(
+        matches!(suite.weighting(), Weighting::Manual(weights) if weights.len() == device_count)
+    );
+}
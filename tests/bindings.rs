@@ -0,0 +1,53 @@
+use wisc::prelude::*;
+
+#[test]
+fn bindings_are_checked_against_the_shader() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let ibuf1 = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let obuf1 = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    let build = |workgroup: &mut Workgroup, inputs: &[u32], outputs: &[u32]| {
+        let mut builder = TaskBuilder::new(workgroup, include_wgsl!("./array_addition.wgsl"))
+            .with_size((4, 1, 1));
+
+        for (&id, handle) in inputs.iter().zip([ibuf1, ibuf2]) {
+            builder = builder.with_input_buffer(id, handle);
+        }
+        for &id in outputs {
+            builder = builder.with_output_buffer(id, obuf1);
+        }
+
+        builder.build().err()
+    };
+
+    // The shader has no binding 3.
+    assert_eq!(
+        build(&mut workgroup, &[0, 1], &[3]),
+        Some(WiscError::BindingMismatch {
+            binding: 3,
+            reason: "the shader doesn't declare it",
+        })
+    );
+
+    // Binding 2 is the shader's read_write result, not an input.
+    assert_eq!(
+        build(&mut workgroup, &[0, 2], &[1]),
+        Some(WiscError::BindingMismatch {
+            binding: 2,
+            reason: "bound as an input, but the shader writes to it",
+        })
+    );
+
+    // The kernel reads binding 1, so leaving it out would fail at dispatch.
+    assert_eq!(
+        build(&mut workgroup, &[0], &[2]),
+        Some(WiscError::BindingMismatch {
+            binding: 1,
+            reason: "the kernel uses it, but nothing is bound there",
+        })
+    );
+
+    assert_eq!(build(&mut workgroup, &[0, 1], &[2]), None);
+}
@@ -0,0 +1,33 @@
+use wisc::prelude::*;
+
+#[test]
+fn broadcast_table_split_work() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let table = workgroup.create_vbuffer((0..64u32).map(|x| x * x).collect());
+
+    // Every task reads the same table; only the first uploads it.
+    for round in 0..3u32 {
+        let keys = workgroup.create_vbuffer((0..1000u32).map(|x| x + round).collect());
+        let values = workgroup.create_vbuffer_uninit::<u32>(1000);
+
+        TaskBuilder::new(&mut workgroup, include_wgsl!("./lookup.wgsl"))
+            .with_size_per_element(values)
+            .with_broadcast_input(0, table)
+            .with_split_input(1, keys)
+            .with_split_output(2, values)
+            .build()
+            .expect("Failed to build task")
+            .run()
+            .expect("Failed to run task");
+
+        let values: Vec<u32> = workgroup.take_vbuffer(values).unwrap();
+        let expected: Vec<u32> = (0..1000u32).map(|x| ((x + round) % 64).pow(2)).collect();
+        assert_eq!(values, expected);
+    }
+
+    // The table is still whole on the host.
+    let table: Vec<u32> = workgroup.take_vbuffer(table).unwrap();
+    assert_eq!(table, (0..64u32).map(|x| x * x).collect::<Vec<_>>());
+}
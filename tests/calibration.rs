@@ -0,0 +1,130 @@
+use std::fs;
+
+use wisc::prelude::*;
+use wisc::workgroup::Weighting;
+
+#[test]
+fn calibration_weights_the_devices() {
+    let devices: Vec<VDevice> = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let device_count = devices.len();
+    let mut workgroup = WorkgroupBuilder::new()
+        .devices(devices)
+        .without_calibration_file()
+        .build();
+
+    let calibration = workgroup.calibrate().expect("Failed to calibrate");
+    assert_eq!(calibration.devices.len(), device_count);
+
+    let weightings = workgroup.vdevice_weightings();
+    for (device, (label, weighting)) in calibration.devices.iter().zip(&weightings) {
+        assert!(device.throughput > 0.0);
+        assert_eq!(&device.label, label);
+        assert_eq!(device.weighting, *weighting);
+    }
+
+    let total: f32 = weightings.iter().map(|(_, weighting)| weighting).sum();
+    assert!((total - 1.0).abs() < 1e-4);
+
+    // Tasks divide their buffers by the new weightings.
+    let a = workgroup.create_vbuffer(vec![1u32; 4096]);
+    let b = workgroup.create_vbuffer(vec![2u32; 4096]);
+    let c = workgroup.create_vbuffer_uninit::<u32>(4096);
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size_per_element(c)
+        .with_input_buffer(0, a)
+        .with_input_buffer(1, b)
+        .with_output_buffer(2, c)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    assert_eq!(workgroup.take_vbuffer(c), Ok(vec![3u32; 4096]));
+}
+
+#[test]
+fn calibration_profiles_are_loaded_by_later_workgroups() {
+    let path = std::env::temp_dir().join(format!("wisc-calibration-{}", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let mut workgroup = WorkgroupBuilder::new()
+        .devices(VDevice::all())
+        .calibration_file(&path)
+        .build();
+    let calibration = workgroup.calibrate().expect("Failed to calibrate");
+
+    let profiles = fs::read_to_string(&path).expect("Failed to read the profiles");
+    assert!(!profiles.is_empty());
+
+    // Every device has a profile, so a later Workgroup goes by it rather than estimating.
+    let later = WorkgroupBuilder::new()
+        .devices(VDevice::all())
+        .calibration_file(&path)
+        .build();
+
+    let weightings: Vec<f32> = later
+        .vdevice_weightings()
+        .into_iter()
+        .map(|(_, weighting)| weighting)
+        .collect();
+    let measured: Vec<f32> = calibration
+        .devices
+        .iter()
+        .map(|device| device.weighting)
+        .collect();
+    assert_eq!(weightings, measured);
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn calibrated_weighting_calibrates_devices_without_a_profile() {
+    let path =
+        std::env::temp_dir().join(format!("wisc-calibration-required-{}", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let workgroup = WorkgroupBuilder::new()
+        .devices(VDevice::all())
+        .weighting(Weighting::Calibrated)
+        .calibration_file(&path)
+        .build();
+
+    // Building measured the devices, and saved what it measured.
+    let profiles = fs::read_to_string(&path).expect("Failed to read the profiles");
+    assert!(!profiles.is_empty());
+
+    let total: f32 = workgroup
+        .vdevice_weightings()
+        .iter()
+        .map(|(_, weighting)| weighting)
+        .sum();
+    assert!((total - 1.0).abs() < 1e-4);
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn workgroups_only_load_profiles_when_asked() {
+    let estimated = |workgroup: Workgroup| -> Vec<f32> {
+        workgroup
+            .vdevice_weightings()
+            .into_iter()
+            .map(|(_, weighting)| weighting)
+            .collect()
+    };
+    let expected = estimated(
+        WorkgroupBuilder::new()
+            .devices(VDevice::all())
+            .without_calibration_file()
+            .build(),
+    );
+
+    // Neither the zero-config constructor nor a default builder reads a profile file, so
+    // whatever was calibrated on this machine before can't change the split.
+    assert_eq!(estimated(Workgroup::from_devices(VDevice::all())), expected);
+    assert_eq!(
+        estimated(WorkgroupBuilder::new().devices(VDevice::all()).build()),
+        expected
+    );
+}
@@ -0,0 +1,32 @@
+use wisc::{partition::PartitionMode, prelude::*};
+
+#[test]
+fn checksums_verify_readback() {
+    // Two devices, so each checksums its own half.
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let ibuf1 = workgroup.create_vbuffer((0..1024u32).collect());
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let obuf1 = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size_per_element(obuf1)
+        .with_input_buffer_partitioned(0, ibuf1, PartitionMode::Split)
+        .with_input_buffer_partitioned(1, ibuf2, PartitionMode::Split)
+        .with_output_buffer_partitioned(2, obuf1, PartitionMode::Split)
+        .with_checksums()
+        .build()
+        .expect("Failed to build task");
+
+    // The checksums start from zero again on every run.
+    for _ in 0..2 {
+        let report = task.run().expect("Checksums should match");
+        assert_eq!(report.checksums_verified, report.devices);
+    }
+
+    drop(task);
+
+    let obuf1: Vec<u32> = workgroup.take_vbuffer(obuf1).unwrap();
+    assert_eq!(obuf1, (3..1027u32).collect::<Vec<_>>());
+}
@@ -0,0 +1,184 @@
+use wisc::{chain::Chain, collective::ReduceOp, prelude::*, task::Task, workgroup::VBufferHandle};
+
+#[test]
+fn reduce_then_broadcast() {
+    // Get all the hardware devices available to our system.
+    let devices = VDevice::all();
+
+    // Create a Workgroup out of our device(s).
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let data: Vec<f32> = (1..=1024).map(|x| x as f32).collect();
+
+    let input = workgroup.create_vbuffer(data.clone());
+    // Uniforms are padded out to a vec4.
+    let max = workgroup.create_vbuffer(vec![0.0f32; 4]);
+    let output = workgroup.create_vbuffer(vec![0.0f32; 1024]);
+
+    // Normalize by the global maximum without reading anything back in between.
+    let reports = Chain::new(&mut workgroup)
+        .then(move |wg| {
+            TaskBuilder::new(wg, include_wgsl!("./partial_max.wgsl"))
+                .with_kernel("main")
+                .with_size((1, 1, 1))
+                .with_input_buffer(0, input)
+                .with_output_buffer(1, max)
+                .build()
+        })
+        .reduce_broadcast::<f32>(max, ReduceOp::Max)
+        .then(move |wg| {
+            TaskBuilder::new(wg, include_wgsl!("./normalize.wgsl"))
+                .with_kernel("main")
+                .with_size((4, 1, 1))
+                .with_input_buffer(0, input)
+                .with_uniform_input(1, max)
+                .with_output_buffer(2, output)
+                .build()
+        })
+        .run()
+        .expect("Chain failed");
+
+    assert_eq!(reports.len(), 2);
+
+    let output: Vec<f32> = workgroup.take_vbuffer(output).unwrap();
+    let expected: Vec<f32> = data.iter().map(|x| x / 1024.0).collect();
+
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn all_gather_between_tasks() {
+    let devices = VDevice::all();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let ibuf1 = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let sum = workgroup.create_vbuffer(vec![0u32; 1024]);
+    let doubled = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    // Every device sees the whole intermediate sum before the second stage.
+    Chain::new(&mut workgroup)
+        .then(move |wg| {
+            TaskBuilder::new(wg, include_wgsl!("./array_addition.wgsl"))
+                .with_kernel("main")
+                .with_size((4, 1, 1))
+                .with_input_buffer(0, ibuf1)
+                .with_input_buffer(1, ibuf2)
+                .with_output_buffer(2, sum)
+                .build()
+        })
+        .all_gather(sum)
+        .then(move |wg| {
+            TaskBuilder::new(wg, include_wgsl!("./double.wgsl"))
+                .with_kernel("main")
+                .with_size((4, 1, 1))
+                .with_input_buffer(0, sum)
+                .with_output_buffer(1, doubled)
+                .build()
+        })
+        .run()
+        .expect("Chain failed");
+
+    let doubled: Vec<u32> = workgroup.take_vbuffer(doubled).unwrap();
+    assert_eq!(doubled, vec![10u32; 1024]);
+}
+
+#[test]
+fn halo_exchange_between_iterations() {
+    let devices = VDevice::all();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let ping = workgroup.create_vbuffer(vec![1u32; 1024]);
+    let pong = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    // Ping-pong between two buffers, refreshing halos after every step.
+    Chain::new(&mut workgroup)
+        .then(move |wg| double(wg, ping, pong))
+        .halo_exchange(pong)
+        .then(move |wg| double(wg, pong, ping))
+        .halo_exchange(ping)
+        .then(move |wg| double(wg, ping, pong))
+        .run()
+        .expect("Chain failed");
+
+    let pong: Vec<u32> = workgroup.take_vbuffer(pong).unwrap();
+    assert_eq!(pong, vec![8u32; 1024]);
+}
+
+#[test]
+fn halo_exchange_between_stencil_steps() {
+    let data: Vec<u32> = (0..1024u32).collect();
+
+    let expected = (0..3).fold(data.clone(), |field, _| {
+        (0..field.len())
+            .map(|i| {
+                let left = if i > 0 { field[i - 1] } else { 0 };
+                let right = field.get(i + 1).copied().unwrap_or(0);
+                left + field[i] + right
+            })
+            .collect::<Vec<u32>>()
+    });
+
+    assert_eq!(stencil_steps(&data, true), expected);
+    // Aliased without an exchange, each device reads the halos it computed itself, which
+    // were missing a neighbour.
+    assert_ne!(stencil_steps(&data, false), expected);
+}
+
+/// Runs three stencil steps over `data` on two devices, keeping the field on the devices
+/// between steps, with or without refreshing the halos.
+fn stencil_steps(data: &[u32], exchange: bool) -> Vec<u32> {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let mut input = workgroup.create_vbuffer(data.to_vec());
+    let mut output = workgroup.create_vbuffer_uninit::<u32>(data.len());
+
+    for _ in 0..3 {
+        stencil(&mut workgroup, input, output)
+            .expect("Failed to build task")
+            .run()
+            .expect("Failed to run task");
+
+        if exchange {
+            workgroup
+                .halo_exchange(output)
+                .expect("Halo exchange failed");
+        } else {
+            workgroup
+                .alias_output_as_input(output)
+                .expect("Aliasing failed");
+        }
+
+        std::mem::swap(&mut input, &mut output);
+    }
+
+    workgroup.take_vbuffer(input).unwrap()
+}
+
+fn stencil(
+    wg: &mut Workgroup,
+    input: VBufferHandle,
+    output: VBufferHandle,
+) -> Result<Task<'_>, WiscError> {
+    let halo = PartitionMode::Split.with_halo(1);
+
+    TaskBuilder::new(wg, include_wgsl!("./stencil.wgsl"))
+        .with_size((5, 1, 1))
+        .with_input_buffer_partitioned(0, input, halo.clone())
+        .with_output_buffer_partitioned(1, output, halo)
+        .build()
+}
+
+fn double(
+    wg: &mut Workgroup,
+    input: VBufferHandle,
+    output: VBufferHandle,
+) -> Result<Task<'_>, WiscError> {
+    TaskBuilder::new(wg, include_wgsl!("./double.wgsl"))
+        .with_kernel("main")
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, input)
+        .with_output_buffer(1, output)
+        .build()
+}
@@ -0,0 +1,144 @@
+use wisc::prelude::*;
+use wisc::shader::CompileMessageKind;
+use wisc::task::OverLimit;
+use wisc::vdevice::LimitsPolicy;
+
+#[test]
+fn clean_shader_compiles_without_errors() {
+    let devices: Vec<VDevice> = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let device_count = devices.len();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let a = workgroup.create_vbuffer(vec![1u32; 1024]);
+    let b = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let c = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    let task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, a)
+        .with_input_buffer(1, b)
+        .with_output_buffer(2, c)
+        .build()
+        .expect("Failed to build task");
+
+    // Whatever a backend has to say about it, nothing it says is an error, and everything
+    // is attributed to a device in the workgroup.
+    assert!(task.compile_messages().iter().all(|message| {
+        message.kind != CompileMessageKind::Error && message.device < device_count
+    }));
+}
+
+#[test]
+fn rejecting_device_sits_out() {
+    // The same adapter twice, once without the feature the shader needs.
+    let devices =
+        VDevice::all_with_features(wgpu::Features::IMMEDIATES, wgpu::Features::IMMEDIATES)
+            .into_iter()
+            .chain(VDevice::all_with_features(
+                wgpu::Features::empty(),
+                wgpu::Features::empty(),
+            ))
+            .collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let input = workgroup.create_vbuffer((0..1024u32).collect());
+    let output = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./unused_immediates.wgsl"))
+        .with_size_per_element(output)
+        .with_input_buffer(0, input)
+        .with_output_buffer(1, output)
+        .build()
+        .expect("Failed to build task");
+
+    assert!(
+        task.compile_messages()
+            .iter()
+            .any(|message| message.device == 1 && message.is_error())
+    );
+
+    let report = task.run().expect("Failed to run task");
+    assert_eq!(report.excluded, [1]);
+
+    // The device that accepted the shader computed every element.
+    drop(task);
+    let output: Vec<u32> = workgroup.take_vbuffer(output).unwrap();
+    assert_eq!(output, (0..1024u32).map(|x| x * 2).collect::<Vec<_>>());
+}
+
+#[test]
+fn shader_rejected_everywhere() {
+    let devices = VDevice::all_with_features(wgpu::Features::empty(), wgpu::Features::empty());
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let input = workgroup.create_vbuffer(vec![1u32; 1024]);
+    let output = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    let task = TaskBuilder::new(&mut workgroup, include_wgsl!("./unused_immediates.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, input)
+        .with_output_buffer(1, output)
+        .build();
+
+    assert!(matches!(task.err(), Some(WiscError::ShaderRejected(_))));
+}
+
+/// The same adapter twice, the second opened with the downlevel limits.
+fn full_and_downlevel() -> Vec<VDevice> {
+    let downlevel = WorkgroupBuilder::new()
+        .limits_policy(LimitsPolicy::Downlevel)
+        .build()
+        .shared_devices();
+
+    VDevice::all().into_iter().chain(downlevel).collect()
+}
+
+#[test]
+fn device_short_of_workgroup_storage_sits_out() {
+    let mut workgroup = Workgroup::from_devices(full_and_downlevel());
+
+    let c = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./workgroup_storage.wgsl"))
+        .with_size_per_element(c)
+        .with_split_output(0, c)
+        .build()
+        .expect("Failed to build task");
+
+    assert!(task.compile_messages().iter().any(|message| {
+        message.device == 1 && message.is_error() && message.message.contains("workgroup storage")
+    }));
+
+    let report = task.run().expect("Failed to run task");
+    assert_eq!(report.excluded, vec![1]);
+
+    drop(task);
+    assert_eq!(
+        workgroup.take_vbuffer::<u32>(c).unwrap(),
+        (0..1024).map(|i| i * 2).collect::<Vec<u32>>()
+    );
+}
+
+#[test]
+fn device_short_of_workgroup_storage_fails_the_build() {
+    let mut workgroup = Workgroup::from_devices(full_and_downlevel());
+
+    let c = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    let result = TaskBuilder::new(&mut workgroup, include_wgsl!("./workgroup_storage.wgsl"))
+        .with_size_per_element(c)
+        .with_split_output(0, c)
+        .with_over_limit(OverLimit::Fail)
+        .build()
+        .map(|_| ());
+
+    assert_eq!(
+        result,
+        Err(WiscError::WorkgroupStorageTooLarge {
+            kernel: "main".to_string(),
+            device: 1,
+            size: 20000,
+            max: wgpu::Limits::downlevel_defaults().max_compute_workgroup_storage_size,
+        })
+    );
+}
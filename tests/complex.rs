@@ -0,0 +1,77 @@
+use wisc::{
+    complex::{COMPLEX32_WGSL, Complex32},
+    partition::PartitionMode,
+    prelude::*,
+};
+
+#[test]
+fn complex_arithmetic() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let a: Vec<Complex32> = (0..500)
+        .map(|i| Complex32::new(i as f32, 1.0 - i as f32 / 2.0))
+        .collect();
+    let b: Vec<Complex32> = (0..500)
+        .map(|i| Complex32::from_polar(2.0, i as f32 * 0.01))
+        .collect();
+
+    let abuf = workgroup.create_vbuffer(a.clone());
+    let bbuf = workgroup.create_vbuffer(b.clone());
+    let product = workgroup.create_vbuffer_uninit::<Complex32>(500);
+    let quotient = workgroup.create_vbuffer_uninit::<Complex32>(500);
+
+    let source = format!("{COMPLEX32_WGSL}{}", include_str!("./complex_mul.wgsl"));
+
+    // Each element is one vec2<f32>, so partitions never split a number in two.
+    TaskBuilder::new(
+        &mut workgroup,
+        wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        },
+    )
+    .with_size_per_element(product)
+    .with_input_buffer_partitioned(0, abuf, PartitionMode::Split)
+    .with_input_buffer_partitioned(1, bbuf, PartitionMode::Split)
+    .with_output_buffer_partitioned(2, product, PartitionMode::Split)
+    .with_output_buffer_partitioned(3, quotient, PartitionMode::Split)
+    .build()
+    .expect("Failed to build task")
+    .run()
+    .expect("Failed to run task");
+
+    let product: Vec<Complex32> = workgroup.take_vbuffer(product).unwrap();
+    let quotient: Vec<Complex32> = workgroup.take_vbuffer(quotient).unwrap();
+
+    for i in 0..500 {
+        let expected = a[i] * b[i];
+        assert!((product[i] - expected).abs() < 1e-3, "{:?}", product[i]);
+
+        // (a / b) * b should give back a.
+        assert!(
+            (quotient[i] * b[i] - a[i]).abs() < 1e-3,
+            "{:?}",
+            quotient[i]
+        );
+    }
+}
+
+#[test]
+fn complex_elements() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let values = vec![Complex32::new(1.0, -1.0), Complex32::new(0.5, 2.0)];
+    let handle = workgroup.create_element_vbuffer(values.clone());
+
+    assert_eq!(
+        workgroup.take_element_vbuffer::<Complex32>(handle).unwrap(),
+        values
+    );
+
+    // Interleaved, as shaders read them.
+    assert_eq!(
+        bytemuck::cast_slice::<Complex32, f32>(&values),
+        [1.0, -1.0, 0.5, 2.0]
+    );
+}
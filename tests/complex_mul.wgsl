@@ -0,0 +1,15 @@
+@group(0) @binding(0) var<storage, read> a: array<vec2<f32>>;
+@group(0) @binding(1) var<storage, read> b: array<vec2<f32>>;
+@group(0) @binding(2) var<storage, read_write> product: array<vec2<f32>>;
+@group(0) @binding(3) var<storage, read_write> quotient: array<vec2<f32>>;
+
+@compute @workgroup_size(64, 1, 1)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let index = global_id.x;
+    if (index >= arrayLength(&product)) {
+        return;
+    }
+
+    product[index] = complex_mul(a[index], b[index]);
+    quotient[index] = complex_div(a[index], b[index]);
+}
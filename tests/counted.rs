@@ -0,0 +1,51 @@
+use wisc::{partition::PartitionMode, prelude::*};
+
+#[test]
+fn counted_output_keeps_only_what_was_counted() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let input = workgroup.create_vbuffer((0..1200u32).collect());
+    let multiples = workgroup.create_vbuffer_uninit::<u32>(1200);
+
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./multiples_of_three.wgsl"))
+        .with_size_per_element(input)
+        .with_input_buffer_partitioned(0, input, PartitionMode::Split)
+        .with_counted_output_buffer::<u32>(1, 2, multiples, PartitionMode::Split)
+        .build()
+        .expect("Failed to build task");
+
+    // Counting starts over on every run.
+    for _ in 0..2 {
+        let report = task.run().expect("Failed to run task");
+
+        assert_eq!(report.counted.len(), 1);
+        assert_eq!(report.counted[0].lengths, vec![200, 200]);
+        assert_eq!(report.counted[0].dropped, 0);
+    }
+
+    drop(task);
+
+    let mut multiples: Vec<u32> = workgroup.take_vbuffer(multiples).unwrap();
+    assert_eq!(multiples.len(), 400);
+
+    multiples.sort();
+    assert_eq!(multiples, (0..1200u32).step_by(3).collect::<Vec<_>>());
+}
+
+#[test]
+fn counted_output_needs_shares_of_its_own() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let input = workgroup.create_vbuffer((0..1200u32).collect());
+    let multiples = workgroup.create_vbuffer(vec![0u32; 1200]);
+
+    let result = TaskBuilder::new(&mut workgroup, include_wgsl!("./multiples_of_three.wgsl"))
+        .with_size_per_element(input)
+        .with_input_buffer(0, input)
+        .with_counted_output_buffer::<u32>(1, 2, multiples, PartitionMode::Unmanaged)
+        .build();
+
+    assert!(matches!(result, Err(WiscError::InvalidPartition(_))));
+}
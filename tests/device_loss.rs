@@ -0,0 +1,51 @@
+use wisc::prelude::*;
+
+fn add(workgroup: &mut Workgroup) -> Result<Vec<u32>, WiscError> {
+    let a = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let b = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let c = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    TaskBuilder::from_workgroup(workgroup)
+        .with_registered_shader("adder")
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, a)
+        .with_input_buffer(1, b)
+        .with_output_buffer(2, c)
+        .build()?
+        .run()?;
+
+    workgroup.take_vbuffer::<u32>(c)
+}
+
+#[test]
+fn lost_device_fails_the_run() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+    workgroup.register_shader("adder", include_wgsl!("./array_addition.wgsl"));
+
+    let devices = workgroup.shared_devices();
+    devices[0].destroy();
+
+    assert!(devices[0].is_lost());
+    assert!(matches!(add(&mut workgroup), Err(WiscError::DeviceLost(_))));
+}
+
+#[test]
+fn retry_replaces_the_lost_device() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+    workgroup.register_shader("adder", include_wgsl!("./array_addition.wgsl"));
+
+    let mut attempts = 0;
+    let result = workgroup.retry_on_device_loss(|workgroup| {
+        attempts += 1;
+
+        if attempts == 1 {
+            workgroup.shared_devices()[0].destroy();
+        }
+
+        add(workgroup)
+    });
+
+    assert_eq!(result.unwrap(), vec![5u32; 1024]);
+    assert_eq!(attempts, 2);
+    assert!(!workgroup.shared_devices()[0].is_lost());
+}
@@ -0,0 +1,206 @@
+use wisc::{dispatch::DispatchSize, partition::PartitionMode, prelude::*};
+
+fn doubled(len: u32) -> Vec<u32> {
+    (0..len).map(|x| x * 2).collect()
+}
+
+#[test]
+fn dispatch_for_elements() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let input = workgroup.create_vbuffer((0..1000u32).collect());
+    let output = workgroup.create_vbuffer(vec![0u32; 1000]);
+
+    // The kernel's workgroup size of 256 is read from the shader.
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./double.wgsl"))
+        .with_size(DispatchSize::ForElements {
+            count: 1000,
+            per_invocation: 1,
+        })
+        .with_input_buffer(0, input)
+        .with_output_buffer(1, output)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let output: Vec<u32> = workgroup.take_vbuffer(output).unwrap();
+
+    assert_eq!(output, doubled(1000));
+}
+
+#[test]
+fn dispatch_rejects_empty_sizes() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let input = workgroup.create_vbuffer(vec![1u32; 256]);
+    let output = workgroup.create_vbuffer(vec![0u32; 256]);
+
+    let task = TaskBuilder::new(&mut workgroup, include_wgsl!("./double.wgsl"))
+        .with_size((1, 0, 1))
+        .with_input_buffer(0, input)
+        .with_output_buffer(1, output)
+        .build();
+
+    assert!(matches!(task, Err(WiscError::InvalidDispatch(_))));
+}
+
+#[test]
+fn dispatch_per_buffer() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let input = workgroup.create_vbuffer((0..4096u32).collect());
+    let output = workgroup.create_vbuffer(vec![0u32; 4096]);
+
+    // Each device only covers the chunk of the output it holds.
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./double.wgsl"))
+        .with_size(DispatchSize::PerBuffer(output))
+        .with_input_buffer_partitioned(0, input, PartitionMode::Split)
+        .with_output_buffer_partitioned(1, output, PartitionMode::Split)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let output: Vec<u32> = workgroup.take_vbuffer(output).unwrap();
+
+    assert_eq!(output, doubled(4096));
+}
+
+#[test]
+fn dispatch_from_indirect() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let input = workgroup.create_vbuffer((0..1024u32).collect());
+    let output = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    // Counts supplied from the host.
+    let args = workgroup.create_vbuffer(vec![4u32, 1, 1]);
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./double.wgsl"))
+        .with_size(DispatchSize::FromIndirect(args))
+        .with_input_buffer(0, input)
+        .with_output_buffer(1, output)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let mut readback = vec![std::mem::MaybeUninit::uninit(); 1024];
+    let values: &mut [u32] = workgroup.read_vbuffer_into(output, &mut readback).unwrap();
+    assert_eq!(values, doubled(1024).as_slice());
+
+    // Counts computed on the device by an earlier task, read where they were written.
+    let output = workgroup.create_vbuffer(vec![0u32; 1024]);
+    let args = workgroup.create_vbuffer_uninit::<u32>(3);
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./indirect_args.wgsl"))
+        .with_size((1, 1, 1))
+        .with_input_buffer(0, input)
+        .with_output_buffer(1, args)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./double.wgsl"))
+        .with_size(DispatchSize::FromIndirect(args))
+        .with_input_buffer(0, input)
+        .with_output_buffer(1, output)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let output: Vec<u32> = workgroup.take_vbuffer(output).unwrap();
+
+    assert_eq!(output, doubled(1024));
+}
+
+#[test]
+fn dispatch_scales_with_weighted_partitions() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let input = workgroup.create_vbuffer((0..3000u32).collect());
+    let output = workgroup.create_vbuffer(vec![0u32; 3000]);
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./double.wgsl"))
+        .with_size_per_element(output)
+        .with_input_buffer_partitioned(0, input, PartitionMode::Weighted)
+        .with_output_buffer_partitioned(1, output, PartitionMode::Weighted)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let output: Vec<u32> = workgroup.take_vbuffer(output).unwrap();
+
+    assert_eq!(output, doubled(3000));
+}
+
+#[test]
+fn dispatch_size_for_elements() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let input = workgroup.create_vbuffer((0..1000u32).collect());
+    let output = workgroup.create_vbuffer(vec![0u32; 1000]);
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./double.wgsl"))
+        .with_size_for_elements(1000)
+        .with_input_buffer(0, input)
+        .with_output_buffer(1, output)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let output: Vec<u32> = workgroup.take_vbuffer(output).unwrap();
+
+    assert_eq!(output, doubled(1000));
+
+    // More single-invocation workgroups than most devices allow along x spill into y.
+    let output = workgroup.create_vbuffer(vec![0u32; 70_000]);
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./linear_index.wgsl"))
+        .with_size_for_elements(70_000)
+        .with_output_buffer(0, output)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let output: Vec<u32> = workgroup.take_vbuffer(output).unwrap();
+
+    assert_eq!(output, (0..70_000u32).collect::<Vec<_>>());
+}
+
+#[test]
+fn dispatch_split_past_device_limit() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    // Past the 65535 workgroups most devices allow along x, so the kernel is dispatched
+    // in parts, each told its offset through the slice uniform.
+    let output = workgroup.create_vbuffer(vec![0u32; 70_000]);
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./split_dispatch.wgsl"))
+        .with_size((70_000, 1, 1))
+        .with_output_buffer(0, output)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let output: Vec<u32> = workgroup.take_vbuffer(output).unwrap();
+
+    assert_eq!(output, (0..70_000u32).collect::<Vec<_>>());
+
+    // Nothing splits along y.
+    let output = workgroup.create_vbuffer(vec![0u32; 16]);
+
+    let task = TaskBuilder::new(&mut workgroup, include_wgsl!("./split_dispatch.wgsl"))
+        .with_size((1, u32::MAX, 1))
+        .with_output_buffer(0, output)
+        .build();
+
+    assert!(matches!(task.err(), Some(WiscError::InvalidDispatch(_))));
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "dlpack")]
+
+use wisc::{dlpack::DLDataType, prelude::*};
+
+#[test]
+fn take_dlpack_tensor() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let ibuf1 = workgroup.create_vbuffer((0..1024u32).collect());
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let obuf1 = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size_per_element(obuf1)
+        .with_input_buffer(0, ibuf1)
+        .with_input_buffer(1, ibuf2)
+        .with_output_buffer(2, obuf1)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    // A shape that doesn't hold the buffer leaves it registered.
+    assert!(workgroup.take_dlpack::<u32>(obuf1, &[32, 31]).is_err());
+
+    let tensor = workgroup.take_dlpack::<u32>(obuf1, &[32, 32]).unwrap();
+
+    assert_eq!(tensor.shape(), vec![32, 32]);
+    assert_eq!(
+        tensor.tensor().dtype,
+        DLDataType {
+            code: DLDataType::UINT,
+            bits: 32,
+            lanes: 1
+        }
+    );
+
+    let expected: Vec<u32> = (3..1027u32).collect();
+    assert_eq!(
+        tensor.as_bytes(),
+        bytemuck::cast_slice::<u32, u8>(&expected)
+    );
+
+    // A consumer frees the tensor through its deleter.
+    let managed = tensor.into_raw();
+    unsafe { ((*managed).deleter.unwrap())(managed) };
+}
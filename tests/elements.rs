@@ -0,0 +1,65 @@
+use wisc::{enum_element, newtype_element, prelude::*};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Count(u32);
+
+newtype_element!(Count(u32));
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(u32)]
+enum Parity {
+    Even = 0,
+    Odd = 1,
+}
+
+enum_element!(Parity: u32 { Even, Odd });
+
+#[test]
+fn newtype_elements() {
+    // Get all the hardware devices available to our system.
+    let devices = VDevice::all();
+
+    // Create a Workgroup out of our device(s).
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    // Domain types go in and come out without manual transmutes.
+    let ibuf = workgroup.create_element_vbuffer(vec![Count(21); 256]);
+    let obuf = workgroup.create_element_vbuffer(vec![Count(0); 256]);
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./double.wgsl"))
+        .with_kernel("main")
+        .with_size((1, 1, 1))
+        .with_input_buffer(0, ibuf)
+        .with_output_buffer(1, obuf)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let obuf: Vec<Count> = workgroup.take_element_vbuffer(obuf).unwrap();
+
+    assert_eq!(obuf, vec![Count(42); 256]);
+}
+
+#[test]
+fn checked_element_conversion() {
+    let mut workgroup = Workgroup::from_devices(vec![]);
+
+    let flags = workgroup.create_element_vbuffer(vec![true, false, true]);
+    let parities = workgroup.create_element_vbuffer(vec![Parity::Odd, Parity::Even]);
+    let raw = workgroup.create_vbuffer(vec![0u32, 1, 2]);
+
+    assert_eq!(
+        workgroup.take_element_vbuffer::<bool>(flags),
+        Ok(vec![true, false, true])
+    );
+    assert_eq!(
+        workgroup.take_element_vbuffer::<Parity>(parities),
+        Ok(vec![Parity::Odd, Parity::Even])
+    );
+
+    // 2 is neither a valid bool nor a valid Parity, so the buffer stays registered.
+    assert!(workgroup.take_element_vbuffer::<bool>(raw).is_err());
+    assert!(workgroup.take_element_vbuffer::<Parity>(raw).is_err());
+    assert_eq!(workgroup.take_vbuffer::<u32>(raw), Ok(vec![0, 1, 2]));
+}
@@ -0,0 +1,87 @@
+use std::sync::mpsc;
+
+use wisc::{partition::PartitionMode, prelude::*, stream::ChannelSource, workgroup::Weighting};
+
+#[test]
+fn zero_length_buffers() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let ibuf1 = workgroup.create_vbuffer(Vec::<u32>::new());
+    let ibuf2 = workgroup.create_vbuffer(Vec::<u32>::new());
+    let obuf1 = workgroup.create_vbuffer_uninit::<u32>(0);
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size((1, 1, 1))
+        .with_input_buffer(0, ibuf1)
+        .with_input_buffer(1, ibuf2)
+        .with_output_buffer(2, obuf1)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let obuf1: Vec<u32> = workgroup.take_vbuffer(obuf1).unwrap();
+
+    assert!(obuf1.is_empty());
+}
+
+#[test]
+fn device_with_an_empty_partition() {
+    // Two devices, one of which is weighted out of the split entirely.
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = WorkgroupBuilder::new()
+        .devices(devices)
+        .weighting(Weighting::Manual(vec![1.0, 0.0]))
+        .build();
+
+    let ibuf1 = workgroup.create_vbuffer((0..1024u32).collect());
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let obuf1 = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size_per_element(obuf1)
+        .with_input_buffer_partitioned(0, ibuf1, PartitionMode::Weighted)
+        .with_input_buffer_partitioned(1, ibuf2, PartitionMode::Weighted)
+        .with_output_buffer_partitioned(2, obuf1, PartitionMode::Weighted)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    // The idle device's empty copy can still be gathered.
+    workgroup.all_gather(obuf1).expect("Failed to gather");
+
+    let obuf1: Vec<u32> = workgroup.take_vbuffer(obuf1).unwrap();
+
+    assert_eq!(obuf1, (3..1027u32).collect::<Vec<_>>());
+}
+
+#[test]
+fn empty_stream_chunks() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let (tx, rx) = mpsc::channel();
+    tx.send(bytemuck::cast_slice(&[1u32; 256]).to_vec())
+        .unwrap();
+    tx.send(vec![]).unwrap();
+    tx.send(bytemuck::cast_slice(&[2u32; 256]).to_vec())
+        .unwrap();
+    drop(tx);
+
+    let mut stream = TaskBuilder::new(&mut workgroup, include_wgsl!("./double.wgsl"))
+        .with_size((1, 1, 1))
+        .with_stream_input::<u32>(0)
+        .with_stream_output::<u32>(1)
+        .build_stream()
+        .expect("Failed to build stream");
+
+    let mut lengths = Vec::new();
+    let chunks = stream
+        .run(&mut ChannelSource::new(rx), |bytes| {
+            lengths.push(bytes.len())
+        })
+        .expect("Failed to run stream");
+
+    assert_eq!(chunks, 3);
+    assert_eq!(lengths, vec![1024, 0, 1024]);
+}
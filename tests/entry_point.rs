@@ -0,0 +1,82 @@
+use wisc::prelude::*;
+
+#[test]
+fn single_entry_point_is_the_default_kernel() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let ibuf1 = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let obuf1 = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, ibuf1)
+        .with_input_buffer(1, ibuf2)
+        .with_output_buffer(2, obuf1)
+        .build()
+        .expect("Failed to build task");
+
+    task.run().expect("Failed to run task");
+
+    let obuf1: Vec<u32> = workgroup.take_vbuffer(obuf1).unwrap();
+
+    assert_eq!(obuf1, vec![5u32; 1024]);
+}
+
+#[test]
+fn several_entry_points_need_a_kernel() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    workgroup.register_shader("two_kernels", include_wgsl!("./two_kernels.wgsl"));
+
+    let obuf1 = workgroup.create_vbuffer(vec![0u32; 256]);
+
+    let task = TaskBuilder::from_workgroup(&mut workgroup)
+        .with_registered_shader("two_kernels")
+        .with_size((1, 1, 1))
+        .with_output_buffer(0, obuf1)
+        .build();
+
+    // The error names the candidates to pick from.
+    assert_eq!(
+        task.err(),
+        Some(WiscError::MissingKernel {
+            candidates: vec!["first".to_string(), "second".to_string()]
+        })
+    );
+}
+
+#[test]
+fn named_kernel_must_exist() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let obuf1 = workgroup.create_vbuffer(vec![0u32; 256]);
+
+    let task = TaskBuilder::new(&mut workgroup, include_wgsl!("./two_kernels.wgsl"))
+        .with_kernel("main")
+        .with_size((1, 1, 1))
+        .with_output_buffer(0, obuf1)
+        .build();
+
+    assert_eq!(
+        task.err(),
+        Some(WiscError::UnknownKernel {
+            kernel: "main".to_string(),
+            candidates: vec!["first".to_string(), "second".to_string()]
+        })
+    );
+}
+
+#[test]
+fn several_entry_points_inline_need_a_kernel() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let obuf1 = workgroup.create_vbuffer(vec![0u32; 256]);
+
+    let task = TaskBuilder::new(&mut workgroup, include_wgsl!("./two_kernels.wgsl"))
+        .with_size((1, 1, 1))
+        .with_output_buffer(0, obuf1)
+        .build();
+
+    assert!(matches!(task.err(), Some(WiscError::MissingKernel { .. })));
+}
@@ -0,0 +1,79 @@
+use wisc::experiment::{Experiment, Params};
+use wisc::prelude::*;
+
+#[test]
+fn runs_every_combination() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let input = workgroup.create_vbuffer(vec![6.0f32; 1024]);
+    let output = workgroup.create_vbuffer_uninit::<f32>(1024);
+
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./normalize.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, input)
+        .with_uniform_buffer(1, 1.0f32)
+        .with_output_buffer(2, output)
+        .build()
+        .expect("Failed to build task");
+
+    let params = [
+        Params::new().with_uniform(1, 2.0f32),
+        Params::new().with_uniform(1, 3.0f32),
+        Params::new().with_input(0, &[12.0f32; 1024]),
+    ];
+
+    let results = Experiment::new(&mut task)
+        .with_batch_size(2)
+        .run(&params)
+        .expect("Experiment failed");
+
+    let quotients: Vec<Vec<f32>> = results
+        .runs()
+        .iter()
+        .map(|run| run.output(2).unwrap())
+        .collect();
+
+    assert_eq!(
+        quotients,
+        vec![vec![3.0f32; 1024], vec![2.0f32; 1024], vec![12.0f32; 1024]]
+    );
+
+    // The outputs are left as the last run left them, and the input as it was.
+    drop(task);
+    assert_eq!(
+        workgroup.take_vbuffer::<f32>(output).unwrap(),
+        vec![12.0f32; 1024]
+    );
+    assert_eq!(
+        workgroup.take_vbuffer::<f32>(input).unwrap(),
+        vec![6.0f32; 1024]
+    );
+}
+
+#[test]
+fn params_must_fit_the_task() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let input = workgroup.create_vbuffer(vec![6.0f32; 1024]);
+    let output = workgroup.create_vbuffer_uninit::<f32>(1024);
+
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./normalize.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, input)
+        .with_uniform_buffer(1, 1.0f32)
+        .with_output_buffer(2, output)
+        .build()
+        .expect("Failed to build task");
+
+    let mut experiment = Experiment::new(&mut task);
+
+    assert!(matches!(
+        experiment.run(&[Params::new().with_uniform(2, 2.0f32)]),
+        Err(WiscError::BindingMismatch { binding: 2, .. })
+    ));
+    assert_eq!(
+        experiment.run(&[Params::new().with_input(0, &[1u32; 1024])]),
+        Err(WiscError::TypeMismatch)
+    );
+}
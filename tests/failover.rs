@@ -0,0 +1,55 @@
+use wisc::prelude::*;
+
+#[test]
+fn lost_device_share_runs_on_the_others() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    // Opened separately, so it can be lost on its own.
+    let lost = workgroup.shared_devices()[1].clone();
+
+    let a = workgroup.create_vbuffer((0..1024u32).collect());
+    let b = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let c = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, a)
+        .with_input_buffer(1, b)
+        .with_output_buffer(2, c)
+        .build()
+        .expect("Failed to build task");
+
+    lost.destroy();
+
+    let report = task.run().expect("Failed to run task");
+    assert_eq!(report.failed_over, vec![1]);
+
+    drop(task);
+    let expected: Vec<u32> = (0..1024).map(|i| i + 3).collect();
+    assert_eq!(workgroup.take_vbuffer::<u32>(c).unwrap(), expected);
+}
+
+#[test]
+fn device_lost_before_building_sits_out() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+    workgroup.shared_devices()[0].destroy();
+
+    let a = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let b = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let c = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    let report = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, a)
+        .with_input_buffer(1, b)
+        .with_output_buffer(2, c)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    assert_eq!(report.excluded, vec![0]);
+    assert_eq!(workgroup.take_vbuffer::<u32>(c).unwrap(), vec![5u32; 1024]);
+}
@@ -0,0 +1,75 @@
+use std::f32::consts::PI;
+
+use wisc::{
+    complex::Complex32,
+    fft::{self, Direction},
+    prelude::*,
+};
+
+fn dft(signal: &[Complex32]) -> Vec<Complex32> {
+    let n = signal.len();
+
+    (0..n)
+        .map(|k| {
+            signal
+                .iter()
+                .enumerate()
+                .fold(Complex32::default(), |sum, (t, x)| {
+                    let theta = -2.0 * PI * ((k * t) % n) as f32 / n as f32;
+                    sum + *x * Complex32::from_polar(1.0, theta)
+                })
+        })
+        .collect()
+}
+
+#[test]
+fn batched_fft() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let (len, batch) = (256, 6);
+    let signals: Vec<Complex32> = (0..len * batch)
+        .map(|i| Complex32::new(((i * 13) % 17) as f32 - 8.0, ((i * 5) % 7) as f32))
+        .collect();
+
+    let input = workgroup.create_vbuffer(signals.clone());
+    let spectrum = workgroup.create_vbuffer_uninit::<Complex32>(len * batch);
+
+    fft::fft(&mut workgroup, input, spectrum, len, Direction::Forward).unwrap();
+
+    let restored = workgroup.create_vbuffer_uninit::<Complex32>(len * batch);
+    workgroup.alias_output_as_input(spectrum).unwrap();
+    fft::fft(&mut workgroup, spectrum, restored, len, Direction::Inverse).unwrap();
+
+    let spectrum: Vec<Complex32> = workgroup.take_vbuffer(spectrum).unwrap();
+    let restored: Vec<Complex32> = workgroup.take_vbuffer(restored).unwrap();
+
+    for (signal, transformed) in signals.chunks(len).zip(spectrum.chunks(len)) {
+        for (expected, actual) in dft(signal).iter().zip(transformed) {
+            assert!(
+                (*expected - *actual).abs() < 1e-2,
+                "{expected:?} != {actual:?}"
+            );
+        }
+    }
+
+    for (expected, actual) in signals.iter().zip(&restored) {
+        assert!(
+            (*expected - *actual).abs() < 1e-4,
+            "{expected:?} != {actual:?}"
+        );
+    }
+}
+
+#[test]
+fn fft_needs_power_of_two() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let input = workgroup.create_vbuffer(vec![Complex32::default(); 24]);
+    let output = workgroup.create_vbuffer_uninit::<Complex32>(24);
+
+    assert!(matches!(
+        fft::fft(&mut workgroup, input, output, 12, Direction::Forward),
+        Err(WiscError::ShapeMismatch(_))
+    ));
+}
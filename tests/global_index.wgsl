@@ -0,0 +1,12 @@
+@group(0) @binding(0) var<storage, read_write> output: array<u32>;
+
+// Writes each element's global index, whichever device's chunk it is in.
+@compute @workgroup_size(256, 1, 1)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let index = global_id.x;
+    if (index >= wisc_partition.len) {
+        return;
+    }
+
+    output[index] = wisc_partition.offset + index;
+}
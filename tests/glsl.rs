@@ -0,0 +1,63 @@
+#![cfg(feature = "glsl")]
+
+use wisc::{partition::PartitionMode, prelude::*, shader};
+
+#[test]
+fn glsl_shader() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let ibuf1 = workgroup.create_vbuffer((0..1024u32).collect());
+    let ibuf2 = workgroup.create_vbuffer(vec![7u32; 1024]);
+    let obuf = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    TaskBuilder::new(
+        &mut workgroup,
+        shader::glsl(include_str!("./array_addition.comp")),
+    )
+    .with_size_per_element(obuf)
+    .with_input_buffer_partitioned(0, ibuf1, PartitionMode::Split)
+    .with_input_buffer_partitioned(1, ibuf2, PartitionMode::Split)
+    .with_output_buffer_partitioned(2, obuf, PartitionMode::Split)
+    .build()
+    .expect("Failed to build task")
+    .run()
+    .expect("Failed to run task");
+
+    let obuf: Vec<u32> = workgroup.take_vbuffer(obuf).unwrap();
+    assert_eq!(obuf, (7..1031u32).collect::<Vec<_>>());
+}
+
+#[test]
+fn glsl_errors() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+    let obuf = workgroup.create_vbuffer_uninit::<u32>(256);
+
+    let source = "#version 450\nlayout(local_size_x = 64) in;\nvoid main() { undeclared = 1; }";
+    let result = TaskBuilder::new(&mut workgroup, shader::glsl(source))
+        .with_size((1, 1, 1))
+        .with_output_buffer(0, obuf)
+        .build();
+
+    match result {
+        Err(WiscError::ShaderParse(messages)) => assert!(messages.contains("undeclared")),
+        other => panic!("expected a parse error, got {:?}", other.err()),
+    }
+
+    // Only compute shaders can run as tasks.
+    let vertex = wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::Glsl {
+            shader: "#version 450\nvoid main() {}".into(),
+            stage: wgpu::naga::ShaderStage::Vertex,
+            defines: &[],
+        },
+    };
+
+    let result = TaskBuilder::new(&mut workgroup, vertex)
+        .with_size((1, 1, 1))
+        .with_output_buffer(0, obuf)
+        .build();
+
+    assert!(matches!(result, Err(WiscError::InvalidShader(_))));
+}
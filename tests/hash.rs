@@ -0,0 +1,52 @@
+use wisc::{ops::hash_buffer, partition::PartitionMode, prelude::*};
+
+fn add(workgroup: &mut Workgroup, len: u32) -> wisc::workgroup::VBufferHandle {
+    let ibuf1 = workgroup.create_vbuffer((0..len).collect());
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; len as usize]);
+    let obuf1 = workgroup.create_vbuffer_uninit::<u32>(len as usize);
+
+    TaskBuilder::new(workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size_per_element(obuf1)
+        .with_input_buffer_partitioned(0, ibuf1, PartitionMode::Split)
+        .with_input_buffer_partitioned(1, ibuf2, PartitionMode::Split)
+        .with_output_buffer_partitioned(2, obuf1, PartitionMode::Split)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    obuf1
+}
+
+#[test]
+fn hash_buffer_contents() {
+    // Two devices, so each hashes the half it computed.
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut split = Workgroup::from_devices(devices);
+    let mut single = Workgroup::from_devices(VDevice::all());
+
+    let on_devices = add(&mut split, 1000);
+    let on_one_device = add(&mut single, 1000);
+    let on_host = single.create_vbuffer((3..1003u32).collect());
+
+    let hash = hash_buffer(&split, on_devices).unwrap();
+
+    // However the contents were computed and wherever they are, they hash the same.
+    assert_eq!(hash_buffer(&single, on_one_device).unwrap(), hash);
+    assert_eq!(hash_buffer(&single, on_host).unwrap(), hash);
+
+    // Moving, changing or dropping a single element changes the hash.
+    let swapped = single.create_vbuffer((3..1003u32).rev().collect());
+    let changed = single.create_vbuffer((3..1002u32).chain([0]).collect());
+    let shorter = single.create_vbuffer((3..1002u32).collect());
+
+    for other in [swapped, changed, shorter] {
+        assert_ne!(hash_buffer(&single, other).unwrap(), hash);
+    }
+
+    let uninit = single.create_vbuffer_uninit::<u32>(16);
+    assert_eq!(
+        hash_buffer(&single, uninit).err(),
+        Some(WiscError::Uninitialized)
+    );
+}
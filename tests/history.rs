@@ -0,0 +1,50 @@
+use wisc::{history::Launch, partition::PartitionMode, prelude::*};
+
+#[test]
+fn launch_history() {
+    // Two devices, so each launch covers its own half.
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let ibuf1 = workgroup.create_vbuffer(vec![1u32; 1024]);
+    let ibuf2 = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let obuf1 = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    let add = |workgroup: &mut Workgroup| {
+        TaskBuilder::new(workgroup, include_wgsl!("./array_addition.wgsl"))
+            .with_size_per_element(obuf1)
+            .with_input_buffer_partitioned(0, ibuf1, PartitionMode::Split)
+            .with_input_buffer_partitioned(1, ibuf2, PartitionMode::Split)
+            .with_output_buffer_partitioned(2, obuf1, PartitionMode::Split)
+            .build()
+            .expect("Failed to build task")
+            .run()
+            .expect("Failed to run task");
+    };
+
+    // Nothing is recorded until asked for.
+    add(&mut workgroup);
+    assert!(workgroup.launch_history().is_empty());
+
+    workgroup.record_launches(true);
+    add(&mut workgroup);
+
+    let half = (0..2)
+        .map(|device| Launch {
+            device,
+            kernel: "main".to_string(),
+            workgroups: Some((2, 1, 1)),
+            bindings: vec![(0, 2048), (1, 2048), (2, 2048)],
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(workgroup.take_launch_history(), half);
+
+    // Taking the history leaves recording on; stopping it discards the rest.
+    add(&mut workgroup);
+    assert_eq!(workgroup.launch_history(), half.as_slice());
+
+    workgroup.record_launches(false);
+    add(&mut workgroup);
+    assert!(workgroup.launch_history().is_empty());
+}
@@ -0,0 +1,25 @@
+use wisc::prelude::*;
+
+#[test]
+fn immediates_parameterize_the_dispatch() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let data = workgroup.create_vbuffer(vec![5u32; 1024]);
+
+    let task = TaskBuilder::new(&mut workgroup, include_wgsl!("./immediates.wgsl"))
+        .with_size((4, 1, 1))
+        .with_output_buffer(0, data)
+        .with_immediates([3u32, 1u32])
+        .build();
+
+    // Not every backend can take immediates.
+    let mut task = match task {
+        Err(WiscError::MissingFeature(_)) => return,
+        task => task.expect("Failed to build task"),
+    };
+
+    task.run().expect("Failed to run task");
+
+    let data: Vec<u32> = workgroup.take_vbuffer(data).unwrap();
+    assert_eq!(data, vec![16u32; 1024]);
+}
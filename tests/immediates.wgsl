@@ -0,0 +1,18 @@
+struct Params {
+    scale: u32,
+    offset: u32,
+}
+
+var<immediate> params: Params;
+
+@group(0) @binding(0) var<storage, read_write> data: array<u32>;
+
+@compute @workgroup_size(256, 1, 1)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let index = global_id.x;
+    if (index >= arrayLength(&data)) {
+        return;
+    }
+
+    data[index] = data[index] * params.scale + params.offset;
+}
@@ -0,0 +1,3 @@
+fn clamp_to_byte(x: u32) -> u32 {
+    return min(x, 255u);
+}
@@ -0,0 +1,2 @@
+//#include "helpers.wgsl"
+//#include "double.wgsl"
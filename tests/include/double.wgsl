@@ -0,0 +1,14 @@
+//#include "helpers.wgsl"
+
+@group(0) @binding(0) var<storage, read> input: array<u32>;
+@group(0) @binding(1) var<storage, read_write> output: array<u32>;
+
+@compute @workgroup_size(256, 1, 1)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let index = global_id.x;
+    if (index >= arrayLength(&output)) {
+        return;
+    }
+
+    output[index] = twice(input[index]);
+}
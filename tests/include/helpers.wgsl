@@ -0,0 +1,3 @@
+fn twice(x: u32) -> u32 {
+    return x * 2u;
+}
@@ -0,0 +1,91 @@
+use wisc::{prelude::*, shader};
+
+const SHADERS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/include");
+
+#[test]
+fn included_helpers_are_compiled() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let input = workgroup.create_vbuffer((0..1024u32).collect());
+    let output = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    let source = shader::wgsl_with_includes(format!("{SHADERS}/double.wgsl")).unwrap();
+
+    let mut task = TaskBuilder::new(&mut workgroup, source)
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, input)
+        .with_output_buffer(1, output)
+        .build()
+        .expect("Failed to build task");
+
+    task.run().expect("Failed to run task");
+
+    let output: Vec<u32> = workgroup.take_vbuffer(output).unwrap();
+
+    assert_eq!(output, (0..1024u32).map(|x| x * 2).collect::<Vec<_>>());
+}
+
+#[test]
+fn files_are_included_once() {
+    let source = shader::resolve_wgsl_includes(format!("{SHADERS}/diamond.wgsl")).unwrap();
+
+    assert_eq!(source.matches("fn twice").count(), 1);
+}
+
+#[test]
+fn missing_include_names_the_file() {
+    let error = shader::resolve_wgsl_includes(format!("{SHADERS}/missing.wgsl")).unwrap_err();
+
+    assert!(error.to_string().contains("missing.wgsl"));
+}
+
+#[test]
+fn builder_searches_paths_and_prepends_snippets() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let input = workgroup.create_vbuffer((0..1024u32).collect());
+    let output = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    // Neither include sits next to the source, so both come from the search paths.
+    let source = shader::ShaderSourceBuilder::new()
+        .search_path(format!("{SHADERS}/common"))
+        .search_path(SHADERS)
+        .snippet("const LIMIT: u32 = 300u;")
+        .build_source(
+            r#"
+#include "clamp.wgsl"
+#include "helpers.wgsl"
+
+@group(0) @binding(0) var<storage, read> input: array<u32>;
+@group(0) @binding(1) var<storage, read_write> output: array<u32>;
+
+@compute @workgroup_size(256, 1, 1)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let index = global_id.x;
+    if (index >= arrayLength(&output)) {
+        return;
+    }
+
+    output[index] = clamp_to_byte(twice(min(input[index], LIMIT)));
+}
+"#,
+        )
+        .unwrap();
+
+    TaskBuilder::new(&mut workgroup, source)
+        .with_size_per_element(output)
+        .with_input_buffer(0, input)
+        .with_output_buffer(1, output)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let output: Vec<u32> = workgroup.take_vbuffer(output).unwrap();
+    assert_eq!(
+        output,
+        (0..1024u32)
+            .map(|x| (x.min(300) * 2).min(255))
+            .collect::<Vec<_>>()
+    );
+}
@@ -0,0 +1,10 @@
+@group(0) @binding(0) var<storage, read> input: array<u32>;
+@group(0) @binding(1) var<storage, read_write> args: array<u32>;
+
+// Writes the workgroup counts that cover the input with workgroups of 256.
+@compute @workgroup_size(1, 1, 1)
+fn main() {
+    args[0] = (arrayLength(&input) + 255u) / 256u;
+    args[1] = 1u;
+    args[2] = 1u;
+}
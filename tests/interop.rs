@@ -0,0 +1,42 @@
+#![cfg(all(feature = "vulkan-interop", unix))]
+
+use std::{fs::File, os::fd::OwnedFd};
+
+use wisc::{interop::ExternalMemory, partition::PartitionMode, prelude::*};
+
+#[test]
+fn on_device_runs_on_one_device() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let ibuf1 = workgroup.create_vbuffer((0..1024u32).collect());
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let obuf1 = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size_per_element(obuf1)
+        .with_input_buffer_partitioned(0, ibuf1, PartitionMode::on_device(1))
+        .with_input_buffer_partitioned(1, ibuf2, PartitionMode::on_device(1))
+        .with_output_buffer_partitioned(2, obuf1, PartitionMode::on_device(1))
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let obuf1: Vec<u32> = workgroup.take_vbuffer(obuf1).unwrap();
+    assert_eq!(obuf1, (3..1027u32).collect::<Vec<_>>());
+}
+
+#[test]
+fn import_needs_an_external_memory_device() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let memory = ExternalMemory {
+        fd: OwnedFd::from(File::open("/dev/null").unwrap()),
+        size: 4096,
+    };
+
+    // The descriptor is never imported, since the device can't.
+    let imported = unsafe { workgroup.import_external_memory::<u32>(0, memory, 1024) };
+    assert!(imported.is_err());
+}
@@ -0,0 +1,88 @@
+#![cfg(feature = "spirv")]
+
+use wisc::{
+    kernel::{BindingAccess, Kernel, KernelBinding},
+    prelude::*,
+};
+
+// The fixtures are the WGSL shaders of the same names compiled to SPIR-V, standing in for
+// rust-gpu output, which reaches wisc the same way.
+
+#[test]
+fn spirv_kernel() {
+    let kernel = Kernel::from_rust_gpu(include_bytes!("./array_addition.spv"), "main").unwrap();
+
+    assert_eq!(kernel.entry_points(), ["main"]);
+    assert_eq!(kernel.workgroup_size(), Some([256, 1, 1]));
+    assert_eq!(
+        kernel.bindings(),
+        [
+            KernelBinding {
+                binding: 0,
+                access: BindingAccess::ReadOnly,
+                used: true,
+            },
+            KernelBinding {
+                binding: 1,
+                access: BindingAccess::ReadOnly,
+                used: true,
+            },
+            KernelBinding {
+                binding: 2,
+                access: BindingAccess::ReadWrite,
+                used: true,
+            },
+        ]
+    );
+
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let ibuf1 = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let obuf = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    kernel
+        .task(&mut workgroup)
+        .with_size_per_element(obuf)
+        .with_input_buffer(0, ibuf1)
+        .with_input_buffer(1, ibuf2)
+        .with_output_buffer(2, obuf)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let obuf: Vec<u32> = workgroup.take_vbuffer(obuf).unwrap();
+    assert_eq!(obuf, vec![5u32; 1024]);
+}
+
+#[test]
+fn entry_point_discovery() {
+    let spirv = include_bytes!("./two_kernels.spv");
+
+    let result = Kernel::from_rust_gpu(spirv, "third");
+    assert!(matches!(
+        result,
+        Err(WiscError::UnknownKernel { candidates, .. }) if candidates == ["first", "second"]
+    ));
+
+    let first = Kernel::from_rust_gpu(spirv, "first").unwrap();
+    let second = first.with_entry_point("second").unwrap();
+    assert_eq!(second.entry_point(), "second");
+
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+    let obuf = workgroup.create_vbuffer_uninit::<u32>(256);
+
+    second
+        .task(&mut workgroup)
+        .with_size((1, 1, 1))
+        .with_output_buffer(0, obuf)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let obuf: Vec<u32> = workgroup.take_vbuffer(obuf).unwrap();
+    assert_eq!(obuf, vec![2u32; 256]);
+}
@@ -0,0 +1,16 @@
+@group(0) @binding(0) var<storage, read_write> output: array<u32>;
+
+// Indexes by workgroup, so it covers every element however the workgroups are laid out.
+@compute @workgroup_size(1, 1, 1)
+fn main(
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+    @builtin(num_workgroups) num_workgroups: vec3<u32>,
+) {
+    let index = (workgroup_id.z * num_workgroups.y + workgroup_id.y) * num_workgroups.x
+        + workgroup_id.x;
+    if (index >= arrayLength(&output)) {
+        return;
+    }
+
+    output[index] = index;
+}
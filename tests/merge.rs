@@ -0,0 +1,160 @@
+use wisc::{
+    collective::{Merge, ReduceOp},
+    partition::PartitionMode,
+    prelude::*,
+};
+
+fn run_double(merge: Merge<u32>) -> Vec<u32> {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let input = workgroup.create_vbuffer((0..1024u32).collect());
+    let output = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./double.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, input)
+        .with_merged_output_buffer(1, output, merge)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    workgroup.take_vbuffer(output).unwrap()
+}
+
+#[test]
+fn replicated_outputs_are_merged() {
+    let devices = VDevice::all().len() as u32;
+    let doubled: Vec<u32> = (0..1024u32).map(|x| x * 2).collect();
+
+    // Every device computes the same values, so only summing changes them.
+    assert_eq!(run_double(Merge::OverwriteFirst), doubled);
+    assert_eq!(run_double(Merge::ReduceMin), doubled);
+    assert_eq!(run_double(Merge::ReduceMax), doubled);
+    assert_eq!(
+        run_double(Merge::ReduceAdd),
+        doubled.iter().map(|x| x * devices).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        run_double(Merge::Custom(Box::new(|a, b| a.max(b)))),
+        doubled
+    );
+}
+
+#[test]
+fn merge_type_must_match_the_buffer() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let input = workgroup.create_vbuffer(vec![1u32; 1024]);
+    let output = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    let task = TaskBuilder::new(&mut workgroup, include_wgsl!("./double.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, input)
+        .with_merged_output_buffer::<f32>(1, output, Merge::ReduceAdd)
+        .build();
+
+    assert!(task.is_err());
+}
+
+#[test]
+fn outputs_accumulate_across_runs() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let input = workgroup.create_vbuffer((0..1024u32).collect());
+    let output = workgroup.create_vbuffer(vec![1u32; 1024]);
+
+    for _ in 0..3 {
+        TaskBuilder::new(&mut workgroup, include_wgsl!("./double.wgsl"))
+            .with_size((4, 1, 1))
+            .with_input_buffer(0, input)
+            .with_accumulated_output_buffer::<u32>(1, output, PartitionMode::Split, ReduceOp::Sum)
+            .build()
+            .expect("Failed to build task")
+            .run()
+            .expect("Failed to run task");
+    }
+
+    let output: Vec<u32> = workgroup.take_vbuffer(output).unwrap();
+
+    assert_eq!(
+        output,
+        (0..1024u32).map(|x| 1 + 3 * x * 2).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn accumulation_needs_initial_contents() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let input = workgroup.create_vbuffer(vec![1u32; 1024]);
+    let output = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    let task = TaskBuilder::new(&mut workgroup, include_wgsl!("./double.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, input)
+        .with_accumulated_output_buffer::<u32>(1, output, PartitionMode::Split, ReduceOp::Max)
+        .build();
+
+    assert!(task.is_err());
+}
+
+#[test]
+fn merges_start_from_the_first_device_that_ran() {
+    let devices = VDevice::all()
+        .into_iter()
+        .chain(VDevice::all())
+        .chain(VDevice::all())
+        .collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+    workgroup.shared_devices()[0].destroy();
+
+    let input = workgroup.create_vbuffer((0..1024u32).collect());
+    let output = workgroup.create_vbuffer(vec![7u32; 1024]);
+
+    let report = TaskBuilder::new(&mut workgroup, include_wgsl!("./double.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, input)
+        .with_merged_output_buffer(1, output, Merge::<u32>::ReduceAdd)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+    assert_eq!(report.excluded, vec![0]);
+
+    // The two devices that ran are summed, and what the host copy held before isn't.
+    let expected: Vec<u32> = (0..1024u32).map(|x| x * 4).collect();
+    assert_eq!(workgroup.take_vbuffer::<u32>(output).unwrap(), expected);
+}
+
+#[test]
+fn unmanaged_accumulation_counts_each_run_once() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let input = workgroup.create_vbuffer((0..1024u32).collect());
+    let output = workgroup.create_vbuffer(vec![1u32; 1024]);
+
+    for _ in 0..3 {
+        TaskBuilder::new(&mut workgroup, include_wgsl!("./double.wgsl"))
+            .with_size((4, 1, 1))
+            .with_input_buffer(0, input)
+            .with_accumulated_output_buffer::<u32>(
+                1,
+                output,
+                PartitionMode::Unmanaged,
+                ReduceOp::Sum,
+            )
+            .build()
+            .expect("Failed to build task")
+            .run()
+            .expect("Failed to run task");
+    }
+
+    // Both devices computed every element, but each run adds it once.
+    let output: Vec<u32> = workgroup.take_vbuffer(output).unwrap();
+    assert_eq!(
+        output,
+        (0..1024u32).map(|x| 1 + 3 * x * 2).collect::<Vec<_>>()
+    );
+}
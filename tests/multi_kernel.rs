@@ -0,0 +1,28 @@
+use wisc::prelude::*;
+
+#[test]
+fn kernels_share_buffers_across_tasks() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    workgroup.register_shader("two_kernels", include_wgsl!("./two_kernels.wgsl"));
+
+    let obuf1 = workgroup.create_vbuffer(vec![0u32; 256]);
+
+    // Each phase binds the same buffer in the same layout and only changes the kernel.
+    for (kernel, expected) in [("first", 1u32), ("second", 2), ("first", 1)] {
+        TaskBuilder::from_workgroup(&mut workgroup)
+            .with_registered_shader("two_kernels")
+            .with_kernel(kernel)
+            .with_size((1, 1, 1))
+            .with_output_buffer(0, obuf1)
+            .build()
+            .expect("Failed to build task")
+            .run()
+            .expect("Failed to run task");
+
+        let mut readback = vec![std::mem::MaybeUninit::uninit(); 256];
+        let values: &mut [u32] = workgroup.read_vbuffer_into(obuf1, &mut readback).unwrap();
+
+        assert!(values.iter().all(|value| *value == expected));
+    }
+}
@@ -0,0 +1,16 @@
+@group(0) @binding(0) var<storage, read> input: array<u32>;
+@group(0) @binding(1) var<storage, read_write> multiples: array<u32>;
+@group(0) @binding(2) var<storage, read_write> count: atomic<u32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let index = global_id.x;
+    if index >= arrayLength(&input) {
+        return;
+    }
+
+    let value = input[index];
+    if value % 3u == 0u {
+        multiples[atomicAdd(&count, 1u)] = value;
+    }
+}
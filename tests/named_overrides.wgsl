@@ -0,0 +1,16 @@
+override SCALE: f32 = 1.0;
+override OFFSET: f32;
+override BLOCK: u32 = 64;
+
+@group(0) @binding(0) var<storage, read> input: array<f32>;
+@group(0) @binding(1) var<storage, read_write> output: array<f32>;
+
+@compute @workgroup_size(BLOCK, 1, 1)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let index = global_id.x;
+    if (index >= arrayLength(&output)) {
+        return;
+    }
+
+    output[index] = SCALE * input[index] + OFFSET;
+}
@@ -0,0 +1,124 @@
+use wisc::{
+    nn::{self, Activation, Shape},
+    prelude::*,
+};
+
+fn two_devices() -> Workgroup {
+    Workgroup::from_devices(VDevice::all().into_iter().chain(VDevice::all()).collect())
+}
+
+fn assert_close(actual: &[f32], expected: &[f32]) {
+    assert_eq!(actual.len(), expected.len());
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a - e).abs() < 1e-4, "{actual:?} != {expected:?}");
+    }
+}
+
+#[test]
+fn dense_layer() {
+    let mut workgroup = two_devices();
+    let shape = Shape::new(5, 3);
+
+    let input: Vec<f32> = (0..15).map(|i| i as f32).collect();
+    let weights = vec![1.0f32, 0.0, 0.0, 0.0, 1.0, 1.0];
+    let bias = vec![0.5f32, -1.0];
+
+    let expected: Vec<f32> = input
+        .chunks(3)
+        .flat_map(|row| [row[0] + 0.5, row[1] + row[2] - 1.0])
+        .collect();
+
+    let input = workgroup.create_vbuffer(input);
+    let weights = workgroup.create_vbuffer(weights);
+    let bias = workgroup.create_vbuffer(bias);
+    let output = workgroup.create_vbuffer_uninit::<f32>(10);
+
+    nn::dense(&mut workgroup, input, shape, weights, bias, output, 2).unwrap();
+
+    let output: Vec<f32> = workgroup.take_vbuffer(output).unwrap();
+    assert_close(&output, &expected);
+}
+
+#[test]
+fn activations() {
+    let mut workgroup = two_devices();
+    let shape = Shape::new(4, 2);
+    let values = vec![-2.0f32, -1.0, 0.0, 1.0, 2.0, 3.0, -0.5, 0.5];
+
+    let input = workgroup.create_vbuffer(values.clone());
+    let relu = workgroup.create_vbuffer_uninit::<f32>(8);
+    let sigmoid = workgroup.create_vbuffer_uninit::<f32>(8);
+
+    nn::activation(&mut workgroup, Activation::Relu, input, relu, shape).unwrap();
+    nn::activation(&mut workgroup, Activation::Sigmoid, input, sigmoid, shape).unwrap();
+
+    let relu: Vec<f32> = workgroup.take_vbuffer(relu).unwrap();
+    let sigmoid: Vec<f32> = workgroup.take_vbuffer(sigmoid).unwrap();
+
+    let expected: Vec<f32> = values.iter().map(|v| v.max(0.0)).collect();
+    assert_close(&relu, &expected);
+
+    let expected: Vec<f32> = values.iter().map(|v| 1.0 / (1.0 + (-v).exp())).collect();
+    assert_close(&sigmoid, &expected);
+}
+
+#[test]
+fn softmax_rows() {
+    let mut workgroup = two_devices();
+    let shape = Shape::new(7, 4);
+
+    let input = workgroup.create_vbuffer((0..28).map(|i| (i % 5) as f32 * 10.0).collect());
+    let output = workgroup.create_vbuffer_uninit::<f32>(28);
+
+    nn::softmax(&mut workgroup, input, output, shape).unwrap();
+
+    let output: Vec<f32> = workgroup.take_vbuffer(output).unwrap();
+    for row in output.chunks(4) {
+        assert!((row.iter().sum::<f32>() - 1.0).abs() < 1e-4, "{row:?}");
+        assert!(row.iter().all(|p| p.is_finite() && *p >= 0.0));
+    }
+}
+
+#[test]
+fn layer_norm_rows() {
+    let mut workgroup = two_devices();
+    let shape = Shape::new(3, 4);
+    let values: Vec<f32> = (0..12).map(|i| (i * i) as f32).collect();
+    let gamma = vec![1.0f32, 2.0, 0.5, 1.0];
+    let beta = vec![0.0f32, 1.0, 0.0, -1.0];
+    let epsilon = 1e-5;
+
+    let expected: Vec<f32> = values
+        .chunks(4)
+        .flat_map(|row| {
+            let mean = row.iter().sum::<f32>() / 4.0;
+            let variance = row.iter().map(|v| (v - mean) * (v - mean)).sum::<f32>() / 4.0;
+            let scale = 1.0 / (variance + epsilon).sqrt();
+
+            (0..4)
+                .map(|k| (row[k] - mean) * scale * gamma[k] + beta[k])
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    let input = workgroup.create_vbuffer(values);
+    let gamma = workgroup.create_vbuffer(gamma);
+    let beta = workgroup.create_vbuffer(beta);
+    let output = workgroup.create_vbuffer_uninit::<f32>(12);
+
+    nn::layer_norm(&mut workgroup, input, gamma, beta, output, shape, epsilon).unwrap();
+
+    let output: Vec<f32> = workgroup.take_vbuffer(output).unwrap();
+    assert_close(&output, &expected);
+}
+
+#[test]
+fn shapes_must_match() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let input = workgroup.create_vbuffer(vec![1.0f32; 6]);
+    let output = workgroup.create_vbuffer_uninit::<f32>(6);
+
+    let result = nn::softmax(&mut workgroup, input, output, Shape::new(2, 4));
+    assert!(matches!(result, Err(WiscError::ShapeMismatch(_))));
+}
@@ -0,0 +1,102 @@
+use wisc::collective::ReduceOp;
+use wisc::ops::{map, reduce, sort};
+use wisc::prelude::*;
+
+fn scrambled(len: u32) -> Vec<u32> {
+    (0..len)
+        .map(|i| i.wrapping_mul(0x9e37_79b9).rotate_left(7) % 100_000)
+        .collect()
+}
+
+/// Runs every op on `workgroup`, checking each against the host.
+fn check_ops(workgroup: &mut Workgroup) {
+    let values = scrambled(5000);
+
+    let handle = workgroup.create_vbuffer(values.clone());
+    let tripled = map(workgroup, handle, "x * 3u", |x: u32| x * 3).expect("Failed to map");
+    assert_eq!(
+        workgroup.take_vbuffer::<u32>(tripled).unwrap(),
+        values.iter().map(|x| x * 3).collect::<Vec<_>>()
+    );
+
+    assert_eq!(
+        reduce::<u32>(workgroup, handle, ReduceOp::Sum),
+        Ok(values.iter().sum())
+    );
+    assert_eq!(
+        reduce::<u32>(workgroup, handle, ReduceOp::Max),
+        Ok(*values.iter().max().unwrap())
+    );
+
+    let sorted = sort::<u32>(workgroup, handle).expect("Failed to sort");
+    let mut expected = values.clone();
+    expected.sort_unstable();
+    assert_eq!(workgroup.take_vbuffer::<u32>(sorted).unwrap(), expected);
+
+    let floats: Vec<f32> = values.iter().map(|&x| x as f32 - 50_000.0).collect();
+    let handle = workgroup.create_vbuffer(floats.clone());
+
+    assert_eq!(
+        reduce::<f32>(workgroup, handle, ReduceOp::Min),
+        Ok(floats.iter().copied().fold(f32::INFINITY, f32::min))
+    );
+
+    let sorted = sort::<f32>(workgroup, handle).expect("Failed to sort");
+    let mut expected = floats;
+    expected.sort_unstable_by(f32::total_cmp);
+    assert_eq!(workgroup.take_vbuffer::<f32>(sorted).unwrap(), expected);
+}
+
+#[test]
+fn ops_on_devices() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+
+    check_ops(&mut Workgroup::from_devices(devices));
+}
+
+#[test]
+fn ops_on_the_host_without_devices() {
+    let mut workgroup = WorkgroupBuilder::new()
+        .devices(vec![])
+        .cpu_fallback()
+        .build();
+
+    check_ops(&mut workgroup);
+}
+
+#[test]
+fn ops_without_devices_or_fallback() {
+    let mut workgroup = Workgroup::from_devices(vec![]);
+    let handle = workgroup.create_vbuffer(vec![1u32; 16]);
+
+    assert_eq!(
+        reduce::<u32>(&mut workgroup, handle, ReduceOp::Sum),
+        Err(WiscError::NoDevices)
+    );
+}
+
+#[test]
+fn reduce_fewer_elements_than_devices() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let handle = workgroup.create_vbuffer(vec![7i32]);
+    assert_eq!(reduce(&mut workgroup, handle, ReduceOp::Product), Ok(7i32));
+
+    let handle = workgroup.create_vbuffer(Vec::<i32>::new());
+    assert_eq!(reduce(&mut workgroup, handle, ReduceOp::Sum), Ok(0i32));
+}
+
+#[test]
+fn reduce_wraps_integers_on_the_host() {
+    let mut workgroup = WorkgroupBuilder::new()
+        .devices(vec![])
+        .cpu_fallback()
+        .build();
+
+    let handle = workgroup.create_vbuffer(vec![u32::MAX, 2]);
+    assert_eq!(reduce(&mut workgroup, handle, ReduceOp::Sum), Ok(1u32));
+
+    let handle = workgroup.create_vbuffer(vec![i32::MAX, 2]);
+    assert_eq!(reduce(&mut workgroup, handle, ReduceOp::Product), Ok(-2i32));
+}
@@ -17,7 +17,7 @@ fn overrides() {
     let obuf = workgroup.create_vbuffer(vec![-1.0f32; 256]);
 
     // Define our task and input our buffers.
-    let task = TaskBuilder::new(&mut workgroup, include_wgsl!("./overrides.wgsl"))
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./overrides.wgsl"))
         .with_kernel("main")
         .with_size((1, 1, 1))
         .with_override(0, a)
@@ -28,10 +28,82 @@ fn overrides() {
         .expect("Failed to build task");
 
     // Block the current thread while the task runs.
-    task.run();
+    task.run().expect("Failed to run task");
 
     // Take ownership of the buffer from the runtime.
     let obuf1: Vec<f32> = workgroup.take_vbuffer(obuf).unwrap();
 
     assert_eq!(obuf1, vec![a * 13.0 + b; 256]);
 }
+
+#[test]
+fn overrides_by_name() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let ibuf = workgroup.create_vbuffer(vec![3.0f32; 512]);
+    let obuf = workgroup.create_vbuffer_uninit::<f32>(512);
+
+    // The workgroup size is an override too, so the dispatch size is given explicitly.
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./named_overrides.wgsl"))
+        .with_size((4, 1, 1))
+        .with_override("SCALE", 4.0)
+        .with_override("OFFSET", 0.5)
+        .with_override("BLOCK", 128)
+        .with_input_buffer(0, ibuf)
+        .with_output_buffer(1, obuf)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let obuf: Vec<f32> = workgroup.take_vbuffer(obuf).unwrap();
+    assert_eq!(obuf, vec![12.5; 512]);
+}
+
+#[test]
+fn override_must_exist() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let ibuf = workgroup.create_vbuffer(vec![3.0f32; 256]);
+    let obuf = workgroup.create_vbuffer_uninit::<f32>(256);
+
+    // Overrides with an `@id` are set by the id, not the name.
+    let result = TaskBuilder::new(&mut workgroup, include_wgsl!("./overrides.wgsl"))
+        .with_size((1, 1, 1))
+        .with_override("A", 2.0)
+        .with_input_buffer(0, ibuf)
+        .with_output_buffer(1, obuf)
+        .build();
+
+    assert!(matches!(
+        result,
+        Err(WiscError::UnknownOverride { key, candidates })
+            if key == "A" && candidates == ["0", "1"]
+    ));
+}
+
+#[test]
+fn overrides_rebuilt() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let ibuf = workgroup.create_vbuffer(vec![13.0f32; 256]);
+
+    // Pipelines are reused between builds, but never across different constants.
+    for a in [2.0, 3.0, 2.0] {
+        let obuf = workgroup.create_vbuffer_uninit::<f32>(256);
+
+        TaskBuilder::new(&mut workgroup, include_wgsl!("./overrides.wgsl"))
+            .with_size((1, 1, 1))
+            .with_override(0, a)
+            .with_override(1, 1.0)
+            .with_input_buffer(0, ibuf)
+            .with_output_buffer(1, obuf)
+            .build()
+            .expect("Failed to build task")
+            .run()
+            .expect("Failed to run task");
+
+        let obuf: Vec<f32> = workgroup.take_vbuffer(obuf).unwrap();
+        assert_eq!(obuf, vec![a as f32 * 13.0 + 1.0; 256]);
+    }
+}
@@ -0,0 +1,13 @@
+@group(0) @binding(0) var<storage, read> input: array<f32>;
+@group(0) @binding(1) var<storage, read_write> result: array<f32>;
+
+// A deliberately simple single-invocation maximum, producing one partial per device.
+@compute @workgroup_size(1, 1, 1)
+fn main() {
+    var m = input[0];
+    for (var i = 1u; i < arrayLength(&input); i++) {
+        m = max(m, input[i]);
+    }
+
+    result[0] = m;
+}
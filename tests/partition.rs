@@ -0,0 +1,402 @@
+use wisc::{
+    partition::{PARTITION_INFO_WGSL, PartitionMode},
+    prelude::*,
+    workgroup::Weighting,
+};
+
+#[test]
+fn split_array_addition() {
+    let devices = VDevice::all();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let ibuf1 = workgroup.create_vbuffer((0..1024u32).collect());
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let obuf1 = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    // Each device only receives and computes its own chunk, so enough invocations for the
+    // whole buffer covers every chunk.
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_kernel("main")
+        .with_size((4, 1, 1))
+        .with_input_buffer_partitioned(0, ibuf1, PartitionMode::Split)
+        .with_input_buffer_partitioned(1, ibuf2, PartitionMode::Split)
+        .with_output_buffer_partitioned(2, obuf1, PartitionMode::Split)
+        .build()
+        .expect("Failed to build task");
+
+    task.run().expect("Failed to run task");
+
+    let obuf1: Vec<u32> = workgroup.take_vbuffer(obuf1).unwrap();
+
+    assert_eq!(obuf1, (3..1027u32).collect::<Vec<_>>());
+}
+
+#[test]
+fn element_group_size_must_divide_length() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let buffer = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    assert!(workgroup.set_element_group_size(buffer, 256).is_ok());
+    assert!(workgroup.set_element_group_size(buffer, 1000).is_err());
+    assert!(workgroup.set_element_group_size(buffer, 0).is_err());
+}
+
+#[test]
+fn weighted_array_addition() {
+    let mut workgroup = WorkgroupBuilder::new().devices(VDevice::all()).build();
+
+    let ibuf1 = workgroup.create_vbuffer((0..1024u32).collect());
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let obuf1 = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    // Whole workgroups of 256 elements keep every device's chunk aligned to the shader.
+    workgroup.set_element_group_size(ibuf1, 256).unwrap();
+    workgroup.set_element_group_size(ibuf2, 256).unwrap();
+    workgroup.set_element_group_size(obuf1, 256).unwrap();
+
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer_partitioned(0, ibuf1, PartitionMode::Weighted)
+        .with_input_buffer_partitioned(1, ibuf2, PartitionMode::Weighted)
+        .with_output_buffer_partitioned(2, obuf1, PartitionMode::Weighted)
+        .build()
+        .expect("Failed to build task");
+
+    task.run().expect("Failed to run task");
+
+    let obuf1: Vec<u32> = workgroup.take_vbuffer(obuf1).unwrap();
+
+    assert_eq!(obuf1, (3..1027u32).collect::<Vec<_>>());
+}
+
+/// Runs array addition over `workgroup`'s devices, split by their weightings.
+fn weighted_addition(workgroup: &mut Workgroup) -> Vec<u32> {
+    let ibuf1 = workgroup.create_vbuffer((0..1024u32).collect());
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let obuf1 = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    for buffer in [ibuf1, ibuf2, obuf1] {
+        workgroup.set_element_group_size(buffer, 256).unwrap();
+    }
+
+    TaskBuilder::new(workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer_partitioned(0, ibuf1, PartitionMode::Weighted)
+        .with_input_buffer_partitioned(1, ibuf2, PartitionMode::Weighted)
+        .with_output_buffer_partitioned(2, obuf1, PartitionMode::Weighted)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    workgroup.take_vbuffer(obuf1).unwrap()
+}
+
+#[test]
+fn set_weightings_split_the_work() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    workgroup
+        .set_vdevice_weightings(&[(0, 3.0), (1, 1.0)])
+        .expect("Failed to set weightings");
+    let weightings: Vec<f32> = workgroup
+        .vdevice_weightings()
+        .into_iter()
+        .map(|(_, weighting)| weighting)
+        .collect();
+    assert_eq!(weightings, vec![0.75, 0.25]);
+    assert_eq!(
+        weighted_addition(&mut workgroup),
+        (3..1027u32).collect::<Vec<_>>()
+    );
+
+    // A device weighted zero sits the task out.
+    workgroup.set_vdevice_weightings(&[(1, 0.0)]).unwrap();
+    assert_eq!(workgroup.vdevice_weightings()[0].1, 1.0);
+    assert_eq!(
+        weighted_addition(&mut workgroup),
+        (3..1027u32).collect::<Vec<_>>()
+    );
+
+    // Labels name every device that has them.
+    let label = workgroup.vdevice_weightings()[0].0.clone();
+    workgroup.set_vdevice_weightings(&[(&label, 1.0)]).unwrap();
+    assert_eq!(workgroup.vdevice_weightings()[1].1, 0.5);
+}
+
+#[test]
+fn manual_weightings_fit_any_number_of_devices() {
+    let weightings = |weights: Vec<f32>| -> Vec<f32> {
+        let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+
+        WorkgroupBuilder::new()
+            .devices(devices)
+            .weighting(Weighting::Manual(weights))
+            .without_calibration_file()
+            .build()
+            .vdevice_weightings()
+            .into_iter()
+            .map(|(_, weighting)| weighting)
+            .collect()
+    };
+
+    assert_eq!(weightings(vec![3.0, 1.0]), vec![0.75, 0.25]);
+    // Weights past the last device are ignored.
+    assert_eq!(weightings(vec![3.0, 1.0, 4.0]), vec![0.75, 0.25]);
+    // Devices past the end of the list get the mean of the weights given.
+    assert_eq!(weightings(vec![3.0]), vec![0.5, 0.5]);
+    assert_eq!(weightings(vec![]), vec![0.5, 0.5]);
+    // Weights that can't be normalized give way to estimates, even for identical devices.
+    assert_eq!(weightings(vec![0.0, 0.0]), vec![0.5, 0.5]);
+    assert_eq!(weightings(vec![-1.0, 3.0]), vec![0.5, 0.5]);
+    assert_eq!(weightings(vec![f32::NAN, 1.0]), vec![0.5, 0.5]);
+}
+
+#[test]
+fn memory_caps_spill_to_other_devices() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+    workgroup
+        .set_vdevice_weightings(&[(0, 0.9), (1, 0.1)])
+        .unwrap();
+
+    // A quarter of the 4 KiB output, less the margin for rounding.
+    workgroup.set_memory_cap(0, Some(1024)).unwrap();
+
+    let output = workgroup.create_vbuffer_uninit::<u32>(1024);
+    let source = format!(
+        "{PARTITION_INFO_WGSL}
+@group(0) @binding(0) var<storage, read_write> devices: array<u32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {{
+    if id.x < wisc_partition.len {{
+        devices[id.x] = wisc_partition.device;
+    }}
+}}"
+    );
+
+    TaskBuilder::new(
+        &mut workgroup,
+        wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        },
+    )
+    .with_size_per_element(output)
+    .with_output_buffer_partitioned(0, output, PartitionMode::Weighted)
+    .build()
+    .expect("Failed to build task")
+    .run()
+    .expect("Failed to run task");
+
+    let devices: Vec<u32> = workgroup.take_vbuffer(output).unwrap();
+    let on_first = devices.iter().filter(|&&device| device == 0).count();
+
+    assert!(on_first > 0 && on_first < 256);
+    assert!(devices[on_first..].iter().all(|&device| device == 1));
+}
+
+#[test]
+fn set_weightings_are_validated() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+    let before = workgroup.vdevice_weightings();
+
+    assert!(matches!(
+        workgroup.set_vdevice_weightings(&[("no such device", 1.0)]),
+        Err(WiscError::UnknownDevice(_))
+    ));
+    assert!(matches!(
+        workgroup.set_vdevice_weightings(&[(0, -1.0)]),
+        Err(WiscError::InvalidWeighting(_))
+    ));
+    assert!(matches!(
+        workgroup.set_vdevice_weightings(&[(0, 0.0)]),
+        Err(WiscError::InvalidWeighting(_))
+    ));
+
+    assert_eq!(workgroup.vdevice_weightings(), before);
+}
+
+#[test]
+fn custom_partition_array_addition() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let ibuf1 = workgroup.create_vbuffer((0..1024u32).collect());
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let obuf1 = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    // Give the last device everything past the first quarter, and the rest a quarter each
+    // at most.
+    let skewed = PartitionMode::custom(|length, weightings| {
+        let num_devices = weightings.len();
+        let quarter = length / 4;
+
+        (0..num_devices)
+            .map(|device| {
+                let start = (device * quarter).min(length);
+                let end = if device + 1 == num_devices {
+                    length
+                } else {
+                    ((device + 1) * quarter).min(length)
+                };
+                start..end
+            })
+            .collect()
+    });
+
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer_partitioned(0, ibuf1, skewed.clone())
+        .with_input_buffer_partitioned(1, ibuf2, skewed.clone())
+        .with_output_buffer_partitioned(2, obuf1, skewed)
+        .build()
+        .expect("Failed to build task");
+
+    task.run().expect("Failed to run task");
+
+    let obuf1: Vec<u32> = workgroup.take_vbuffer(obuf1).unwrap();
+
+    assert_eq!(obuf1, (3..1027u32).collect::<Vec<_>>());
+}
+
+#[test]
+fn custom_partition_is_validated() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let ibuf1 = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let obuf1 = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    let out_of_bounds =
+        PartitionMode::custom(|length, weightings| vec![0..length + 1; weightings.len()]);
+
+    let task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, ibuf1)
+        .with_input_buffer(1, ibuf2)
+        .with_output_buffer_partitioned(2, obuf1, out_of_bounds)
+        .build();
+
+    assert!(task.is_err());
+
+    // Every device writing back the first half would race.
+    let overlapping =
+        PartitionMode::custom(|length, weightings| vec![0..length / 2; weightings.len()]);
+
+    if workgroup.vdevice_weightings().len() > 1 {
+        let task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+            .with_size((4, 1, 1))
+            .with_input_buffer(0, ibuf1)
+            .with_input_buffer(1, ibuf2)
+            .with_output_buffer_partitioned(2, obuf1, overlapping)
+            .build();
+
+        assert!(task.is_err());
+    }
+
+    // A buffer with nothing in it yet has to be written in full.
+    let uninit = workgroup.create_vbuffer_uninit::<u32>(1024);
+    let first_half = PartitionMode::custom(|length, weightings| {
+        let mut ranges = vec![0..0; weightings.len()];
+        ranges[0] = 0..length / 2;
+        ranges
+    });
+
+    let task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, ibuf1)
+        .with_input_buffer(1, ibuf2)
+        .with_output_buffer_partitioned(2, uninit, first_half)
+        .build();
+
+    assert!(task.is_err());
+}
+
+#[test]
+fn split_with_halo_stencil() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let data: Vec<u32> = (0..1024u32).collect();
+    let input = workgroup.create_vbuffer(data.clone());
+    let output = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    // Every device needs one element past each edge of its chunk.
+    let stencil = PartitionMode::Split.with_halo(1);
+
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./stencil.wgsl"))
+        .with_size((5, 1, 1))
+        .with_input_buffer_partitioned(0, input, stencil.clone())
+        .with_output_buffer_partitioned(1, output, stencil)
+        .build()
+        .expect("Failed to build task");
+
+    task.run().expect("Failed to run task");
+
+    let output: Vec<u32> = workgroup.take_vbuffer(output).unwrap();
+
+    let expected: Vec<u32> = (0..data.len())
+        .map(|i| {
+            let left = if i > 0 { data[i - 1] } else { 0 };
+            let right = data.get(i + 1).copied().unwrap_or(0);
+            left + data[i] + right
+        })
+        .collect();
+
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn partition_info_gives_global_indices() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let output = workgroup.create_vbuffer_uninit::<u32>(2048);
+
+    let source = format!(
+        "{PARTITION_INFO_WGSL}{}",
+        include_str!("./global_index.wgsl")
+    );
+
+    TaskBuilder::new(
+        &mut workgroup,
+        wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        },
+    )
+    .with_size_per_element(output)
+    .with_output_buffer_partitioned(0, output, PartitionMode::Split)
+    .build()
+    .expect("Failed to build task")
+    .run()
+    .expect("Failed to run task");
+
+    let output: Vec<u32> = workgroup.take_vbuffer(output).unwrap();
+
+    assert_eq!(output, (0..2048u32).collect::<Vec<_>>());
+}
+
+#[test]
+fn global_ids_continue_across_devices() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let input = workgroup.create_vbuffer((0..2000u32).collect());
+    let output = workgroup.create_vbuffer_uninit::<u32>(2000);
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./single_gpu.wgsl"))
+        .with_size_per_element(output)
+        .with_input_buffer_partitioned(0, input, PartitionMode::Split)
+        .with_output_buffer_partitioned(1, output, PartitionMode::Split)
+        .with_global_ids()
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let output: Vec<u32> = workgroup.take_vbuffer(output).unwrap();
+
+    assert_eq!(output, (0..2000u32).map(|i| i * 3).collect::<Vec<_>>());
+}
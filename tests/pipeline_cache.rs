@@ -0,0 +1,59 @@
+use std::fs;
+
+use wisc::prelude::*;
+
+fn add(workgroup: &mut Workgroup) -> Vec<u32> {
+    let a = workgroup.create_vbuffer((0..1024u32).collect());
+    let b = workgroup.create_vbuffer(vec![1u32; 1024]);
+    let result = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    TaskBuilder::new(workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size_per_element(result)
+        .with_input_buffer(0, a)
+        .with_input_buffer(1, b)
+        .with_output_buffer(2, result)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    workgroup.take_vbuffer(result).unwrap()
+}
+
+#[test]
+fn pipeline_cache_round_trips_through_its_directory() {
+    let dir = std::env::temp_dir().join(format!("wisc-pipeline-cache-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+
+    let mut workgroup = WorkgroupBuilder::new()
+        .devices(VDevice::all())
+        .pipeline_cache_dir(&dir)
+        .build();
+
+    assert_eq!(add(&mut workgroup), (1..1025u32).collect::<Vec<_>>());
+
+    // Devices without a pipeline cache write nothing.
+    let written = workgroup.save_pipeline_cache().unwrap();
+    let files = fs::read_dir(&dir).map_or(0, |entries| entries.count());
+    assert_eq!(files, written);
+
+    // A later run starts from what was saved.
+    let mut workgroup = WorkgroupBuilder::new()
+        .devices(VDevice::all())
+        .pipeline_cache_dir(&dir)
+        .build();
+
+    assert_eq!(add(&mut workgroup), (1..1025u32).collect::<Vec<_>>());
+    assert_eq!(workgroup.save_pipeline_cache().unwrap(), written);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn pipeline_cache_needs_a_directory() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    add(&mut workgroup);
+
+    assert_eq!(workgroup.save_pipeline_cache().unwrap(), 0);
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "playground")]
+
+use wisc::playground::{Fill, PREVIEW_LENGTH, Playground};
+use wisc::prelude::*;
+
+#[test]
+fn playground_runs_a_loaded_kernel() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut playground = Playground::new(Workgroup::from_devices(devices));
+
+    playground
+        .load("tests/array_addition.wgsl")
+        .expect("Failed to load shader");
+    assert_eq!(playground.kernels(), Ok(vec!["main".to_string()]));
+
+    playground.fill = Fill::Indices;
+    let trial = playground.run().expect("Failed to run kernel");
+
+    assert_eq!(trial.kernel, "main");
+    assert_eq!(trial.report.devices, 2);
+    assert_eq!(
+        trial.outputs,
+        vec![(2, (0..PREVIEW_LENGTH as u32).map(|i| i * 2).collect())]
+    );
+    assert_eq!(
+        trial
+            .launches
+            .iter()
+            .map(|launch| launch.device)
+            .collect::<Vec<_>>(),
+        vec![0, 1]
+    );
+}
+
+#[test]
+fn playground_reports_unusable_kernels() {
+    let mut playground = Playground::new(Workgroup::from_devices(VDevice::all()));
+
+    playground.source = "@compute @workgroup_size(1) fn main(".to_string();
+    assert!(matches!(playground.run(), Err(WiscError::ShaderParse(_))));
+
+    assert!(matches!(
+        playground.load("tests/missing.wgsl"),
+        Err(WiscError::ShaderFile(_))
+    ));
+    assert!(playground.trials().is_empty());
+}
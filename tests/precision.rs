@@ -0,0 +1,56 @@
+use wisc::precision::{Precision, cross_check};
+use wisc::prelude::*;
+
+fn normalize(workgroup: &mut Workgroup, precision: Precision) -> Result<Vec<f32>, WiscError> {
+    let input = workgroup.create_vbuffer((0..2048).map(|i| i as f32 * 0.37).collect());
+    let output = workgroup.create_vbuffer_uninit::<f32>(2048);
+
+    let report = TaskBuilder::new(workgroup, include_wgsl!("./normalize.wgsl"))
+        .with_size_per_element(output)
+        .with_input_buffer_partitioned(0, input, PartitionMode::Split)
+        .with_uniform_buffer(1, 3.0f32)
+        .with_output_buffer_partitioned(2, output, PartitionMode::Split)
+        .with_precision(precision)
+        .build()?
+        .run()?;
+
+    // The same adapter twice runs the same driver, so neither sits out.
+    assert!(report.excluded.is_empty());
+
+    workgroup.take_vbuffer(output)
+}
+
+#[test]
+fn consistent_devices_share_the_task() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let output = normalize(&mut workgroup, Precision::Consistent).expect("Failed to run task");
+
+    let expected: Vec<f32> = (0..2048).map(|i| i as f32 * 0.37 / 3.0).collect();
+    assert_eq!(output.len(), expected.len());
+    assert!(
+        output
+            .iter()
+            .zip(&expected)
+            .all(|(value, expected)| (value - expected).abs() <= expected.abs() * 1e-6)
+    );
+}
+
+#[test]
+fn identical_devices_cross_check_clean() {
+    let devices: Vec<VDevice> = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let device_count = devices.len();
+
+    let deviations = cross_check(&devices, |workgroup| {
+        normalize(workgroup, Precision::Relaxed)
+    })
+    .expect("Failed to cross-check");
+
+    assert_eq!(deviations.len(), device_count);
+    assert!(
+        deviations
+            .iter()
+            .all(|deviation| { deviation.max_ulps == 0 && deviation.differing == 0 })
+    );
+}
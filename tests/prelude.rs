@@ -0,0 +1,38 @@
+use wisc::prelude::*;
+
+#[test]
+fn tasks_build_without_touching_wgpu() {
+    let devices = VDevice::all_with_features(Features::IMMEDIATES, Features::empty());
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let a = workgroup.create_vbuffer(vec![1u32; 1024]);
+    let b = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let c = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    let shader = Shader::wgsl(include_str!("./array_addition.wgsl")).with_label("addition");
+    let mut task = TaskBuilder::new(&mut workgroup, shader)
+        .with_size_per_element(c)
+        .with_input_buffer(0, a)
+        .with_input_buffer(1, b)
+        .with_output_buffer(2, c)
+        .build()
+        .expect("Failed to build task");
+
+    let report: RunReport = task.run().expect("Failed to run task");
+    assert!(report.devices > 0);
+
+    drop(task);
+    let c: Vec<u32> = workgroup.take_vbuffer(c).unwrap();
+    assert_eq!(c, vec![3u32; 1024]);
+}
+
+#[test]
+fn features_convert_to_and_from_wgpu() {
+    let features = Features::SHADER_F16 | Features::IMMEDIATES;
+    let raw: wisc::wgpu::Features = features.into();
+
+    assert!(raw.contains(wisc::wgpu::Features::SHADER_F16 | wisc::wgpu::Features::IMMEDIATES));
+    assert_eq!(Features::from(raw), features);
+    assert!(features.contains(Features::IMMEDIATES));
+    assert!(!features.contains(Features::SHADER_F64));
+}
@@ -0,0 +1,69 @@
+use wisc::{prelude::*, quant::Quantization};
+
+fn two_devices() -> Workgroup {
+    Workgroup::from_devices(VDevice::all().into_iter().chain(VDevice::all()).collect())
+}
+
+#[test]
+fn host_round_trip() {
+    let mut workgroup = two_devices();
+
+    // Not a whole number of words.
+    let values: Vec<f32> = (0..10).map(|i| i as f32 * 0.5 - 2.0).collect();
+    let quantization = Quantization::for_values(&values);
+
+    let quantized = workgroup.create_quantized_vbuffer(&values, quantization);
+    assert_eq!(workgroup.quantization(quantized), Some(quantization));
+
+    let restored = workgroup.take_quantized_vbuffer(quantized).unwrap();
+    assert_eq!(restored.len(), values.len());
+    for (r, v) in restored.iter().zip(&values) {
+        assert!((r - v).abs() <= quantization.scale / 2.0, "{r} != {v}");
+    }
+}
+
+#[test]
+fn device_round_trip() {
+    let mut workgroup = two_devices();
+
+    let values: Vec<f32> = (0..1024)
+        .map(|i| ((i * 37) % 200) as f32 / 10.0 - 10.0)
+        .collect();
+    let quantization = Quantization::for_values(&values);
+
+    // Quantize on the devices, then dequantize again.
+    let input = workgroup.create_vbuffer(values.clone());
+    let quantized = workgroup.create_quantized_vbuffer_uninit(1024, quantization);
+    workgroup.quantize(input, quantized).unwrap();
+
+    let host_quantized = workgroup.create_quantized_vbuffer(&values, quantization);
+    let on_device: Vec<[i8; 4]> = workgroup.take_vbuffer(quantized).unwrap();
+    let on_host: Vec<[i8; 4]> = workgroup.take_vbuffer(host_quantized).unwrap();
+    assert_eq!(on_device, on_host);
+
+    let quantized = workgroup.create_quantized_vbuffer(&values, quantization);
+    let output = workgroup.create_vbuffer_uninit::<f32>(1024);
+    workgroup.dequantize(quantized, output).unwrap();
+
+    let output: Vec<f32> = workgroup.take_vbuffer(output).unwrap();
+    for (o, v) in output.iter().zip(&values) {
+        assert!(
+            (o - v).abs() <= quantization.scale / 2.0 + 1e-6,
+            "{o} != {v}"
+        );
+    }
+}
+
+#[test]
+fn conversions_need_whole_words() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+    let quantization = Quantization::new(0.1, 0);
+
+    let quantized = workgroup.create_quantized_vbuffer(&[1.0; 6], quantization);
+    let output = workgroup.create_vbuffer_uninit::<f32>(6);
+
+    assert!(matches!(
+        workgroup.dequantize(quantized, output),
+        Err(WiscError::ShapeMismatch(_))
+    ));
+}
@@ -0,0 +1,138 @@
+use std::time::Duration;
+
+use wisc::{partition::PartitionMode, prelude::*};
+
+#[test]
+fn single_device_fast_path() {
+    // Build a workgroup around exactly one device.
+    let device = VDevice::best().expect("No compute device available");
+    let mut workgroup = Workgroup::from_devices(vec![device]);
+
+    let ibuf1 = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let obuf1 = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    let report = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_kernel("main")
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, ibuf1)
+        .with_input_buffer(1, ibuf2)
+        .with_output_buffer(2, obuf1)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    assert_eq!(report.devices, 1);
+    assert!(report.single_device_fast_path);
+
+    let obuf1: Vec<u32> = workgroup.take_vbuffer(obuf1).unwrap();
+    assert_eq!(obuf1, vec![5u32; 1024]);
+}
+
+#[test]
+fn run_report_times_each_device() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let ibuf1 = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let obuf1 = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    let report = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size_per_element(obuf1)
+        .with_input_buffer_partitioned(0, ibuf1, PartitionMode::Split)
+        .with_input_buffer_partitioned(1, ibuf2, PartitionMode::Split)
+        .with_output_buffer_partitioned(2, obuf1, PartitionMode::Split)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    assert_eq!(report.timings.len(), 2);
+
+    for timing in &report.timings {
+        assert!(timing.submit > Duration::ZERO);
+        assert!(timing.execution > Duration::ZERO);
+        assert_eq!(timing.bytes_uploaded, 0);
+    }
+
+    // Each device reads back its half of the output.
+    let read_back: u64 = report.timings.iter().map(|t| t.bytes_read_back).sum();
+    assert_eq!(read_back, 1024 * 4);
+}
+
+fn addition_report(devices: Vec<VDevice>) -> RunReport {
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let ibuf1 = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let obuf1 = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size_per_element(obuf1)
+        .with_input_buffer(0, ibuf1)
+        .with_input_buffer(1, ibuf2)
+        .with_output_buffer(2, obuf1)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task")
+}
+
+#[test]
+fn run_report_times_kernels_on_the_device() {
+    // Without timestamp queries, only the host's times are known.
+    let report = addition_report(VDevice::all_with_features(
+        wgpu::Features::empty(),
+        wgpu::Features::empty(),
+    ));
+    assert!(report.timings.iter().all(|timing| timing.kernel.is_none()));
+
+    let devices = VDevice::all_with_features(
+        wgpu::Features::TIMESTAMP_QUERY,
+        wgpu::Features::TIMESTAMP_QUERY,
+    );
+    if devices.is_empty() {
+        return;
+    }
+
+    let report = addition_report(devices);
+    assert!(report.timings.iter().all(|timing| timing.kernel.is_some()));
+}
+
+#[test]
+fn pipeline_statistics_count_each_devices_invocations() {
+    let devices = VDevice::all()
+        .into_iter()
+        .chain(VDevice::all_with_features(
+            wgpu::Features::PIPELINE_STATISTICS_QUERY,
+            wgpu::Features::PIPELINE_STATISTICS_QUERY,
+        ))
+        .collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let ibuf1 = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let obuf1 = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    let report = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size_per_element(obuf1)
+        .with_input_buffer_partitioned(0, ibuf1, PartitionMode::Split)
+        .with_input_buffer_partitioned(1, ibuf2, PartitionMode::Split)
+        .with_output_buffer_partitioned(2, obuf1, PartitionMode::Split)
+        .with_pipeline_statistics()
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    assert_eq!(report.invocations.len(), report.devices);
+
+    // Each device runs whole workgroups of 256, and between them they cover every element.
+    let counted: Vec<u64> = report.invocations.iter().flatten().copied().collect();
+    assert!(counted.iter().all(|invocations| invocations % 256 == 0));
+    if counted.len() == report.devices {
+        assert!(counted.iter().sum::<u64>() >= 1024);
+    }
+}
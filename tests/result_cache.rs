@@ -0,0 +1,108 @@
+use std::fs;
+
+use wisc::{collective::Merge, prelude::*};
+
+fn add(workgroup: &mut Workgroup, b: u32) -> (Vec<u32>, bool) {
+    let ibuf1 = workgroup.create_vbuffer((0..1024u32).collect());
+    let ibuf2 = workgroup.create_vbuffer(vec![b; 1024]);
+    let obuf1 = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    let report = TaskBuilder::new(workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size_per_element(obuf1)
+        .with_input_buffer(0, ibuf1)
+        .with_input_buffer(1, ibuf2)
+        .with_output_buffer(2, obuf1)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    (workgroup.take_vbuffer(obuf1).unwrap(), report.from_cache)
+}
+
+fn expected(b: u32) -> Vec<u32> {
+    (0..1024u32).map(|a| a + b).collect()
+}
+
+#[test]
+fn result_cache_memoizes_identical_runs() {
+    let dir = std::env::temp_dir().join(format!("wisc-result-cache-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+
+    // Room for two results of 4 KiB each, but not three.
+    let mut workgroup = WorkgroupBuilder::new()
+        .devices(VDevice::all())
+        .result_cache(&dir, 10_000)
+        .build();
+
+    assert_eq!(add(&mut workgroup, 1), (expected(1), false));
+    assert_eq!(add(&mut workgroup, 1), (expected(1), true));
+
+    // Different inputs are a different run.
+    assert_eq!(add(&mut workgroup, 2), (expected(2), false));
+    assert_eq!(add(&mut workgroup, 2), (expected(2), true));
+
+    // The oldest result makes way for a third.
+    assert_eq!(add(&mut workgroup, 3), (expected(3), false));
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+    assert_eq!(add(&mut workgroup, 1), (expected(1), false));
+
+    // Entries outlive the Workgroup that wrote them.
+    drop(workgroup);
+
+    let mut workgroup = WorkgroupBuilder::new()
+        .devices(VDevice::all())
+        .result_cache(&dir, 10_000)
+        .build();
+
+    assert_eq!(add(&mut workgroup, 1), (expected(1), true));
+
+    // Without a cache, everything runs.
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+    assert_eq!(add(&mut workgroup, 1), (expected(1), false));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+fn double_merged(workgroup: &mut Workgroup, merge: Merge<u32>) -> bool {
+    let input = workgroup.create_vbuffer((0..1024u32).collect());
+    let output = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    TaskBuilder::new(workgroup, include_wgsl!("./double.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, input)
+        .with_merged_output_buffer(1, output, merge)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task")
+        .from_cache
+}
+
+#[test]
+fn result_cache_tells_merges_apart() {
+    let dir = std::env::temp_dir().join(format!("wisc-result-merges-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+
+    let mut workgroup = WorkgroupBuilder::new()
+        .devices(VDevice::all())
+        .result_cache(&dir, 1_000_000)
+        .build();
+
+    assert!(!double_merged(&mut workgroup, Merge::ReduceAdd));
+    assert!(double_merged(&mut workgroup, Merge::ReduceAdd));
+
+    // Merging the same copies another way is a different run.
+    assert!(!double_merged(&mut workgroup, Merge::ReduceMax));
+    assert!(double_merged(&mut workgroup, Merge::ReduceMax));
+
+    // Custom merges can't be told apart, so always run.
+    for _ in 0..2 {
+        assert!(!double_merged(
+            &mut workgroup,
+            Merge::Custom(Box::new(|a, b| a.min(b)))
+        ));
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
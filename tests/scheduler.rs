@@ -0,0 +1,170 @@
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use wisc::prelude::*;
+
+use wisc::scheduler::{Priority, STARVATION_LIMIT, Scheduler};
+
+fn add(workgroup: &mut Workgroup, value: u32) -> Result<Vec<u32>, WiscError> {
+    let a = workgroup.create_vbuffer(vec![value; 1024]);
+    let b = workgroup.create_vbuffer(vec![1u32; 1024]);
+    let c = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    TaskBuilder::new(workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size_per_element(c)
+        .with_input_buffer(0, a)
+        .with_input_buffer(1, b)
+        .with_output_buffer(2, c)
+        .build()?
+        .run()?;
+
+    workgroup.take_vbuffer(c)
+}
+
+#[test]
+fn scheduled_jobs_all_run() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let scheduler = Scheduler::new(devices);
+
+    let tasks: Vec<_> = (0..16u32)
+        .map(|value| {
+            scheduler
+                .submit(move |workgroup| add(workgroup, value))
+                .expect("Failed to submit job")
+        })
+        .collect();
+
+    for (value, task) in (0..16u32).zip(tasks) {
+        assert_eq!(task.join(), Ok(vec![value + 1; 1024]));
+    }
+
+    assert_eq!(scheduler.queued(), vec![0, 0]);
+}
+
+#[test]
+fn scheduled_job_errors_come_back() {
+    let scheduler = Scheduler::new(VDevice::all());
+
+    let task = scheduler
+        .submit(|workgroup| {
+            TaskBuilder::from_workgroup(workgroup)
+                .with_size((1, 1, 1))
+                .build()
+                .map(|_| ())
+        })
+        .expect("Failed to submit job");
+
+    assert_eq!(task.join(), Err(WiscError::MissingShader));
+}
+
+#[test]
+fn scheduler_without_devices() {
+    let scheduler = Scheduler::new(vec![]);
+
+    assert!(matches!(
+        scheduler.submit(|_| Ok(())),
+        Err(WiscError::NoDevices)
+    ));
+}
+
+/// Holds the scheduler's only device busy with one job while jobs of each of `priorities`
+/// queue up behind it, then returns the order they ran in.
+fn run_in_order(priorities: &[Priority]) -> Vec<usize> {
+    let scheduler = Scheduler::new(VDevice::all().into_iter().take(1).collect());
+    let order = Arc::new(Mutex::new(vec![]));
+
+    let (release, gate) = mpsc::channel::<()>();
+    let blocker = scheduler
+        .submit(move |_| {
+            let _ = gate.recv();
+            Ok(())
+        })
+        .expect("Failed to submit job");
+
+    // Give the worker time to take the blocking job, so the rest all wait together.
+    while scheduler.queued() != vec![0] {
+        std::thread::yield_now();
+    }
+
+    let tasks: Vec<_> = priorities
+        .iter()
+        .enumerate()
+        .map(|(index, &priority)| {
+            let order = order.clone();
+
+            scheduler
+                .submit_with_priority(priority, move |_| {
+                    order.lock().unwrap().push(index);
+                    Ok(())
+                })
+                .expect("Failed to submit job")
+        })
+        .collect();
+
+    drop(release);
+    blocker.join().expect("Blocking job failed");
+
+    for task in tasks {
+        task.join().expect("Job failed");
+    }
+
+    Arc::try_unwrap(order).unwrap().into_inner().unwrap()
+}
+
+#[test]
+fn higher_priority_jobs_run_first() {
+    let order = run_in_order(&[
+        Priority::Background,
+        Priority::Normal,
+        Priority::High,
+        Priority::Background,
+        Priority::High,
+    ]);
+
+    assert_eq!(order, vec![2, 4, 1, 0, 3]);
+}
+
+#[test]
+fn passed_over_jobs_run_eventually() {
+    let mut priorities = vec![Priority::Background];
+    priorities.extend([Priority::High; STARVATION_LIMIT as usize + 2]);
+
+    let order = run_in_order(&priorities);
+
+    let background = order.iter().position(|&index| index == 0).unwrap();
+    assert_eq!(background, STARVATION_LIMIT as usize);
+}
+
+#[test]
+fn shutdown_cancels_queued_jobs() {
+    let scheduler = Scheduler::new(VDevice::all().into_iter().take(1).collect());
+
+    let (release, gate) = mpsc::channel::<()>();
+    let blocker = scheduler
+        .submit(move |workgroup| {
+            let _ = gate.recv();
+            add(workgroup, 1)
+        })
+        .expect("Failed to submit job");
+
+    while scheduler.queued() != vec![0] {
+        std::thread::yield_now();
+    }
+
+    let queued = scheduler
+        .submit(|workgroup| add(workgroup, 2))
+        .expect("Failed to submit job");
+
+    let shutdown = std::thread::spawn(move || scheduler.shutdown());
+
+    // The queued job is cancelled before the running one is let go.
+    assert_eq!(queued.join(), Err(WiscError::Cancelled));
+    drop(release);
+
+    assert_eq!(blocker.join(), Ok(vec![2; 1024]));
+
+    let report = shutdown.join().expect("Shutdown panicked");
+    assert_eq!(report.cancelled, 1);
+    assert_eq!(report.devices_destroyed, vec![0]);
+    assert!(report.pipelines_released > 0);
+    assert!(report.errors.is_empty());
+}
@@ -0,0 +1,99 @@
+use wisc::prelude::*;
+
+#[test]
+fn registered_shader() {
+    // Get all the hardware devices available to our system.
+    let devices = VDevice::all();
+
+    // Create a Workgroup out of our device(s).
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    // Compile our kernels once, up front.
+    workgroup.register_shaders(vec![
+        ("add", include_wgsl!("./array_addition.wgsl")),
+        ("affine", include_wgsl!("./overrides.wgsl")),
+    ]);
+
+    assert!(workgroup.has_registered_shader("add"));
+    assert!(workgroup.has_registered_shader("affine"));
+
+    let ibuf1 = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let obuf1 = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    // Tasks reference the compiled modules by name.
+    let mut task = TaskBuilder::from_workgroup(&mut workgroup)
+        .with_registered_shader("add")
+        .with_kernel("main")
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, ibuf1)
+        .with_input_buffer(1, ibuf2)
+        .with_output_buffer(2, obuf1)
+        .build()
+        .expect("Failed to build task");
+
+    task.run().expect("Failed to run task");
+
+    let obuf1: Vec<u32> = workgroup.take_vbuffer(obuf1).unwrap();
+
+    assert_eq!(obuf1, vec![5u32; 1024]);
+
+    // Unknown names fail to build.
+    let missing = TaskBuilder::from_workgroup(&mut workgroup)
+        .with_registered_shader("missing")
+        .with_kernel("main")
+        .with_size((1, 1, 1))
+        .build();
+
+    assert_eq!(
+        missing.err(),
+        Some(WiscError::UnknownShader("missing".to_string()))
+    );
+}
+
+#[test]
+fn shaders_registered_at_construction() {
+    let mut workgroup = WorkgroupBuilder::new()
+        .devices(VDevice::all().into_iter().chain(VDevice::all()).collect())
+        .shader("add", include_wgsl!("./array_addition.wgsl"))
+        .shader("double", include_wgsl!("./double.wgsl"))
+        .build();
+
+    assert!(workgroup.has_registered_shader("add"));
+    assert!(workgroup.has_registered_shader("double"));
+
+    let a = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let b = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let c = workgroup.create_vbuffer_uninit::<u32>(1024);
+    let d = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    // Any number of tasks can share each compiled module.
+    for _ in 0..2 {
+        let mut task = TaskBuilder::from_workgroup(&mut workgroup)
+            .with_registered_shader("add")
+            .with_kernel("main")
+            .with_size_per_element(c)
+            .with_input_buffer(0, a)
+            .with_input_buffer(1, b)
+            .with_output_buffer(2, c)
+            .build()
+            .expect("Failed to build task");
+
+        task.run().expect("Failed to run task");
+    }
+
+    let mut task = TaskBuilder::from_workgroup(&mut workgroup)
+        .with_registered_shader("double")
+        .with_kernel("main")
+        .with_size_per_element(d)
+        .with_input_buffer(0, c)
+        .with_output_buffer(1, d)
+        .build()
+        .expect("Failed to build task");
+
+    task.run().expect("Failed to run task");
+    drop(task);
+
+    let d: Vec<u32> = workgroup.take_vbuffer(d).unwrap();
+    assert_eq!(d, vec![10u32; 1024]);
+}
@@ -0,0 +1,34 @@
+use wisc::prelude::*;
+
+fn add(workgroup: &mut Workgroup, a: u32, b: u32) -> Vec<u32> {
+    let ibuf1 = workgroup.create_vbuffer(vec![a; 1024]);
+    let ibuf2 = workgroup.create_vbuffer(vec![b; 1024]);
+    let obuf1 = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    TaskBuilder::new(workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size_per_element(obuf1)
+        .with_input_buffer(0, ibuf1)
+        .with_input_buffer(1, ibuf2)
+        .with_output_buffer(2, obuf1)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    workgroup.take_vbuffer(obuf1).unwrap()
+}
+
+#[test]
+fn workgroups_share_devices() {
+    let mut first = Workgroup::from_devices(VDevice::all());
+    let mut second = Workgroup::from_devices(first.shared_devices());
+
+    // Tasks of either one run on the same devices, each with its own buffers.
+    assert_eq!(add(&mut first, 1, 2), vec![3u32; 1024]);
+    assert_eq!(add(&mut second, 10, 20), vec![30u32; 1024]);
+    assert_eq!(add(&mut first, 4, 5), vec![9u32; 1024]);
+
+    // Either one outlives the other.
+    drop(first);
+    assert_eq!(add(&mut second, 2, 2), vec![4u32; 1024]);
+}
@@ -0,0 +1,52 @@
+use wisc::prelude::*;
+
+fn add(workgroup: &mut Workgroup) -> VBufferHandle {
+    let a = workgroup.create_vbuffer(vec![1u32; 1024]);
+    let b = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let c = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    TaskBuilder::new(workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size_per_element(c)
+        .with_input_buffer(0, a)
+        .with_input_buffer(1, b)
+        .with_output_buffer(2, c)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    c
+}
+
+#[test]
+fn shutdown_releases_everything() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+    let devices = workgroup.vdevice_weightings().len();
+    add(&mut workgroup);
+
+    let report = workgroup.shutdown();
+
+    assert_eq!(report.devices_destroyed, (0..devices).collect::<Vec<_>>());
+    assert!(report.leaked_devices.is_empty());
+    assert_eq!(report.buffers_destroyed, devices);
+    assert!(report.pipelines_released > 0);
+    assert!(report.errors.is_empty());
+}
+
+#[test]
+fn shared_devices_are_left_open() {
+    let mut first = Workgroup::from_devices(VDevice::all());
+    let mut second = Workgroup::from_devices(first.shared_devices());
+    add(&mut first);
+
+    let report = first.shutdown();
+
+    assert!(report.devices_destroyed.is_empty());
+    assert_eq!(
+        report.leaked_devices,
+        (0..second.vdevice_weightings().len()).collect::<Vec<_>>()
+    );
+
+    let c = add(&mut second);
+    assert_eq!(second.take_vbuffer(c), Ok(vec![3u32; 1024]));
+}
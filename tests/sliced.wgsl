@@ -0,0 +1,20 @@
+struct WiscSlice {
+    workgroup_offset: u32,
+}
+
+@group(0) @binding(998) var<uniform> wisc_slice: WiscSlice;
+
+@group(0) @binding(0) var<storage, read_write> data: array<u32>;
+
+@compute @workgroup_size(64, 1, 1)
+fn main(
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    let index = (wisc_slice.workgroup_offset + workgroup_id.x) * 64u + local_index;
+    if (index >= arrayLength(&data)) {
+        return;
+    }
+
+    data[index] = data[index] * 2u + index;
+}
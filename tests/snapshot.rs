@@ -0,0 +1,94 @@
+use std::fs;
+
+use bytemuck::{Pod, Zeroable};
+use wisc::{prelude::*, quant::Quantization};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+struct Particle {
+    position: [f32; 2],
+    mass: f32,
+}
+
+#[test]
+fn buffers_round_trip_through_a_snapshot() {
+    let path = std::env::temp_dir().join(format!("wisc-snapshot-{}", std::process::id()));
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let a = workgroup.create_vbuffer((0..1024u32).collect());
+    let b = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let sum = workgroup.create_vbuffer_uninit::<u32>(1024);
+    let pending = workgroup.create_vbuffer_uninit::<f32>(16);
+    let quantized =
+        workgroup.create_quantized_vbuffer(&[0.5, -1.0, 2.0], Quantization::new(0.5, 0));
+    let particles = workgroup.create_vbuffer(vec![
+        Particle {
+            position: [1.0, 2.0],
+            mass: 3.0,
+        };
+        4
+    ]);
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size_per_element(sum)
+        .with_input_buffer(0, a)
+        .with_input_buffer(1, b)
+        .with_output_buffer(2, sum)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let saved = workgroup.save_buffers(&path).unwrap();
+    let saved_sum = saved.iter().position(|handle| *handle == sum).unwrap();
+    let saved_pending = saved.iter().position(|handle| *handle == pending).unwrap();
+    let saved_quantized = saved
+        .iter()
+        .position(|handle| *handle == quantized)
+        .unwrap();
+    let saved_particles = saved
+        .iter()
+        .position(|handle| *handle == particles)
+        .unwrap();
+
+    // A fresh Workgroup only knows the custom element type once it is registered.
+    let mut restored = Workgroup::from_devices(VDevice::all());
+    assert!(matches!(
+        restored.load_buffers(&path),
+        Err(WiscError::SnapshotFile(_))
+    ));
+
+    restored.register_element_type::<Particle>();
+    let loaded = restored.load_buffers(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.len(), saved.len());
+
+    let sum: Vec<u32> = restored.take_vbuffer(loaded[saved_sum]).unwrap();
+    assert_eq!(sum, (3..1027u32).collect::<Vec<_>>());
+
+    assert!(matches!(
+        restored.take_vbuffer::<f32>(loaded[saved_pending]),
+        Err(WiscError::Uninitialized)
+    ));
+
+    let values = restored
+        .take_quantized_vbuffer(loaded[saved_quantized])
+        .unwrap();
+    assert_eq!(values, vec![0.5, -1.0, 2.0]);
+
+    let particles: Vec<Particle> = restored.take_vbuffer(loaded[saved_particles]).unwrap();
+    assert_eq!(particles[3].mass, 3.0);
+}
+
+#[test]
+fn load_buffers_rejects_other_files() {
+    let path = std::env::temp_dir().join(format!("wisc-not-a-snapshot-{}", std::process::id()));
+    fs::write(&path, b"not a snapshot").unwrap();
+
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+    let loaded = workgroup.load_buffers(&path);
+    fs::remove_file(&path).unwrap();
+
+    assert!(matches!(loaded, Err(WiscError::SnapshotFile(_))));
+}
@@ -0,0 +1,42 @@
+#![cfg(feature = "spirv")]
+
+use wisc::{partition::PartitionMode, prelude::*, shader};
+
+#[test]
+fn spirv_shader() {
+    // tests/array_addition.wgsl, compiled to SPIR-V.
+    let source = include_spirv!("./array_addition.spv").unwrap();
+
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let ibuf1 = workgroup.create_vbuffer((0..1024u32).collect());
+    let ibuf2 = workgroup.create_vbuffer(vec![5u32; 1024]);
+    let obuf = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    // The module is reflected like WGSL, so the kernel and dispatch size are worked out.
+    TaskBuilder::new(&mut workgroup, source)
+        .with_size_per_element(obuf)
+        .with_input_buffer_partitioned(0, ibuf1, PartitionMode::Split)
+        .with_input_buffer_partitioned(1, ibuf2, PartitionMode::Split)
+        .with_output_buffer_partitioned(2, obuf, PartitionMode::Split)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let obuf: Vec<u32> = workgroup.take_vbuffer(obuf).unwrap();
+    assert_eq!(obuf, (5..1029u32).collect::<Vec<_>>());
+}
+
+#[test]
+fn invalid_spirv() {
+    assert!(matches!(
+        shader::spirv(b"@compute"),
+        Err(WiscError::InvalidShader(_))
+    ));
+    assert!(matches!(
+        shader::spirv(&[0x03, 0x02, 0x23]),
+        Err(WiscError::InvalidShader(_))
+    ));
+}
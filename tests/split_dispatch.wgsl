@@ -0,0 +1,17 @@
+struct WiscSlice {
+    workgroup_offset: u32,
+}
+
+@group(0) @binding(998) var<uniform> wisc_slice: WiscSlice;
+@group(0) @binding(0) var<storage, read_write> output: array<u32>;
+
+// One element per workgroup, found by the offset of the dispatch it was split into.
+@compute @workgroup_size(1, 1, 1)
+fn main(@builtin(workgroup_id) workgroup_id: vec3<u32>) {
+    let index = wisc_slice.workgroup_offset + workgroup_id.x;
+    if (index >= arrayLength(&output)) {
+        return;
+    }
+
+    output[index] = index;
+}
@@ -0,0 +1,22 @@
+@group(0) @binding(0) var<storage, read> input: array<u32>;
+@group(0) @binding(1) var<storage, read_write> output: array<u32>;
+
+// Sums each element with its neighbours, treating anything past either end as zero.
+@compute @workgroup_size(256, 1, 1)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let index = global_id.x;
+    let len = arrayLength(&output);
+    if (index >= len) {
+        return;
+    }
+
+    var sum = input[index];
+    if (index > 0u) {
+        sum += input[index - 1u];
+    }
+    if (index + 1u < len) {
+        sum += input[index + 1u];
+    }
+
+    output[index] = sum;
+}
@@ -0,0 +1,111 @@
+use std::sync::mpsc;
+
+use wisc::{
+    prelude::*,
+    stream::{ChannelSource, ReaderSource, SliceSource, StreamPipeline},
+};
+
+#[test]
+fn stream_from_slice() {
+    // Get all the hardware devices available to our system.
+    let devices = VDevice::all();
+
+    // Create a Workgroup out of our device(s).
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let data: Vec<u32> = (0..4096).collect();
+
+    // Each chunk holds 1024 elements, which (4, 1, 1) workgroups of 256 cover.
+    let mut stream = TaskBuilder::new(&mut workgroup, include_wgsl!("./double.wgsl"))
+        .with_kernel("main")
+        .with_size((4, 1, 1))
+        .with_stream_input::<u32>(0)
+        .with_stream_output::<u32>(1)
+        .build_stream()
+        .expect("Failed to build stream");
+
+    let mut source = SliceSource::new(bytemuck::cast_slice(&data), 1024 * 4).unwrap();
+    let mut results: Vec<u32> = Vec::new();
+
+    let chunks = stream
+        .run(&mut source, |bytes| {
+            results.extend_from_slice(bytemuck::cast_slice(bytes));
+        })
+        .expect("Failed to run stream");
+
+    assert_eq!(chunks, 4);
+    assert_eq!(results, data.iter().map(|x| x * 2).collect::<Vec<_>>());
+}
+
+#[test]
+fn stream_from_channel() {
+    let devices = VDevice::all();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    // A bounded channel makes the producer wait for the devices.
+    let (tx, rx) = mpsc::sync_channel(1);
+    let producer = std::thread::spawn(move || {
+        for i in 0..8u32 {
+            tx.send(bytemuck::cast_slice(&[i; 256]).to_vec()).unwrap();
+        }
+    });
+
+    let mut stream = TaskBuilder::new(&mut workgroup, include_wgsl!("./double.wgsl"))
+        .with_kernel("main")
+        .with_size((1, 1, 1))
+        .with_stream_input::<u32>(0)
+        .with_stream_output::<u32>(1)
+        .build_stream()
+        .expect("Failed to build stream");
+
+    let mut sums = Vec::new();
+    stream
+        .run(&mut ChannelSource::new(rx), |bytes| {
+            sums.push(bytemuck::cast_slice::<u8, u32>(bytes).iter().sum::<u32>());
+        })
+        .expect("Failed to run stream");
+
+    producer.join().unwrap();
+
+    assert_eq!(sums, (0..8u32).map(|i| i * 2 * 256).collect::<Vec<_>>());
+}
+
+#[test]
+fn pipelined_stages() {
+    let devices = VDevice::all();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let stage = |workgroup: &mut Workgroup| {
+        TaskBuilder::new(workgroup, include_wgsl!("./double.wgsl"))
+            .with_size((1, 1, 1))
+            .with_stream_input::<u32>(0)
+            .with_stream_output::<u32>(1)
+            .build_stage()
+            .expect("Failed to build stage")
+    };
+
+    let producer = stage(&mut workgroup);
+    let consumer = stage(&mut workgroup);
+
+    let data: Vec<u32> = (0..2048).collect();
+    let mut source = SliceSource::new(bytemuck::cast_slice(&data), 256 * 4).unwrap();
+    let mut results: Vec<u32> = Vec::new();
+
+    let chunks = StreamPipeline::new(&mut workgroup, producer, consumer)
+        .run(&mut source, |bytes| {
+            results.extend_from_slice(bytemuck::cast_slice(bytes))
+        })
+        .expect("Failed to run stream");
+
+    // Each stage doubles, so every element comes out quadrupled, still in order.
+    assert_eq!(chunks, 8);
+    assert_eq!(results, data.iter().map(|x| x * 4).collect::<Vec<_>>());
+}
+
+#[test]
+fn sources_reject_empty_chunks() {
+    let data = [0u8; 16];
+
+    assert!(SliceSource::new(&data, 0).is_err());
+    assert!(ReaderSource::new(data.as_slice(), 0).is_err());
+}
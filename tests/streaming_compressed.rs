@@ -0,0 +1,70 @@
+#![cfg(feature = "zstd")]
+
+use wisc::{
+    prelude::*,
+    stream::{Compression, DecompressSource, ReaderSource, SliceSource},
+};
+
+#[test]
+fn stream_from_zstd_reader() {
+    // Get all the hardware devices available to our system.
+    let devices = VDevice::all();
+
+    // Create a Workgroup out of our device(s).
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let data: Vec<u32> = (0..4096).collect();
+    let compressed = zstd::encode_all(bytemuck::cast_slice::<u32, u8>(&data), 3).unwrap();
+
+    let mut stream = TaskBuilder::new(&mut workgroup, include_wgsl!("./double.wgsl"))
+        .with_kernel("main")
+        .with_size((4, 1, 1))
+        .with_stream_input::<u32>(0)
+        .with_stream_output::<u32>(1)
+        .build_stream()
+        .expect("Failed to build stream");
+
+    // Decompression happens on the host as chunks are pulled.
+    let mut source = ReaderSource::zstd(compressed.as_slice(), 1024 * 4).unwrap();
+    let mut results: Vec<u32> = Vec::new();
+
+    stream
+        .run(&mut source, |bytes| {
+            results.extend_from_slice(bytemuck::cast_slice(bytes));
+        })
+        .expect("Failed to run stream");
+
+    assert_eq!(results, data.iter().map(|x| x * 2).collect::<Vec<_>>());
+}
+
+#[test]
+fn stream_from_compressed_chunks() {
+    let devices = VDevice::all();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    // A single compressed frame, handed over as one chunk.
+    let data = vec![7u32; 1024];
+    let compressed = zstd::encode_all(bytemuck::cast_slice::<u32, u8>(&data), 3).unwrap();
+
+    let mut stream = TaskBuilder::new(&mut workgroup, include_wgsl!("./double.wgsl"))
+        .with_kernel("main")
+        .with_size((4, 1, 1))
+        .with_stream_input::<u32>(0)
+        .with_stream_output::<u32>(1)
+        .build_stream()
+        .expect("Failed to build stream");
+
+    let mut source = DecompressSource::new(
+        SliceSource::new(&compressed, compressed.len()).unwrap(),
+        Compression::Zstd,
+    );
+    let mut results: Vec<u32> = Vec::new();
+
+    stream
+        .run(&mut source, |bytes| {
+            results.extend_from_slice(bytemuck::cast_slice(bytes));
+        })
+        .expect("Failed to run stream");
+
+    assert_eq!(results, vec![14u32; 1024]);
+}
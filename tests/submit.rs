@@ -0,0 +1,196 @@
+use std::cell::Cell;
+use std::mem::MaybeUninit;
+use std::time::Duration;
+
+use wisc::prelude::*;
+
+#[test]
+fn submit_then_wait() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let a = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let b = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let c = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, a)
+        .with_input_buffer(1, b)
+        .with_output_buffer(2, c)
+        .build()
+        .expect("Failed to build task");
+
+    let mut pending = task.submit().expect("Failed to submit task");
+
+    // The host is free until it asks for the results.
+    while !pending.poll().expect("Failed to poll task") {
+        std::thread::yield_now();
+    }
+
+    let report = pending.wait().expect("Failed to run task");
+    assert_eq!(report.devices, 2);
+
+    drop(task);
+    assert_eq!(workgroup.take_vbuffer::<u32>(c).unwrap(), vec![5u32; 1024]);
+}
+
+#[test]
+fn dropped_submission_discards_results() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let a = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let b = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let c = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, a)
+        .with_input_buffer(1, b)
+        .with_output_buffer(2, c)
+        .build()
+        .expect("Failed to build task");
+
+    drop(task.submit().expect("Failed to submit task"));
+
+    let mut readback = vec![MaybeUninit::uninit(); 1024];
+    let values: &mut [u32] = task
+        .workgroup()
+        .read_vbuffer_into(c, &mut readback)
+        .unwrap();
+    assert_eq!(values, &[0u32; 1024]);
+
+    // Nothing is left mapped, so the task runs again as usual.
+    task.run().expect("Failed to run task");
+
+    drop(task);
+    assert_eq!(workgroup.take_vbuffer::<u32>(c).unwrap(), vec![5u32; 1024]);
+}
+
+#[test]
+fn callback_on_completion() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let a = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let b = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let c = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, a)
+        .with_input_buffer(1, b)
+        .with_output_buffer(2, c)
+        .build()
+        .expect("Failed to build task");
+
+    let completed = Cell::new(0);
+
+    // As an event loop would, check in on the run until the hook fires.
+    let mut completion = task
+        .run_with_callback(|result| {
+            assert!(result.is_ok());
+            completed.set(completed.get() + 1);
+        })
+        .expect("Failed to submit task");
+
+    while !completion.poll() {
+        std::thread::yield_now();
+    }
+
+    drop(completion);
+    assert_eq!(completed.get(), 1);
+
+    // A completion dropped early still delivers its result.
+    drop(task.run_with_callback(|_| completed.set(completed.get() + 1)));
+    assert_eq!(completed.get(), 2);
+
+    drop(task);
+    assert_eq!(workgroup.take_vbuffer::<u32>(c).unwrap(), vec![5u32; 1024]);
+}
+
+#[test]
+fn wait_idle_fences_shared_devices() {
+    let workgroup = Workgroup::from_devices(VDevice::all());
+    let mut other = Workgroup::from_devices(workgroup.shared_devices());
+
+    let a = other.create_vbuffer(vec![2u32; 1024]);
+    let b = other.create_vbuffer(vec![3u32; 1024]);
+    let c = other.create_vbuffer_uninit::<u32>(1024);
+
+    let mut task = TaskBuilder::new(&mut other, include_wgsl!("./array_addition.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, a)
+        .with_input_buffer(1, b)
+        .with_output_buffer(2, c)
+        .build()
+        .expect("Failed to build task");
+
+    let mut pending = task.submit().expect("Failed to submit task");
+
+    // The other Workgroup's work is on the same queues, so it is done once they are idle.
+    workgroup
+        .wait_idle()
+        .expect("Failed to wait for the devices");
+    assert!(pending.poll().expect("Failed to poll task"));
+
+    pending.wait().expect("Failed to run task");
+}
+
+#[test]
+fn cancelled_submission_skips_write_back() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let a = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let b = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let c = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, a)
+        .with_input_buffer(1, b)
+        .with_output_buffer(2, c)
+        .build()
+        .expect("Failed to build task");
+
+    task.submit().expect("Failed to submit task").cancel();
+
+    let mut readback = vec![MaybeUninit::uninit(); 1024];
+    let values: &mut [u32] = task
+        .workgroup()
+        .read_vbuffer_into(c, &mut readback)
+        .unwrap();
+    assert_eq!(values, &[0u32; 1024]);
+
+    // The staging buffers were released, so the task runs again as usual.
+    task.run().expect("Failed to run task");
+
+    drop(task);
+    assert_eq!(workgroup.take_vbuffer::<u32>(c).unwrap(), vec![5u32; 1024]);
+}
+
+#[test]
+fn run_within_timeout() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let a = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let b = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let c = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, a)
+        .with_input_buffer(1, b)
+        .with_output_buffer(2, c)
+        .build()
+        .expect("Failed to build task");
+
+    let report = task
+        .run_with_timeout(Duration::from_secs(30))
+        .expect("Failed to run task");
+    assert_eq!(report.devices, 2);
+
+    drop(task);
+    assert_eq!(workgroup.take_vbuffer::<u32>(c).unwrap(), vec![5u32; 1024]);
+}
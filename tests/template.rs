@@ -0,0 +1,34 @@
+use wisc::{prelude::*, template::TaskTemplate};
+
+#[test]
+fn template_parameter_sweep() {
+    let devices = VDevice::all();
+    let num_devices = devices.len();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    workgroup.register_shader("normalize", include_wgsl!("./normalize.wgsl"));
+
+    let template = TaskTemplate::new("normalize").with_kernel("main");
+    let input = workgroup.create_vbuffer(vec![12.0f32; 1024]);
+
+    // Every instance binds its own divisor and output, but shares the template's pipeline.
+    for divisor in [1.0f32, 2.0, 3.0, 4.0] {
+        let output = workgroup.create_vbuffer_uninit::<f32>(1024);
+
+        template
+            .instantiate(&mut workgroup)
+            .with_size((4, 1, 1))
+            .with_input_buffer(0, input)
+            .with_uniform_buffer(1, divisor)
+            .with_output_buffer(2, output)
+            .build()
+            .expect("Failed to build task")
+            .run()
+            .expect("Failed to run task");
+
+        let output: Vec<f32> = workgroup.take_vbuffer(output).unwrap();
+        assert_eq!(output, vec![12.0 / divisor; 1024]);
+    }
+
+    assert_eq!(template.pipeline_count(), num_devices);
+}
@@ -0,0 +1,44 @@
+use wisc::prelude::*;
+
+#[test]
+fn devices_take_tiles_from_a_shared_queue() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let data = workgroup.create_vbuffer(vec![1u32; 64 * 256]);
+
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./sliced.wgsl"))
+        .with_size_per_element(data)
+        .with_output_buffer_partitioned(0, data, PartitionMode::Split)
+        .with_tiles(4)
+        .build()
+        .expect("Failed to build task");
+
+    // 256 workgroups in tiles of 4, however the devices shared them out.
+    let report = task.run().expect("Failed to run task");
+    assert_eq!(report.tiles.len(), 2);
+    assert_eq!(report.tiles.iter().sum::<usize>(), 64);
+
+    // Every workgroup ran exactly once, at its place in the whole dispatch, and the
+    // pieced-together result is what the next run starts from.
+    task.run().expect("Failed to run task");
+    drop(task);
+
+    let data: Vec<u32> = workgroup.take_vbuffer(data).unwrap();
+    assert_eq!(data, (0..64 * 256).map(|i| 4 + 3 * i).collect::<Vec<u32>>());
+}
+
+#[test]
+fn tiles_need_a_per_element_size() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let data = workgroup.create_vbuffer(vec![1u32; 64 * 256]);
+
+    let task = TaskBuilder::new(&mut workgroup, include_wgsl!("./sliced.wgsl"))
+        .with_size((256, 1, 1))
+        .with_output_buffer(0, data)
+        .with_tiles(4)
+        .build();
+
+    assert!(matches!(task.err(), Some(WiscError::InvalidDispatch(_))));
+}
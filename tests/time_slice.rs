@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+use wisc::prelude::*;
+
+#[test]
+fn time_sliced_dispatch() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let data = workgroup.create_vbuffer(vec![1u32; 64 * 256]);
+
+    // A slice this short is over after a workgroup or two, so the dispatch is cut up a lot.
+    let report = TaskBuilder::new(&mut workgroup, include_wgsl!("./sliced.wgsl"))
+        .with_size((256, 1, 1))
+        .with_output_buffer(0, data)
+        .with_time_slice(Duration::from_micros(1))
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    assert!(report.time_slices > report.devices);
+
+    // Every workgroup ran exactly once, at its place in the whole dispatch.
+    let data: Vec<u32> = workgroup.take_vbuffer(data).unwrap();
+    assert_eq!(data, (0..64 * 256).map(|i| 2 + i).collect::<Vec<u32>>());
+}
+
+#[test]
+fn time_slicing_needs_the_slice_uniform() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let input = workgroup.create_vbuffer(vec![1u32; 256]);
+    let output = workgroup.create_vbuffer(vec![0u32; 256]);
+
+    let task = TaskBuilder::new(&mut workgroup, include_wgsl!("./double.wgsl"))
+        .with_size((1, 1, 1))
+        .with_input_buffer(0, input)
+        .with_output_buffer(1, output)
+        .with_time_slice(Duration::from_millis(1))
+        .build();
+
+    assert!(matches!(task, Err(WiscError::InvalidBinding(_))));
+}
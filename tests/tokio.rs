@@ -0,0 +1,28 @@
+#![cfg(feature = "tokio")]
+
+use wisc::prelude::*;
+
+#[tokio::test]
+async fn run_async_on_tokio() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let a = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let b = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let c = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, a)
+        .with_input_buffer(1, b)
+        .with_output_buffer(2, c)
+        .build()
+        .expect("Failed to build task");
+
+    // The single-threaded runtime would stall if the wait blocked it.
+    let report = task.run_async().await.expect("Failed to run task");
+    assert_eq!(report.devices, 2);
+
+    drop(task);
+    assert_eq!(workgroup.take_vbuffer::<u32>(c).unwrap(), vec![5u32; 1024]);
+}
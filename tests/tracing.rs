@@ -0,0 +1,95 @@
+#![cfg(feature = "tracing")]
+
+use std::sync::{Arc, Mutex};
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+use wisc::prelude::*;
+
+// A span's name, and the device it names, if any.
+type Opened = (&'static str, Option<String>);
+
+/// Keeps the name and `device` field of every span wisc opens.
+#[derive(Clone, Default)]
+struct Spans(Arc<Mutex<Vec<Opened>>>);
+
+struct DeviceField(Option<String>);
+
+impl Visit for DeviceField {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "device" {
+            self.0 = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+}
+
+impl Subscriber for Spans {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.target().starts_with("wisc")
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut device = DeviceField(None);
+        span.record(&mut device);
+
+        let mut spans = self.0.lock().unwrap();
+        spans.push((span.metadata().name(), device.0));
+
+        Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, _event: &Event<'_>) {}
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+#[test]
+fn spans_cover_building_and_running() {
+    let spans = Spans::default();
+
+    tracing::subscriber::with_default(spans.clone(), || {
+        let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+        let ibuf1 = workgroup.create_vbuffer(vec![2u32; 1024]);
+        let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+        let obuf1 = workgroup.create_vbuffer(vec![0u32; 1024]);
+
+        TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+            .with_size_per_element(obuf1)
+            .with_input_buffer(0, ibuf1)
+            .with_input_buffer(1, ibuf2)
+            .with_output_buffer(2, obuf1)
+            .build()
+            .expect("Failed to build task")
+            .run()
+            .expect("Failed to run task");
+    });
+
+    let spans = spans.0.lock().unwrap();
+    let opened = |name| spans.iter().filter(move |(opened, _)| *opened == name);
+
+    assert_eq!(opened("enumerate_adapters").count(), 1);
+    assert_eq!(opened("create_buffer").count(), 3);
+
+    // Every span on a device names it.
+    for name in [
+        "request_device",
+        "create_buffer",
+        "create_pipeline",
+        "submit",
+        "poll",
+        "readback",
+    ] {
+        assert!(opened(name).count() > 0, "no {name} span");
+        assert!(opened(name).all(|(_, device)| device.is_some()));
+    }
+}
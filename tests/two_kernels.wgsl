@@ -0,0 +1,11 @@
+@group(0) @binding(0) var<storage, read_write> result: array<u32>;
+
+@compute @workgroup_size(256, 1, 1)
+fn first(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    result[global_id.x] = 1u;
+}
+
+@compute @workgroup_size(256, 1, 1)
+fn second(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    result[global_id.x] = 2u;
+}
@@ -0,0 +1,54 @@
+use wisc::prelude::*;
+
+#[test]
+fn uniform_buffer_value() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let input = workgroup.create_vbuffer(vec![6.0f32; 1024]);
+    let output = workgroup.create_vbuffer_uninit::<f32>(1024);
+
+    // The shader reads a vec4<f32>, of which only the first component matters; the value
+    // is padded out to the whole vector.
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./normalize.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, input)
+        .with_uniform_buffer(1, 2.0f32)
+        .with_output_buffer(2, output)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let output: Vec<f32> = workgroup.take_vbuffer(output).unwrap();
+    assert_eq!(output, vec![3.0f32; 1024]);
+}
+
+#[test]
+fn uniform_input_too_large() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let input = workgroup.create_vbuffer(vec![6.0f32; 1024]);
+    let output = workgroup.create_vbuffer_uninit::<f32>(1024);
+
+    // Far past what any device binds as a uniform, so the build fails up front, naming
+    // the binding and the limit.
+    let divisor = workgroup.create_vbuffer(vec![2.0f32; 1 << 20]);
+
+    let task = TaskBuilder::new(&mut workgroup, include_wgsl!("./normalize.wgsl"))
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, input)
+        .with_uniform_input(1, divisor)
+        .with_output_buffer(2, output)
+        .build();
+
+    assert!(matches!(
+        task.err(),
+        Some(WiscError::BindingTooLarge {
+            binding: 1,
+            device: 0,
+            size: 4194304,
+            limit: "max_uniform_buffer_binding_size",
+            ..
+        })
+    ));
+}
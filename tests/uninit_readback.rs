@@ -0,0 +1,83 @@
+use std::mem::MaybeUninit;
+
+use wisc::prelude::*;
+
+#[test]
+fn uninit_output_readback() {
+    // Get all the hardware devices available to our system.
+    let devices = VDevice::all();
+
+    // Create a Workgroup out of our device(s).
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let ibuf1 = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+
+    // The output's host memory is never zeroed, and nothing is uploaded for it.
+    let obuf1 = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    // An unwritten buffer can neither be read nor taken.
+    assert_eq!(
+        workgroup.take_vbuffer::<u32>(obuf1),
+        Err(WiscError::Uninitialized)
+    );
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./array_addition.wgsl"))
+        .with_kernel("main")
+        .with_size((4, 1, 1))
+        .with_input_buffer(0, ibuf1)
+        .with_input_buffer(1, ibuf2)
+        .with_output_buffer(2, obuf1)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    // Read the results into caller-owned, uninitialized memory.
+    let mut destination: Box<[MaybeUninit<u32>]> = Box::new_uninit_slice(1024);
+    let results = workgroup
+        .read_vbuffer_into(obuf1, &mut destination)
+        .expect("Buffer should be readable after a run");
+
+    assert_eq!(results, &[5u32; 1024][..]);
+
+    let obuf1: Vec<u32> = workgroup.take_vbuffer(obuf1).unwrap();
+    assert_eq!(obuf1, vec![5u32; 1024]);
+}
+
+#[test]
+fn readback_of_results_left_on_devices() {
+    // The same devices twice over, so the output is split even on a single GPU.
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let ibuf1 = workgroup.create_vbuffer(vec![2u32; 1024]);
+    let ibuf2 = workgroup.create_vbuffer(vec![3u32; 1024]);
+    let obuf1 = workgroup.create_vbuffer_uninit::<u32>(1024);
+
+    let add = |workgroup: &mut Workgroup, a, b| {
+        TaskBuilder::new(workgroup, include_wgsl!("./array_addition.wgsl"))
+            .with_size((4, 1, 1))
+            .with_input_buffer(0, a)
+            .with_input_buffer(1, b)
+            .with_output_buffer_partitioned(2, obuf1, PartitionMode::Weighted)
+            .build()
+            .expect("Failed to build task")
+            .run()
+            .expect("Failed to run task")
+    };
+
+    add(&mut workgroup, ibuf1, ibuf2);
+    workgroup.alias_output_as_input(obuf1).unwrap();
+
+    // Written while aliased, the results stay on the devices until they are read.
+    let report = add(&mut workgroup, ibuf2, ibuf2);
+    assert!(report.timings.iter().all(|t| t.bytes_read_back == 0));
+
+    let mut destination: Box<[MaybeUninit<u32>]> = Box::new_uninit_slice(1024);
+    let results = workgroup
+        .read_vbuffer_into(obuf1, &mut destination)
+        .expect("Buffer should be readable after a run");
+
+    assert_eq!(results, &[6u32; 1024][..]);
+}
@@ -0,0 +1,150 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use wisc::prelude::*;
+
+fn scale_shader(factor: &str) -> String {
+    format!(
+        "//#include \"bounds.wgsl\"
+
+@group(0) @binding(0) var<storage, read> input: array<u32>;
+@group(0) @binding(1) var<storage, read_write> output: array<u32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {{
+    if out_of_bounds(global_id.x) {{
+        return;
+    }}
+
+    output[global_id.x] = input[global_id.x] * {factor};
+}}
+"
+    )
+}
+
+fn bounds_helper(limit: &str) -> String {
+    format!("fn out_of_bounds(index: u32) -> bool {{\n    return index >= {limit};\n}}\n")
+}
+
+/// Writes `contents` to `path`, marking it modified well after any earlier write, since
+/// file systems may only keep modification times to the second.
+fn write(path: &Path, contents: &str, generation: u64) {
+    fs::write(path, contents).unwrap();
+
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(10 * generation))
+        .unwrap();
+}
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("wisc-watch-{name}-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn run(workgroup: &mut Workgroup) -> Result<Vec<u32>, WiscError> {
+    let input = workgroup.create_vbuffer((0..256u32).collect());
+    let output = workgroup.create_vbuffer(vec![0u32; 256]);
+
+    TaskBuilder::from_workgroup(workgroup)
+        .with_registered_shader("scale")
+        .with_size_per_element(output)
+        .with_input_buffer(0, input)
+        .with_output_buffer(1, output)
+        .build()?
+        .run()?;
+
+    workgroup.take_vbuffer(output)
+}
+
+#[test]
+fn watched_shader_picks_up_edits() {
+    let dir = scratch_dir("edits");
+    let path = dir.join("scale.wgsl");
+    write(
+        &dir.join("bounds.wgsl"),
+        &bounds_helper("arrayLength(&output)"),
+        0,
+    );
+    write(&path, &scale_shader("2u"), 0);
+
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+    workgroup.watch_shader("scale", &path).unwrap();
+
+    let output = run(&mut workgroup).unwrap();
+    assert_eq!(output, (0..256u32).map(|x| x * 2).collect::<Vec<_>>());
+
+    // Nothing changed, so nothing is recompiled.
+    assert!(workgroup.reload_shaders().unwrap().is_empty());
+
+    write(&path, &scale_shader("3u"), 1);
+
+    let output = run(&mut workgroup).unwrap();
+    assert_eq!(output, (0..256u32).map(|x| x * 3).collect::<Vec<_>>());
+
+    // Edits to included files count too.
+    write(&dir.join("bounds.wgsl"), &bounds_helper("128u"), 2);
+
+    assert_eq!(
+        workgroup.reload_shaders().unwrap(),
+        vec!["scale".to_string()]
+    );
+
+    let output = run(&mut workgroup).unwrap();
+    assert_eq!(
+        &output[..128],
+        &(0..128u32).map(|x| x * 3).collect::<Vec<_>>()
+    );
+    assert!(output[128..].iter().all(|x| *x == 0));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn broken_edit_keeps_the_last_good_shader() {
+    let dir = scratch_dir("broken");
+    let path = dir.join("scale.wgsl");
+    write(
+        &dir.join("bounds.wgsl"),
+        &bounds_helper("arrayLength(&output)"),
+        0,
+    );
+    write(&path, &scale_shader("2u"), 0);
+
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+    workgroup.watch_shader("scale", &path).unwrap();
+
+    // Doesn't validate: a float times an unsigned integer.
+    write(&path, &scale_shader("2.5"), 1);
+
+    assert!(matches!(
+        run(&mut workgroup),
+        Err(WiscError::ShaderParse(_))
+    ));
+
+    // The broken edit is still pending, so it keeps failing until it is fixed.
+    assert!(workgroup.reload_shaders().is_err());
+    assert!(workgroup.has_registered_shader("scale"));
+
+    write(&path, &scale_shader("4u"), 2);
+
+    let output = run(&mut workgroup).unwrap();
+    assert_eq!(output, (0..256u32).map(|x| x * 4).collect::<Vec<_>>());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn missing_file_fails_to_watch() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    assert!(matches!(
+        workgroup.watch_shader("scale", "tests/no_such_shader.wgsl"),
+        Err(WiscError::ShaderFile(_))
+    ));
+    assert!(!workgroup.has_registered_shader("scale"));
+}
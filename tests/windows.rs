@@ -0,0 +1,90 @@
+use wisc::prelude::*;
+
+#[test]
+fn sliding_windows() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let (size, stride) = (16, 4);
+    let samples: Vec<f32> = (0..1000).map(|i| ((i * 7) % 31) as f32).collect();
+    let windows = (samples.len() - size) / stride + 1;
+
+    let signal = workgroup.create_vbuffer(samples.clone());
+    let stats = workgroup.create_vbuffer_uninit::<f32>(windows * 2);
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./windows.wgsl"))
+        .with_size_per_element(signal)
+        .with_override("SIZE", size as u32)
+        .with_override("STRIDE", stride as u32)
+        .with_sliding_windows(0, signal, size, stride, 1, stats)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let expected: Vec<f32> = samples
+        .windows(size)
+        .step_by(stride)
+        .flat_map(|window| {
+            let mean = window.iter().sum::<f32>() / size as f32;
+            let largest = window.iter().copied().fold(f32::MIN, f32::max);
+            [mean, largest]
+        })
+        .collect();
+
+    let stats: Vec<f32> = workgroup.take_vbuffer(stats).unwrap();
+    assert_eq!(stats, expected);
+}
+
+#[test]
+fn windows_must_fit() {
+    let mut workgroup = Workgroup::from_devices(VDevice::all());
+
+    let signal = workgroup.create_vbuffer(vec![0.0f32; 100]);
+    let stats = workgroup.create_vbuffer_uninit::<f32>(7);
+
+    // 22 windows can't share 7 results.
+    let result = TaskBuilder::new(&mut workgroup, include_wgsl!("./windows.wgsl"))
+        .with_size((1, 1, 1))
+        .with_sliding_windows(0, signal, 16, 4, 1, stats)
+        .build();
+
+    assert!(matches!(result, Err(WiscError::ShapeMismatch(_))));
+}
+
+#[test]
+fn windows_only_group_outputs_of_their_task() {
+    let devices = VDevice::all().into_iter().chain(VDevice::all()).collect();
+    let mut workgroup = Workgroup::from_devices(devices);
+
+    let signal = workgroup.create_vbuffer(vec![6.0f32; 100]);
+    let stats = workgroup.create_vbuffer(vec![0.0f32; 44]);
+
+    // Without a size, building fails after the windows have been counted.
+    let result = TaskBuilder::new(&mut workgroup, include_wgsl!("./windows.wgsl"))
+        .with_sliding_windows(0, signal, 16, 4, 1, stats)
+        .build();
+
+    assert!(matches!(result, Err(WiscError::MissingSize)));
+
+    // Splitting a window's mean from its maximum is fine once no windows are bound.
+    let odd = PartitionMode::custom(|length, weightings| {
+        let mut ranges = vec![0..0; weightings.len()];
+        ranges[0] = 0..1;
+        ranges[1] = 1..length;
+        ranges
+    });
+
+    TaskBuilder::new(&mut workgroup, include_wgsl!("./normalize.wgsl"))
+        .with_size_per_element(stats)
+        .with_input_buffer(0, signal)
+        .with_uniform_buffer(1, 2.0f32)
+        .with_output_buffer_partitioned(2, stats, odd)
+        .build()
+        .expect("Failed to build task")
+        .run()
+        .expect("Failed to run task");
+
+    let stats: Vec<f32> = workgroup.take_vbuffer(stats).unwrap();
+    assert_eq!(stats, vec![3.0f32; 44]);
+}
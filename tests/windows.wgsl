@@ -0,0 +1,25 @@
+override SIZE: u32;
+override STRIDE: u32;
+
+@group(0) @binding(0) var<storage, read> signal: array<f32>;
+// The mean and maximum of each window.
+@group(0) @binding(1) var<storage, read_write> stats: array<vec2<f32>>;
+
+@compute @workgroup_size(64, 1, 1)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let window = global_id.x;
+    if (window >= arrayLength(&stats)) {
+        return;
+    }
+
+    let start = window * STRIDE;
+    var sum = 0.0;
+    var largest = signal[start];
+
+    for (var k = 0u; k < SIZE; k++) {
+        sum += signal[start + k];
+        largest = max(largest, signal[start + k]);
+    }
+
+    stats[window] = vec2<f32>(sum / f32(SIZE), largest);
+}
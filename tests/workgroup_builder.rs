@@ -0,0 +1,58 @@
+use wisc::{
+    prelude::*,
+    vdevice::{Backend, LimitsPolicy},
+    workgroup::Weighting,
+};
+
+#[test]
+fn workgroup_builder_policies() {
+    // Enumerate the system's adapters with explicit policies.
+    let workgroup = WorkgroupBuilder::new()
+        .weighting(Weighting::Uniform)
+        .limits_policy(LimitsPolicy::Downlevel)
+        .backend_preference([Backend::Vulkan, Backend::Gl])
+        .build();
+
+    let weightings = workgroup.vdevice_weightings();
+    let share = 1.0 / weightings.len() as f32;
+
+    assert!(weightings.iter().all(|(_, w)| (w - share).abs() < 1e-6));
+}
+
+#[test]
+fn workgroup_builder_deny() {
+    // Every adapter name contains the empty string, so nothing survives.
+    let workgroup = WorkgroupBuilder::new()
+        .devices(VDevice::all())
+        .deny([""])
+        .build();
+
+    assert!(workgroup.vdevice_weightings().is_empty());
+}
+
+#[test]
+fn workgroup_builder_throttle() {
+    let mut workgroup = WorkgroupBuilder::new()
+        .devices(VDevice::all())
+        .throttle(0.5)
+        .build();
+
+    let input = workgroup.create_vbuffer(vec![1u32; 256]);
+    let output = workgroup.create_vbuffer_uninit::<u32>(256);
+
+    let mut task = TaskBuilder::new(&mut workgroup, include_wgsl!("./double.wgsl"))
+        .with_size_per_element(output)
+        .with_input_buffer(0, input)
+        .with_output_buffer(1, output)
+        .build()
+        .expect("Failed to build task");
+
+    // Each run is followed by an idle gap about as long as itself.
+    for _ in 0..4 {
+        task.run().expect("Failed to run task");
+    }
+
+    drop(task);
+    let output: Vec<u32> = workgroup.take_vbuffer(output).unwrap();
+    assert_eq!(output, vec![2u32; 256]);
+}
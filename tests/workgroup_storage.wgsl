@@ -0,0 +1,14 @@
+// Stages 5000 words (20000 bytes) in workgroup storage, more than the downlevel limit.
+@group(0) @binding(0) var<storage, read_write> result: array<u32>;
+
+var<workgroup> staged: array<u32, 5000>;
+
+@compute @workgroup_size(64, 1, 1)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>, @builtin(local_invocation_index) local: u32) {
+    staged[local] = global_id.x * 2u;
+    workgroupBarrier();
+
+    if (global_id.x < arrayLength(&result)) {
+        result[global_id.x] = staged[local];
+    }
+}